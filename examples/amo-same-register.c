@@ -0,0 +1,39 @@
+#include "whisker.h"
+
+static void check(const char* name, int ok) {
+    whisker_write_uart(name);
+    whisker_write_uart(ok ? ": correct\n" : ": wrong\n");
+}
+
+int main() {
+    volatile uint32_t word = 100;
+    volatile uint64_t dword = 1000;
+    int64_t val;
+
+    // rd and rs2 are the same register, memory gets rs2's value and only then rd gets the old one
+    val = 50;
+    __asm__ volatile("amoadd.w %0, %0, (%1)" : "+r"(val) : "r"(&word) : "memory");
+    check("amoadd.w rd == rs2", val == 100 && word == 150);
+
+    val = -1;
+    __asm__ volatile("amoswap.w %0, %0, (%1)" : "+r"(val) : "r"(&word) : "memory");
+    check("amoswap.w rd == rs2", val == 150 && word == 0xFFFFFFFF);
+
+    val = 7;
+    __asm__ volatile("amomaxu.w %0, %0, (%1)" : "+r"(val) : "r"(&word) : "memory");
+    check("amomaxu.w rd == rs2", val == -1 && word == 0xFFFFFFFF);
+
+    val = 24;
+    __asm__ volatile("amoadd.d %0, %0, (%1)" : "+r"(val) : "r"(&dword) : "memory");
+    check("amoadd.d rd == rs2", val == 1000 && dword == 1024);
+
+    val = 0x0F;
+    __asm__ volatile("amoand.d %0, %0, (%1)" : "+r"(val) : "r"(&dword) : "memory");
+    check("amoand.d rd == rs2", val == 1024 && dword == 0);
+
+    val = -3;
+    __asm__ volatile("amomin.d %0, %0, (%1)" : "+r"(val) : "r"(&dword) : "memory");
+    check("amomin.d rd == rs2", val == 0 && dword == (uint64_t)-3);
+
+    while(true) {}
+}
@@ -0,0 +1,31 @@
+#include "whisker.h"
+
+#define DRAM_BASE 0x80000000
+#define STORE_ADDR_MISALIGNED 6
+
+// not 4 byte aligned, amoadd.w must trap instead of touching memory
+#define MISALIGNED_ADDR (DRAM_BASE + 0x2042)
+
+__attribute__((interrupt("machine"), aligned(4))) static void trap_handler(void) {
+    uint64_t mcause;
+    uint64_t mtval;
+    __asm__ volatile("csrr %0, mcause" : "=r"(mcause));
+    __asm__ volatile("csrr %0, mtval" : "=r"(mtval));
+
+    if (mcause == STORE_ADDR_MISALIGNED && mtval == MISALIGNED_ADDR) {
+        whisker_write_uart("misaligned amo trap is correct\n");
+    } else {
+        whisker_write_uart("misaligned amo trap is wrong\n");
+    }
+    while(true) {}
+}
+
+int main() {
+    __asm__ volatile("csrw mtvec, %0" : : "r"(trap_handler));
+
+    uint32_t result;
+    __asm__ volatile("amoadd.w %0, %1, (%2)" : "=r"(result) : "r"(1), "r"(MISALIGNED_ADDR) : "memory");
+
+    whisker_write_uart("misaligned amo did not trap\n");
+    while(true) {}
+}
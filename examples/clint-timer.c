@@ -0,0 +1,53 @@
+#include "whisker.h"
+
+#define CLINT_MTIMECMP ((volatile uint64_t*)0x02004000)
+#define CLINT_MTIME ((volatile uint64_t*)0x0200BFF8)
+#define MSTATUS_MIE (1 << 3)
+#define MIE_MTIE (1 << 7)
+#define MACHINE_TIMER_INTERRUPT ((1ull << 63) | 7)
+
+static volatile uint64_t timer_cause = 0;
+static volatile uint64_t timer_fired_at = 0;
+
+static void check(const char* name, int ok) {
+    whisker_write_uart(name);
+    whisker_write_uart(ok ? ": correct\n" : ": wrong\n");
+}
+
+__attribute__((interrupt("machine"), aligned(4))) static void handler(void) {
+    __asm__ volatile("csrr %0, mcause" : "=r"(timer_cause));
+    timer_fired_at = *CLINT_MTIME;
+    // moving mtimecmp forward is how software clears the interrupt
+    *CLINT_MTIMECMP = ~0ull;
+}
+
+int main() {
+    __asm__ volatile("csrw mtvec, %0" : : "r"(handler));
+
+    uint64_t start = *CLINT_MTIME;
+    for (volatile int i = 0; i < 1000; i++) {}
+    check("mtime counts up", *CLINT_MTIME > start);
+
+    uint64_t time;
+    __asm__ volatile("csrr %0, time" : "=r"(time));
+    check("time CSR follows mtime", time >= start);
+
+    uint64_t mip;
+    __asm__ volatile("csrr %0, mip" : "=r"(mip));
+    check("no timer interrupt before mtimecmp is set", !(mip & MIE_MTIE));
+
+    uint64_t deadline = *CLINT_MTIME + 1000;
+    *CLINT_MTIMECMP = deadline;
+    __asm__ volatile("csrs mie, %0" : : "r"(MIE_MTIE));
+    __asm__ volatile("csrs mstatus, %0" : : "r"(MSTATUS_MIE));
+    while (timer_cause == 0) {
+        __asm__ volatile("wfi");
+    }
+    check("timer interrupt cause", timer_cause == MACHINE_TIMER_INTERRUPT);
+    check("timer interrupt after mtimecmp", timer_fired_at >= deadline);
+
+    __asm__ volatile("csrr %0, mip" : "=r"(mip));
+    check("writing mtimecmp clears MTIP", !(mip & MIE_MTIE));
+
+    while(true) {}
+}
@@ -0,0 +1,84 @@
+#include "whisker.h"
+
+#define MSTATUS_MPP (3 << 11)
+#define MSTATUS_MPP_S (1 << 11)
+#define ILLEGAL_INSTRUCTION 2
+#define ECALL_UMODE 8
+#define ECALL_SMODE 9
+#define COUNTEREN_CY (1 << 0)
+#define COUNTEREN_TM (1 << 1)
+
+static volatile int illegal_count = 0;
+
+static void check(const char* name, int ok) {
+    whisker_write_uart(name);
+    whisker_write_uart(ok ? ": correct\n" : ": wrong\n");
+}
+
+// the counter reads are followed by an ecall, which comes back to M-mode
+__attribute__((interrupt("machine"), aligned(4))) static void trap_handler(void) {
+    uint64_t mcause, mepc;
+    __asm__ volatile("csrr %0, mcause" : "=r"(mcause));
+    __asm__ volatile("csrr %0, mepc" : "=r"(mepc));
+    if (mcause == ILLEGAL_INSTRUCTION) {
+        illegal_count++;
+    } else if (mcause == ECALL_UMODE || mcause == ECALL_SMODE) {
+        __asm__ volatile("csrs mstatus, %0" : : "r"(MSTATUS_MPP));
+    }
+    __asm__ volatile("csrw mepc, %0" : : "r"(mepc + 4));
+}
+
+static void read_counters(void) {
+    uint64_t val;
+    __asm__ volatile(
+        ".option push\n"
+        ".option norvc\n"
+        "rdcycle %0\n"
+        "rdtime %0\n"
+        "ecall\n"
+        ".option pop\n"
+        : "=r"(val));
+}
+
+// calls read_counters in a lower privilege mode, it returns here in M-mode after its ecall
+static void read_counters_in(uint64_t mpp) {
+    __asm__ volatile("csrc mstatus, %0" : : "r"(MSTATUS_MPP));
+    __asm__ volatile("csrs mstatus, %0" : : "r"(mpp));
+    __asm__ volatile(
+        "csrw mepc, %0\n"
+        "lla ra, 1f\n"
+        "mret\n"
+        "1:\n"
+        : : "r"(read_counters)
+        : "ra", "t0", "t1", "t2", "t3", "t4", "t5", "t6", "a0", "a1", "a2", "a3", "a4", "a5", "a6", "a7", "memory");
+}
+
+int main() {
+    __asm__ volatile("csrw mtvec, %0" : : "r"(trap_handler));
+    // let S and U-mode access all of memory
+    __asm__ volatile("csrw pmpaddr0, %0" : : "r"(-1));
+    __asm__ volatile("csrw pmpcfg0, %0" : : "r"(0x1f));
+
+    uint64_t val;
+    __asm__ volatile("csrw mcounteren, %1\n\tcsrr %0, mcounteren" : "=r"(val) : "r"(-1));
+    check("mcounteren has 32 bits", val == 0xFFFFFFFF);
+    __asm__ volatile("csrw mcounteren, zero");
+
+    illegal_count = 0;
+    read_counters_in(MSTATUS_MPP_S);
+    check("both counter reads trapped", illegal_count == 2);
+
+    __asm__ volatile("csrw mcounteren, %0" : : "r"(COUNTEREN_CY));
+    illegal_count = 0;
+    read_counters_in(MSTATUS_MPP_S);
+    check("only time trapped in S-mode", illegal_count == 1);
+
+    // U-mode also needs scounteren
+    __asm__ volatile("csrw mcounteren, %0" : : "r"(COUNTEREN_CY | COUNTEREN_TM));
+    __asm__ volatile("csrw scounteren, %0" : : "r"(COUNTEREN_TM));
+    illegal_count = 0;
+    read_counters_in(0);
+    check("only cycle trapped in U-mode", illegal_count == 1);
+
+    while(true) {}
+}
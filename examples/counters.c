@@ -0,0 +1,38 @@
+#include "whisker.h"
+
+static void check(const char* name, int ok) {
+    whisker_write_uart(name);
+    whisker_write_uart(ok ? ": correct\n" : ": wrong\n");
+}
+
+int main() {
+    uint64_t cycle_before, cycle_after;
+    uint64_t instret_before, instret_after;
+    uint64_t time_before, time_after;
+
+    __asm__ volatile("rdcycle %0" : "=r"(cycle_before));
+    __asm__ volatile("rdinstret %0" : "=r"(instret_before));
+    __asm__ volatile("rdtime %0" : "=r"(time_before));
+
+    // burn some time so the counters have to move
+    for (volatile int i = 0; i < 100000; i++) {}
+
+    __asm__ volatile("rdcycle %0" : "=r"(cycle_after));
+    __asm__ volatile("rdinstret %0" : "=r"(instret_after));
+    __asm__ volatile("rdtime %0" : "=r"(time_after));
+
+    check("cycle advances", cycle_after > cycle_before);
+    check("instret advances", instret_after > instret_before);
+    check("time advances", time_after > time_before);
+
+    // the machine level counters can be written
+    uint64_t mcycle;
+    __asm__ volatile("csrw mcycle, zero\n\tcsrr %0, mcycle" : "=r"(mcycle));
+    check("mcycle is writable", mcycle < 10);
+
+    uint64_t minstret;
+    __asm__ volatile("csrw minstret, zero\n\tcsrr %0, minstret" : "=r"(minstret));
+    check("minstret is writable", minstret < 10);
+
+    while(true) {}
+}
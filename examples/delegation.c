@@ -0,0 +1,42 @@
+#include "whisker.h"
+
+#define MSTATUS_SPP (1 << 8)
+#define ILLEGAL_INSTRUCTION 2
+
+__attribute__((interrupt("machine"), aligned(4))) static void machine_handler(void) {
+    whisker_write_uart("delegated trap went to M-mode, delegation is wrong\n");
+    while(true) {}
+}
+
+__attribute__((interrupt("supervisor"), aligned(4))) static void supervisor_handler(void) {
+    uint64_t scause;
+    __asm__ volatile("csrr %0, scause" : "=r"(scause));
+
+    if (scause == ILLEGAL_INSTRUCTION) {
+        whisker_write_uart("delegation is correct\n");
+    } else {
+        whisker_write_uart("delegated trap has the wrong scause\n");
+    }
+    while(true) {}
+}
+
+static void in_supervisor(void) {
+    // an all zero parcel is always illegal
+    __asm__ volatile(".word 0");
+    while(true) {}
+}
+
+int main() {
+    __asm__ volatile("csrw mtvec, %0" : : "r"(machine_handler));
+    __asm__ volatile("csrw stvec, %0" : : "r"(supervisor_handler));
+    __asm__ volatile("csrw medeleg, %0" : : "r"(1 << ILLEGAL_INSTRUCTION));
+
+    // let S and U-mode access all of memory
+    __asm__ volatile("csrw pmpaddr0, %0" : : "r"(-1));
+    __asm__ volatile("csrw pmpcfg0, %0" : : "r"(0x1f));
+    __asm__ volatile("csrw sepc, %0" : : "r"(in_supervisor));
+    __asm__ volatile("csrs mstatus, %0" : : "r"(MSTATUS_SPP));
+    __asm__ volatile("sret");
+
+    while(true) {}
+}
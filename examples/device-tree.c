@@ -0,0 +1,36 @@
+#include "whisker.h"
+
+#define FDT_MAGIC 0xD00DFEED
+#define DRAM_BASE 0x80000000UL
+#define DRAM_END 0x90000000UL
+
+static void check(const char* name, int ok) {
+    whisker_write_uart(name);
+    whisker_write_uart(ok ? ": correct\n" : ": wrong\n");
+}
+
+// the device tree is big endian
+static uint32_t be32(const volatile uint32_t* ptr) {
+    return __builtin_bswap32(*ptr);
+}
+
+// runtime.s leaves a0 and a1 alone, so they arrive here as they were at reset
+int main(uint64_t hartid, const volatile uint32_t* fdt) {
+    check("a0 is the hart id", hartid == 0);
+
+    uint64_t addr = (uint64_t)fdt;
+    check("a1 points into DRAM", addr >= DRAM_BASE && addr < DRAM_END);
+    check("the device tree is 8 byte aligned", addr % 8 == 0);
+    check("the magic is there", be32(&fdt[0]) == FDT_MAGIC);
+
+    uint32_t total_size = be32(&fdt[1]);
+    check("the device tree fits in DRAM", addr + total_size <= DRAM_END);
+    check("the version is 17", be32(&fdt[5]) == 17);
+
+    // the structure block starts with the root node, whose name is empty
+    const volatile uint32_t* structure = (const volatile uint32_t*)(addr + be32(&fdt[2]));
+    check("the root node comes first", be32(&structure[0]) == 1 && structure[1] == 0);
+
+    while (true) {
+    }
+}
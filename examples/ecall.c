@@ -0,0 +1,23 @@
+#include "whisker.h"
+
+#define ECALL_MMODE 11
+
+__attribute__((interrupt("machine"), aligned(4))) static void trap_handler(void) {
+    uint64_t mcause;
+    __asm__ volatile("csrr %0, mcause" : "=r"(mcause));
+
+    if (mcause == ECALL_MMODE) {
+        whisker_write_uart("ecall from M-mode is correct\n");
+    } else {
+        whisker_write_uart("ecall from M-mode is wrong\n");
+    }
+    while(true) {}
+}
+
+int main() {
+    __asm__ volatile("csrw mtvec, %0" : : "r"(trap_handler));
+    __asm__ volatile("ecall");
+
+    whisker_write_uart("ecall did not trap\n");
+    while(true) {}
+}
@@ -0,0 +1,30 @@
+#include "whisker.h"
+
+#define CHECK(insn, ty, val, expected)                                         \
+    do {                                                                       \
+        ty a = val;                                                            \
+        int64_t result;                                                        \
+        __asm__(insn " %0, %1" : "=r"(result) : "f"(a));                       \
+        if (result == (expected)) {                                            \
+            whisker_write_uart(insn " " #val " is correct\n");                 \
+        } else {                                                               \
+            whisker_write_uart(insn " " #val " is wrong\n");                   \
+        }                                                                      \
+    } while (0)
+
+int main() {
+    CHECK("fclass.s", float, -1.0f / 0.0f, 1 << 0);
+    CHECK("fclass.s", float, -1.0f, 1 << 1);
+    CHECK("fclass.s", float, -0.0f, 1 << 3);
+    CHECK("fclass.s", float, 0.0f, 1 << 4);
+    CHECK("fclass.s", float, 1.0f, 1 << 6);
+    CHECK("fclass.s", float, 0.0f / 0.0f, 1 << 9);
+
+    CHECK("fclass.d", double, 1e-310, 1 << 5);
+    CHECK("fclass.d", double, 1.0 / 0.0, 1 << 7);
+
+    CHECK("fmv.x.w", float, -1.0f, (int32_t)0xBF800000);
+    CHECK("fmv.x.d", double, 1.0, 0x3FF0000000000000);
+
+    while(true) {}
+}
@@ -0,0 +1,46 @@
+#include "whisker.h"
+
+#define FLAG_INEXACT (1 << 0)
+#define FLAG_INVALID (1 << 4)
+#define RM_RTZ 1
+#define RM_RUP 3
+
+static void check(const char* name, int ok) {
+    whisker_write_uart(name);
+    whisker_write_uart(ok ? ": correct\n" : ": wrong\n");
+}
+
+int main() {
+    uint64_t val;
+
+    // fcsr only holds frm and fflags
+    __asm__ volatile("csrw fcsr, %1\n\tcsrr %0, fcsr" : "=r"(val) : "r"(-1));
+    check("fcsr upper bits are zero", val == 0xFF);
+
+    __asm__ volatile("csrw fcsr, zero");
+    __asm__ volatile("csrw frm, %0" : : "r"(RM_RUP));
+    __asm__ volatile("csrr %0, fcsr" : "=r"(val));
+    check("frm writes bits 7:5 of fcsr", val == (RM_RUP << 5));
+
+    __asm__ volatile("csrw fflags, %0" : : "r"(FLAG_INVALID | FLAG_INEXACT));
+    __asm__ volatile("csrr %0, fcsr" : "=r"(val));
+    check("fflags leaves frm alone", val == ((RM_RUP << 5) | FLAG_INVALID | FLAG_INEXACT));
+
+    __asm__ volatile("csrr %0, frm" : "=r"(val));
+    check("frm reads its field", val == RM_RUP);
+    __asm__ volatile("csrr %0, fflags" : "=r"(val));
+    check("fflags reads its field", val == (FLAG_INVALID | FLAG_INEXACT));
+
+    // the fields are masked to their width
+    __asm__ volatile("fsrm %0, %1" : "=r"(val) : "r"(0xF8 | RM_RTZ));
+    check("fsrm returns the old frm", val == RM_RUP);
+    __asm__ volatile("csrr %0, fcsr" : "=r"(val));
+    check("frm is 3 bits", val == ((RM_RTZ << 5) | FLAG_INVALID | FLAG_INEXACT));
+
+    __asm__ volatile("fsflags %0, zero" : "=r"(val));
+    check("fsflags returns the old flags", val == (FLAG_INVALID | FLAG_INEXACT));
+    __asm__ volatile("frcsr %0" : "=r"(val));
+    check("fsflags cleared the flags", val == (RM_RTZ << 5));
+
+    while(true) {}
+}
@@ -0,0 +1,35 @@
+#include "whisker.h"
+
+#define CHECK(name, expr)                                                      \
+    do {                                                                       \
+        if (expr) {                                                            \
+            whisker_write_uart(name " is correct\n");                          \
+        } else {                                                               \
+            whisker_write_uart(name " is wrong\n");                            \
+        }                                                                      \
+    } while (0)
+
+int main() {
+    volatile float f = -2.5f;
+    volatile double big = 1e20;
+    volatile int32_t w = -7;
+    volatile uint64_t lu = 0xFFFFFFFFFFFFFFFF;
+    int64_t result;
+
+    CHECK("fcvt.w.s", (int32_t)f == -2);
+    CHECK("fcvt.s.w", (float)w == -7.0f);
+    CHECK("fcvt.d.w", (double)w == -7.0);
+    CHECK("fcvt.d.lu", (double)lu == 18446744073709551616.0);
+
+    // out of range conversions saturate instead of being UB like in C
+    __asm__("fcvt.wu.s %0, %1, rtz" : "=r"(result) : "f"(f));
+    CHECK("fcvt.wu.s saturates", result == 0);
+    __asm__("fcvt.l.d %0, %1, rtz" : "=r"(result) : "f"(-big));
+    CHECK("fcvt.l.d saturates", result == INT64_MIN);
+    __asm__("fcvt.lu.d %0, %1, rtz" : "=r"(result) : "f"(big));
+    CHECK("fcvt.lu.d saturates", (uint64_t)result == UINT64_MAX);
+    __asm__("fcvt.wu.d %0, %1, rtz" : "=r"(result) : "f"(big));
+    CHECK("fcvt.wu.d sign extends", result == -1);
+
+    while(true) {}
+}
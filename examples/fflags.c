@@ -0,0 +1,36 @@
+#include "whisker.h"
+
+#define FLAG_INEXACT (1 << 0)
+#define FLAG_OVERFLOW (1 << 2)
+#define FLAG_DIV_ZERO (1 << 3)
+#define FLAG_INVALID (1 << 4)
+
+static uint64_t swap_fflags() {
+    uint64_t flags;
+    __asm__ volatile("csrrw %0, fcsr, zero" : "=r"(flags));
+    return flags & 0x1F;
+}
+
+#define CHECK(name, expr, expected)                                            \
+    do {                                                                       \
+        swap_fflags();                                                         \
+        expr;                                                                  \
+        if (swap_fflags() == (expected)) {                                     \
+            whisker_write_uart(name " is correct\n");                          \
+        } else {                                                               \
+            whisker_write_uart(name " is wrong\n");                            \
+        }                                                                      \
+    } while (0)
+
+int main() {
+    volatile double one = 1.0, three = 3.0, zero = 0.0, huge = 1e308;
+    volatile double result;
+
+    CHECK("exact", result = one + three, 0);
+    CHECK("inexact", result = one / three, FLAG_INEXACT);
+    CHECK("div by zero", result = one / zero, FLAG_DIV_ZERO);
+    CHECK("invalid", result = zero / zero, FLAG_INVALID);
+    CHECK("overflow", result = huge * huge, FLAG_OVERFLOW | FLAG_INEXACT);
+
+    while(true) {}
+}
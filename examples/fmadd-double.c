@@ -0,0 +1,22 @@
+#include "whisker.h"
+
+int main() {
+    double a = 2.0;
+    double b = 3.0;
+    double c = 4.0;
+    double result;
+
+    __asm__(
+        "fmadd.d %0, %1, %2, %3"
+        : "=f"(result)
+        : "f"(a), "f"(b), "f"(c)
+    );
+
+    if(result == 10) {
+        whisker_write_uart("fmadd.d is correct");
+    } else {
+        whisker_write_uart("fmadd.d is wrong");
+    }
+
+    while(true) {}
+}
@@ -0,0 +1,31 @@
+#include "whisker.h"
+
+static int64_t bits_of(float f) {
+    int64_t bits;
+    __asm__("fmv.x.w %0, %1" : "=r"(bits) : "f"(f));
+    return bits;
+}
+
+#define CHECK(insn, lhs, rhs, expected)                                        \
+    do {                                                                       \
+        float a = lhs, b = rhs, result;                                        \
+        __asm__(insn " %0, %1, %2" : "=f"(result) : "f"(a), "f"(b));           \
+        if (bits_of(result) == bits_of(expected)) {                            \
+            whisker_write_uart(insn " " #lhs " " #rhs " is correct\n");        \
+        } else {                                                               \
+            whisker_write_uart(insn " " #lhs " " #rhs " is wrong\n");          \
+        }                                                                      \
+    } while (0)
+
+int main() {
+    float nan = 0.0f / 0.0f;
+
+    CHECK("fmin.s", 1.0f, 2.0f, 1.0f);
+    CHECK("fmax.s", 1.0f, 2.0f, 2.0f);
+    CHECK("fmin.s", -0.0f, 0.0f, -0.0f);
+    CHECK("fmax.s", -0.0f, 0.0f, 0.0f);
+    CHECK("fmin.s", nan, 3.0f, 3.0f);
+    CHECK("fmax.s", 3.0f, nan, 3.0f);
+
+    while(true) {}
+}
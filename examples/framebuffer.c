@@ -0,0 +1,35 @@
+#include "whisker.h"
+
+// run with --framebuffer 320x240
+#define FB_BASE 0x50000000
+#define FB_WIDTH 320
+#define FB_HEIGHT 240
+#define FB ((volatile uint32_t*)FB_BASE)
+
+static void check(const char* name, int ok) {
+    whisker_write_uart(name);
+    whisker_write_uart(ok ? ": correct\n" : ": wrong\n");
+}
+
+int main() {
+    // red increases to the right, green increases downwards
+    for (int y = 0; y < FB_HEIGHT; y++) {
+        for (int x = 0; x < FB_WIDTH; x++) {
+            uint32_t red = x * 255 / (FB_WIDTH - 1);
+            uint32_t green = y * 255 / (FB_HEIGHT - 1);
+            FB[y * FB_WIDTH + x] = (red << 16) | (green << 8) | 0x40;
+        }
+    }
+
+    check("pixels read back", FB[0] == 0x000040 && FB[FB_WIDTH * FB_HEIGHT - 1] == 0xFFFF40);
+    check("rows are packed", FB[FB_WIDTH] == ((0 << 16) | (1 * 255 / (FB_HEIGHT - 1) << 8) | 0x40));
+
+    // single bytes land in the right channel
+    volatile uint8_t* bytes = (volatile uint8_t*)FB_BASE;
+    bytes[2] = 0x80;
+    check("byte writes are little endian", FB[0] == 0x800040);
+
+    whisker_write_uart("the window should show a gradient\n");
+    while (true) {
+    }
+}
@@ -0,0 +1,72 @@
+#include "whisker.h"
+
+#define MSTATUS_FS (3 << 13)
+#define FS_OFF (0 << 13)
+#define FS_INITIAL (1 << 13)
+#define FS_CLEAN (2 << 13)
+#define FS_DIRTY (3 << 13)
+#define MSTATUS_SD (1ull << 63)
+#define ILLEGAL_INSTRUCTION 2
+
+static volatile uint64_t trap_cause = 0;
+
+static void check(const char* name, int ok) {
+    whisker_write_uart(name);
+    whisker_write_uart(ok ? ": correct\n" : ": wrong\n");
+}
+
+__attribute__((interrupt("machine"), aligned(4))) static void handler(void) {
+    uint64_t mepc;
+    __asm__ volatile("csrr %0, mcause" : "=r"(trap_cause));
+    __asm__ volatile("csrr %0, mepc" : "=r"(mepc));
+    __asm__ volatile("csrw mepc, %0" : : "r"(mepc + 4));
+}
+
+static void set_fs(uint64_t fs) {
+    __asm__ volatile("csrc mstatus, %0" : : "r"(MSTATUS_FS));
+    __asm__ volatile("csrs mstatus, %0" : : "r"(fs));
+}
+
+static uint64_t status(void) {
+    uint64_t val;
+    __asm__ volatile("csrr %0, mstatus" : "=r"(val));
+    return val;
+}
+
+int main() {
+    __asm__ volatile("csrw mtvec, %0" : : "r"(handler));
+
+    // with FS off float instructions and CSRs don't exist
+    set_fs(FS_OFF);
+    trap_cause = 0;
+    __asm__ volatile(".option push\n.option norvc\nfmv.w.x ft0, zero\n.option pop" : : : "ft0");
+    check("float instruction with FS off", trap_cause == ILLEGAL_INSTRUCTION);
+    trap_cause = 0;
+    uint64_t val;
+    __asm__ volatile("csrr %0, fcsr" : "=r"(val));
+    check("fcsr with FS off", trap_cause == ILLEGAL_INSTRUCTION);
+    check("FS stays off", (status() & MSTATUS_FS) == FS_OFF);
+
+    // a store reads the float registers without changing them
+    uint64_t scratch;
+    set_fs(FS_CLEAN);
+    __asm__ volatile(".option push\n.option norvc\nfsd ft0, 0(%0)\n.option pop" : : "r"(&scratch) : "memory");
+    check("float store leaves FS clean", (status() & MSTATUS_FS) == FS_CLEAN);
+    check("SD is clear while clean", !(status() & MSTATUS_SD));
+
+    __asm__ volatile(".option push\n.option norvc\nfmv.w.x ft0, zero\n.option pop" : : : "ft0");
+    check("float register write sets FS dirty", (status() & MSTATUS_FS) == FS_DIRTY);
+    check("SD follows FS", status() & MSTATUS_SD);
+
+    set_fs(FS_CLEAN);
+    __asm__ volatile("csrw fflags, zero");
+    check("fflags write sets FS dirty", (status() & MSTATUS_FS) == FS_DIRTY);
+
+    // comparisons only write an integer register, but raising a flag still dirties fcsr
+    __asm__ volatile("fmv.d.x ft0, %0" : : "r"(0x7FF8000000000000ull) : "ft0");
+    set_fs(FS_INITIAL);
+    __asm__ volatile("flt.d t0, ft0, ft0" : : : "t0");
+    check("raising a flag sets FS dirty", (status() & MSTATUS_FS) == FS_DIRTY);
+
+    while(true) {}
+}
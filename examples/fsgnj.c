@@ -0,0 +1,24 @@
+#include "whisker.h"
+
+#define CHECK(insn, ty, lhs, rhs, expected)                                    \
+    do {                                                                       \
+        ty a = lhs, b = rhs, result;                                           \
+        __asm__(insn " %0, %1, %2" : "=f"(result) : "f"(a), "f"(b));           \
+        if (result == (expected)) {                                            \
+            whisker_write_uart(insn " is correct\n");                          \
+        } else {                                                               \
+            whisker_write_uart(insn " is wrong\n");                            \
+        }                                                                      \
+    } while (0)
+
+int main() {
+    CHECK("fsgnj.s", float, 3, -1, -3);
+    CHECK("fsgnjn.s", float, 3, -1, 3);
+    CHECK("fsgnjx.s", float, -3, -1, 3);
+
+    CHECK("fsgnj.d", double, 3, -1, -3);
+    CHECK("fsgnjn.d", double, 3, -1, 3);
+    CHECK("fsgnjx.d", double, -3, -1, 3);
+
+    while(true) {}
+}
@@ -0,0 +1,28 @@
+#include "whisker.h"
+
+#define CHECK(insn, ty, expected)                                              \
+    do {                                                                       \
+        ty a = 2, b = 3, c = 4, result;                                        \
+        __asm__(insn " %0, %1, %2, %3"                                         \
+                : "=f"(result)                                                 \
+                : "f"(a), "f"(b), "f"(c));                                     \
+        if (result == (expected)) {                                            \
+            whisker_write_uart(insn " is correct\n");                          \
+        } else {                                                               \
+            whisker_write_uart(insn " is wrong\n");                            \
+        }                                                                      \
+    } while (0)
+
+int main() {
+    CHECK("fmadd.s", float, 10);
+    CHECK("fmsub.s", float, 2);
+    CHECK("fnmsub.s", float, -2);
+    CHECK("fnmadd.s", float, -10);
+
+    CHECK("fmadd.d", double, 10);
+    CHECK("fmsub.d", double, 2);
+    CHECK("fnmsub.d", double, -2);
+    CHECK("fnmadd.d", double, -10);
+
+    while(true) {}
+}
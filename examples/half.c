@@ -0,0 +1,47 @@
+#include "whisker.h"
+
+#define CHECK(name, expected, actual)                                          \
+    do {                                                                       \
+        if ((actual) == (expected)) {                                          \
+            whisker_write_uart(name " is correct\n");                          \
+        } else {                                                               \
+            whisker_write_uart(name " is wrong\n");                            \
+        }                                                                      \
+    } while (0)
+
+// half precision values are passed around as raw bits so this builds
+// without compiler support for _Float16
+static uint64_t half_op(float lhs, float rhs) {
+    uint64_t result;
+    __asm__(".option push\n"
+            ".option arch, +zfh\n"
+            "fcvt.h.s fa0, %1\n"
+            "fcvt.h.s fa1, %2\n"
+            "fadd.h fa0, fa0, fa1\n"
+            "fmv.x.h %0, fa0\n"
+            ".option pop"
+            : "=r"(result)
+            : "f"(lhs), "f"(rhs)
+            : "fa0", "fa1");
+    return result;
+}
+
+int main() {
+    // 1.5 + 2.0 = 3.5 = 0x4300, sign extended by fmv.x.h
+    CHECK("fadd.h", 0x4300, half_op(1.5f, 2.0f));
+    // -1.0 + -1.0 = -2.0 = 0xC000
+    CHECK("fadd.h negative", (uint64_t)(int64_t)(int16_t)0xC000, half_op(-1.0f, -1.0f));
+
+    float back;
+    __asm__(".option push\n"
+            ".option arch, +zfh\n"
+            "fcvt.h.s fa0, %1\n"
+            "fcvt.s.h %0, fa0\n"
+            ".option pop"
+            : "=f"(back)
+            : "f"(0.25f)
+            : "fa0");
+    CHECK("fcvt.s.h", 0.25f, back);
+
+    while(true) {}
+}
@@ -0,0 +1,57 @@
+#include "whisker.h"
+
+#define EVENT_LOAD 6
+#define EVENT_STORE 7
+#define EVENT_BRANCH 8
+#define EVENT_BRANCH_TAKEN 9
+
+static void check(const char* name, int ok) {
+    whisker_write_uart(name);
+    whisker_write_uart(ok ? ": correct\n" : ": wrong\n");
+}
+
+int main() {
+    __asm__ volatile("csrw mhpmevent3, %0" : : "r"(EVENT_LOAD));
+    __asm__ volatile("csrw mhpmevent4, %0" : : "r"(EVENT_STORE));
+    __asm__ volatile("csrw mhpmevent5, %0" : : "r"(EVENT_BRANCH));
+    __asm__ volatile("csrw mhpmevent6, %0" : : "r"(EVENT_BRANCH_TAKEN));
+
+    uint64_t event;
+    __asm__ volatile("csrw mhpmevent7, %1\n\tcsrr %0, mhpmevent7" : "=r"(event) : "r"(0x1234));
+    check("unknown events read back as zero", event == 0);
+
+    uint64_t loads, stores, branches, taken;
+    uint64_t value = 0;
+    // 2 loads, 1 store, 2 branches where only the first is taken
+    __asm__ volatile(
+        ".option push\n"
+        ".option norvc\n"
+        "csrw mhpmcounter3, zero\n"
+        "csrw mhpmcounter4, zero\n"
+        "csrw mhpmcounter5, zero\n"
+        "csrw mhpmcounter6, zero\n"
+        "ld t0, 0(%4)\n"
+        "ld t0, 0(%4)\n"
+        "sd t0, 0(%4)\n"
+        "beqz zero, 1f\n"
+        "1: bnez zero, 1f\n"
+        "1: csrr %0, mhpmcounter3\n"
+        "csrr %1, mhpmcounter4\n"
+        "csrr %2, mhpmcounter5\n"
+        "csrr %3, mhpmcounter6\n"
+        ".option pop\n"
+        : "=&r"(loads), "=&r"(stores), "=&r"(branches), "=&r"(taken)
+        : "r"(&value)
+        : "t0", "memory");
+    check("loads counted", loads == 2);
+    check("stores counted", stores == 1);
+    check("branches counted", branches == 2);
+    check("taken branches counted", taken == 1);
+
+    // the user mode views read the same counters
+    uint64_t hpm;
+    __asm__ volatile("csrr %0, hpmcounter3" : "=r"(hpm));
+    check("hpmcounter3 mirrors mhpmcounter3", hpm == loads);
+
+    while(true) {}
+}
@@ -0,0 +1,26 @@
+#include "whisker.h"
+
+// a custom-0 opcode with some payload bits, whisker doesn't implement any custom instructions
+#define CUSTOM_INSN 0x1234500B
+
+__attribute__((interrupt("machine"), aligned(4))) static void trap_handler(void) {
+    uint64_t mcause;
+    uint64_t mtval;
+    __asm__ volatile("csrr %0, mcause" : "=r"(mcause));
+    __asm__ volatile("csrr %0, mtval" : "=r"(mtval));
+
+    if (mcause == 2 && mtval == CUSTOM_INSN) {
+        whisker_write_uart("illegal instruction trap is correct\n");
+    } else {
+        whisker_write_uart("illegal instruction trap is wrong\n");
+    }
+    while(true) {}
+}
+
+int main() {
+    __asm__ volatile("csrw mtvec, %0" : : "r"(trap_handler));
+    __asm__ volatile(".word %0" : : "i"(CUSTOM_INSN));
+
+    whisker_write_uart("custom opcode did not trap\n");
+    while(true) {}
+}
@@ -0,0 +1,60 @@
+#include "whisker.h"
+
+#define DRAM_BASE 0x80000000
+
+volatile uint32_t* reserved_word = (volatile uint32_t*)(DRAM_BASE + 0x2048);
+// far enough away to be in a different reservation granule
+volatile uint32_t* other_word = (volatile uint32_t*)(DRAM_BASE + 0x2148);
+
+static uint32_t load_reserved(volatile uint32_t* addr) {
+    uint32_t val;
+    __asm__ volatile("lr.w %0, (%1)" : "=r"(val) : "r"(addr) : "memory");
+    return val;
+}
+
+static uint64_t store_conditional(volatile uint32_t* addr, uint32_t val) {
+    uint64_t code;
+    __asm__ volatile("sc.w %0, %2, (%1)" : "=r"(code) : "r"(addr), "r"(val) : "memory");
+    return code;
+}
+
+static void check(const char* name, uint64_t code, uint64_t expected) {
+    whisker_write_uart(name);
+    whisker_write_uart(code == expected ? ": correct\n" : ": wrong\n");
+}
+
+int main() {
+    // the plain case, the SC succeeds and writes
+    *reserved_word = 1;
+    load_reserved(reserved_word);
+    check("sc after lr", store_conditional(reserved_word, 2), 0);
+    check("sc wrote", *reserved_word, 2);
+
+    // the reservation is consumed by the first SC
+    check("second sc", store_conditional(reserved_word, 3), 1);
+    check("failed sc didn't write", *reserved_word, 2);
+
+    // ABA: the value is the same again but the reservation was still broken
+    load_reserved(reserved_word);
+    *reserved_word = 5;
+    *reserved_word = 2;
+    check("sc after aba", store_conditional(reserved_word, 4), 1);
+
+    // an AMO is a store too
+    load_reserved(reserved_word);
+    __asm__ volatile("amoadd.w zero, zero, (%0)" : : "r"(reserved_word) : "memory");
+    check("sc after amo", store_conditional(reserved_word, 4), 1);
+
+    // the reservation doesn't cover a different granule
+    load_reserved(reserved_word);
+    check("sc to another address", store_conditional(other_word, 4), 1);
+    // and the mismatched SC still consumed it
+    check("sc after mismatched sc", store_conditional(reserved_word, 4), 1);
+
+    // a newer LR replaces the old reservation
+    load_reserved(other_word);
+    load_reserved(reserved_word);
+    check("sc to replaced reservation", store_conditional(other_word, 4), 1);
+
+    while(true) {}
+}
@@ -0,0 +1,26 @@
+#include "whisker.h"
+
+#define MENVCFG_FIOM 1
+
+static void check(const char* name, int ok) {
+    whisker_write_uart(name);
+    whisker_write_uart(ok ? ": correct\n" : ": wrong\n");
+}
+
+int main() {
+    uint64_t val;
+
+    __asm__ volatile("csrw mscratch, %1\n\tcsrr %0, mscratch" : "=r"(val) : "r"(0x1234567890ABCDEF));
+    check("mscratch holds any value", val == 0x1234567890ABCDEF);
+
+    __asm__ volatile("csrr %0, mconfigptr" : "=r"(val));
+    check("mconfigptr is zero", val == 0);
+
+    __asm__ volatile("csrw menvcfg, %1\n\tcsrr %0, menvcfg" : "=r"(val) : "r"(-1));
+    check("menvcfg only has FIOM", val == MENVCFG_FIOM);
+
+    __asm__ volatile("csrw mseccfg, %1\n\tcsrr %0, mseccfg" : "=r"(val) : "r"(-1));
+    check("mseccfg is zero", val == 0);
+
+    while(true) {}
+}
@@ -0,0 +1,14 @@
+#include "whisker.h"
+
+int main() {
+    uint64_t hartid;
+    __asm__ volatile("csrr %0, mhartid" : "=r"(hartid));
+
+    // whisker only has hart 0
+    if (hartid == 0) {
+        whisker_write_uart("mhartid is correct\n");
+    } else {
+        whisker_write_uart("mhartid is wrong\n");
+    }
+    while(true) {}
+}
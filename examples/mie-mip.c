@@ -0,0 +1,29 @@
+#include "whisker.h"
+
+#define MIP_MSIP (1ull << 3)
+#define MIE_MSIE (1ull << 3)
+#define MIE_MTIE (1ull << 7)
+#define MIE_MEIE (1ull << 11)
+
+static void check(const char* name, int ok) {
+    whisker_write_uart(name);
+    whisker_write_uart(ok ? ": correct\n" : ": wrong\n");
+}
+
+int main() {
+    uint64_t val;
+
+    // only the bits of interrupts that exist stick
+    __asm__ volatile("csrw mie, %0" : : "r"(-1ull));
+    __asm__ volatile("csrr %0, mie" : "=r"(val));
+    check("machine interrupt enables are writable", (val & (MIE_MSIE | MIE_MTIE | MIE_MEIE)) == (MIE_MSIE | MIE_MTIE | MIE_MEIE));
+    check("reserved mie bits are zero", (val & ~(MIE_MSIE | MIE_MTIE | MIE_MEIE | 0x222ull)) == 0);
+    __asm__ volatile("csrw mie, zero");
+
+    // MSIP is driven by the interrupt controller, software can't set it through mip
+    __asm__ volatile("csrs mip, %0" : : "r"(MIP_MSIP));
+    __asm__ volatile("csrr %0, mip" : "=r"(val));
+    check("MSIP is read-only in mip", !(val & MIP_MSIP));
+
+    while(true) {}
+}
@@ -0,0 +1,46 @@
+#include "whisker.h"
+
+#define MISA_LETTER(c) (1ull << ((c) - 'A'))
+#define ILLEGAL_INSTRUCTION 2
+
+__attribute__((interrupt("machine"), aligned(4))) static void trap_handler(void) {
+    uint64_t mcause;
+    __asm__ volatile("csrr %0, mcause" : "=r"(mcause));
+
+    if (mcause == ILLEGAL_INSTRUCTION) {
+        whisker_write_uart("mul trapped with M disabled, misa is correct\n");
+    } else {
+        whisker_write_uart("mul trap is wrong\n");
+    }
+    while(true) {}
+}
+
+static void check(const char* name, int ok) {
+    whisker_write_uart(name);
+    whisker_write_uart(ok ? ": correct\n" : ": wrong\n");
+}
+
+int main() {
+    __asm__ volatile("csrw mtvec, %0" : : "r"(trap_handler));
+
+    uint64_t misa;
+    __asm__ volatile("csrr %0, misa" : "=r"(misa));
+    check("misa reports rv64", (misa >> 62) == 2);
+    check("misa reports I", misa & MISA_LETTER('I'));
+    check("misa reports M", misa & MISA_LETTER('M'));
+
+    // I can't be turned off
+    __asm__ volatile("csrc misa, %0" : : "r"(MISA_LETTER('I')));
+    __asm__ volatile("csrr %0, misa" : "=r"(misa));
+    check("I stays enabled", misa & MISA_LETTER('I'));
+
+    __asm__ volatile("csrc misa, %0" : : "r"(MISA_LETTER('M')));
+    __asm__ volatile("csrr %0, misa" : "=r"(misa));
+    check("M can be disabled", !(misa & MISA_LETTER('M')));
+
+    uint64_t res;
+    __asm__ volatile("mul %0, %1, %2" : "=r"(res) : "r"(3), "r"(4));
+
+    whisker_write_uart("mul executed with M disabled\n");
+    while(true) {}
+}
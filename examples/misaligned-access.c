@@ -0,0 +1,35 @@
+#include "whisker.h"
+
+// run with --misaligned=trap to take the trap path, by default the load is emulated
+
+#define LOAD_ADDR_MISALIGNED 4
+
+static volatile uint8_t buffer[8] __attribute__((aligned(8))) = {0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88};
+
+__attribute__((interrupt("machine"), aligned(4))) static void trap_handler(void) {
+    uint64_t mcause;
+    uint64_t mtval;
+    __asm__ volatile("csrr %0, mcause" : "=r"(mcause));
+    __asm__ volatile("csrr %0, mtval" : "=r"(mtval));
+
+    if (mcause == LOAD_ADDR_MISALIGNED && mtval == (uint64_t)&buffer[1]) {
+        whisker_write_uart("misaligned load trapped correctly\n");
+    } else {
+        whisker_write_uart("misaligned load trap is wrong\n");
+    }
+    while(true) {}
+}
+
+int main() {
+    __asm__ volatile("csrw mtvec, %0" : : "r"(trap_handler));
+
+    uint32_t val;
+    __asm__ volatile("lw %0, 0(%1)" : "=r"(val) : "r"(&buffer[1]));
+
+    if (val == 0x55443322) {
+        whisker_write_uart("misaligned load emulated correctly\n");
+    } else {
+        whisker_write_uart("misaligned load emulated wrong\n");
+    }
+    while(true) {}
+}
@@ -0,0 +1,28 @@
+#include "whisker.h"
+
+#define MSTATUS_MIE (1 << 3)
+#define MSTATUS_MPIE (1 << 7)
+#define MSTATUS_MPP (3 << 11)
+#define MSTATUS_MPP_M (3 << 11)
+
+static void after_mret(void) {
+    uint64_t mstatus;
+    __asm__ volatile("csrr %0, mstatus" : "=r"(mstatus));
+
+    // MIE comes from MPIE, MPIE is set and MPP is left holding U-mode
+    if ((mstatus & MSTATUS_MIE) && (mstatus & MSTATUS_MPIE) && (mstatus & MSTATUS_MPP) == 0) {
+        whisker_write_uart("mret is correct\n");
+    } else {
+        whisker_write_uart("mret restored mstatus wrong\n");
+    }
+    while(true) {}
+}
+
+int main() {
+    __asm__ volatile("csrw mepc, %0" : : "r"(after_mret));
+    __asm__ volatile("csrw mstatus, %0" : : "r"(MSTATUS_MPIE | MSTATUS_MPP_M));
+    __asm__ volatile("mret");
+
+    whisker_write_uart("mret did not jump to mepc\n");
+    while(true) {}
+}
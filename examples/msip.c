@@ -0,0 +1,50 @@
+#include "whisker.h"
+
+#define CLINT_MSIP ((volatile uint32_t*)0x02000000)
+#define MSTATUS_MIE (1 << 3)
+#define MIE_MSIE (1 << 3)
+#define MACHINE_SOFTWARE_INTERRUPT ((1ull << 63) | 3)
+
+static volatile uint64_t software_cause = 0;
+static volatile uint64_t software_interrupts = 0;
+
+static void check(const char* name, int ok) {
+    whisker_write_uart(name);
+    whisker_write_uart(ok ? ": correct\n" : ": wrong\n");
+}
+
+__attribute__((interrupt("machine"), aligned(4))) static void handler(void) {
+    __asm__ volatile("csrr %0, mcause" : "=r"(software_cause));
+    software_interrupts++;
+    // the interrupt stays pending until msip is cleared
+    *CLINT_MSIP = 0;
+}
+
+int main() {
+    __asm__ volatile("csrw mtvec, %0" : : "r"(handler));
+
+    uint64_t mip;
+    *CLINT_MSIP = 1;
+    check("msip reads back", *CLINT_MSIP == 1);
+    __asm__ volatile("csrr %0, mip" : "=r"(mip));
+    check("msip sets mip.MSIP", mip & MIE_MSIE);
+
+    // mip.MSIP is read-only, only the CLINT can clear it
+    __asm__ volatile("csrc mip, %0" : : "r"(MIE_MSIE));
+    __asm__ volatile("csrr %0, mip" : "=r"(mip));
+    check("mip.MSIP is read-only", mip & MIE_MSIE);
+
+    __asm__ volatile("csrs mie, %0" : : "r"(MIE_MSIE));
+    __asm__ volatile("csrs mstatus, %0" : : "r"(MSTATUS_MIE));
+    check("software interrupt cause", software_cause == MACHINE_SOFTWARE_INTERRUPT);
+    check("software interrupt taken once", software_interrupts == 1);
+
+    __asm__ volatile("csrr %0, mip" : "=r"(mip));
+    check("clearing msip clears mip.MSIP", !(mip & MIE_MSIE));
+
+    // only bit 0 is writable
+    *CLINT_MSIP = 2;
+    check("msip upper bits are zero", *CLINT_MSIP == 0);
+
+    while(true) {}
+}
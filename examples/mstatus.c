@@ -0,0 +1,48 @@
+#include "whisker.h"
+
+#define MSTATUS_MIE (1ull << 3)
+#define MSTATUS_MPIE (1ull << 7)
+#define MSTATUS_MPP (3ull << 11)
+#define MSTATUS_MPP_H (2ull << 11)
+#define MSTATUS_FS (3ull << 13)
+#define MSTATUS_SD (1ull << 63)
+
+static void check(const char* name, int ok) {
+    whisker_write_uart(name);
+    whisker_write_uart(ok ? ": correct\n" : ": wrong\n");
+}
+
+__attribute__((interrupt("machine"), aligned(4))) static void trap_handler(void) {
+    uint64_t mstatus;
+    __asm__ volatile("csrr %0, mstatus" : "=r"(mstatus));
+
+    // trap entry moves MIE into MPIE, clears MIE and records M-mode in MPP
+    check("trap entry clears MIE", !(mstatus & MSTATUS_MIE));
+    check("trap entry saves MIE in MPIE", mstatus & MSTATUS_MPIE);
+    check("trap entry saves M in MPP", (mstatus & MSTATUS_MPP) == MSTATUS_MPP);
+    while(true) {}
+}
+
+int main() {
+    __asm__ volatile("csrw mtvec, %0" : : "r"(trap_handler));
+
+    uint64_t mstatus;
+
+    // FS is writable and SD follows it
+    __asm__ volatile("csrs mstatus, %0" : : "r"(MSTATUS_FS));
+    __asm__ volatile("csrr %0, mstatus" : "=r"(mstatus));
+    check("FS is writable", (mstatus & MSTATUS_FS) == MSTATUS_FS);
+    check("SD is set when FS is dirty", mstatus & MSTATUS_SD);
+
+    // there's no H-mode, so MPP can't be set to its encoding
+    __asm__ volatile("csrc mstatus, %0" : : "r"(MSTATUS_MPP));
+    __asm__ volatile("csrs mstatus, %0" : : "r"(MSTATUS_MPP_H));
+    __asm__ volatile("csrr %0, mstatus" : "=r"(mstatus));
+    check("MPP can't hold an unsupported mode", (mstatus & MSTATUS_MPP) == MSTATUS_MPP);
+
+    __asm__ volatile("csrs mstatus, %0" : : "r"(MSTATUS_MIE));
+    __asm__ volatile("ecall");
+
+    whisker_write_uart("ecall did not trap\n");
+    while(true) {}
+}
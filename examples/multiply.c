@@ -0,0 +1,49 @@
+#include "whisker.h"
+
+#define CHECK(name, insn, lhs, rhs, expected)                                  \
+    do {                                                                       \
+        int64_t result;                                                        \
+        __asm__(insn " %0, %1, %2" : "=r"(result) : "r"(lhs), "r"(rhs));       \
+        if (result == (int64_t)(expected)) {                                   \
+            whisker_write_uart(name " is correct\n");                          \
+        } else {                                                               \
+            whisker_write_uart(name " is wrong\n");                            \
+        }                                                                      \
+    } while (0)
+
+int main() {
+    int64_t min = INT64_MIN;
+    int64_t min_w = INT32_MIN;
+    int64_t neg_one = -1;
+    int64_t zero = 0;
+    int64_t seven = 7;
+    int64_t big = 0x80000000;
+
+    CHECK("mul", "mul", seven, neg_one, -7);
+    CHECK("mulh", "mulh", min, neg_one, 0);
+    CHECK("mulhu", "mulhu", neg_one, neg_one, -2);
+    CHECK("mulhsu", "mulhsu", neg_one, neg_one, -1);
+    CHECK("mulw", "mulw", big, neg_one, INT32_MIN);
+
+    // division by zero
+    CHECK("div by zero", "div", seven, zero, -1);
+    CHECK("divu by zero", "divu", seven, zero, -1);
+    CHECK("rem by zero", "rem", seven, zero, 7);
+    CHECK("remu by zero", "remu", seven, zero, 7);
+    CHECK("divw by zero", "divw", seven, zero, -1);
+    CHECK("divuw by zero", "divuw", seven, zero, -1);
+    CHECK("remw by zero", "remw", seven, zero, 7);
+    CHECK("remuw by zero", "remuw", big, zero, INT32_MIN);
+
+    // signed overflow
+    CHECK("div overflow", "div", min, neg_one, INT64_MIN);
+    CHECK("rem overflow", "rem", min, neg_one, 0);
+    CHECK("divw overflow", "divw", min_w, neg_one, INT32_MIN);
+    CHECK("remw overflow", "remw", min_w, neg_one, 0);
+
+    // unsigned word results are sign extended
+    CHECK("divuw", "divuw", neg_one, seven - 6, -1);
+    CHECK("remuw", "remuw", big, seven + 0x80000000, INT32_MIN);
+
+    while(true) {}
+}
@@ -0,0 +1,79 @@
+#include "whisker.h"
+
+#define MSTATUS_MPP (3 << 11)
+#define MSTATUS_MPP_S (1 << 11)
+#define ILLEGAL_INSTRUCTION 2
+#define ECALL_SMODE 9
+#define ECALL_MMODE 11
+
+static int depth = 0;
+
+static void check(const char* name, int ok) {
+    whisker_write_uart(name);
+    whisker_write_uart(ok ? ": correct\n" : ": wrong\n");
+}
+
+static void skip_instruction(void) {
+    uint64_t mepc;
+    __asm__ volatile("csrr %0, mepc" : "=r"(mepc));
+    __asm__ volatile("csrw mepc, %0" : : "r"(mepc + 4));
+}
+
+__attribute__((interrupt("machine"), aligned(4))) static void trap_handler(void) {
+    uint64_t mcause;
+    __asm__ volatile("csrr %0, mcause" : "=r"(mcause));
+
+    if (depth > 0) {
+        // the nested trap, taken from inside this handler
+        check("nested trap came from M-mode", mcause == ECALL_MMODE);
+        skip_instruction();
+        return;
+    }
+
+    if (mcause == ECALL_SMODE) {
+        // the nested trap clobbered mepc and MPP, they have to be saved like a real handler would
+        uint64_t mepc;
+        uint64_t mstatus;
+        __asm__ volatile("csrr %0, mepc" : "=r"(mepc));
+        __asm__ volatile("csrr %0, mstatus" : "=r"(mstatus));
+
+        depth++;
+        __asm__ volatile("ecall");
+        depth--;
+
+        uint64_t clobbered;
+        __asm__ volatile("csrr %0, mstatus" : "=r"(clobbered));
+        check("nested trap overwrote MPP", (clobbered & MSTATUS_MPP) != (mstatus & MSTATUS_MPP));
+
+        __asm__ volatile("csrw mepc, %0" : : "r"(mepc + 4));
+        __asm__ volatile("csrw mstatus, %0" : : "r"(mstatus));
+        return;
+    }
+
+    check("back in S-mode after the outer trap", mcause == ILLEGAL_INSTRUCTION);
+    while(true) {}
+}
+
+static void in_supervisor(void) {
+    __asm__ volatile("ecall");
+    // only M-mode can read mstatus, so this traps if the outer handler returned to S-mode
+    uint64_t val;
+    __asm__ volatile("csrr %0, mstatus" : "=r"(val));
+
+    whisker_write_uart("outer trap returned to the wrong mode\n");
+    while(true) {}
+}
+
+int main() {
+    __asm__ volatile("csrw mtvec, %0" : : "r"(trap_handler));
+    // let S and U-mode access all of memory
+    __asm__ volatile("csrw pmpaddr0, %0" : : "r"(-1));
+    __asm__ volatile("csrw pmpcfg0, %0" : : "r"(0x1f));
+
+    __asm__ volatile("csrw mepc, %0" : : "r"(in_supervisor));
+    __asm__ volatile("csrc mstatus, %0" : : "r"(MSTATUS_MPP));
+    __asm__ volatile("csrs mstatus, %0" : : "r"(MSTATUS_MPP_S));
+    __asm__ volatile("mret");
+
+    while(true) {}
+}
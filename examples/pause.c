@@ -0,0 +1,16 @@
+#include "whisker.h"
+
+// older assemblers don't know the pause mnemonic, it's fence w, 0
+#define PAUSE ".word 0x0100000F\n"
+
+int main() {
+    uint64_t count = 0;
+    // pause is a hint, a spin-wait loop using it behaves the same as without it
+    for (int i = 0; i < 100; i++) {
+        __asm__ volatile(PAUSE);
+        count++;
+    }
+    whisker_write_uart(count == 100 ? "pause loop: correct\n" : "pause loop: wrong\n");
+
+    while(true) {}
+}
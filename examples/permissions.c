@@ -0,0 +1,52 @@
+#include "whisker.h"
+
+// the bootrom is read-only unless whisker is run with --writable-bootrom, device registers are never executable
+#define BOOTROM_BASE 0x1000
+#define UART_BASE 0x10000000
+#define INSTRUCTION_ACCESS_FAULT 1
+#define STORE_ACCESS_FAULT 7
+
+static int step = 0;
+
+static void check(const char* name, int ok) {
+    whisker_write_uart(name);
+    whisker_write_uart(ok ? ": correct\n" : ": wrong\n");
+}
+
+__attribute__((interrupt("machine"), aligned(4))) static void trap_handler(void) {
+    uint64_t mcause;
+    uint64_t mtval;
+    __asm__ volatile("csrr %0, mcause" : "=r"(mcause));
+    __asm__ volatile("csrr %0, mtval" : "=r"(mtval));
+
+    if (step == 0) {
+        check("storing to the bootrom faults", mcause == STORE_ACCESS_FAULT && mtval == BOOTROM_BASE);
+    } else {
+        check("fetching from the UART faults", mcause == INSTRUCTION_ACCESS_FAULT && mtval == UART_BASE);
+        while(true) {}
+    }
+    step++;
+
+    // skip the faulting instruction
+    uint64_t mepc;
+    __asm__ volatile("csrr %0, mepc" : "=r"(mepc));
+    __asm__ volatile("csrw mepc, %0" : : "r"(mepc + 4));
+}
+
+int main() {
+    __asm__ volatile("csrw mtvec, %0" : : "r"(trap_handler));
+
+    volatile uint32_t* bootrom = (volatile uint32_t*)BOOTROM_BASE;
+    uint32_t first = *bootrom;
+    check("the bootrom can be read", first != 0);
+
+    // the trap handler skips 4 bytes, so this can't be compressed
+    __asm__ volatile(".option push\n.option norvc\nsw zero, 0(%0)\n.option pop" : : "r"(bootrom) : "memory");
+    check("the bootrom wasn't changed", *bootrom == first);
+
+    // jumping to a device window faults on the fetch, the handler ends the test from there
+    ((void (*)(void))UART_BASE)();
+
+    whisker_write_uart("fetching from the UART did not fault\n");
+    while(true) {}
+}
@@ -0,0 +1,39 @@
+#include "whisker.h"
+
+#define PLIC_BASE 0x0C000000
+#define PLIC_PRIORITY(source) ((volatile uint32_t*)(PLIC_BASE + 4 * (source)))
+#define PLIC_PENDING ((volatile uint32_t*)(PLIC_BASE + 0x1000))
+#define PLIC_ENABLE(context) ((volatile uint32_t*)(PLIC_BASE + 0x2000 + 0x80 * (context)))
+#define PLIC_THRESHOLD(context) ((volatile uint32_t*)(PLIC_BASE + 0x200000 + 0x1000 * (context)))
+#define PLIC_CLAIM(context) ((volatile uint32_t*)(PLIC_BASE + 0x200004 + 0x1000 * (context)))
+#define MIP_SEIP (1 << 9)
+#define MIP_MEIP (1 << 11)
+
+static void check(const char* name, int ok) {
+    whisker_write_uart(name);
+    whisker_write_uart(ok ? ": correct\n" : ": wrong\n");
+}
+
+int main() {
+    // no device is raising anything yet, so this only checks the registers
+    *PLIC_PRIORITY(10) = 0xFF;
+    check("priorities are 3 bits", *PLIC_PRIORITY(10) == 7);
+    *PLIC_PRIORITY(0) = 1;
+    check("source 0 has no priority", *PLIC_PRIORITY(0) == 0);
+
+    *PLIC_ENABLE(0) = 0xFFFFFFFF;
+    check("source 0 can't be enabled", *PLIC_ENABLE(0) == 0xFFFFFFFE);
+    check("enables are per context", *PLIC_ENABLE(1) == 0);
+
+    *PLIC_THRESHOLD(0) = 3;
+    check("threshold reads back", *PLIC_THRESHOLD(0) == 3);
+
+    check("nothing pending", *PLIC_PENDING == 0);
+    check("claim with nothing pending", *PLIC_CLAIM(0) == 0);
+
+    uint64_t mip;
+    __asm__ volatile("csrr %0, mip" : "=r"(mip));
+    check("no external interrupts", !(mip & (MIP_MEIP | MIP_SEIP)));
+
+    while(true) {}
+}
@@ -0,0 +1,80 @@
+#include "whisker.h"
+
+#define PMP_R (1 << 0)
+#define PMP_W (1 << 1)
+#define PMP_X (1 << 2)
+#define PMP_TOR (1 << 3)
+#define PMP_NA4 (2 << 3)
+#define PMP_NAPOT (3 << 3)
+#define PMP_L (1 << 7)
+
+#define MSTATUS_MPP (3 << 11)
+#define MSTATUS_MPRV (1 << 17)
+#define LOAD_ACCESS_FAULT 5
+#define STORE_ACCESS_FAULT 7
+
+static volatile uint64_t guarded __attribute__((aligned(8)));
+static int step = 0;
+
+static void check(const char* name, int ok) {
+    whisker_write_uart(name);
+    whisker_write_uart(ok ? ": correct\n" : ": wrong\n");
+}
+
+__attribute__((interrupt("machine"), aligned(4))) static void trap_handler(void) {
+    uint64_t mcause;
+    uint64_t mtval;
+    __asm__ volatile("csrr %0, mcause" : "=r"(mcause));
+    __asm__ volatile("csrr %0, mtval" : "=r"(mtval));
+
+    if (step == 0) {
+        check("U-mode load from a guarded word faults", mcause == LOAD_ACCESS_FAULT && mtval == (uint64_t)&guarded);
+    } else {
+        check("locked entry stops M-mode stores", mcause == STORE_ACCESS_FAULT && mtval == (uint64_t)&guarded);
+        while(true) {}
+    }
+    step++;
+
+    // skip the faulting instruction
+    uint64_t mepc;
+    __asm__ volatile("csrr %0, mepc" : "=r"(mepc));
+    __asm__ volatile("csrw mepc, %0" : : "r"(mepc + 4));
+}
+
+int main() {
+    __asm__ volatile("csrw mtvec, %0" : : "r"(trap_handler));
+
+    // entry 0 guards a single word with no permissions, entry 1 lets everything else through
+    __asm__ volatile("csrw pmpaddr0, %0" : : "r"((uint64_t)&guarded >> 2));
+    __asm__ volatile("csrw pmpaddr1, %0" : : "r"(-1));
+    __asm__ volatile("csrw pmpcfg0, %0" : : "r"(PMP_NA4 | ((PMP_NAPOT | PMP_R | PMP_W | PMP_X) << 8)));
+
+    // the R=0 W=1 combination is reserved
+    uint64_t cfg;
+    __asm__ volatile("csrw pmpcfg2, %0" : : "r"(PMP_TOR | PMP_W));
+    __asm__ volatile("csrr %0, pmpcfg2" : "=r"(cfg));
+    check("W without R is cleared", cfg == PMP_TOR);
+    __asm__ volatile("csrw pmpcfg2, zero");
+
+    // M-mode isn't affected by unlocked entries
+    guarded = 1;
+    check("M-mode ignores unlocked entries", guarded == 1);
+
+    // MPRV with MPP=U makes the load act like it came from U-mode
+    uint64_t val;
+    __asm__ volatile("csrc mstatus, %0" : : "r"(MSTATUS_MPP));
+    __asm__ volatile("csrs mstatus, %0" : : "r"(MSTATUS_MPRV));
+    // the trap handler skips 4 bytes, so this can't be compressed
+    __asm__ volatile(".option push\n.option norvc\nld %0, 0(%1)\n.option pop" : "=r"(val) : "r"(&guarded) : "memory");
+    __asm__ volatile("csrc mstatus, %0" : : "r"(MSTATUS_MPRV));
+
+    // locking applies the entry to M-mode too, and it can't be unlocked
+    __asm__ volatile("csrs pmpcfg0, %0" : : "r"(PMP_L));
+    __asm__ volatile("csrc pmpcfg0, %0" : : "r"(PMP_L));
+    __asm__ volatile("csrr %0, pmpcfg0" : "=r"(cfg));
+    check("locked entries stay locked", cfg & PMP_L);
+    __asm__ volatile("sd zero, 0(%0)" : : "r"(&guarded) : "memory");
+
+    whisker_write_uart("locked entry did not fault\n");
+    while(true) {}
+}
@@ -3,16 +3,20 @@
 .pushsection .text.entry
 .global _start
 _start:
-    # zero bss segment
-    la a0, _bss_start
-    la a1, _bss_end
+    # zero bss segment, a0 and a1 hold the hart id and device tree for main
+    la t0, _bss_start
+    la t1, _bss_end
 _zero_bss:
-    bgeu a0, a1, 2f
-    sd zero, (a0)
-    addi a0, a0, 8
+    bgeu t0, t1, 2f
+    sd zero, (t0)
+    addi t0, t0, 8
     j _zero_bss
 2:
 
+    # float instructions are illegal until mstatus.FS is turned on, set it to initial
+    li t0, 0x2000
+    csrs mstatus, t0
+
     la sp, _stack_top
     call main
     # insurance for if main returns
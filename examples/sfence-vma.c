@@ -0,0 +1,63 @@
+#include "whisker.h"
+
+#define PTE_V (1 << 0)
+#define PTE_R (1 << 1)
+#define PTE_W (1 << 2)
+#define PTE_X (1 << 3)
+#define PTE_A (1 << 6)
+#define PTE_D (1 << 7)
+#define PTE(pa, flags) ((((uint64_t)(pa) >> 12) << 10) | (flags))
+
+#define SATP_SV39 (8ull << 60)
+#define MSTATUS_MPP (3 << 11)
+#define MSTATUS_MPP_S (1 << 11)
+
+#define DRAM_BASE 0x80000000ull
+#define WINDOW 0xC0000000ull
+
+static uint64_t root_table[512] __attribute__((aligned(4096)));
+static uint64_t window_table[512] __attribute__((aligned(4096)));
+static uint64_t window_leaves[512] __attribute__((aligned(4096)));
+static volatile uint64_t page_a[512] __attribute__((aligned(4096)));
+static volatile uint64_t page_b[512] __attribute__((aligned(4096)));
+
+static void in_supervisor(void) {
+    volatile uint64_t* window = (volatile uint64_t*)WINDOW;
+    whisker_write_uart(*window == 0xAAAA ? "window shows page a: correct\n" : "window shows page a: wrong\n");
+
+    // remap the window and flush just that page
+    window_leaves[0] = PTE(page_b, PTE_V | PTE_R | PTE_A);
+    __asm__ volatile("sfence.vma %0, zero" : : "r"(WINDOW) : "memory");
+    whisker_write_uart(*window == 0xBBBB ? "window shows page b: correct\n" : "window shows page b: wrong\n");
+
+    // and back again, flushing everything
+    window_leaves[0] = PTE(page_a, PTE_V | PTE_R | PTE_A);
+    __asm__ volatile("sfence.vma" : : : "memory");
+    whisker_write_uart(*window == 0xAAAA ? "window shows page a again: correct\n"
+                                         : "window shows page a again: wrong\n");
+    while(true) {}
+}
+
+int main() {
+    page_a[0] = 0xAAAA;
+    page_b[0] = 0xBBBB;
+
+    // the UART and DRAM are mapped with gigapages, a single 4KiB window sits at WINDOW
+    root_table[0] = PTE(0, PTE_V | PTE_R | PTE_W | PTE_A | PTE_D);
+    root_table[2] = PTE(DRAM_BASE, PTE_V | PTE_R | PTE_W | PTE_X | PTE_A | PTE_D);
+    root_table[3] = PTE(window_table, PTE_V);
+    window_table[0] = PTE(window_leaves, PTE_V);
+    window_leaves[0] = PTE(page_a, PTE_V | PTE_R | PTE_A);
+
+    // let S and U-mode access all of memory
+    __asm__ volatile("csrw pmpaddr0, %0" : : "r"(-1));
+    __asm__ volatile("csrw pmpcfg0, %0" : : "r"(0x1f));
+
+    __asm__ volatile("csrw satp, %0" : : "r"(SATP_SV39 | ((uint64_t)root_table >> 12)));
+    __asm__ volatile("csrw mepc, %0" : : "r"(in_supervisor));
+    __asm__ volatile("csrc mstatus, %0" : : "r"(MSTATUS_MPP));
+    __asm__ volatile("csrs mstatus, %0" : : "r"(MSTATUS_MPP_S));
+    __asm__ volatile("mret");
+
+    while(true) {}
+}
@@ -0,0 +1,35 @@
+#include "whisker.h"
+
+static void check(const char* name, int64_t got, int64_t expected) {
+    whisker_write_uart(name);
+    whisker_write_uart(got == expected ? ": correct\n" : ": wrong\n");
+}
+
+int main() {
+    volatile int8_t byte = -5;
+    volatile int16_t half = -1234;
+    volatile int32_t word = -123456;
+    volatile uint8_t ubyte = 0xFB;
+
+    int64_t val;
+
+    // the destination starts out with garbage in its upper bits, none of it may survive the load
+    __asm__ volatile("li %0, 0x5555555555555555\n\tlb %0, 0(%1)" : "=&r"(val) : "r"(&byte));
+    check("lb", val, -5);
+
+    __asm__ volatile("li %0, 0x5555555555555555\n\tlh %0, 0(%1)" : "=&r"(val) : "r"(&half));
+    check("lh", val, -1234);
+
+    __asm__ volatile("li %0, 0x5555555555555555\n\tlw %0, 0(%1)" : "=&r"(val) : "r"(&word));
+    check("lw", val, -123456);
+
+    __asm__ volatile("li %0, 0x5555555555555555\n\tlbu %0, 0(%1)" : "=&r"(val) : "r"(&ubyte));
+    check("lbu", val, 0xFB);
+
+    // positive values must clear the upper bits too
+    byte = 5;
+    __asm__ volatile("li %0, -1\n\tlb %0, 0(%1)" : "=&r"(val) : "r"(&byte));
+    check("lb positive", val, 5);
+
+    while(true) {}
+}
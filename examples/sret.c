@@ -0,0 +1,36 @@
+#include "whisker.h"
+
+#define MSTATUS_SPIE (1 << 5)
+#define MSTATUS_SPP (1 << 8)
+#define ECALL_SMODE 9
+
+__attribute__((interrupt("machine"), aligned(4))) static void trap_handler(void) {
+    uint64_t mcause;
+    __asm__ volatile("csrr %0, mcause" : "=r"(mcause));
+
+    // the ecall came from S-mode so sret must have dropped the privilege level
+    if (mcause == ECALL_SMODE) {
+        whisker_write_uart("sret is correct\n");
+    } else {
+        whisker_write_uart("sret is wrong\n");
+    }
+    while(true) {}
+}
+
+static void in_supervisor(void) {
+    __asm__ volatile("ecall");
+    while(true) {}
+}
+
+int main() {
+    __asm__ volatile("csrw mtvec, %0" : : "r"(trap_handler));
+    // let S and U-mode access all of memory
+    __asm__ volatile("csrw pmpaddr0, %0" : : "r"(-1));
+    __asm__ volatile("csrw pmpcfg0, %0" : : "r"(0x1f));
+    __asm__ volatile("csrw sepc, %0" : : "r"(in_supervisor));
+    __asm__ volatile("csrs mstatus, %0" : : "r"(MSTATUS_SPIE | MSTATUS_SPP));
+    __asm__ volatile("sret");
+
+    whisker_write_uart("sret did not jump to sepc\n");
+    while(true) {}
+}
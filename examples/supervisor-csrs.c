@@ -0,0 +1,58 @@
+#include "whisker.h"
+
+#define MSTATUS_SIE (1 << 1)
+#define MSTATUS_MIE (1 << 3)
+#define MSTATUS_SPP (1 << 8)
+#define SSI (1 << 1)
+#define MSI (1 << 3)
+#define STI (1 << 5)
+
+static void check(const char* name, uint64_t val, uint64_t expected) {
+    whisker_write_uart(name);
+    whisker_write_uart(val == expected ? ": correct\n" : ": wrong\n");
+}
+
+int main() {
+    uint64_t val;
+
+    // sstatus is a view of mstatus that hides the machine fields
+    __asm__ volatile("csrs mstatus, %0" : : "r"(MSTATUS_MIE | MSTATUS_SIE));
+    __asm__ volatile("csrr %0, sstatus" : "=r"(val));
+    check("sstatus hides mie", val & MSTATUS_MIE, 0);
+    check("sstatus shows sie", val & MSTATUS_SIE, MSTATUS_SIE);
+    __asm__ volatile("csrc sstatus, %0" : : "r"(MSTATUS_MIE | MSTATUS_SIE));
+    __asm__ volatile("csrr %0, mstatus" : "=r"(val));
+    check("sstatus can't clear mie", val & MSTATUS_MIE, MSTATUS_MIE);
+    check("sstatus clears sie", val & MSTATUS_SIE, 0);
+    __asm__ volatile("csrc mstatus, %0" : : "r"(MSTATUS_MIE));
+
+    // sie and sip only show the delegated interrupts
+    __asm__ volatile("csrw mie, %0" : : "r"(SSI | STI | MSI));
+    __asm__ volatile("csrr %0, sie" : "=r"(val));
+    check("sie without delegation", val, 0);
+    __asm__ volatile("csrw mideleg, %0" : : "r"(SSI));
+    __asm__ volatile("csrr %0, sie" : "=r"(val));
+    check("sie with delegation", val, SSI);
+    __asm__ volatile("csrw sie, zero");
+    __asm__ volatile("csrr %0, mie" : "=r"(val));
+    check("sie only clears delegated bits", val, STI | MSI);
+    __asm__ volatile("csrw mie, zero");
+
+    __asm__ volatile("csrs sip, %0" : : "r"(SSI | STI));
+    __asm__ volatile("csrr %0, mip" : "=r"(val));
+    check("sip only sets ssip", val, SSI);
+    __asm__ volatile("csrc sip, %0" : : "r"(SSI));
+    __asm__ volatile("csrw mideleg, zero");
+
+    // sscratch is a plain register for the supervisor's trap handler
+    __asm__ volatile("csrw sscratch, %0" : : "r"(0x1234));
+    __asm__ volatile("csrr %0, sscratch" : "=r"(val));
+    check("sscratch", val, 0x1234);
+
+    // bit 0 of sepc is always zero
+    __asm__ volatile("csrw sepc, %0" : : "r"(0x80001235));
+    __asm__ volatile("csrr %0, sepc" : "=r"(val));
+    check("sepc alignment", val, 0x80001234);
+
+    while(true) {}
+}
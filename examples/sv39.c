@@ -0,0 +1,68 @@
+#include "whisker.h"
+
+#define PTE_V (1 << 0)
+#define PTE_R (1 << 1)
+#define PTE_W (1 << 2)
+#define PTE_X (1 << 3)
+#define PTE_A (1 << 6)
+#define PTE_D (1 << 7)
+#define GIGAPAGE(pa, flags) ((((uint64_t)(pa) >> 12) << 10) | (flags))
+
+#define SATP_SV39 (8ull << 60)
+#define MSTATUS_MPP (3 << 11)
+#define MSTATUS_MPP_S (1 << 11)
+#define STORE_PAGE_FAULT 15
+
+#define DRAM_BASE 0x80000000ull
+// a read-only alias of DRAM
+#define ALIAS_BASE 0xC0000000ull
+
+static uint64_t root_table[512] __attribute__((aligned(4096)));
+static volatile uint64_t shared_value = 0x1234;
+
+static void check(const char* name, int ok) {
+    whisker_write_uart(name);
+    whisker_write_uart(ok ? ": correct\n" : ": wrong\n");
+}
+
+__attribute__((interrupt("machine"), aligned(4))) static void trap_handler(void) {
+    uint64_t mcause;
+    uint64_t mtval;
+    __asm__ volatile("csrr %0, mcause" : "=r"(mcause));
+    __asm__ volatile("csrr %0, mtval" : "=r"(mtval));
+
+    uint64_t alias = (uint64_t)&shared_value - DRAM_BASE + ALIAS_BASE;
+    check("store to read-only page faults", mcause == STORE_PAGE_FAULT && mtval == alias);
+    check("the alias has its accessed bit set", root_table[3] & PTE_A);
+    check("the alias isn't dirty", !(root_table[3] & PTE_D));
+    while(true) {}
+}
+
+static void in_supervisor(void) {
+    volatile uint64_t* alias = (volatile uint64_t*)((uint64_t)&shared_value - DRAM_BASE + ALIAS_BASE);
+    check("read through the alias", *alias == 0x1234);
+    *alias = 0x5678;
+
+    whisker_write_uart("store to read-only page did not fault\n");
+    while(true) {}
+}
+
+int main() {
+    // the first gigabyte holds the UART, then DRAM is identity mapped and aliased read-only
+    root_table[0] = GIGAPAGE(0, PTE_V | PTE_R | PTE_W | PTE_A | PTE_D);
+    root_table[2] = GIGAPAGE(DRAM_BASE, PTE_V | PTE_R | PTE_W | PTE_X | PTE_A | PTE_D);
+    root_table[3] = GIGAPAGE(DRAM_BASE, PTE_V | PTE_R);
+
+    __asm__ volatile("csrw mtvec, %0" : : "r"(trap_handler));
+    __asm__ volatile("csrw satp, %0" : : "r"(SATP_SV39 | ((uint64_t)root_table >> 12)));
+
+    // let S and U-mode access all of memory
+    __asm__ volatile("csrw pmpaddr0, %0" : : "r"(-1));
+    __asm__ volatile("csrw pmpcfg0, %0" : : "r"(0x1f));
+    __asm__ volatile("csrw mepc, %0" : : "r"(in_supervisor));
+    __asm__ volatile("csrc mstatus, %0" : : "r"(MSTATUS_MPP));
+    __asm__ volatile("csrs mstatus, %0" : : "r"(MSTATUS_MPP_S));
+    __asm__ volatile("mret");
+
+    while(true) {}
+}
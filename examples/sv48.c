@@ -0,0 +1,65 @@
+#include "whisker.h"
+
+#define PTE_V (1 << 0)
+#define PTE_R (1 << 1)
+#define PTE_W (1 << 2)
+#define PTE_X (1 << 3)
+#define PTE_A (1 << 6)
+#define PTE_D (1 << 7)
+#define PTE(pa, flags) ((((uint64_t)(pa) >> 12) << 10) | (flags))
+
+#define SATP_MODE_SHIFT 60
+#define SATP_SV39 8ull
+#define SATP_SV48 9ull
+#define SATP_SV57 10ull
+#define MSTATUS_MPP (3 << 11)
+#define MSTATUS_MPP_S (1 << 11)
+
+#define DRAM_BASE 0x80000000ull
+// past the end of the Sv39 address space, only reachable with Sv48 and up
+#define HIGH_ALIAS_BASE 0x8000000000ull
+
+static uint64_t root_table[512] __attribute__((aligned(4096)));
+static uint64_t low_table[512] __attribute__((aligned(4096)));
+static uint64_t high_table[512] __attribute__((aligned(4096)));
+static volatile uint64_t shared_value = 0x1234;
+
+// like Linux, find the supported modes by writing satp and reading it back
+static void probe(const char* name, uint64_t mode) {
+    uint64_t satp;
+    __asm__ volatile("csrw satp, %0" : : "r"(mode << SATP_MODE_SHIFT));
+    __asm__ volatile("csrr %0, satp" : "=r"(satp));
+    whisker_write_uart(name);
+    whisker_write_uart((satp >> SATP_MODE_SHIFT) == mode ? ": supported\n" : ": unsupported\n");
+    __asm__ volatile("csrw satp, zero");
+}
+
+static void in_supervisor(void) {
+    volatile uint64_t* alias = (volatile uint64_t*)((uint64_t)&shared_value - DRAM_BASE + HIGH_ALIAS_BASE);
+    whisker_write_uart(*alias == 0x1234 ? "sv48 translation is correct\n" : "sv48 translation is wrong\n");
+    while(true) {}
+}
+
+int main() {
+    probe("sv39", SATP_SV39);
+    probe("sv48", SATP_SV48);
+    probe("sv57", SATP_SV57);
+
+    // the first 512GiB holds the UART and DRAM, the second 512GiB aliases DRAM
+    root_table[0] = PTE(low_table, PTE_V);
+    root_table[1] = PTE(high_table, PTE_V);
+    low_table[0] = PTE(0, PTE_V | PTE_R | PTE_W | PTE_A | PTE_D);
+    low_table[2] = PTE(DRAM_BASE, PTE_V | PTE_R | PTE_W | PTE_X | PTE_A | PTE_D);
+    high_table[0] = PTE(DRAM_BASE, PTE_V | PTE_R | PTE_A);
+
+    __asm__ volatile("csrw satp, %0" : : "r"((SATP_SV48 << SATP_MODE_SHIFT) | ((uint64_t)root_table >> 12)));
+    // let S and U-mode access all of memory
+    __asm__ volatile("csrw pmpaddr0, %0" : : "r"(-1));
+    __asm__ volatile("csrw pmpcfg0, %0" : : "r"(0x1f));
+    __asm__ volatile("csrw mepc, %0" : : "r"(in_supervisor));
+    __asm__ volatile("csrc mstatus, %0" : : "r"(MSTATUS_MPP));
+    __asm__ volatile("csrs mstatus, %0" : : "r"(MSTATUS_MPP_S));
+    __asm__ volatile("mret");
+
+    while(true) {}
+}
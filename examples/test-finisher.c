@@ -0,0 +1,36 @@
+#include "whisker.h"
+
+// whisker exits with the code written here, run it and check $?
+#define TEST_FINISHER ((volatile uint32_t*)0x100000)
+#define FINISHER_FAIL 0x3333
+#define FINISHER_PASS 0x5555
+
+static int failures = 0;
+
+static void check(const char* name, int ok) {
+    whisker_write_uart(name);
+    whisker_write_uart(ok ? ": correct\n" : ": wrong\n");
+    if (!ok) {
+        failures++;
+    }
+}
+
+int main() {
+    check("the finisher reads as zero", *TEST_FINISHER == 0);
+
+    // an unknown command is ignored
+    *TEST_FINISHER = 0x1234;
+    check("unknown commands don't stop the machine", true);
+
+    if (failures == 0) {
+        whisker_write_uart("exiting with 0\n");
+        *TEST_FINISHER = FINISHER_PASS;
+    } else {
+        whisker_write_uart("exiting with the number of failures\n");
+        *TEST_FINISHER = (failures << 16) | FINISHER_FAIL;
+    }
+
+    whisker_write_uart("still running: wrong\n");
+    while (true) {
+    }
+}
@@ -0,0 +1,81 @@
+#include "whisker.h"
+
+#define MSTATUS_MIE (1 << 3)
+#define MSTATUS_MPIE (1 << 7)
+#define MSTATUS_MPP (3 << 11)
+#define MIP_SSIP (1 << 1)
+#define MTVEC_VECTORED 1
+#define ECALL_MMODE 11
+#define SUPERVISOR_SOFTWARE_INTERRUPT ((1ull << 63) | 1)
+
+static volatile uint64_t ecall_epc = 0;
+static volatile uint64_t ecall_status = 0;
+static volatile uint64_t interrupt_cause = 0;
+
+static void check(const char* name, int ok) {
+    whisker_write_uart(name);
+    whisker_write_uart(ok ? ": correct\n" : ": wrong\n");
+}
+
+__attribute__((interrupt("machine"), aligned(4))) static void direct_handler(void) {
+    uint64_t mcause;
+    __asm__ volatile("csrr %0, mcause" : "=r"(mcause));
+    __asm__ volatile("csrr %0, mepc" : "=r"(ecall_epc));
+    __asm__ volatile("csrr %0, mstatus" : "=r"(ecall_status));
+    check("ecall cause", mcause == ECALL_MMODE);
+
+    // return past the ecall
+    __asm__ volatile("csrw mepc, %0" : : "r"(ecall_epc + 4));
+}
+
+// in vectored mode exceptions still go to the base and interrupts go to base + 4 * cause
+extern void vector_table(void);
+__attribute__((interrupt("machine"))) void vectored_exception(void) {
+    whisker_write_uart("exception went to the interrupt vector table: wrong\n");
+    while(true) {}
+}
+__attribute__((interrupt("machine"))) void vectored_ssi(void) {
+    __asm__ volatile("csrr %0, mcause" : "=r"(interrupt_cause));
+    __asm__ volatile("csrc mip, %0" : : "r"(MIP_SSIP));
+}
+__asm__(
+    ".balign 4\n"
+    "vector_table:\n"
+    ".option push\n"
+    ".option norvc\n"
+    "j vectored_exception\n"
+    "j vectored_ssi\n"
+    ".option pop\n"
+);
+
+int main() {
+    __asm__ volatile("csrw mtvec, %0" : : "r"(direct_handler));
+    __asm__ volatile("csrs mstatus, %0" : : "r"(MSTATUS_MIE));
+
+    uint64_t ecall_addr;
+    __asm__ volatile(
+        ".option push\n"
+        ".option norvc\n"
+        "lla %0, 1f\n"
+        "1: ecall\n"
+        ".option pop\n"
+        : "=&r"(ecall_addr) : : "memory");
+    check("mepc points at the ecall", ecall_epc == ecall_addr);
+    check("trap entry cleared MIE", !(ecall_status & MSTATUS_MIE));
+    check("trap entry saved MIE in MPIE", ecall_status & MSTATUS_MPIE);
+    check("trap entry saved M-mode in MPP", (ecall_status & MSTATUS_MPP) == MSTATUS_MPP);
+
+    // a reserved mode falls back to direct
+    uint64_t mtvec;
+    __asm__ volatile("csrw mtvec, %0" : : "r"((uint64_t)vector_table | 2));
+    __asm__ volatile("csrr %0, mtvec" : "=r"(mtvec));
+    check("reserved mtvec mode", mtvec == (uint64_t)vector_table);
+
+    __asm__ volatile("csrw mtvec, %0" : : "r"((uint64_t)vector_table | MTVEC_VECTORED));
+    __asm__ volatile("csrs mie, %0" : : "r"(MIP_SSIP));
+    __asm__ volatile("csrs mip, %0" : : "r"(MIP_SSIP));
+    // the interrupt is taken before the next instruction
+    check("vectored interrupt", interrupt_cause == SUPERVISOR_SOFTWARE_INTERRUPT);
+
+    while(true) {}
+}
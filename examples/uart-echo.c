@@ -0,0 +1,48 @@
+#include "whisker.h"
+
+#define UART_RBR ((volatile uint8_t*)0x10000000)
+#define UART_THR ((volatile uint8_t*)0x10000000)
+#define UART_IER ((volatile uint8_t*)0x10000001)
+#define UART_IIR ((volatile uint8_t*)0x10000002)
+#define UART_LSR ((volatile uint8_t*)0x10000005)
+#define UART_IRQ 10
+#define IER_RX_AVAILABLE 1
+#define IIR_RX_AVAILABLE 4
+#define LSR_DATA_READY 1
+
+#define PLIC_BASE 0x0C000000
+#define PLIC_PRIORITY(source) ((volatile uint32_t*)(PLIC_BASE + 4 * (source)))
+#define PLIC_ENABLE(context) ((volatile uint32_t*)(PLIC_BASE + 0x2000 + 0x80 * (context)))
+#define PLIC_THRESHOLD(context) ((volatile uint32_t*)(PLIC_BASE + 0x200000 + 0x1000 * (context)))
+#define PLIC_CLAIM(context) ((volatile uint32_t*)(PLIC_BASE + 0x200004 + 0x1000 * (context)))
+
+#define MSTATUS_MIE (1 << 3)
+#define MIE_MEIE (1 << 11)
+
+// echoes everything typed into whisker's stdin back, a byte at a time from the UART interrupt
+__attribute__((interrupt("machine"), aligned(4))) static void handler(void) {
+    uint32_t source = *PLIC_CLAIM(0);
+    if (source == UART_IRQ && (*UART_IIR & 0xF) == IIR_RX_AVAILABLE) {
+        while (*UART_LSR & LSR_DATA_READY) {
+            *UART_THR = *UART_RBR;
+        }
+    }
+    *PLIC_CLAIM(0) = source;
+}
+
+int main() {
+    __asm__ volatile("csrw mtvec, %0" : : "r"(handler));
+
+    *PLIC_PRIORITY(UART_IRQ) = 1;
+    *PLIC_ENABLE(0) = 1 << UART_IRQ;
+    *PLIC_THRESHOLD(0) = 0;
+    *UART_IER = IER_RX_AVAILABLE;
+
+    __asm__ volatile("csrs mie, %0" : : "r"(MIE_MEIE));
+    __asm__ volatile("csrs mstatus, %0" : : "r"(MSTATUS_MIE));
+    whisker_write_uart("type something:\n");
+
+    while(true) {
+        __asm__ volatile("wfi");
+    }
+}
@@ -0,0 +1,56 @@
+#include "whisker.h"
+
+#define MSTATUS_MPP (3 << 11)
+#define ILLEGAL_INSTRUCTION 2
+#define ECALL_UMODE 8
+
+static int step = 0;
+
+__attribute__((interrupt("machine"), aligned(4))) static void trap_handler(void) {
+    uint64_t mcause;
+    __asm__ volatile("csrr %0, mcause" : "=r"(mcause));
+
+    if (step == 0) {
+        // reading a machine CSR from U-mode
+        whisker_write_uart(mcause == ILLEGAL_INSTRUCTION ? "mstatus from U-mode: correct\n"
+                                                         : "mstatus from U-mode: wrong\n");
+    } else if (step == 1) {
+        whisker_write_uart(mcause == ILLEGAL_INSTRUCTION ? "sscratch from U-mode: correct\n"
+                                                         : "sscratch from U-mode: wrong\n");
+    } else {
+        whisker_write_uart(mcause == ECALL_UMODE ? "ecall from U-mode: correct\n" : "ecall from U-mode: wrong\n");
+        while(true) {}
+    }
+    step++;
+
+    // skip the trapping csr instruction and go back to U-mode
+    uint64_t mepc;
+    __asm__ volatile("csrr %0, mepc" : "=r"(mepc));
+    __asm__ volatile("csrw mepc, %0" : : "r"(mepc + 4));
+    __asm__ volatile("csrc mstatus, %0" : : "r"(MSTATUS_MPP));
+}
+
+static void in_user(void) {
+    uint64_t val;
+    __asm__ volatile("csrr %0, mstatus" : "=r"(val));
+    __asm__ volatile("csrr %0, sscratch" : "=r"(val));
+    // the user counters are readable when mcounteren and scounteren allow it
+    __asm__ volatile("csrr %0, cycle" : "=r"(val));
+    __asm__ volatile("ecall");
+    while(true) {}
+}
+
+int main() {
+    __asm__ volatile("csrw mtvec, %0" : : "r"(trap_handler));
+    // let S and U-mode access all of memory
+    __asm__ volatile("csrw pmpaddr0, %0" : : "r"(-1));
+    __asm__ volatile("csrw pmpcfg0, %0" : : "r"(0x1f));
+    __asm__ volatile("csrw mcounteren, %0" : : "r"(-1));
+    __asm__ volatile("csrw scounteren, %0" : : "r"(-1));
+    __asm__ volatile("csrw mepc, %0" : : "r"(in_user));
+    // MPP=U
+    __asm__ volatile("csrc mstatus, %0" : : "r"(MSTATUS_MPP));
+    __asm__ volatile("mret");
+
+    while(true) {}
+}
@@ -0,0 +1,83 @@
+#include "whisker.h"
+
+// a vectorized memcpy in the style of newlib's
+static void vec_memcpy(uint8_t* dst, const uint8_t* src, uint64_t n) {
+    while (n > 0) {
+        uint64_t vl;
+        __asm__ volatile(".option push\n"
+                         ".option arch, +v\n"
+                         "vsetvli %0, %1, e8, m8, ta, ma\n"
+                         "vle8.v v0, (%2)\n"
+                         "vse8.v v0, (%3)\n"
+                         ".option pop"
+                         : "=&r"(vl)
+                         : "r"(n), "r"(src), "r"(dst)
+                         : "memory");
+        n -= vl;
+        src += vl;
+        dst += vl;
+    }
+}
+
+// a vectorized strlen using fault-only-first loads
+static uint64_t vec_strlen(const char* s) {
+    const char* start = s;
+    while (true) {
+        uint64_t vl;
+        int64_t first;
+        __asm__ volatile(".option push\n"
+                         ".option arch, +v\n"
+                         "vsetvli zero, %2, e8, m1, ta, ma\n"
+                         "vle8ff.v v8, (%3)\n"
+                         "csrr %0, vl\n"
+                         "vmseq.vi v0, v8, 0\n"
+                         "vfirst.m %1, v0\n"
+                         ".option pop"
+                         : "=&r"(vl), "=&r"(first)
+                         : "r"((uint64_t)-1), "r"(s)
+                         : "memory");
+        if (first >= 0) {
+            return (uint64_t)(s - start) + (uint64_t)first;
+        }
+        s += vl;
+    }
+}
+
+int main() {
+    uint8_t src[100];
+    uint8_t dst[100];
+    for (int i = 0; i < 100; i++) {
+        src[i] = (uint8_t)i;
+        dst[i] = 0;
+    }
+    vec_memcpy(dst, src, 100);
+
+    bool ok = true;
+    for (int i = 0; i < 100; i++) {
+        if (dst[i] != i) {
+            ok = false;
+        }
+    }
+    whisker_write_uart(ok ? "memcpy is correct\n" : "memcpy is wrong\n");
+
+    const char* str = "the quick brown fox jumps over the lazy dog";
+    whisker_write_uart(vec_strlen(str) == 43 ? "strlen is correct\n" : "strlen is wrong\n");
+
+    uint64_t a[4] = {1, 2, 3, 4};
+    uint64_t b[4] = {10, 20, 30, 40};
+    __asm__ volatile(".option push\n"
+                     ".option arch, +v\n"
+                     "vsetivli zero, 4, e64, m2, ta, ma\n"
+                     "vle64.v v2, (%0)\n"
+                     "vle64.v v4, (%1)\n"
+                     "vadd.vv v2, v2, v4\n"
+                     "vsll.vi v2, v2, 1\n"
+                     "vse64.v v2, (%0)\n"
+                     ".option pop"
+                     :
+                     : "r"(a), "r"(b)
+                     : "memory");
+    whisker_write_uart(a[0] == 22 && a[3] == 88 ? "vadd is correct\n" : "vadd is wrong\n");
+
+    while(true) {}
+}
@@ -0,0 +1,12 @@
+#include "whisker.h"
+
+int main() {
+    // with nothing enabled in mie no interrupt can ever wake the hart
+    __asm__ volatile("csrw mie, zero");
+
+    whisker_write_uart("entering wfi, whisker should now sit idle\n");
+    __asm__ volatile("wfi");
+
+    whisker_write_uart("woke up from wfi without an interrupt\n");
+    while(true) {}
+}
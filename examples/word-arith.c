@@ -0,0 +1,47 @@
+#include "whisker.h"
+
+#define CHECK(name, insn, lhs, rhs, expected)                                  \
+    do {                                                                       \
+        int64_t result;                                                        \
+        __asm__(insn " %0, %1, %2" : "=r"(result) : "r"(lhs), "r"(rhs));       \
+        if (result == (int64_t)(expected)) {                                   \
+            whisker_write_uart(name " is correct\n");                          \
+        } else {                                                               \
+            whisker_write_uart(name " is wrong\n");                            \
+        }                                                                      \
+    } while (0)
+
+#define CHECK_IMM(name, insn, lhs, imm, expected)                              \
+    do {                                                                       \
+        int64_t result;                                                        \
+        __asm__(insn " %0, %1, " #imm : "=r"(result) : "r"(lhs));              \
+        if (result == (int64_t)(expected)) {                                   \
+            whisker_write_uart(name " is correct\n");                          \
+        } else {                                                               \
+            whisker_write_uart(name " is wrong\n");                            \
+        }                                                                      \
+    } while (0)
+
+int main() {
+    int64_t max_w = INT32_MAX;
+    int64_t min_w = INT32_MIN;
+    int64_t one = 1;
+    int64_t high_bits = 0x1234567800000001;
+    int64_t neg_one = -1;
+
+    // results are the low 32 bits sign extended, upper source bits are ignored
+    CHECK("addw", "addw", max_w, one, INT32_MIN);
+    CHECK("addw upper bits", "addw", high_bits, one, 2);
+    CHECK("subw", "subw", min_w, one, INT32_MAX);
+    CHECK("sllw", "sllw", one, 31, INT32_MIN);
+    CHECK("sllw shamt", "sllw", one, 32 + 1, 2);
+    CHECK("srlw", "srlw", neg_one, 1, INT32_MAX);
+    CHECK("srlw zero shift", "srlw", min_w, 0, INT32_MIN);
+    CHECK("sraw", "sraw", min_w, 31, -1);
+
+    CHECK_IMM("slliw", "slliw", one, 31, INT32_MIN);
+    CHECK_IMM("srliw", "srliw", min_w, 0, INT32_MIN);
+    CHECK_IMM("sraiw", "sraiw", min_w, 4, INT32_MIN >> 4);
+
+    while(true) {}
+}
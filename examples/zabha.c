@@ -0,0 +1,51 @@
+#include "whisker.h"
+
+#define MISALIGNED_STORE 6
+
+static volatile uint64_t trap_cause = 0;
+
+static void check(const char* name, int ok) {
+    whisker_write_uart(name);
+    whisker_write_uart(ok ? ": correct\n" : ": wrong\n");
+}
+
+__attribute__((interrupt("machine"), aligned(4))) static void handler(void) {
+    uint64_t mepc;
+    __asm__ volatile("csrr %0, mcause" : "=r"(trap_cause));
+    __asm__ volatile("csrr %0, mepc" : "=r"(mepc));
+    __asm__ volatile("csrw mepc, %0" : : "r"(mepc + 4));
+}
+
+int main() {
+    __asm__ volatile("csrw mtvec, %0" : : "r"(handler));
+
+    volatile uint8_t bytes[4] = {0x10, 0x80, 0x33, 0x44};
+    volatile uint16_t halves[2] = {0x1234, 0x8000};
+    int64_t old;
+
+    // the old value is sign extended and the neighbouring bytes are left alone
+    __asm__ volatile("amoadd.b %0, %2, (%1)" : "=r"(old) : "r"(&bytes[1]), "r"(1) : "memory");
+    check("amoadd.b old value", old == -128);
+    check("amoadd.b new value", bytes[1] == 0x81);
+    check("amoadd.b neighbours", bytes[0] == 0x10 && bytes[2] == 0x33);
+
+    __asm__ volatile("amoswap.b %0, %2, (%1)" : "=r"(old) : "r"(&bytes[2]), "r"(0x1FF) : "memory");
+    check("amoswap.b", old == 0x33 && bytes[2] == 0xFF);
+
+    __asm__ volatile("amomin.b %0, %2, (%1)" : "=r"(old) : "r"(&bytes[0]), "r"(-5) : "memory");
+    check("amomin.b is signed", bytes[0] == 0xFB);
+    __asm__ volatile("amomaxu.b %0, %2, (%1)" : "=r"(old) : "r"(&bytes[3]), "r"(0xF0) : "memory");
+    check("amomaxu.b is unsigned", bytes[3] == 0xF0);
+
+    __asm__ volatile("amoor.h %0, %2, (%1)" : "=r"(old) : "r"(&halves[0]), "r"(0x0F00) : "memory");
+    check("amoor.h", old == 0x1234 && halves[0] == 0x1F34);
+    __asm__ volatile("amomax.h %0, %2, (%1)" : "=r"(old) : "r"(&halves[1]), "r"(1) : "memory");
+    check("amomax.h is signed", old == -32768 && halves[1] == 1);
+
+    // halfword AMOs still have to be naturally aligned
+    __asm__ volatile(".option push\n.option norvc\namoadd.h %0, %2, (%1)\n.option pop"
+                     : "=r"(old) : "r"((uintptr_t)&halves[0] + 1), "r"(1) : "memory");
+    check("misaligned amoadd.h", trap_cause == MISALIGNED_STORE);
+
+    while(true) {}
+}
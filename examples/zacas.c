@@ -0,0 +1,57 @@
+#include "whisker.h"
+
+#define ILLEGAL_INSTRUCTION 2
+
+static volatile uint64_t trap_cause = 0;
+
+static void check(const char* name, int ok) {
+    whisker_write_uart(name);
+    whisker_write_uart(ok ? ": correct\n" : ": wrong\n");
+}
+
+__attribute__((interrupt("machine"), aligned(4))) static void handler(void) {
+    uint64_t mepc;
+    __asm__ volatile("csrr %0, mcause" : "=r"(trap_cause));
+    __asm__ volatile("csrr %0, mepc" : "=r"(mepc));
+    __asm__ volatile("csrw mepc, %0" : : "r"(mepc + 4));
+}
+
+int main() {
+    __asm__ volatile("csrw mtvec, %0" : : "r"(handler));
+
+    volatile uint64_t dword = 5;
+    uint64_t expected = 5;
+    __asm__ volatile("amocas.d %0, %2, (%1)" : "+r"(expected) : "r"(&dword), "r"(7ull) : "memory");
+    check("amocas.d swaps on a match", expected == 5 && dword == 7);
+
+    expected = 5;
+    __asm__ volatile("amocas.d %0, %2, (%1)" : "+r"(expected) : "r"(&dword), "r"(9ull) : "memory");
+    check("amocas.d leaves memory alone on a mismatch", expected == 7 && dword == 7);
+
+    // the old word is sign extended
+    volatile uint32_t word = 0x80000000;
+    int64_t old = (int32_t)0x80000000;
+    __asm__ volatile("amocas.w %0, %2, (%1)" : "+r"(old) : "r"(&word), "r"(1) : "memory");
+    check("amocas.w", old == (int32_t)0x80000000 && word == 1);
+
+    volatile uint16_t half = 0xFFFF;
+    old = -1;
+    __asm__ volatile("amocas.h %0, %2, (%1)" : "+r"(old) : "r"(&half), "r"(2) : "memory");
+    check("amocas.h", old == -1 && half == 2);
+
+    // the quadword form works on the even-odd pairs a0/a1 and a2/a3
+    volatile uint64_t qword[2] __attribute__((aligned(16))) = {1, 2};
+    register uint64_t a0 __asm__("a0") = 1;
+    register uint64_t a1 __asm__("a1") = 2;
+    register uint64_t a2 __asm__("a2") = 3;
+    register uint64_t a3 __asm__("a3") = 4;
+    __asm__ volatile("amocas.q a0, a2, (%2)" : "+r"(a0), "+r"(a1) : "r"(qword), "r"(a2), "r"(a3) : "memory");
+    check("amocas.q", a0 == 1 && a1 == 2 && qword[0] == 3 && qword[1] == 4);
+
+    // an odd register pair is reserved, amocas.q a1, a2, (a0)
+    trap_cause = 0;
+    __asm__ volatile(".option push\n.option norvc\n.word 0x28C545AF\n.option pop" : : : "a1", "memory");
+    check("amocas.q with an odd pair", trap_cause == ILLEGAL_INSTRUCTION);
+
+    while(true) {}
+}
@@ -0,0 +1,48 @@
+#include "whisker.h"
+
+#define CLINT_MTIMECMP ((volatile uint64_t*)0x02004000)
+#define CLINT_MTIME ((volatile uint64_t*)0x0200BFF8)
+#define MIE_MTIE (1 << 7)
+#define MACHINE_TIMER_INTERRUPT ((1ull << 63) | 7)
+
+// older assemblers don't know the Zawrs mnemonics
+#define WRS_NTO ".word 0x00D00073\n"
+#define WRS_STO ".word 0x01D00073\n"
+
+static volatile uint64_t timer_cause = 0;
+static volatile uint64_t flag = 0;
+
+static void check(const char* name, int ok) {
+    whisker_write_uart(name);
+    whisker_write_uart(ok ? ": correct\n" : ": wrong\n");
+}
+
+__attribute__((interrupt("machine"), aligned(4))) static void handler(void) {
+    __asm__ volatile("csrr %0, mcause" : "=r"(timer_cause));
+    *CLINT_MTIMECMP = ~0ull;
+}
+
+int main() {
+    __asm__ volatile("csrw mtvec, %0" : : "r"(handler));
+
+    // without a reservation there's nothing to wait on
+    __asm__ volatile(WRS_NTO);
+    check("wrs.nto without a reservation returns", 1);
+
+    uint64_t val;
+    __asm__ volatile("lr.d %0, (%1)\n" WRS_STO : "=r"(val) : "r"(&flag) : "memory");
+    check("wrs.sto times out", 1);
+
+    // an interrupt ends the wait even with interrupts globally disabled, like wfi
+    *CLINT_MTIMECMP = *CLINT_MTIME + 1000;
+    __asm__ volatile("csrs mie, %0" : : "r"(MIE_MTIE));
+    __asm__ volatile("lr.d %0, (%1)\n" WRS_NTO : "=r"(val) : "r"(&flag) : "memory");
+    uint64_t mip;
+    __asm__ volatile("csrr %0, mip" : "=r"(mip));
+    check("wrs.nto woke up for the timer", mip & MIE_MTIE);
+
+    __asm__ volatile("csrsi mstatus, 8");
+    check("timer interrupt taken after waking", timer_cause == MACHINE_TIMER_INTERRUPT);
+
+    while(true) {}
+}
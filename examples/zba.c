@@ -0,0 +1,43 @@
+#include "whisker.h"
+
+#define CHECK(insn, lhs, rhs, expected)                                        \
+    do {                                                                       \
+        uint64_t a = lhs;                                                      \
+        uint64_t b = rhs;                                                      \
+        uint64_t result;                                                       \
+        __asm__(".option push\n"                                               \
+                ".option arch, +zba\n" insn " %0, %1, %2\n"                    \
+                ".option pop"                                                  \
+                : "=r"(result)                                                 \
+                : "r"(a), "r"(b));                                             \
+        if (result == (expected)) {                                            \
+            whisker_write_uart(insn " " #lhs " " #rhs " is correct\n");        \
+        } else {                                                               \
+            whisker_write_uart(insn " " #lhs " " #rhs " is wrong\n");          \
+        }                                                                      \
+    } while (0)
+
+int main() {
+    CHECK("add.uw", 0xFFFFFFFF00000001, 1, 2);
+    CHECK("sh1add", 3, 1, 7);
+    CHECK("sh2add", 3, 1, 13);
+    CHECK("sh3add", 3, 1, 25);
+    CHECK("sh1add.uw", 0xFFFFFFFF80000000, 0, 0x100000000);
+    CHECK("sh3add.uw", 0x1000000001, 0, 8);
+
+    uint64_t a = 0xFFFFFFFF80000000;
+    uint64_t result;
+    __asm__(".option push\n"
+            ".option arch, +zba\n"
+            "slli.uw %0, %1, 4\n"
+            ".option pop"
+            : "=r"(result)
+            : "r"(a));
+    if (result == 0x800000000) {
+        whisker_write_uart("slli.uw is correct\n");
+    } else {
+        whisker_write_uart("slli.uw is wrong\n");
+    }
+
+    while(true) {}
+}
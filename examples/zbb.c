@@ -0,0 +1,62 @@
+#include "whisker.h"
+
+#define CHECK_BIN(insn, lhs, rhs, expected)                                    \
+    do {                                                                       \
+        uint64_t a = lhs;                                                      \
+        uint64_t b = rhs;                                                      \
+        uint64_t result;                                                       \
+        __asm__(".option push\n"                                               \
+                ".option arch, +zbb\n" insn " %0, %1, %2\n"                    \
+                ".option pop"                                                  \
+                : "=r"(result)                                                 \
+                : "r"(a), "r"(b));                                             \
+        if (result == (expected)) {                                            \
+            whisker_write_uart(insn " " #lhs " " #rhs " is correct\n");        \
+        } else {                                                               \
+            whisker_write_uart(insn " " #lhs " " #rhs " is wrong\n");          \
+        }                                                                      \
+    } while (0)
+
+#define CHECK_UN(insn, val, expected)                                          \
+    do {                                                                       \
+        uint64_t a = val;                                                      \
+        uint64_t result;                                                       \
+        __asm__(".option push\n"                                               \
+                ".option arch, +zbb\n" insn " %0, %1\n"                        \
+                ".option pop"                                                  \
+                : "=r"(result)                                                 \
+                : "r"(a));                                                     \
+        if (result == (expected)) {                                            \
+            whisker_write_uart(insn " " #val " is correct\n");                 \
+        } else {                                                               \
+            whisker_write_uart(insn " " #val " is wrong\n");                   \
+        }                                                                      \
+    } while (0)
+
+int main() {
+    CHECK_BIN("andn", 0xFF, 0x0F, 0xF0);
+    CHECK_BIN("orn", 0, 0xFFFFFFFFFFFFFF00, 0xFF);
+    CHECK_BIN("xnor", 0xFF, 0x0F, 0xFFFFFFFFFFFFFF0F);
+    CHECK_BIN("min", -1, 1, (uint64_t)-1);
+    CHECK_BIN("minu", -1, 1, 1);
+    CHECK_BIN("max", -1, 1, 1);
+    CHECK_BIN("maxu", -1, 1, (uint64_t)-1);
+    CHECK_BIN("rol", 0x8000000000000001, 1, 3);
+    CHECK_BIN("ror", 1, 1, 0x8000000000000000);
+    CHECK_BIN("rolw", 0x80000000, 1, 1);
+    CHECK_BIN("rorw", 1, 1, 0xFFFFFFFF80000000);
+
+    CHECK_UN("clz", 1, 63);
+    CHECK_UN("clzw", 1, 31);
+    CHECK_UN("ctz", 0x100, 8);
+    CHECK_UN("ctzw", 0, 32);
+    CHECK_UN("cpop", 0xFF00FF, 16);
+    CHECK_UN("cpopw", 0xFFFFFFFF00000001, 1);
+    CHECK_UN("sext.b", 0x80, 0xFFFFFFFFFFFFFF80);
+    CHECK_UN("sext.h", 0x8000, 0xFFFFFFFFFFFF8000);
+    CHECK_UN("zext.h", 0xFFFFFFFFFFFF8000, 0x8000);
+    CHECK_UN("orc.b", 0x0100200000000003, 0xFF00FF00000000FF);
+    CHECK_UN("rev8", 0x0102030405060708, 0x0807060504030201);
+
+    while(true) {}
+}
@@ -0,0 +1,30 @@
+#include "whisker.h"
+
+#define CHECK(insn, lhs, rhs, expected)                                        \
+    do {                                                                       \
+        uint64_t a = lhs;                                                      \
+        uint64_t b = rhs;                                                      \
+        uint64_t result;                                                       \
+        __asm__(".option push\n"                                               \
+                ".option arch, +zbc\n" insn " %0, %1, %2\n"                    \
+                ".option pop"                                                  \
+                : "=r"(result)                                                 \
+                : "r"(a), "r"(b));                                             \
+        if (result == (expected)) {                                            \
+            whisker_write_uart(insn " " #lhs " " #rhs " is correct\n");        \
+        } else {                                                               \
+            whisker_write_uart(insn " " #lhs " " #rhs " is wrong\n");          \
+        }                                                                      \
+    } while (0)
+
+int main() {
+    // 0b11 clmul 0b11 = 0b101
+    CHECK("clmul", 3, 3, 5);
+    CHECK("clmul", 0x8000000000000000, 2, 0);
+    CHECK("clmulh", 0x8000000000000000, 2, 1);
+    CHECK("clmulh", 3, 3, 0);
+    CHECK("clmulr", 0x8000000000000000, 1, 1);
+    CHECK("clmulr", 0x8000000000000000, 2, 2);
+
+    while(true) {}
+}
@@ -0,0 +1,51 @@
+#include "whisker.h"
+
+#define CHECK(insn, lhs, rhs, expected)                                        \
+    do {                                                                       \
+        uint64_t a = lhs;                                                      \
+        uint64_t b = rhs;                                                      \
+        uint64_t result;                                                       \
+        __asm__(".option push\n"                                               \
+                ".option arch, +zbs\n" insn " %0, %1, %2\n"                    \
+                ".option pop"                                                  \
+                : "=r"(result)                                                 \
+                : "r"(a), "r"(b));                                             \
+        if (result == (expected)) {                                            \
+            whisker_write_uart(insn " " #lhs " " #rhs " is correct\n");        \
+        } else {                                                               \
+            whisker_write_uart(insn " " #lhs " " #rhs " is wrong\n");          \
+        }                                                                      \
+    } while (0)
+
+#define CHECK_IMM(insn, lhs, imm, expected)                                    \
+    do {                                                                       \
+        uint64_t a = lhs;                                                      \
+        uint64_t result;                                                       \
+        __asm__(".option push\n"                                               \
+                ".option arch, +zbs\n" insn " %0, %1, " #imm "\n"              \
+                ".option pop"                                                  \
+                : "=r"(result)                                                 \
+                : "r"(a));                                                     \
+        if (result == (expected)) {                                            \
+            whisker_write_uart(insn " " #lhs " " #imm " is correct\n");        \
+        } else {                                                               \
+            whisker_write_uart(insn " " #lhs " " #imm " is wrong\n");          \
+        }                                                                      \
+    } while (0)
+
+int main() {
+    CHECK("bset", 0, 63, 0x8000000000000000);
+    // only the low 6 bits of the index are used
+    CHECK("bset", 0, 64, 1);
+    CHECK("bclr", 0xFF, 0, 0xFE);
+    CHECK("binv", 0xFF, 8, 0x1FF);
+    CHECK("bext", 0x10, 4, 1);
+    CHECK("bext", 0x10, 3, 0);
+
+    CHECK_IMM("bseti", 0, 40, 0x10000000000);
+    CHECK_IMM("bclri", 0xFF, 7, 0x7F);
+    CHECK_IMM("binvi", 0, 1, 2);
+    CHECK_IMM("bexti", 0x8000000000000000, 63, 1);
+
+    while(true) {}
+}
@@ -0,0 +1,27 @@
+#include "whisker.h"
+
+#define CHECK(insn, lhs, rhs, expected)                                        \
+    do {                                                                       \
+        uint64_t a = lhs;                                                      \
+        uint64_t b = rhs;                                                      \
+        uint64_t result;                                                       \
+        __asm__(".option push\n"                                               \
+                ".option arch, +zicond\n" insn " %0, %1, %2\n"                 \
+                ".option pop"                                                  \
+                : "=r"(result)                                                 \
+                : "r"(a), "r"(b));                                             \
+        if (result == (expected)) {                                            \
+            whisker_write_uart(insn " " #lhs " " #rhs " is correct\n");        \
+        } else {                                                               \
+            whisker_write_uart(insn " " #lhs " " #rhs " is wrong\n");          \
+        }                                                                      \
+    } while (0)
+
+int main() {
+    CHECK("czero.eqz", 42, 0, 0);
+    CHECK("czero.eqz", 42, 1, 42);
+    CHECK("czero.nez", 42, 0, 42);
+    CHECK("czero.nez", 42, 1, 0);
+
+    while(true) {}
+}
@@ -7,7 +7,8 @@ __bootrom_start:
     la t0, trap
     csrw 0x305, t0
 
-    tail _user_start
+    # whisker passes the kernel's entry point in a2
+    jr a2
 .popsection
 
 trap:
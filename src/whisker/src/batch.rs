@@ -0,0 +1,188 @@
+use std::fmt::Write as _;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+
+use crate::LIMIT_EXIT_CODE;
+
+/// a list of tests, see examples/batch.toml
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct BatchFile {
+	/// seconds each test gets unless it sets its own
+	#[serde(default = "default_timeout")]
+	timeout: f64,
+	/// options passed to whisker run for every test
+	#[serde(default)]
+	args: Vec<String>,
+	#[serde(rename = "test")]
+	tests: Vec<TestSpec>,
+}
+
+fn default_timeout() -> f64 {
+	10.0
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+struct TestSpec {
+	/// defaults to the kernel's file name, or the bootrom's if there's no kernel
+	name: Option<String>,
+	bootrom: PathBuf,
+	kernel: Option<PathBuf>,
+	timeout: Option<f64>,
+	max_cycles: Option<u64>,
+	/// options passed to whisker run after the batch wide ones
+	#[serde(default)]
+	args: Vec<String>,
+	/// the exit code that means the test passed
+	#[serde(default)]
+	expect: i32,
+	/// the test signals its result with an ebreak, a0 holding the exit code
+	#[serde(default)]
+	exit_on_ebreak: bool,
+}
+
+impl TestSpec {
+	fn name(&self) -> String {
+		self.name.clone().unwrap_or_else(|| {
+			let image = self.kernel.as_ref().unwrap_or(&self.bootrom);
+			image.file_name().map_or_else(
+				|| image.display().to_string(),
+				|name| name.to_string_lossy().into_owned(),
+			)
+		})
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Verdict {
+	Pass,
+	Fail,
+	Timeout,
+}
+
+struct TestResult {
+	name: String,
+	verdict: Verdict,
+	/// None if whisker was killed by a signal
+	exit_code: Option<i32>,
+	elapsed: Duration,
+	output: String,
+}
+
+impl BatchFile {
+	pub fn from_file(path: &Path) -> Result<Self, String> {
+		let text = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+		toml::from_str(&text).map_err(|err| err.to_string())
+	}
+}
+
+/// runs every test in its own whisker, so one crashing or hanging can't take the rest down
+/// paths in the file are relative to its directory, returns whether every test passed
+pub fn run_batch(path: &Path, verbose: bool) -> Result<bool, String> {
+	let batch = BatchFile::from_file(path).map_err(|err| format!("could not load {}: {err}", path.display()))?;
+	let dir = path
+		.parent()
+		.filter(|dir| !dir.as_os_str().is_empty())
+		.unwrap_or(Path::new("."));
+	let whisker = std::env::current_exe().map_err(|err| format!("could not find the whisker binary: {err}"))?;
+
+	let mut results = Vec::with_capacity(batch.tests.len());
+	for test in &batch.tests {
+		let result = run_test(&whisker, dir, &batch, test)
+			.map_err(|err| format!("could not run test {}: {err}", test.name()))?;
+		eprintln!("{:<7} {}", verdict_name(result.verdict), result.name);
+		results.push(result);
+	}
+
+	print!("{}", summary(&results));
+	if verbose {
+		for result in results.iter().filter(|result| result.verdict != Verdict::Pass) {
+			println!("\n--- output of {} ---\n{}", result.name, result.output.trim_end());
+		}
+	}
+	Ok(results.iter().all(|result| result.verdict == Verdict::Pass))
+}
+
+fn run_test(whisker: &Path, dir: &Path, batch: &BatchFile, test: &TestSpec) -> io::Result<TestResult> {
+	let timeout = test.timeout.unwrap_or(batch.timeout);
+	let mut cmd = Command::new(whisker);
+	cmd.current_dir(dir)
+		.arg("run")
+		.args(&batch.args)
+		.args(&test.args)
+		.arg("--timeout")
+		.arg(timeout.to_string());
+	if let Some(max_cycles) = test.max_cycles {
+		cmd.arg("--max-cycles").arg(max_cycles.to_string());
+	}
+	if test.exit_on_ebreak {
+		cmd.arg("--exit-on-ebreak");
+	}
+	cmd.arg(&test.bootrom).args(&test.kernel).stdin(Stdio::null());
+
+	let started = Instant::now();
+	let output = cmd.output()?;
+	let elapsed = started.elapsed();
+
+	let exit_code = output.status.code();
+	let verdict = match exit_code {
+		Some(code) if code == test.expect => Verdict::Pass,
+		Some(LIMIT_EXIT_CODE) => Verdict::Timeout,
+		_ => Verdict::Fail,
+	};
+	let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+	text.push_str(&String::from_utf8_lossy(&output.stderr));
+	Ok(TestResult {
+		name: test.name(),
+		verdict,
+		exit_code,
+		elapsed,
+		output: text,
+	})
+}
+
+fn verdict_name(verdict: Verdict) -> &'static str {
+	match verdict {
+		Verdict::Pass => "PASS",
+		Verdict::Fail => "FAIL",
+		Verdict::Timeout => "TIMEOUT",
+	}
+}
+
+fn summary(results: &[TestResult]) -> String {
+	let width = results
+		.iter()
+		.map(|result| result.name.len())
+		.max()
+		.unwrap_or(0)
+		.max("test".len());
+	let mut out = format!("\n{:<width$}  {:<7}  {:>5}  {:>9}\n", "test", "result", "exit", "time");
+	for result in results {
+		let exit = result
+			.exit_code
+			.map_or_else(|| "-".to_string(), |code| code.to_string());
+		writeln!(
+			out,
+			"{:<width$}  {:<7}  {exit:>5}  {:>8.3}s",
+			result.name,
+			verdict_name(result.verdict),
+			result.elapsed.as_secs_f64()
+		)
+		.unwrap();
+	}
+	let count = |verdict| results.iter().filter(|result| result.verdict == verdict).count();
+	writeln!(
+		out,
+		"\n{} passed, {} failed, {} timed out",
+		count(Verdict::Pass),
+		count(Verdict::Fail),
+		count(Verdict::Timeout)
+	)
+	.unwrap();
+	out
+}
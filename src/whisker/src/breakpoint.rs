@@ -0,0 +1,146 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::regs::GPRegisters;
+use crate::ty::GPRegisterIndex;
+use crate::util::parse_number;
+
+/// a software breakpoint, the condition and ignore count are checked here so gdb only hears about real stops
+#[derive(Debug, Clone, Default)]
+pub struct Breakpoint {
+	pub condition: Option<Condition>,
+	/// how many hits are let through before the hart stops
+	pub ignore: u64,
+	/// times the pc reached it with the condition holding
+	pub hits: u64,
+	/// set from a monitor command, gdb removing its breakpoint at the same address leaves it alone
+	pub sticky: bool,
+}
+
+impl Breakpoint {
+	/// counts the hit if the condition holds and returns whether the hart should stop
+	pub fn hit(&mut self, pc: u64, regs: &GPRegisters) -> bool {
+		if let Some(condition) = &self.condition {
+			if !condition.holds(pc, regs) {
+				return false;
+			}
+		}
+		self.hits += 1;
+		self.hits > self.ignore
+	}
+
+	/// parses the arguments of `monitor break`: ADDR [if REG OP VALUE] [ignore N]
+	pub fn parse_command(args: &str) -> Result<(u64, Self), String> {
+		let mut words = args.split_whitespace();
+		let addr = words.next().ok_or("expected an address")?;
+		let addr = parse_number(addr).map_err(|e| format!("invalid address {addr:?}: {e}"))?;
+		let mut bp = Self {
+			sticky: true,
+			..Self::default()
+		};
+		while let Some(word) = words.next() {
+			match word {
+				"if" => {
+					let condition = words.by_ref().take(3).collect::<Vec<_>>().join(" ");
+					bp.condition = Some(condition.parse()?);
+				}
+				"ignore" => {
+					let count = words.next().ok_or("expected an ignore count")?;
+					bp.ignore = parse_number(count).map_err(|e| format!("invalid ignore count {count:?}: {e}"))?;
+				}
+				_ => return Err(format!("unexpected {word:?}, expected `if` or `ignore`")),
+			}
+		}
+		Ok((addr, bp))
+	}
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Operand {
+	Pc,
+	Reg(GPRegisterIndex),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum CmpOp {
+	Eq,
+	Ne,
+	Lt,
+	Le,
+	Gt,
+	Ge,
+}
+
+impl CmpOp {
+	fn symbol(self) -> &'static str {
+		match self {
+			Self::Eq => "==",
+			Self::Ne => "!=",
+			Self::Lt => "<",
+			Self::Le => "<=",
+			Self::Gt => ">",
+			Self::Ge => ">=",
+		}
+	}
+}
+
+/// compares a register against a constant, unsigned
+#[derive(Debug, Clone, Copy)]
+pub struct Condition {
+	pub lhs: Operand,
+	pub op: CmpOp,
+	pub rhs: u64,
+}
+
+impl Condition {
+	pub fn holds(&self, pc: u64, regs: &GPRegisters) -> bool {
+		let lhs = match self.lhs {
+			Operand::Pc => pc,
+			Operand::Reg(reg) => regs.get(reg),
+		};
+		match self.op {
+			CmpOp::Eq => lhs == self.rhs,
+			CmpOp::Ne => lhs != self.rhs,
+			CmpOp::Lt => lhs < self.rhs,
+			CmpOp::Le => lhs <= self.rhs,
+			CmpOp::Gt => lhs > self.rhs,
+			CmpOp::Ge => lhs >= self.rhs,
+		}
+	}
+}
+
+impl FromStr for Condition {
+	type Err = String;
+
+	/// REG OP VALUE, e.g. `a0 == 0x10`
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let [reg, op, value] = s.split_whitespace().collect::<Vec<_>>()[..] else {
+			return Err(format!("expected REG OP VALUE, got {s:?}"));
+		};
+		let lhs = match reg {
+			"pc" => Operand::Pc,
+			_ => Operand::Reg(GPRegisterIndex::from_name(reg).ok_or_else(|| format!("unknown register {reg:?}"))?),
+		};
+		let op = match op {
+			"==" => CmpOp::Eq,
+			"!=" => CmpOp::Ne,
+			"<" => CmpOp::Lt,
+			"<=" => CmpOp::Le,
+			">" => CmpOp::Gt,
+			">=" => CmpOp::Ge,
+			_ => return Err(format!("unknown comparison {op:?}")),
+		};
+		let rhs = parse_number(value).map_err(|e| format!("invalid value {value:?}: {e}"))?;
+		Ok(Self { lhs, op, rhs })
+	}
+}
+
+impl fmt::Display for Condition {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self.lhs {
+			Operand::Pc => write!(f, "pc")?,
+			Operand::Reg(reg) => write!(f, "{}", reg.display())?,
+		}
+		write!(f, " {} {:#x}", self.op.symbol(), self.rhs)
+	}
+}
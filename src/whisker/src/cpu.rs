@@ -1,57 +1,411 @@
 use std::cmp::Ordering;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Write as _;
 use std::fs::{File, OpenOptions};
 use std::io::Write as _;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering as AtomicOrdering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use tracing::*;
 
-use crate::csr::ControlStatusRegisters;
+use crate::breakpoint::Breakpoint;
+use crate::csr::{interrupt, CSRInfo, CSRPrivilege, ControlStatusRegisters, FloatStatus, Mstatus};
+use crate::devices::clint::Clint;
+use crate::devices::framebuffer::Framebuffer;
+use crate::devices::plic::Plic;
+use crate::devices::uart::Uart;
+use crate::elf::SymbolTable;
+use crate::hpm::{event, HpmCounters, HpmEvents};
+use crate::htif::Htif;
 use crate::insn::atomic::AtomicInstruction;
+use crate::insn::bitmanip::BitmanipInstruction;
 use crate::insn::compressed::CompressedInstruction;
 use crate::insn::csr::CSRInstruction;
+use crate::insn::double::DoubleInstruction;
 use crate::insn::float::FloatInstruction;
+use crate::insn::half::HalfInstruction;
 use crate::insn::int::IntInstruction;
 use crate::insn::multiply::MultiplyInstruction;
+use crate::insn::vector::{
+	VectorCmpOp, VectorInstruction, VectorIntOp, VectorLengthSource, VectorOperand, VectorTypeSource,
+};
 use crate::insn::Instruction;
-use crate::mem::Memory;
-use crate::regs::{FPRegisters, GPRegisters};
-use crate::soft::ExceptionFlags;
-use crate::ty::{GPRegisterIndex, SupportedExtensions, TrapIdx};
+use crate::machine::MachineLayout;
+use crate::mem::{Memory, PoisonedAccess};
+use crate::mmu::{AccessType, Satp, Tlb, TranslationMode};
+use crate::pmp::{Pmp, DEFAULT_PMP_ENTRIES};
+use crate::regs::{FPRegisters, GPRegisters, VectorRegisters, VectorType};
+use crate::semihosting::Semihosting;
+use crate::snapshot::{expect_eq, Snapshot, SnapshotReader, SnapshotWriter};
+use crate::soft::double::SoftDouble;
+use crate::soft::float::SoftFloat;
+use crate::soft::half::SoftHalf;
+use crate::soft::{ExceptionFlags, RoundingMode, FCSR_FLAGS_MASK, FCSR_ROUNDING_MODE_MASK};
+use crate::stats::{OpcodeStats, PerfSummary};
+use crate::trace::Tracer;
+use crate::ty::{GPRegisterIndex, PrivilegeMode, SupportedExtensions, TrapIdx, TrapKind, VectorRegisterIndex};
+use crate::util::carryless_mul;
+
+// the same as qemu's virt machine
+pub const DEFAULT_TIMEBASE_FREQ: u64 = 10_000_000;
+
+// how long a hart halted in WFI sleeps between checks for pending interrupts
+const HALTED_POLL_INTERVAL: Duration = Duration::from_millis(1);
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum WhiskerExecState {
 	Step,
 	Running,
 	Paused,
+	// stalled in WFI, becomes Running again once an interrupt is pending
+	Halted,
+}
+
+/// how a CSR instruction modifies the CSR
+enum CSRUpdate {
+	Write(u64),
+	Set(u64),
+	Clear(u64),
+}
+
+/// what happens when a load or store isn't naturally aligned
+/// atomics always trap on misaligned addresses regardless of this
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum MisalignedAccess {
+	/// perform the access byte by byte
+	#[default]
+	Emulate,
+	/// raise a LOAD/STORE_ADDR_MISALIGNED trap
+	Trap,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum WhiskerExecStatus {
 	Stepped,
 	HitBreakpoint,
+	HitHwBreakpoint,
+	/// a watchpoint saw an access of this kind to this address, the instruction that did it has finished
+	HitWatchpoint(WatchKind, u64),
 	Paused,
+	/// the last instruction touched poisoned memory, it's been reported already
+	HitPoison,
+	/// an exception with a handler that can't run, the pc is still at the instruction that raised it
+	/// resuming takes the trap anyway
+	FatalTrap(TrapIdx),
+	/// the guest powered the machine off, with this exit code
+	Exited(i32),
+}
+
+/// the accesses a watchpoint stops on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum WatchKind {
+	Write,
+	Read,
+	ReadWrite,
+}
+
+impl WatchKind {
+	/// a compare-and-swap always reads but only writes when the comparison succeeds
+	fn compare_and_swap(swapped: bool) -> Self {
+		if swapped {
+			WatchKind::ReadWrite
+		} else {
+			WatchKind::Read
+		}
+	}
+}
+
+/// the last trap taken, kept until an instruction retires so recursive faults can be diagnosed
+#[derive(Debug, Clone, Copy)]
+struct TrapRecord {
+	cause: TrapIdx,
+	tval: u64,
+	epc: u64,
+	privilege: PrivilegeMode,
+	handler: u64,
+	// whether this trap already got reported as a double fault
+	double_fault: bool,
+}
+
+/// a cloneable handle that devices (or the debugger) can use to request a machine reset
+/// the reset is performed at the start of the next execution cycle
+#[derive(Debug, Clone, Default)]
+pub struct ResetLine(Arc<AtomicBool>);
+
+impl ResetLine {
+	pub fn request(&self) {
+		self.0.store(true, AtomicOrdering::Release);
+	}
+
+	/// returns true if a reset was requested, clearing the request
+	fn take(&self) -> bool {
+		self.0.swap(false, AtomicOrdering::AcqRel)
+	}
+}
+
+/// a cloneable handle that devices use to power the machine off with an exit code
+/// like a reset, it takes effect at the start of the next execution cycle
+#[derive(Debug, Clone, Default)]
+pub struct PowerOffLine(Arc<Mutex<Option<i32>>>);
+
+impl PowerOffLine {
+	pub fn request(&self, exit_code: i32) {
+		*self.0.lock().expect("power off lock poisoned") = Some(exit_code);
+	}
+
+	/// returns the exit code if a power off was requested, clearing the request
+	fn take(&self) -> Option<i32> {
+		self.0.lock().expect("power off lock poisoned").take()
+	}
+}
+
+/// a cloneable handle that devices use to drive the hart's interrupt lines
+/// the raised lines show up in mip next to the bits software can set itself
+#[derive(Debug, Clone, Default)]
+pub struct InterruptLines(Arc<AtomicU64>);
+
+#[allow(unused)]
+impl InterruptLines {
+	/// raises the lines in mask, the bits are the same as in mip
+	pub fn raise(&self, mask: u64) {
+		self.0.fetch_or(mask, AtomicOrdering::AcqRel);
+	}
+
+	pub fn lower(&self, mask: u64) {
+		self.0.fetch_and(!mask, AtomicOrdering::AcqRel);
+	}
+
+	pub fn set(&self, mask: u64, raised: bool) {
+		if raised {
+			self.raise(mask);
+		} else {
+			self.lower(mask);
+		}
+	}
+
+	fn get(&self) -> u64 {
+		self.0.load(AtomicOrdering::Acquire)
+	}
+}
+
+/// a cloneable handle to the hart's timebase, the time CSR and the CLINT's mtime are both backed by it
+/// it counts up at its frequency in host wall-clock time
+#[derive(Debug, Clone)]
+pub struct Timer(Arc<Mutex<TimerState>>);
+
+#[derive(Debug)]
+struct TimerState {
+	freq: u64,
+	// the timer held base at start
+	start: Instant,
+	base: u64,
+}
+
+impl Timer {
+	pub fn new(freq: u64) -> Self {
+		Self(Arc::new(Mutex::new(TimerState {
+			freq,
+			start: Instant::now(),
+			base: 0,
+		})))
+	}
+
+	/// ticks of the timer's frequency since it was last written
+	pub fn read(&self) -> u64 {
+		let state = self.0.lock().expect("timer lock poisoned");
+		let nanos = state.start.elapsed().as_nanos();
+		state.base + (nanos * u128::from(state.freq) / 1_000_000_000) as u64
+	}
+
+	/// sets the current time, the timer keeps counting up from there
+	pub fn write(&self, val: u64) {
+		let mut state = self.0.lock().expect("timer lock poisoned");
+		state.start = Instant::now();
+		state.base = val;
+	}
+}
+
+impl Snapshot for Timer {
+	fn save(&self, snapshot: &mut SnapshotWriter) {
+		snapshot.u64(self.read());
+	}
+
+	/// the timer picks up counting from where it was saved
+	fn restore(&mut self, snapshot: &mut SnapshotReader) -> Result<(), String> {
+		self.write(snapshot.u64()?);
+		Ok(())
+	}
 }
 
 #[derive(Debug)]
 pub struct WhiskerCpu {
 	logfile: Option<File>,
 
+	/// the extensions that are currently enabled, the decoder checks against these
 	pub supported_extensions: SupportedExtensions,
+	/// every extension the hart has, misa can only enable extensions from this set
+	implemented_extensions: SupportedExtensions,
+	/// the value of mhartid, memory reservations are tracked per hart id
+	pub hart_id: usize,
 	pub mem: Memory,
 	pub registers: GPRegisters,
 	pub fp_registers: FPRegisters,
+	pub vec_registers: VectorRegisters,
 
-	should_trap: bool,
+	// the cause and xtval of the trap to take at the start of the next cycle
+	pending_trap: Option<(TrapIdx, u64)>,
+	last_trap: Option<TrapRecord>,
 
 	pub csrs: ControlStatusRegisters,
 
 	pub pc: u64,
 	pub cycles: u64,
+	/// retired instructions, instructions that trap don't retire
+	pub instret: u64,
+	pub hpm: HpmCounters,
+	/// backs the time CSR, shared with the CLINT
+	pub timer: Timer,
+	pub clint: Option<Clint>,
+	pub plic: Option<Plic>,
+	pub uart: Option<Uart>,
+	pub framebuffer: Option<Framebuffer>,
+	/// set when the guest's semihosting calls are serviced, otherwise they're plain ebreaks
+	pub semihosting: Option<Semihosting>,
+	/// set when the guest talks to the host through tohost and fromhost
+	pub htif: Option<Htif>,
+	/// instruction trace from --trace-out
+	pub tracer: Option<Tracer>,
+	/// the histogram from --opcode-stats
+	pub opcode_stats: Option<OpcodeStats>,
+	/// the speed report from --perf
+	pub perf: Option<PerfSummary>,
 	pub exec_state: WhiskerExecState,
+	pub privilege: PrivilegeMode,
+	pub misaligned_access: MisalignedAccess,
+	/// the largest address space satp can select, satp also accepts every smaller mode
+	pub max_translation_mode: TranslationMode,
+	pub pmp: Pmp,
+	pub tlb: Tlb,
+
+	/// where the pc points after power on and after every reset
+	pub reset_vector: u64,
+	/// the device tree's address, handed to the bootrom in a1 at reset
+	fdt_addr: u64,
+	/// where the kernel starts, handed to the bootrom in a2 at reset
+	kernel_entry: u64,
+	/// symbols from the loaded ELF files, used to make diagnostics readable
+	pub symbols: SymbolTable,
+	/// where memory and the devices were put when the machine was built
+	pub layout: MachineLayout,
+	pub reset_line: ResetLine,
+	pub power_off_line: PowerOffLine,
+	pub interrupt_lines: InterruptLines,
+
+	/// software breakpoints by address, from gdb or `monitor break`
+	pub breakpoints: HashMap<u64, Breakpoint>,
+	// the breakpoint the hart last stopped at, passed once so resuming doesn't stop there again
+	stopped_at_breakpoint: Option<u64>,
+	/// hardware breakpoints from gdb, kept apart from the software ones so each kind is removed on its own
+	pub hw_breakpoints: HashSet<u64>,
+	/// gdb asked for extended mode, killing the machine then leaves whisker waiting for it to run the machine again
+	pub gdb_extended_mode: bool,
+	/// treat ebreak as the end of the program, powering off with a0 as the exit code
+	pub exit_on_ebreak: bool,
+	/// stop with FatalTrap instead of taking an exception whose handler can't be fetched, set while gdb is attached
+	pub stop_on_fatal_traps: bool,
+	// the pending trap was already reported as fatal, so it's taken this time
+	fatal_trap_reported: bool,
+	// the hart is halted by wrs.nto, so losing the reservation wakes it up too
+	waiting_on_reservation: bool,
+	// the current instruction wrote minstret, so it doesn't also count itself as retired
+	instret_written: bool,
+	/// gdb watchpoints by (addr, len, kind), the range is virtual like every address gdb sends
+	watchpoints: HashSet<(u64, u64, WatchKind)>,
+	/// the first access a watchpoint saw during the current instruction
+	watch_hit: Option<(WatchKind, u64)>,
+}
+
+/// devices are optional, so whether each one is there is saved too
+fn save_device<T: Snapshot>(device: &Option<T>, snapshot: &mut SnapshotWriter) {
+	snapshot.bool(device.is_some());
+	if let Some(device) = device {
+		device.save(snapshot);
+	}
+}
+
+fn restore_device<T: Snapshot>(
+	name: &str,
+	device: &mut Option<T>,
+	snapshot: &mut SnapshotReader,
+) -> Result<(), String> {
+	expect_eq(name, device.is_some(), snapshot.bool()?)?;
+	match device {
+		Some(device) => device.restore(snapshot),
+		None => Ok(()),
+	}
+}
 
-	pub breakpoints: HashSet<u64>,
+impl Snapshot for WhiskerCpu {
+	fn save(&self, snapshot: &mut SnapshotWriter) {
+		snapshot.u64(self.implemented_extensions.misa());
+		snapshot.u64(self.supported_extensions.misa());
+		self.registers.save(snapshot);
+		self.fp_registers.save(snapshot);
+		self.vec_registers.save(snapshot);
+		self.csrs.save(snapshot);
+		snapshot.u64(self.pc);
+		snapshot.u8(self.privilege as u8);
+		snapshot.u64(self.cycles);
+		snapshot.u64(self.instret);
+		// a trap raised by the last instruction is only taken on the next cycle
+		snapshot.bool(self.pending_trap.is_some());
+		if let Some((cause, tval)) = self.pending_trap {
+			snapshot.u64(cause.inner());
+			snapshot.u64(tval);
+		}
+		self.hpm.save(snapshot);
+		self.pmp.save(snapshot);
+		self.timer.save(snapshot);
+		self.mem.save(snapshot);
+		save_device(&self.clint, snapshot);
+		save_device(&self.plic, snapshot);
+		save_device(&self.uart, snapshot);
+		save_device(&self.framebuffer, snapshot);
+	}
+
+	fn restore(&mut self, snapshot: &mut SnapshotReader) -> Result<(), String> {
+		expect_eq("misa", self.implemented_extensions.misa(), snapshot.u64()?)?;
+		self.supported_extensions = self.implemented_extensions.with_misa_letters(snapshot.u64()?);
+		self.registers.restore(snapshot)?;
+		self.fp_registers.restore(snapshot)?;
+		self.vec_registers.restore(snapshot)?;
+		self.csrs.restore(snapshot)?;
+		self.pc = snapshot.u64()?;
+		let privilege = snapshot.u8()?;
+		self.privilege = PrivilegeMode::from_bits(privilege.into())
+			.ok_or_else(|| format!("the snapshot has an invalid privilege mode {privilege}"))?;
+		self.cycles = snapshot.u64()?;
+		self.instret = snapshot.u64()?;
+		self.pending_trap = if snapshot.bool()? {
+			Some((TrapIdx::from_inner(snapshot.u64()?), snapshot.u64()?))
+		} else {
+			None
+		};
+		self.last_trap = None;
+		self.hpm.restore(snapshot)?;
+		self.pmp.restore(snapshot)?;
+		self.timer.restore(snapshot)?;
+		self.mem.restore(snapshot)?;
+		restore_device("a CLINT", &mut self.clint, snapshot)?;
+		restore_device("a PLIC", &mut self.plic, snapshot)?;
+		restore_device("a UART", &mut self.uart, snapshot)?;
+		restore_device("a framebuffer", &mut self.framebuffer, snapshot)?;
+		// the translations cached for the old state are all stale
+		self.tlb.flush(None, None);
+		Ok(())
+	}
 }
 
 macro_rules! log {
@@ -66,7 +420,13 @@ macro_rules! log {
 }
 
 impl WhiskerCpu {
-	pub fn new(supported_extensions: SupportedExtensions, mem: Memory, logfile: Option<PathBuf>) -> Self {
+	pub fn new(
+		supported_extensions: SupportedExtensions,
+		mem: Memory,
+		reset_vector: u64,
+		vlen: usize,
+		logfile: Option<PathBuf>,
+	) -> Self {
 		let logfile = logfile.map(|path| {
 			OpenOptions::new()
 				.write(true)
@@ -75,64 +435,304 @@ impl WhiskerCpu {
 				.open(&path)
 				.unwrap_or_else(|e| panic!("failed to create logfile {}: {:?}", path.display(), e))
 		});
-		Self {
+		let mut cpu = Self {
 			logfile,
 
 			supported_extensions,
+			implemented_extensions: supported_extensions,
+			// whisker only emulates a single hart so far, which has to be hart 0
+			hart_id: 0,
 			mem,
 			registers: GPRegisters::default(),
 			fp_registers: FPRegisters::default(),
+			vec_registers: VectorRegisters::new(vlen),
 
-			should_trap: false,
+			pending_trap: None,
+			last_trap: None,
 			csrs: ControlStatusRegisters::new(),
 
-			pc: 0,
+			pc: reset_vector,
 			cycles: 0,
+			instret: 0,
+			timer: Timer::new(DEFAULT_TIMEBASE_FREQ),
+			clint: None,
+			plic: None,
+			uart: None,
+			framebuffer: None,
+			semihosting: None,
+			htif: None,
+			tracer: None,
+			opcode_stats: None,
+			perf: None,
 			exec_state: WhiskerExecState::Paused,
-			breakpoints: HashSet::default(),
+			privilege: PrivilegeMode::Machine,
+			misaligned_access: MisalignedAccess::default(),
+			max_translation_mode: TranslationMode::default(),
+			pmp: Pmp::new(DEFAULT_PMP_ENTRIES),
+			hpm: HpmCounters::new(),
+			tlb: Tlb::default(),
+
+			reset_vector,
+			fdt_addr: 0,
+			kernel_entry: 0,
+			symbols: SymbolTable::default(),
+			layout: MachineLayout::default(),
+			reset_line: ResetLine::default(),
+			power_off_line: PowerOffLine::default(),
+			interrupt_lines: InterruptLines::default(),
+
+			breakpoints: HashMap::default(),
+			stopped_at_breakpoint: None,
+			hw_breakpoints: HashSet::default(),
+			gdb_extended_mode: false,
+			exit_on_ebreak: false,
+			stop_on_fatal_traps: false,
+			fatal_trap_reported: false,
+			waiting_on_reservation: false,
+			instret_written: false,
+			watchpoints: HashSet::default(),
+			watch_hit: None,
+		};
+		cpu.reset_csrs();
+		cpu.reset_registers();
+		cpu
+	}
+
+	/// the boot protocol Linux and OpenSBI expect, a0 is the hart id and a1 points at the device tree
+	/// a2 tells the bootrom where to jump to
+	fn reset_registers(&mut self) {
+		self.registers = GPRegisters::default();
+		self.registers.set(GPRegisterIndex::A0, self.hart_id as u64);
+		self.registers.set(GPRegisterIndex::A1, self.fdt_addr);
+		self.registers.set(GPRegisterIndex::A2, self.kernel_entry);
+	}
+
+	/// the machine is still in its reset state when this is called, so a1 is updated right away too
+	pub fn set_fdt_addr(&mut self, addr: u64) {
+		self.fdt_addr = addr;
+		self.registers.set(GPRegisterIndex::A1, addr);
+	}
+
+	/// like set_fdt_addr, a2 is updated right away
+	pub fn set_kernel_entry(&mut self, addr: u64) {
+		self.kernel_entry = addr;
+		self.registers.set(GPRegisterIndex::A2, addr);
+	}
+
+	/// saves the hart, memory and every device, breakpoints and the host side of devices aren't part of it
+	pub fn save_snapshot(&self) -> Vec<u8> {
+		let mut snapshot = SnapshotWriter::new();
+		self.save(&mut snapshot);
+		snapshot.finish()
+	}
+
+	/// the machine has to be built the same way as the one the snapshot was taken on
+	/// a hart stalled in WFI wakes up after a restore, which WFI is allowed to do anyway
+	pub fn restore_snapshot(&mut self, bytes: &[u8]) -> Result<(), String> {
+		let mut snapshot = SnapshotReader::new(bytes)?;
+		self.restore(&mut snapshot)?;
+		snapshot.finish()
+	}
+
+	/// puts every CSR into its power-on state
+	fn reset_csrs(&mut self) {
+		self.csrs = ControlStatusRegisters::new();
+		self.csrs.write_vlenb(self.vec_registers.vlenb() as u64);
+		self.csrs.write_misa(self.supported_extensions.misa());
+		let status = self.legalize_mstatus(0);
+		self.csrs.write_mstatus(status.0);
+	}
+
+	/// puts the hart back into its power-on state and jumps to the reset vector
+	/// memory is preserved or reloaded depending on how it was built, breakpoints are kept
+	pub fn reset(&mut self) {
+		log!(self, "  resetting machine, jumping to {:#018X}", self.reset_vector);
+		info!("machine reset");
+
+		self.reset_registers();
+		self.fp_registers = FPRegisters::default();
+		// VLEN is fixed for the lifetime of the machine
+		let vlenb = self.vec_registers.vlenb();
+		self.vec_registers = VectorRegisters::new(vlenb * 8);
+		// extensions disabled through misa come back on reset
+		self.supported_extensions = self.implemented_extensions;
+		self.reset_csrs();
+		self.pending_trap = None;
+		self.fatal_trap_reported = false;
+		self.last_trap = None;
+		self.pmp.reset();
+		self.hpm.reset();
+		self.tlb.flush(None, None);
+		self.mem.reset();
+		if self.exec_state == WhiskerExecState::Halted {
+			self.exec_state = WhiskerExecState::Running;
+		}
+		self.waiting_on_reservation = false;
+
+		self.pc = self.reset_vector;
+		self.cycles = 0;
+		self.instret = 0;
+		self.timer.write(0);
+		if let Some(clint) = &self.clint {
+			clint.reset();
+		}
+		if let Some(plic) = &self.plic {
+			plic.reset();
 		}
+		if let Some(uart) = &self.uart {
+			uart.reset();
+		}
+		if let Some(framebuffer) = &self.framebuffer {
+			framebuffer.reset();
+		}
+		self.privilege = PrivilegeMode::Machine;
+	}
+
+	/// starts the machine over like it was just launched, unlike reset memory is always reloaded
+	/// resets and power offs the guest asked for but didn't get to are dropped
+	pub fn restart(&mut self) {
+		self.reset_line.take();
+		self.power_off_line.take();
+		self.reset();
+		self.mem.reload();
 	}
 
 	pub fn execute_one(&mut self) -> Result<(), WhiskerExecStatus> {
+		if let Some(exit_code) = self.power_off_line.take() {
+			info!("machine powered off with exit code {exit_code}");
+			return Err(WhiskerExecStatus::Exited(exit_code));
+		}
+		if self.reset_line.take() {
+			self.reset();
+		}
+		// time moves on by itself, so the timer interrupt has to be checked every cycle
+		if let Some(clint) = &self.clint {
+			clint.update_timer_interrupt();
+		}
+
+		if self.exec_state == WhiskerExecState::Halted {
+			// htif can still answer the guest, which may be what it's waiting for
+			if self.htif.as_ref().is_some_and(Htif::busy) {
+				self.service_htif();
+			}
+			// gdb and htif write memory behind the hart's back, breaking its reservation
+			let reservation_lost = self.waiting_on_reservation && !self.mem.has_reservation(self.hart_id);
+			if !reservation_lost && !self.interrupt_pending() {
+				// nothing can happen until an interrupt arrives, so don't spin the host cpu
+				std::thread::sleep(HALTED_POLL_INTERVAL);
+				return Ok(());
+			}
+			if std::mem::take(&mut self.waiting_on_reservation) {
+				log!(self, "  waking up from wrs.nto");
+			} else {
+				log!(self, "  waking up from wfi");
+			}
+			self.exec_state = WhiskerExecState::Running;
+		}
+
 		self.cycles += 1;
 		log!(self, "cycle {}", self.cycles);
+		let resuming_from = self.stopped_at_breakpoint.take();
 
-		if self.should_trap {
+		if let Some((cause, tval)) = self.pending_trap.take() {
 			log!(self, "  trapping");
-			return self.exec_trap();
+			return self.exec_trap(cause, tval);
+		}
+
+		if let Some(interrupt) = self.pending_interrupt() {
+			log!(self, "  taking interrupt {:#018X}", interrupt.inner());
+			return self.exec_trap(interrupt, 0);
 		}
 
 		// some instructions (particularly jumps) need the program counter at the start of the instruction
 		let start_pc = self.pc;
 
-		if self.breakpoints.contains(&start_pc) {
-			log!(self, "  reached breakpoint at {:#018X}", start_pc);
-			return Err(WhiskerExecStatus::HitBreakpoint);
+		if resuming_from != Some(start_pc) {
+			if let Some(bp) = self.breakpoints.get_mut(&start_pc) {
+				if bp.hit(start_pc, &self.registers) {
+					log!(self, "  reached breakpoint at {:#018X}", start_pc);
+					self.stopped_at_breakpoint = Some(start_pc);
+					return Err(WhiskerExecStatus::HitBreakpoint);
+				}
+			}
+		}
+		if self.hw_breakpoints.contains(&start_pc) {
+			log!(self, "  reached hardware breakpoint at {:#018X}", start_pc);
+			return Err(WhiskerExecStatus::HitHwBreakpoint);
 		}
 
+		let traced = self.trace_before(start_pc);
+		let mut fetched = None;
 		match Instruction::fetch_instruction(self) {
-			Ok((inst, size)) => {
+			Ok((inst, raw, size)) => {
 				log!(self, "  {:#018X}: fetched {:?}", start_pc, inst);
+				if traced.is_some() {
+					fetched = Some((raw, size, format!("{inst:?}")));
+				}
+				if let Some(stats) = self.opcode_stats.as_mut() {
+					stats.decoded(&inst);
+				}
+				let mut events = HpmEvents::of(&inst);
 				self.pc = self.pc.wrapping_add(size);
 				match inst {
 					Instruction::IntExtension(insn) => self.execute_i_insn(insn, start_pc),
 					Instruction::FloatExtension(insn) => self.execute_f_insn(insn, start_pc),
+					Instruction::DoubleExtension(insn) => self.execute_d_insn(insn, start_pc),
+					Instruction::HalfExtension(insn) => self.execute_h_insn(insn, start_pc),
 					Instruction::Csr(insn) => self.exec_csr(insn, start_pc),
 					Instruction::CompressedExtension(insn) => self.exec_compressed_insn(insn, start_pc),
 					Instruction::AtomicExtension(insn) => self.exec_atomic_insn(insn, start_pc),
 					Instruction::MultiplyInstruction(insn) => self.exec_multiply_insn(insn, start_pc),
+					Instruction::BitmanipExtension(insn) => self.exec_bitmanip_insn(insn, start_pc),
+					Instruction::VectorExtension(insn) => self.exec_vector_insn(insn, start_pc),
+				}
+				let instret_written = std::mem::take(&mut self.instret_written);
+				if self.pending_trap.is_none() {
+					if !instret_written {
+						self.instret += 1;
+					}
+					if self.fp_registers.take_dirty() {
+						self.mark_float_dirty();
+					}
+					// a conditional branch was taken if it went anywhere but the next instruction
+					if events.has(event::BRANCH) && self.pc != start_pc.wrapping_add(size) {
+						events.add(event::BRANCH_TAKEN);
+					}
+					self.hpm.retire(events);
+					if let Some(stats) = self.opcode_stats.as_mut() {
+						stats.retired();
+					}
+					self.last_trap = None;
+				} else {
+					// the trapping instruction didn't complete, xepc has to point back at it
+					self.pc = start_pc;
 				}
 
 				log!(self, "state after cycle {}", self.cycles);
 				self.dump();
-
-				Ok(())
 			}
 			Err(()) => {
 				// error during instruction decoding, trap was requested
-				Ok(())
 			}
 		}
+		if let Some(before) = traced {
+			let trap = self.pending_trap.map(|(cause, _)| cause);
+			self.trace_after(start_pc, before, fetched, trap);
+		}
+		if self.htif.as_ref().is_some_and(Htif::busy) {
+			self.service_htif();
+		}
+
+		// checked after the instruction so fetches and page table walks are caught too
+		if let Some(access) = self.mem.take_poisoned_access() {
+			self.report_poisoned_access(start_pc, &access);
+			return Err(WhiskerExecStatus::HitPoison);
+		}
+		if let Some((kind, addr)) = self.watch_hit.take() {
+			log!(self, "  watchpoint hit at {:#018X}", addr);
+			return Err(WhiskerExecStatus::HitWatchpoint(kind, addr));
+		}
+		Ok(())
 	}
 
 	pub fn request_trap(&mut self, trap: TrapIdx, mtval: u64) {
@@ -143,9 +743,7 @@ impl WhiskerCpu {
 			mtval,
 		);
 		// trap causes have the high bit set if they are an interrupt, or unset for exceptions
-		self.csrs.write_mcause(trap.inner());
-		self.csrs.write_mtval(mtval);
-		self.should_trap = true;
+		self.pending_trap = Some((trap, mtval));
 	}
 
 	/// If this routine returns [None] then there's incoming GDB data
@@ -157,8 +755,10 @@ impl WhiskerCpu {
 				Ok(()) => Some(WhiskerExecStatus::Stepped),
 				Err(e) => Some(e),
 			},
-			WhiskerExecState::Running => loop {
-				if self.should_poll() && poll_incoming_data() {
+			WhiskerExecState::Running | WhiskerExecState::Halted => loop {
+				// cycles don't advance while halted so poll every time instead
+				let halted = self.exec_state == WhiskerExecState::Halted;
+				if (halted || self.should_poll()) && poll_incoming_data() {
 					return None;
 				}
 
@@ -169,14 +769,55 @@ impl WhiskerCpu {
 			WhiskerExecState::Paused => Some(WhiskerExecStatus::Paused),
 		}
 	}
+
+	/// stops execution after any instruction that accesses [addr, addr + len) the way kind says
+	/// returns false if the same watchpoint is already set
+	pub fn add_watchpoint(&mut self, addr: u64, len: u64, kind: WatchKind) -> bool {
+		self.watchpoints.insert((addr, len, kind))
+	}
+
+	/// returns false if there was no such watchpoint
+	pub fn remove_watchpoint(&mut self, addr: u64, len: u64, kind: WatchKind) -> bool {
+		self.watchpoints.remove(&(addr, len, kind))
+	}
+
+	/// checks a load or store of len bytes at vaddr against the watchpoints, access is Read, Write or ReadWrite for AMOs
+	/// only the load and store paths call this, so fetches and page table walks never stop on a watchpoint
+	pub fn watch_access(&mut self, vaddr: u64, len: u64, access: WatchKind) {
+		if self.watch_hit.is_some() {
+			return;
+		}
+		let reads = matches!(access, WatchKind::Read | WatchKind::ReadWrite);
+		let writes = matches!(access, WatchKind::Write | WatchKind::ReadWrite);
+		let end = vaddr.saturating_add(len);
+		self.watch_hit = self.watchpoints.iter().find_map(|&(addr, watch_len, kind)| {
+			let wanted = match kind {
+				WatchKind::Read => reads,
+				WatchKind::Write => writes,
+				WatchKind::ReadWrite => true,
+			};
+			// gdb only recognizes addresses inside the range it watches
+			(wanted && vaddr < addr.saturating_add(watch_len) && addr < end).then_some((kind, vaddr.max(addr)))
+		});
+	}
+}
+
+/// raises $trap if $offset isn't aligned to $size bytes and misaligned accesses are configured to trap
+macro_rules! check_alignment {
+	($self:ident, $offset:ident, $size:literal, $trap:expr) => {
+		if $self.misaligned_access == MisalignedAccess::Trap && $offset % $size != 0 {
+			$self.request_trap($trap, $offset);
+			return;
+		}
+	};
 }
 
 macro_rules! read_mem_u8 {
 	($self:ident, $offset:ident) => {
-		match $self.mem.read_u8($offset) {
+		match $self.read_virt_u8($offset, AccessType::Load) {
 			Ok(val) => val,
-			Err(addr) => {
-				$self.request_trap(TrapIdx::LOAD_PAGE_FAULT, addr);
+			Err((cause, addr)) => {
+				$self.request_trap(cause, addr);
 				return;
 			}
 		}
@@ -184,72 +825,76 @@ macro_rules! read_mem_u8 {
 }
 
 macro_rules! read_mem_u16 {
-	($self:ident, $offset:ident) => {
-		match $self.mem.read_u16($offset) {
+	($self:ident, $offset:ident) => {{
+		check_alignment!($self, $offset, 2, TrapIdx::LOAD_ADDR_MISALIGNED);
+		match $self.read_virt_u16($offset, AccessType::Load) {
 			Ok(val) => val,
-			Err(addr) => {
-				$self.request_trap(TrapIdx::LOAD_PAGE_FAULT, addr);
+			Err((cause, addr)) => {
+				$self.request_trap(cause, addr);
 				return;
 			}
 		}
-	};
+	}};
 }
 
 macro_rules! read_mem_u32 {
-	($self:ident, $offset:ident) => {
-		match $self.mem.read_u32($offset) {
+	($self:ident, $offset:ident) => {{
+		check_alignment!($self, $offset, 4, TrapIdx::LOAD_ADDR_MISALIGNED);
+		match $self.read_virt_u32($offset, AccessType::Load) {
 			Ok(val) => val,
-			Err(addr) => {
-				$self.request_trap(TrapIdx::LOAD_PAGE_FAULT, addr);
+			Err((cause, addr)) => {
+				$self.request_trap(cause, addr);
 				return;
 			}
 		}
-	};
+	}};
 }
 
 macro_rules! read_mem_u64 {
-	($self:ident, $offset:ident) => {
-		match $self.mem.read_u64($offset) {
+	($self:ident, $offset:ident) => {{
+		check_alignment!($self, $offset, 8, TrapIdx::LOAD_ADDR_MISALIGNED);
+		match $self.read_virt_u64($offset, AccessType::Load) {
 			Ok(val) => val,
-			Err(addr) => {
-				$self.request_trap(TrapIdx::LOAD_PAGE_FAULT, addr);
+			Err((cause, addr)) => {
+				$self.request_trap(cause, addr);
 				return;
 			}
 		}
-	};
+	}};
 }
 
 macro_rules! read_mem_float {
-	($self:ident, $offset:ident) => {
-		match $self.mem.read_soft_float($offset) {
+	($self:ident, $offset:ident) => {{
+		check_alignment!($self, $offset, 4, TrapIdx::LOAD_ADDR_MISALIGNED);
+		match $self.read_virt_soft_float($offset, AccessType::Load) {
 			Ok(val) => val,
-			Err(addr) => {
-				$self.request_trap(TrapIdx::LOAD_PAGE_FAULT, addr);
+			Err((cause, addr)) => {
+				$self.request_trap(cause, addr);
 				return;
 			}
 		}
-	};
+	}};
 }
 
-#[allow(unused)]
 macro_rules! read_mem_double {
-	($self:ident, $offset:ident) => {
-		match $self.mem.read_soft_double($offset) {
+	($self:ident, $offset:ident) => {{
+		check_alignment!($self, $offset, 8, TrapIdx::LOAD_ADDR_MISALIGNED);
+		match $self.read_virt_soft_double($offset, AccessType::Load) {
 			Ok(val) => val,
-			Err(addr) => {
-				$self.request_trap(TrapIdx::LOAD_PAGE_FAULT, addr);
+			Err((cause, addr)) => {
+				$self.request_trap(cause, addr);
 				return;
 			}
 		}
-	};
+	}};
 }
 
 macro_rules! write_mem_u8 {
 	($self:ident, $offset:ident, $val:ident) => {
-		match $self.mem.write_u8($offset, $val) {
+		match $self.write_virt_u8($offset, $val) {
 			Ok(()) => (),
-			Err(addr) => {
-				$self.request_trap(TrapIdx::STORE_PAGE_FAULT, addr);
+			Err((cause, addr)) => {
+				$self.request_trap(cause, addr);
 				return;
 			}
 		}
@@ -258,10 +903,11 @@ macro_rules! write_mem_u8 {
 
 macro_rules! write_mem_u16 {
 	($self:ident, $offset:ident, $val:ident) => {
-		match $self.mem.write_u16($offset, $val) {
+		check_alignment!($self, $offset, 2, TrapIdx::STORE_ADDR_MISALIGNED);
+		match $self.write_virt_u16($offset, $val) {
 			Ok(()) => (),
-			Err(addr) => {
-				$self.request_trap(TrapIdx::STORE_PAGE_FAULT, addr);
+			Err((cause, addr)) => {
+				$self.request_trap(cause, addr);
 				return;
 			}
 		}
@@ -270,10 +916,11 @@ macro_rules! write_mem_u16 {
 
 macro_rules! write_mem_u32 {
 	($self:ident, $offset:ident, $val:ident) => {
-		match $self.mem.write_u32($offset, $val) {
+		check_alignment!($self, $offset, 4, TrapIdx::STORE_ADDR_MISALIGNED);
+		match $self.write_virt_u32($offset, $val) {
 			Ok(()) => (),
-			Err(addr) => {
-				$self.request_trap(TrapIdx::STORE_PAGE_FAULT, addr);
+			Err((cause, addr)) => {
+				$self.request_trap(cause, addr);
 				return;
 			}
 		}
@@ -282,24 +929,101 @@ macro_rules! write_mem_u32 {
 
 macro_rules! write_mem_u64 {
 	($self:ident, $offset:ident, $val:ident) => {
-		match $self.mem.write_u64($offset, $val) {
+		check_alignment!($self, $offset, 8, TrapIdx::STORE_ADDR_MISALIGNED);
+		match $self.write_virt_u64($offset, $val) {
 			Ok(()) => (),
-			Err(addr) => {
-				$self.request_trap(TrapIdx::STORE_PAGE_FAULT, addr);
+			Err((cause, addr)) => {
+				$self.request_trap(cause, addr);
+				return;
+			}
+		}
+	};
+}
+
+/// reads the address of an atomic access from $reg and translates it
+/// raises a trap if it isn't naturally aligned to $size bytes or translation fails
+/// evaluates to (virtual address, physical address)
+macro_rules! atomic_addr {
+	($self:ident, $reg:ident, $size:literal, $access:expr) => {{
+		let vaddr = $self.registers.get($reg);
+		let size: u64 = $size;
+		if vaddr % size != 0 {
+			$self.request_trap($access.misaligned(), vaddr);
+			return;
+		}
+		// naturally aligned accesses never cross a page, so one translation covers all of them
+		match $self.translate(vaddr, $size, $access) {
+			Ok(paddr) => (vaddr, paddr),
+			Err((cause, tval)) => {
+				$self.request_trap(cause, tval);
+				return;
+			}
+		}
+	}};
+}
+
+/// unwraps the result of an atomic memory access, raising $trap with $vaddr on failure
+macro_rules! atomic_mem {
+	($self:ident, $trap:expr, $vaddr:ident, $access:expr) => {
+		match $access {
+			Ok(val) => val,
+			Err(_) => {
+				$self.request_trap($trap, $vaddr);
 				return;
 			}
 		}
 	};
 }
 
+/// the AMOs, generated per width and op so they all order their register accesses and sign extend rd the same way
+/// rs2 is read before the access so rd == rs2 still uses rs2, and rd gets the old value sign extended to XLEN
+macro_rules! amo {
+	(@op swap, $old:ident, $src:ident, $uty:ty, $sty:ty) => {{
+		// the old value only goes to rd
+		let _ = $old;
+		$src
+	}};
+	(@op add, $old:ident, $src:ident, $uty:ty, $sty:ty) => { $old.wrapping_add($src) };
+	(@op xor, $old:ident, $src:ident, $uty:ty, $sty:ty) => { $old ^ $src };
+	(@op and, $old:ident, $src:ident, $uty:ty, $sty:ty) => { $old & $src };
+	(@op or, $old:ident, $src:ident, $uty:ty, $sty:ty) => { $old | $src };
+	(@op min, $old:ident, $src:ident, $uty:ty, $sty:ty) => { std::cmp::min($old as $sty, $src as $sty) as $uty };
+	(@op max, $old:ident, $src:ident, $uty:ty, $sty:ty) => { std::cmp::max($old as $sty, $src as $sty) as $uty };
+	(@op minu, $old:ident, $src:ident, $uty:ty, $sty:ty) => { std::cmp::min($old, $src) };
+	(@op maxu, $old:ident, $src:ident, $uty:ty, $sty:ty) => { std::cmp::max($old, $src) };
+	($self:ident, byte, $op:ident, $src1:ident, $src2:ident, $dst:ident) => {
+		amo!($self, 1, u8, i8, atomic_op_byte, $op, $src1, $src2, $dst)
+	};
+	($self:ident, half, $op:ident, $src1:ident, $src2:ident, $dst:ident) => {
+		amo!($self, 2, u16, i16, atomic_op_half, $op, $src1, $src2, $dst)
+	};
+	($self:ident, word, $op:ident, $src1:ident, $src2:ident, $dst:ident) => {
+		amo!($self, 4, u32, i32, atomic_op_word, $op, $src1, $src2, $dst)
+	};
+	($self:ident, dword, $op:ident, $src1:ident, $src2:ident, $dst:ident) => {
+		amo!($self, 8, u64, i64, atomic_op_dword, $op, $src1, $src2, $dst)
+	};
+	($self:ident, $size:literal, $uty:ty, $sty:ty, $atomic_op:ident, $op:ident, $src1:ident, $src2:ident, $dst:ident) => {{
+		let (vaddr, addr) = atomic_addr!($self, $src1, $size, AccessType::Store);
+		let src = $self.registers.get($src2) as $uty;
+		let old = atomic_mem!(
+			$self,
+			TrapIdx::STORE_ACCESS_FAULT,
+			vaddr,
+			$self.mem.$atomic_op(addr, |old| Some(amo!(@op $op, old, src, $uty, $sty)))
+		);
+		$self.watch_access(vaddr, $size, WatchKind::ReadWrite);
+		$self.registers.set($dst, old as $sty as u64);
+	}};
+}
+
 /// gets a reference to the CSR specified by $addr
-/// raises an illegal instruction exception if the CSR does not exist
+/// raises an illegal instruction exception if the CSR does not exist or can't be accessed at the current privilege
 macro_rules! get_csr {
 	($self:ident, $addr:ident) => {
 		match $self.csrs.get($addr) {
-			// FIXME: check privilege
-			Some(info) => info,
-			None => {
+			Some(info) if $self.csr_accessible(info) => info,
+			_ => {
 				$self.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
 				return;
 			}
@@ -311,22 +1035,16 @@ macro_rules! get_csr {
 /// raises an illegal instruction exception if the CSR does not exist, is not writable,
 /// or could not be read or written at the current privilege
 macro_rules! get_csr_mut {
-	($self:ident, $addr:ident) => {
+	($self:ident, $addr:ident) => {{
+		get_csr!($self, $addr);
 		match $self.csrs.get_mut($addr) {
-			// FIXME: check privilege
-			Some(info) => {
-				if !info.is_rw() {
-					$self.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
-					return;
-				}
-				info
-			}
-			None => {
+			Some(info) if info.is_rw() => info,
+			_ => {
 				$self.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
 				return;
 			}
 		}
-	};
+	}};
 }
 
 impl WhiskerCpu {
@@ -365,19 +1083,160 @@ impl WhiskerCpu {
 		}
 	}
 
-	fn exec_trap(&mut self) -> Result<(), WhiskerExecStatus> {
-		let cause = self.csrs.read_mcause();
-		let mtval = self.csrs.read_mtval();
-		trace!("executing trap mcause={cause:#018X} mtval={mtval:#018X}");
-		let mtvec = self.csrs.read_mtvec();
-		trace!("trap handler at {mtvec:#018X}");
+	fn exec_trap(&mut self, cause: TrapIdx, tval: u64) -> Result<(), WhiskerExecStatus> {
+		let target = self.trap_target(cause);
+		trace!(
+			"executing trap cause={:#018X} tval={tval:#018X} in {target:?} mode",
+			cause.inner()
+		);
 
-		let start_pc = self.pc;
-		// TODO: there's a lot more CSRs that need to be set up properly here and in request_trap
-		self.pc = mtvec;
-		// make it so that the next execution cycle of the cpu doesn't go here
-		self.should_trap = false;
-		panic!("pc={:#08X}", start_pc);
+		// checked before anything changes, so gdb sees the hart as it was when the exception happened
+		if self.stop_on_fatal_traps
+			&& cause.kind() == TrapKind::Exception
+			&& !std::mem::take(&mut self.fatal_trap_reported)
+			&& !self.can_run_handler(cause, target)
+		{
+			log!(self, "  the trap handler can't be fetched, stopping");
+			self.fatal_trap_reported = true;
+			self.pending_trap = Some((cause, tval));
+			return Err(WhiskerExecStatus::FatalTrap(cause));
+		}
+
+		// the pc still points at the instruction that trapped, or the next one to execute for interrupts
+		let epc = self.pc;
+		let mut status = Mstatus(self.csrs.read_mstatus());
+		let tvec = match target {
+			PrivilegeMode::Machine => {
+				self.csrs.write_mepc(epc);
+				// trap causes have the high bit set if they are an interrupt, or unset for exceptions
+				self.csrs.write_mcause(cause.inner());
+				self.csrs.write_mtval(tval);
+				status.set_mpie(status.mie());
+				status.set_mie(false);
+				status.set_mpp(self.privilege);
+				self.csrs.read_mtvec()
+			}
+			PrivilegeMode::Supervisor => {
+				self.csrs.write_sepc(epc);
+				self.csrs.write_scause(cause.inner());
+				self.csrs.write_stval(tval);
+				status.set_spie(status.sie());
+				status.set_sie(false);
+				status.set_spp(self.privilege);
+				self.csrs.read_stvec()
+			}
+			PrivilegeMode::User => unreachable!("traps are never taken in U-mode"),
+		};
+		let handler = trap_handler_addr(tvec, cause);
+		trace!("trap handler at {handler:#018X}");
+
+		// an exception on the very first instruction of the handler means it can never run
+		// the hart keeps trapping like real hardware would, but it only gets reported once
+		let prev = self
+			.last_trap
+			.filter(|prev| cause.kind() == TrapKind::Exception && prev.handler == epc);
+		let double_fault = prev.is_some();
+		if let Some(prev) = prev.filter(|prev| !prev.double_fault) {
+			self.report_double_fault(prev, cause, tval, epc);
+		}
+		self.last_trap = Some(TrapRecord {
+			cause,
+			tval,
+			epc,
+			privilege: self.privilege,
+			handler,
+			double_fault,
+		});
+
+		self.csrs.write_mstatus(status.0);
+		self.privilege = target;
+
+		self.pc = handler;
+		// an SC can't succeed on a reservation taken before the trap
+		self.mem.release_reservation(self.hart_id);
+		Ok(())
+	}
+
+	/// whether the first instruction of the handler for cause could be fetched in the mode the trap goes to
+	fn can_run_handler(&mut self, cause: TrapIdx, target: PrivilegeMode) -> bool {
+		let tvec = match target {
+			PrivilegeMode::Machine => self.csrs.read_mtvec(),
+			PrivilegeMode::Supervisor => self.csrs.read_stvec(),
+			PrivilegeMode::User => unreachable!("traps are never taken in U-mode"),
+		};
+		let handler = trap_handler_addr(tvec, cause);
+		let current = std::mem::replace(&mut self.privilege, target);
+		let fetchable = self.translate(handler, 2, AccessType::Fetch).is_ok();
+		self.privilege = current;
+		fetchable
+	}
+
+	fn report_poisoned_access(&mut self, pc: u64, access: &PoisonedAccess) {
+		let pc = self.symbols.describe(pc);
+		let kind = if access.write { "wrote" } else { "read" };
+		let value = access.value();
+		let len = access.data.len();
+		error!(
+			"poisoned memory: the instruction at {pc} {kind} {len} bytes at {:#018X}, value={value}",
+			access.addr
+		);
+		log!(
+			self,
+			"  POISONED MEMORY: the instruction at {} {} {} bytes at {:#018X}, value={}",
+			pc,
+			kind,
+			len,
+			access.addr,
+			value
+		);
+	}
+
+	fn report_double_fault(&mut self, prev: TrapRecord, cause: TrapIdx, tval: u64, epc: u64) {
+		let handler = self.symbols.describe(epc);
+		let first_epc = self.symbols.describe(prev.epc);
+		error!(
+			"double fault: the trap handler at {handler} faulted before running, cause={:#018X} tval={tval:#018X}",
+			cause.inner()
+		);
+		log!(
+			self,
+			"  DOUBLE FAULT: the trap handler at {} faulted before running",
+			handler
+		);
+		log!(
+			self,
+			"    first trap:  cause={:#018X} tval={:#018X} epc={} from {:?} mode",
+			prev.cause.inner(),
+			prev.tval,
+			first_epc,
+			prev.privilege
+		);
+		log!(
+			self,
+			"    second trap: cause={:#018X} tval={:#018X} epc={} from {:?} mode",
+			cause.inner(),
+			tval,
+			handler,
+			self.privilege
+		);
+	}
+
+	/// the privilege level that handles a trap, medeleg and mideleg can hand traps from S and U-mode to S-mode
+	/// traps never go to a lower privilege level than the one they happened in
+	fn trap_target(&self, cause: TrapIdx) -> PrivilegeMode {
+		if self.privilege == PrivilegeMode::Machine {
+			return PrivilegeMode::Machine;
+		}
+
+		let delegated = match cause.kind() {
+			TrapKind::Interrupt => self.csrs.read_mideleg(),
+			TrapKind::Exception => self.csrs.read_medeleg(),
+		};
+		if delegated & (1 << cause.code()) != 0 {
+			PrivilegeMode::Supervisor
+		} else {
+			PrivilegeMode::Machine
+		}
 	}
 
 	fn execute_i_insn(&mut self, insn: IntInstruction, start_pc: u64) {
@@ -410,26 +1269,20 @@ impl WhiskerCpu {
 			}
 			IntInstruction::LoadByte { dst, src, src_offset } => {
 				let offset = self.registers.get(src).wrapping_add_signed(src_offset);
-				let val = read_mem_u8!(self, offset) as u64;
-
-				let reg_val = self.registers.get(dst);
-				let val = (reg_val & 0xFFFFFFFF_FFFFFF00) | val;
+				// sign extended into the whole register
+				let val = read_mem_u8!(self, offset) as i8 as i64 as u64;
 				self.registers.set(dst, val);
 			}
 			IntInstruction::LoadHalf { dst, src, src_offset } => {
 				let offset = self.registers.get(src).wrapping_add_signed(src_offset);
-				let val = read_mem_u16!(self, offset) as u64;
-
-				let reg_val = self.registers.get(dst);
-				let val = (reg_val & 0xFFFFFFFF_FFFF0000) | val;
+				// sign extended into the whole register
+				let val = read_mem_u16!(self, offset) as i16 as i64 as u64;
 				self.registers.set(dst, val);
 			}
 			IntInstruction::LoadWord { dst, src, src_offset } => {
 				let offset = self.registers.get(src).wrapping_add_signed(src_offset);
-				let val = read_mem_u32!(self, offset) as u64;
-
-				let reg_val = self.registers.get(dst);
-				let val = (reg_val & 0xFFFFFFFF_00000000) | val;
+				// sign extended into the whole register
+				let val = read_mem_u32!(self, offset) as i32 as i64 as u64;
 				self.registers.set(dst, val);
 			}
 			IntInstruction::LoadDoubleWord { dst, src, src_offset } => {
@@ -453,8 +1306,11 @@ impl WhiskerCpu {
 				self.registers.set(dst, val);
 			}
 			IntInstruction::JumpAndLink { link_reg, jmp_off } => {
-				self.registers.set(link_reg, start_pc + 4);
-				self.pc = start_pc.wrapping_add_signed(jmp_off);
+				// pc already points past this instruction, which also handles the compressed forms
+				let link = self.pc;
+				if self.jump_to(start_pc.wrapping_add_signed(jmp_off)) {
+					self.registers.set(link_reg, link);
+				}
 			}
 			IntInstruction::Add { dst, lhs, rhs } => {
 				let lhs = self.registers.get(lhs);
@@ -506,13 +1362,27 @@ impl WhiskerCpu {
 				let rhs = self.registers.get(rhs);
 				self.registers.set(dst, (lhs < rhs) as u64);
 			}
+			IntInstruction::ConditionalZeroIfEqualZero { dst, lhs, rhs } => {
+				let lhs = self.registers.get(lhs);
+				let rhs = self.registers.get(rhs);
+				self.registers.set(dst, if rhs == 0 { 0 } else { lhs });
+			}
+			IntInstruction::ConditionalZeroIfNotEqualZero { dst, lhs, rhs } => {
+				let lhs = self.registers.get(lhs);
+				let rhs = self.registers.get(rhs);
+				self.registers.set(dst, if rhs != 0 { 0 } else { lhs });
+			}
 			IntInstruction::JumpAndLinkRegister {
 				link_reg,
 				jmp_reg,
 				jmp_off,
 			} => {
-				self.registers.set(link_reg, start_pc + 4);
-				self.pc = self.registers.get(jmp_reg).wrapping_add_signed(jmp_off) & !1;
+				let link = self.pc;
+				// read before writing the link register in case they're the same register
+				let target = self.registers.get(jmp_reg).wrapping_add_signed(jmp_off) & !1;
+				if self.jump_to(target) {
+					self.registers.set(link_reg, link);
+				}
 			}
 
 			IntInstruction::AddImmediate { dst, lhs, rhs } => {
@@ -560,11 +1430,15 @@ impl WhiskerCpu {
 			}
 			IntInstruction::ShiftLeftLogicalImmediateWord { dst, lhs, shift_amt } => {
 				let lhs = self.registers.get(lhs) as u32;
-				self.registers.set(dst, lhs.wrapping_shl(shift_amt) as u64);
+				let result = lhs.wrapping_shl(shift_amt);
+				// sign extend
+				self.registers.set(dst, (result as i32) as i64 as u64);
 			}
 			IntInstruction::ShiftRightLogicalImmediateWord { dst, lhs, shift_amt } => {
 				let lhs = self.registers.get(lhs) as u32;
-				self.registers.set(dst, lhs.wrapping_shr(shift_amt) as u64);
+				let result = lhs.wrapping_shr(shift_amt);
+				// sign extend
+				self.registers.set(dst, (result as i32) as i64 as u64);
 			}
 			IntInstruction::ShiftRightArithmeticImmediateWord { dst, lhs, shift_amt } => {
 				let lhs = self.registers.get(lhs) as i32;
@@ -576,44 +1450,48 @@ impl WhiskerCpu {
 			// ============
 			IntInstruction::BranchEqual { lhs, rhs, imm } => {
 				if self.registers.get(lhs) == self.registers.get(rhs) {
-					self.pc = start_pc.wrapping_add_signed(imm);
+					self.jump_to(start_pc.wrapping_add_signed(imm));
 				}
 			}
 			IntInstruction::BranchNotEqual { lhs, rhs, imm } => {
 				if self.registers.get(lhs) != self.registers.get(rhs) {
-					self.pc = start_pc.wrapping_add_signed(imm);
+					self.jump_to(start_pc.wrapping_add_signed(imm));
 				}
 			}
 			IntInstruction::BranchLessThan { lhs, rhs, imm } => {
 				if (self.registers.get(lhs) as i64) < self.registers.get(rhs) as i64 {
-					self.pc = start_pc.wrapping_add_signed(imm);
+					self.jump_to(start_pc.wrapping_add_signed(imm));
 				}
 			}
 			IntInstruction::BranchGreaterEqual { lhs, rhs, imm } => {
 				if (self.registers.get(lhs) as i64) >= self.registers.get(rhs) as i64 {
-					self.pc = start_pc.wrapping_add_signed(imm);
+					self.jump_to(start_pc.wrapping_add_signed(imm));
 				}
 			}
 			IntInstruction::BranchLessThanUnsigned { lhs, rhs, imm } => {
 				if self.registers.get(lhs) < self.registers.get(rhs) {
-					self.pc = start_pc.wrapping_add_signed(imm);
+					self.jump_to(start_pc.wrapping_add_signed(imm));
 				}
 			}
 			IntInstruction::BranchGreaterEqualUnsigned { lhs, rhs, imm } => {
 				if self.registers.get(lhs) >= self.registers.get(rhs) {
-					self.pc = start_pc.wrapping_add_signed(imm);
+					self.jump_to(start_pc.wrapping_add_signed(imm));
 				}
 			}
 
 			IntInstruction::AddWord { lhs, rhs, dst } => {
 				let lhs = self.registers.get(lhs) as u32;
 				let rhs = self.registers.get(rhs) as u32;
-				self.registers.set(dst, lhs.wrapping_add(rhs) as u64);
+				let result = lhs.wrapping_add(rhs);
+				// sign extension
+				self.registers.set(dst, (result as i32) as i64 as u64);
 			}
 			IntInstruction::SubWord { lhs, rhs, dst } => {
 				let lhs = self.registers.get(lhs) as u32;
 				let rhs = self.registers.get(rhs) as u32;
-				self.registers.set(dst, lhs.wrapping_sub(rhs) as u64);
+				let result = lhs.wrapping_sub(rhs);
+				// sign extension
+				self.registers.set(dst, (result as i32) as i64 as u64);
 			}
 			// These only use the lower 5 bits of the rhs register for shamt
 			IntInstruction::ShiftLeftLogicalWord { lhs, rhs, dst } => {
@@ -638,21 +1516,136 @@ impl WhiskerCpu {
 				self.registers.set(dst, result as i64 as u64);
 			}
 
+			// ============
+			// MISC-MEM
+			// ============
+			// there is a single hart executing in program order, loads and stores are never reordered
+			// and decoded instructions are not cached, so all fences are already satisfied
+			IntInstruction::Fence { .. } | IntInstruction::FenceTso | IntInstruction::FenceInstruction => {}
+			// the guest is spinning, let the host run something else for a moment
+			IntInstruction::Pause => std::thread::yield_now(),
+
 			// =========
 			// SYSTEM
 			// =========
 			IntInstruction::ECall => {
-				// TODO: handle different modes
-				self.request_trap(TrapIdx::ECALL_MMODE, 0);
+				let cause = match self.privilege {
+					PrivilegeMode::User => TrapIdx::ECALL_UMODE,
+					PrivilegeMode::Supervisor => TrapIdx::ECALL_SMODE,
+					PrivilegeMode::Machine => TrapIdx::ECALL_MMODE,
+				};
+				self.request_trap(cause, 0);
 			}
 			IntInstruction::EBreak => {
-				// TODO: should this do anything else?
+				if self.semihosting.is_some() && self.is_semihosting_call(start_pc) {
+					self.semihosting_call();
+					return;
+				}
+				if self.exit_on_ebreak {
+					self.power_off_line
+						.request(self.registers.get(GPRegisterIndex::A0) as i32);
+					return;
+				}
 				self.request_trap(TrapIdx::BREAKPOINT, 0);
 			}
+			IntInstruction::WaitForInterrupt => {
+				// we never time out waiting, so WFI is always illegal where TW would trap it
+				let status = Mstatus(self.csrs.read_mstatus());
+				let trapped_by_tw = self.privilege < PrivilegeMode::Machine && status.tw();
+				let user_with_supervisor = self.privilege == PrivilegeMode::User
+					&& self.supported_extensions.has(SupportedExtensions::SUPERVISOR);
+				if trapped_by_tw || user_with_supervisor {
+					self.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+					return;
+				}
+				self.waiting_on_reservation = false;
+				self.exec_state = WhiskerExecState::Halted;
+			}
+			IntInstruction::WaitOnReservationSet { short_timeout } => {
+				// there's no stall without a reservation to wait on
+				if !self.mem.has_reservation(self.hart_id) {
+					return;
+				}
+				// the short timeout just lets the host do something else for a moment
+				if short_timeout {
+					std::thread::yield_now();
+					return;
+				}
+				// like WFI we never time out, so TW makes this illegal below M-mode
+				let status = Mstatus(self.csrs.read_mstatus());
+				if self.privilege < PrivilegeMode::Machine && status.tw() {
+					self.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+					return;
+				}
+				// the halted loop wakes the hart once gdb or htif write the reservation set
+				self.waiting_on_reservation = true;
+				self.exec_state = WhiskerExecState::Halted;
+			}
+			IntInstruction::SupervisorFenceVirtualMemory { vaddr, asid } => {
+				// mstatus.TVM lets M-mode take over the page tables
+				let trapped_by_tvm =
+					self.privilege == PrivilegeMode::Supervisor && Mstatus(self.csrs.read_mstatus()).tvm();
+				if !self.supported_extensions.has(SupportedExtensions::SUPERVISOR)
+					|| self.privilege == PrivilegeMode::User
+					|| trapped_by_tvm
+				{
+					self.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+					return;
+				}
+
+				let vaddr = (vaddr != GPRegisterIndex::ZERO).then(|| self.registers.get(vaddr));
+				let asid = (asid != GPRegisterIndex::ZERO).then(|| self.registers.get(asid) & 0xFFFF);
+				self.tlb.flush(vaddr, asid);
+			}
+			IntInstruction::MachineReturn => {
+				if self.privilege != PrivilegeMode::Machine {
+					self.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+					return;
+				}
+
+				let mut status = Mstatus(self.csrs.read_mstatus());
+				let prev_privilege = status.mpp().expect("MPP always holds a legal mode");
+				status.set_mie(status.mpie());
+				status.set_mpie(true);
+				// MPP is left holding the least privileged mode we support
+				status.set_mpp(self.least_privileged_mode());
+
+				let epc = self.csrs.read_mepc();
+				self.exec_trap_return(status, prev_privilege, epc);
+			}
+			IntInstruction::SupervisorReturn => {
+				let mut status = Mstatus(self.csrs.read_mstatus());
+				let trapped_by_tsr = self.privilege == PrivilegeMode::Supervisor && status.tsr();
+				if !self.supported_extensions.has(SupportedExtensions::SUPERVISOR)
+					|| self.privilege == PrivilegeMode::User
+					|| trapped_by_tsr
+				{
+					self.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+					return;
+				}
+
+				let prev_privilege = status.spp();
+				status.set_sie(status.spie());
+				status.set_spie(true);
+				status.set_spp(PrivilegeMode::User);
+
+				let epc = self.csrs.read_sepc();
+				self.exec_trap_return(status, prev_privilege, epc);
+			}
 		}
 	}
 
 	fn execute_f_insn(&mut self, insn: FloatInstruction, _start_pc: u64) {
+		if !self.float_enabled() {
+			self.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+			return;
+		}
+		// a dynamic rounding mode is illegal if frm holds a reserved value
+		if insn.rounding_mode() == Some(RoundingMode::Dynamic) && RoundingMode::from_frm(self).is_none() {
+			self.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+			return;
+		}
+
 		match insn {
 			FloatInstruction::LoadWord { dst, src, src_offset } => {
 				let offset = self.registers.get(src).wrapping_add_signed(src_offset);
@@ -689,14 +1682,124 @@ impl WhiskerCpu {
 				let result = mul_lhs.mul_add(&mul_rhs, &add, rm, self);
 				self.fp_registers.set_float(dst, result);
 			}
-			FloatInstruction::Mul { dst, lhs, rhs, rm } => {
-				let lhs = self.fp_registers.get_float(lhs);
-				let rhs = self.fp_registers.get_float(rhs);
-				let result = lhs.mul(&rhs, rm, self);
-				self.fp_registers.set_float(dst, result);
-			}
-			FloatInstruction::Div { dst, lhs, rhs, rm } => {
-				let lhs = self.fp_registers.get_float(lhs);
+			FloatInstruction::MulSub {
+				dst,
+				mul_lhs,
+				mul_rhs,
+				sub,
+				rm,
+			} => {
+				let mul_lhs = self.fp_registers.get_float(mul_lhs);
+				let mul_rhs = self.fp_registers.get_float(mul_rhs);
+				let sub = self.fp_registers.get_float(sub);
+				let result = mul_lhs.mul_add(&mul_rhs, &sub.neg(), rm, self);
+				self.fp_registers.set_float(dst, result);
+			}
+			FloatInstruction::NegMulSub {
+				dst,
+				mul_lhs,
+				mul_rhs,
+				sub,
+				rm,
+			} => {
+				let mul_lhs = self.fp_registers.get_float(mul_lhs);
+				let mul_rhs = self.fp_registers.get_float(mul_rhs);
+				let sub = self.fp_registers.get_float(sub);
+				let result = mul_lhs.neg().mul_add(&mul_rhs, &sub, rm, self);
+				self.fp_registers.set_float(dst, result);
+			}
+			FloatInstruction::NegMulAdd {
+				dst,
+				mul_lhs,
+				mul_rhs,
+				add,
+				rm,
+			} => {
+				let mul_lhs = self.fp_registers.get_float(mul_lhs);
+				let mul_rhs = self.fp_registers.get_float(mul_rhs);
+				let add = self.fp_registers.get_float(add);
+				let result = mul_lhs.neg().mul_add(&mul_rhs, &add.neg(), rm, self);
+				self.fp_registers.set_float(dst, result);
+			}
+			FloatInstruction::Classify { dst, src } => {
+				let class = self.fp_registers.get_float(src).fclass();
+				self.registers.set(dst, u64::from(class.to_shift()));
+			}
+			FloatInstruction::MoveToInt { dst, src } => {
+				// the low 32 bits are moved as is, even if the register isn't properly NaN boxed
+				let val = self.fp_registers.get_raw(src) as u32;
+				self.registers.set(dst, val as i32 as i64 as u64);
+			}
+			FloatInstruction::MoveFromInt { dst, src } => {
+				let val = self.registers.get(src) as u32;
+				self.fp_registers.set_float(dst, SoftFloat::from_u32(val));
+			}
+			FloatInstruction::SignInject { dst, lhs, rhs } => {
+				let lhs = self.fp_registers.get_float(lhs).to_u32();
+				let rhs = self.fp_registers.get_float(rhs).to_u32();
+				let sign_bit = 1 << (u32::BITS - 1);
+				let result = (lhs & !sign_bit) | (rhs & sign_bit);
+				self.fp_registers.set_float(dst, SoftFloat::from_u32(result));
+			}
+			FloatInstruction::SignInjectNegate { dst, lhs, rhs } => {
+				let lhs = self.fp_registers.get_float(lhs).to_u32();
+				let rhs = self.fp_registers.get_float(rhs).to_u32();
+				let sign_bit = 1 << (u32::BITS - 1);
+				let result = (lhs & !sign_bit) | (!rhs & sign_bit);
+				self.fp_registers.set_float(dst, SoftFloat::from_u32(result));
+			}
+			FloatInstruction::SignInjectXor { dst, lhs, rhs } => {
+				let lhs = self.fp_registers.get_float(lhs).to_u32();
+				let rhs = self.fp_registers.get_float(rhs).to_u32();
+				let sign_bit = 1 << (u32::BITS - 1);
+				let result = lhs ^ (rhs & sign_bit);
+				self.fp_registers.set_float(dst, SoftFloat::from_u32(result));
+			}
+			FloatInstruction::ConvertToWord { dst, src, rm } => {
+				let val = self.fp_registers.get_float(src).convert_to_i32(rm, self);
+				self.registers.set(dst, val as i64 as u64);
+			}
+			FloatInstruction::ConvertToUnsignedWord { dst, src, rm } => {
+				let val = self.fp_registers.get_float(src).convert_to_u32(rm, self);
+				// the 32bit result is sign extended even for the unsigned variant
+				self.registers.set(dst, val as i32 as i64 as u64);
+			}
+			FloatInstruction::ConvertToDoubleWord { dst, src, rm } => {
+				let val = self.fp_registers.get_float(src).convert_to_i64(rm, self);
+				self.registers.set(dst, val as u64);
+			}
+			FloatInstruction::ConvertToUnsignedDoubleWord { dst, src, rm } => {
+				let val = self.fp_registers.get_float(src).convert_to_u64(rm, self);
+				self.registers.set(dst, val);
+			}
+			FloatInstruction::ConvertFromWord { dst, src, rm } => {
+				let val = self.registers.get(src) as i32;
+				let result = SoftFloat::convert_from_i32(val, rm, self);
+				self.fp_registers.set_float(dst, result);
+			}
+			FloatInstruction::ConvertFromUnsignedWord { dst, src, rm } => {
+				let val = self.registers.get(src) as u32;
+				let result = SoftFloat::convert_from_u32(val, rm, self);
+				self.fp_registers.set_float(dst, result);
+			}
+			FloatInstruction::ConvertFromDoubleWord { dst, src, rm } => {
+				let val = self.registers.get(src) as i64;
+				let result = SoftFloat::convert_from_i64(val, rm, self);
+				self.fp_registers.set_float(dst, result);
+			}
+			FloatInstruction::ConvertFromUnsignedDoubleWord { dst, src, rm } => {
+				let val = self.registers.get(src);
+				let result = SoftFloat::convert_from_u64(val, rm, self);
+				self.fp_registers.set_float(dst, result);
+			}
+			FloatInstruction::Mul { dst, lhs, rhs, rm } => {
+				let lhs = self.fp_registers.get_float(lhs);
+				let rhs = self.fp_registers.get_float(rhs);
+				let result = lhs.mul(&rhs, rm, self);
+				self.fp_registers.set_float(dst, result);
+			}
+			FloatInstruction::Div { dst, lhs, rhs, rm } => {
+				let lhs = self.fp_registers.get_float(lhs);
 				let rhs = self.fp_registers.get_float(rhs);
 				let result = lhs.div(&rhs, rm, self);
 				self.fp_registers.set_float(dst, result);
@@ -706,28 +1809,16 @@ impl WhiskerCpu {
 				self.fp_registers.set_float(dst, result);
 			}
 			FloatInstruction::Min { dst, lhs, rhs } => {
-				// TODO: Fix this implementation
-				// PAGE: 115
-				warn!("FMIN.S Implementation is incorrect");
 				let lhs = self.fp_registers.get_float(lhs);
 				let rhs = self.fp_registers.get_float(rhs);
-				if lhs.lt(&rhs) {
-					self.fp_registers.set_float(dst, lhs);
-				} else {
-					self.fp_registers.set_float(dst, rhs);
-				}
+				let result = lhs.min(&rhs, self);
+				self.fp_registers.set_float(dst, result);
 			}
 			FloatInstruction::Max { dst, lhs, rhs } => {
-				// TODO: Fix this implementation
-				// PAGE: 115
-				warn!("FMAX.S Implementation is incorrect");
 				let lhs = self.fp_registers.get_float(lhs);
 				let rhs = self.fp_registers.get_float(rhs);
-				if lhs.gt(&rhs) {
-					self.fp_registers.set_float(dst, lhs);
-				} else {
-					self.fp_registers.set_float(dst, rhs);
-				}
+				let result = lhs.max(&rhs, self);
+				self.fp_registers.set_float(dst, result);
 			}
 			FloatInstruction::Equal { dst, lhs, rhs } => {
 				//FEQ.S performs a quiet comparison:
@@ -743,8 +1834,7 @@ impl WhiskerCpu {
 					self.registers.set(dst, 0);
 					// if either input was sNaN, write invalid operation
 					if lhs.is_snan() || rhs.is_snan() {
-						let val = self.csrs.read_fcsr() | u64::from(ExceptionFlags::FLAG_INVALID);
-						self.csrs.write_fcsr(val);
+						self.accrue_fflags(ExceptionFlags::FLAG_INVALID);
 					}
 					return;
 				};
@@ -760,8 +1850,7 @@ impl WhiskerCpu {
 				// the partial_cmp here returns None if either lhs or rhs is nan
 				let Some(cmp) = lhs.partial_cmp(&rhs) else {
 					self.registers.set(dst, 0);
-					let val = self.csrs.read_fcsr() | u64::from(ExceptionFlags::FLAG_INVALID);
-					self.csrs.write_fcsr(val);
+					self.accrue_fflags(ExceptionFlags::FLAG_INVALID);
 					return;
 				};
 
@@ -774,8 +1863,7 @@ impl WhiskerCpu {
 				// the partial_cmp here returns None if either lhs or rhs is nan
 				let Some(cmp) = lhs.partial_cmp(&rhs) else {
 					self.registers.set(dst, 0);
-					let val = self.csrs.read_fcsr() | u64::from(ExceptionFlags::FLAG_INVALID);
-					self.csrs.write_fcsr(val);
+					self.accrue_fflags(ExceptionFlags::FLAG_INVALID);
 					return;
 				};
 
@@ -785,505 +1873,1003 @@ impl WhiskerCpu {
 		}
 	}
 
-	fn exec_csr(&mut self, insn: CSRInstruction, _start_pc: u64) {
-		// FIXME: ordering of effects on registers and traps???
-		match insn {
-			CSRInstruction::CSRReadWrite { dst, src, csr } => {
-				let csr_info = get_csr_mut!(self, csr);
-				// reads dont happen when dst is zero
-				if dst != GPRegisterIndex::ZERO {
-					self.registers.set(dst, csr_info.val);
-				}
-				csr_info.val = self.registers.get(src);
-			}
-			CSRInstruction::CSRReadAndSet { dst, mask, csr } => {
-				// we must not check for writability if the mask register is x0
-				if mask != GPRegisterIndex::ZERO {
-					let csr = get_csr_mut!(self, csr);
-					self.registers.set(dst, csr.val);
-					csr.val |= self.registers.get(mask);
-				} else {
-					let csr = get_csr!(self, csr);
-					self.registers.set(dst, csr.val);
-				}
-			}
-			CSRInstruction::CSRReadAndClear { dst, mask, csr } => {
-				// we must not check for writability if the mask register is x0
-				if mask != GPRegisterIndex::ZERO {
-					let csr = get_csr_mut!(self, csr);
-					self.registers.set(dst, csr.val);
-					csr.val &= self.registers.get(mask);
-				} else {
-					let csr = get_csr!(self, csr);
-					self.registers.set(dst, csr.val);
-				}
-			}
-			CSRInstruction::CSRReadWriteImm { dst, src, csr } => {
-				let csr_info = get_csr_mut!(self, csr);
-				// reads dont happen when dst is zero
-				if dst != GPRegisterIndex::ZERO {
-					self.registers.set(dst, csr_info.val);
-				}
-				csr_info.val = src;
-			}
-			CSRInstruction::CSRReadAndSetImm { dst, mask, csr } => {
-				// we must not check for writability if the mask is 0
-				if mask != 0 {
-					let csr = get_csr_mut!(self, csr);
-					self.registers.set(dst, csr.val);
-					csr.val |= mask;
-				} else {
-					let csr = get_csr!(self, csr);
-					self.registers.set(dst, csr.val);
-				}
-			}
-			CSRInstruction::CSRReadAndClearImm { dst, mask, csr } => {
-				// we must not check for writability if the mask is 0
-				if mask != 0 {
-					let csr = get_csr_mut!(self, csr);
-					self.registers.set(dst, csr.val);
-					csr.val &= mask;
-				} else {
-					let csr = get_csr!(self, csr);
-					self.registers.set(dst, csr.val);
-				}
-			}
+	fn execute_d_insn(&mut self, insn: DoubleInstruction, _start_pc: u64) {
+		if !self.float_enabled() {
+			self.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+			return;
 		}
-	}
-
-	fn exec_compressed_insn(&mut self, insn: CompressedInstruction, _start_pc: u64) {
-		match insn {
-			// this nop is special in that it's designated as an explicit NOP for future standard use
-			// so it cannot be combined into an integer instruction
-			CompressedInstruction::Nop => {}
+		// a dynamic rounding mode is illegal if frm holds a reserved value
+		if insn.rounding_mode() == Some(RoundingMode::Dynamic) && RoundingMode::from_frm(self).is_none() {
+			self.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+			return;
 		}
-	}
 
-	fn exec_atomic_insn(&mut self, insn: AtomicInstruction, _start_pc: u64) {
-		// TODO: For now we'll be ignoring the aq: _ and rl: _ bits as it requires fencing logic and other things we do-
-		// not currently implement.
-		const HART_ID: usize = 0;
 		match insn {
-			AtomicInstruction::LoadReservedWord { src, dst, _aq, _rl } => {
-				let addr = self.registers.get(src);
-
-				let val = self
-					.mem
-					.load_reserved_word(addr, HART_ID)
-					.expect("addr to be in physmem");
-
-				self.registers.set(dst, val as u64);
+			DoubleInstruction::LoadDoubleWord { dst, src, src_offset } => {
+				let offset = self.registers.get(src).wrapping_add_signed(src_offset);
+				let val = read_mem_double!(self, offset);
+				self.fp_registers.set_double(dst, val);
 			}
-			AtomicInstruction::StoreConditionalWord {
-				src1,
-				src2,
-				dst,
-				_aq,
-				_rl,
-			} => {
-				let addr = self.registers.get(src1);
-				let val = self.registers.get(src2) as u32;
-				let success = self
-					.mem
-					.store_conditional_word(addr, HART_ID, val)
-					.expect("addr to be in physmem");
-				if success {
-					self.registers.set(dst, 0);
-				} else {
-					// TODO: From the little bit I read it says "nonzero code on failure"
-					// We should figure out what that value should be, unless it's arbitrary
-					self.registers.set(dst, 1);
-				}
+			DoubleInstruction::StoreDoubleWord { dst, dst_offset, src } => {
+				let offset = self.registers.get(dst).wrapping_add_signed(dst_offset);
+				let val = self.fp_registers.get_double(src).to_u64();
+				write_mem_u64!(self, offset, val);
 			}
-			AtomicInstruction::SwapWord {
-				src1,
-				src2,
-				dst,
-				_aq,
-				_rl,
-			} => {
-				let addr = self.registers.get(src1);
-				self.mem
-					.atomic_op_word(addr, |word| {
-						// put (src1) value into rd
-						self.registers.set(dst, u64::from(word));
-
-						// swap src2 to (src1)
-						let src2_val = self.registers.get(src2);
-						Some(src2_val as u32)
-					})
-					.expect("addr to be in physmem");
+			DoubleInstruction::Add { dst, lhs, rhs, rm } => {
+				let lhs = self.fp_registers.get_double(lhs);
+				let rhs = self.fp_registers.get_double(rhs);
+				let result = lhs.add(&rhs, rm, self);
+				self.fp_registers.set_double(dst, result);
 			}
-			AtomicInstruction::AddWord {
-				src1,
-				src2,
+			DoubleInstruction::Sub { dst, lhs, rhs, rm } => {
+				let lhs = self.fp_registers.get_double(lhs);
+				let rhs = self.fp_registers.get_double(rhs);
+				let result = lhs.sub(&rhs, rm, self);
+				self.fp_registers.set_double(dst, result);
+			}
+			DoubleInstruction::MulAdd {
 				dst,
-				_aq,
-				_rl,
+				mul_lhs,
+				mul_rhs,
+				add,
+				rm,
 			} => {
-				let addr = self.registers.get(src1);
-				self.mem
-					.atomic_op_word(addr, |word| {
-						// put (src1) value into rd
-						self.registers.set(dst, u64::from(word));
-
-						// add src2 value to (src1)
-						let src2_val = self.registers.get(src2) as u32;
-						let new_val = word.wrapping_add(src2_val);
-						Some(new_val)
-					})
-					.expect("addr to be in physmem");
+				let mul_lhs = self.fp_registers.get_double(mul_lhs);
+				let mul_rhs = self.fp_registers.get_double(mul_rhs);
+				let add = self.fp_registers.get_double(add);
+				let result = mul_lhs.mul_add(&mul_rhs, &add, rm, self);
+				self.fp_registers.set_double(dst, result);
 			}
-			AtomicInstruction::XorWord {
-				src1,
-				src2,
+			DoubleInstruction::MulSub {
 				dst,
-				_aq,
-				_rl,
+				mul_lhs,
+				mul_rhs,
+				sub,
+				rm,
 			} => {
-				let addr = self.registers.get(src1);
-				self.mem
-					.atomic_op_word(addr, |word| {
-						// put (src1) value into rd
-						self.registers.set(dst, u64::from(word));
-
-						// xor src2 value with (src1)
-						let src2_val = self.registers.get(src2) as u32;
-						let new_val = word ^ src2_val;
-						Some(new_val)
-					})
-					.expect("addr to be in physmem");
+				let mul_lhs = self.fp_registers.get_double(mul_lhs);
+				let mul_rhs = self.fp_registers.get_double(mul_rhs);
+				let sub = self.fp_registers.get_double(sub);
+				let result = mul_lhs.mul_add(&mul_rhs, &sub.neg(), rm, self);
+				self.fp_registers.set_double(dst, result);
 			}
-			AtomicInstruction::AndWord {
-				src1,
-				src2,
+			DoubleInstruction::NegMulSub {
 				dst,
-				_aq,
-				_rl,
+				mul_lhs,
+				mul_rhs,
+				sub,
+				rm,
 			} => {
-				let addr = self.registers.get(src1);
-				self.mem
-					.atomic_op_word(addr, |word| {
-						// put (src1) value into rd
-						self.registers.set(dst, u64::from(word));
-
-						// and src2 value with (src1)
-						let src2_val = self.registers.get(src2) as u32;
-						let new_val = word & src2_val;
-						Some(new_val)
-					})
-					.expect("addr to be in physmem");
+				let mul_lhs = self.fp_registers.get_double(mul_lhs);
+				let mul_rhs = self.fp_registers.get_double(mul_rhs);
+				let sub = self.fp_registers.get_double(sub);
+				let result = mul_lhs.neg().mul_add(&mul_rhs, &sub, rm, self);
+				self.fp_registers.set_double(dst, result);
 			}
-			AtomicInstruction::OrWord {
-				src1,
-				src2,
+			DoubleInstruction::NegMulAdd {
 				dst,
-				_aq,
-				_rl,
+				mul_lhs,
+				mul_rhs,
+				add,
+				rm,
 			} => {
-				let addr = self.registers.get(src1);
-				self.mem
-					.atomic_op_word(addr, |word| {
-						// put (src1) value into rd
-						self.registers.set(dst, u64::from(word));
-
-						// or src2 value with (src1)
-						let src2_val = self.registers.get(src2) as u32;
-						let new_val = word | src2_val;
-						Some(new_val)
-					})
-					.expect("addr to be in physmem");
+				let mul_lhs = self.fp_registers.get_double(mul_lhs);
+				let mul_rhs = self.fp_registers.get_double(mul_rhs);
+				let add = self.fp_registers.get_double(add);
+				let result = mul_lhs.neg().mul_add(&mul_rhs, &add.neg(), rm, self);
+				self.fp_registers.set_double(dst, result);
+			}
+			DoubleInstruction::ConvertToFloat { dst, src, rm } => {
+				let result = self.fp_registers.get_double(src).convert_to_float(rm, self);
+				self.fp_registers.set_float(dst, result);
 			}
-			AtomicInstruction::MinWord {
-				src1,
-				src2,
+			DoubleInstruction::ConvertFromFloat { dst, src } => {
+				let result = self.fp_registers.get_float(src).convert_to_double(self);
+				self.fp_registers.set_double(dst, result);
+			}
+			DoubleInstruction::Classify { dst, src } => {
+				let class = self.fp_registers.get_double(src).fclass();
+				self.registers.set(dst, u64::from(class.to_shift()));
+			}
+			DoubleInstruction::MoveToInt { dst, src } => {
+				let val = self.fp_registers.get_raw(src);
+				self.registers.set(dst, val);
+			}
+			DoubleInstruction::MoveFromInt { dst, src } => {
+				let val = self.registers.get(src);
+				self.fp_registers.set_raw(dst, val);
+			}
+			DoubleInstruction::SignInject { dst, lhs, rhs } => {
+				let lhs = self.fp_registers.get_double(lhs).to_u64();
+				let rhs = self.fp_registers.get_double(rhs).to_u64();
+				let sign_bit = 1 << (u64::BITS - 1);
+				let result = (lhs & !sign_bit) | (rhs & sign_bit);
+				self.fp_registers.set_double(dst, SoftDouble::from_u64(result));
+			}
+			DoubleInstruction::SignInjectNegate { dst, lhs, rhs } => {
+				let lhs = self.fp_registers.get_double(lhs).to_u64();
+				let rhs = self.fp_registers.get_double(rhs).to_u64();
+				let sign_bit = 1 << (u64::BITS - 1);
+				let result = (lhs & !sign_bit) | (!rhs & sign_bit);
+				self.fp_registers.set_double(dst, SoftDouble::from_u64(result));
+			}
+			DoubleInstruction::SignInjectXor { dst, lhs, rhs } => {
+				let lhs = self.fp_registers.get_double(lhs).to_u64();
+				let rhs = self.fp_registers.get_double(rhs).to_u64();
+				let sign_bit = 1 << (u64::BITS - 1);
+				let result = lhs ^ (rhs & sign_bit);
+				self.fp_registers.set_double(dst, SoftDouble::from_u64(result));
+			}
+			DoubleInstruction::ConvertToWord { dst, src, rm } => {
+				let val = self.fp_registers.get_double(src).convert_to_i32(rm, self);
+				self.registers.set(dst, val as i64 as u64);
+			}
+			DoubleInstruction::ConvertToUnsignedWord { dst, src, rm } => {
+				let val = self.fp_registers.get_double(src).convert_to_u32(rm, self);
+				// the 32bit result is sign extended even for the unsigned variant
+				self.registers.set(dst, val as i32 as i64 as u64);
+			}
+			DoubleInstruction::ConvertToDoubleWord { dst, src, rm } => {
+				let val = self.fp_registers.get_double(src).convert_to_i64(rm, self);
+				self.registers.set(dst, val as u64);
+			}
+			DoubleInstruction::ConvertToUnsignedDoubleWord { dst, src, rm } => {
+				let val = self.fp_registers.get_double(src).convert_to_u64(rm, self);
+				self.registers.set(dst, val);
+			}
+			DoubleInstruction::ConvertFromWord { dst, src, rm } => {
+				let val = self.registers.get(src) as i32;
+				let result = SoftDouble::convert_from_i32(val, rm, self);
+				self.fp_registers.set_double(dst, result);
+			}
+			DoubleInstruction::ConvertFromUnsignedWord { dst, src, rm } => {
+				let val = self.registers.get(src) as u32;
+				let result = SoftDouble::convert_from_u32(val, rm, self);
+				self.fp_registers.set_double(dst, result);
+			}
+			DoubleInstruction::ConvertFromDoubleWord { dst, src, rm } => {
+				let val = self.registers.get(src) as i64;
+				let result = SoftDouble::convert_from_i64(val, rm, self);
+				self.fp_registers.set_double(dst, result);
+			}
+			DoubleInstruction::ConvertFromUnsignedDoubleWord { dst, src, rm } => {
+				let val = self.registers.get(src);
+				let result = SoftDouble::convert_from_u64(val, rm, self);
+				self.fp_registers.set_double(dst, result);
+			}
+			DoubleInstruction::Mul { dst, lhs, rhs, rm } => {
+				let lhs = self.fp_registers.get_double(lhs);
+				let rhs = self.fp_registers.get_double(rhs);
+				let result = lhs.mul(&rhs, rm, self);
+				self.fp_registers.set_double(dst, result);
+			}
+			DoubleInstruction::Div { dst, lhs, rhs, rm } => {
+				let lhs = self.fp_registers.get_double(lhs);
+				let rhs = self.fp_registers.get_double(rhs);
+				let result = lhs.div(&rhs, rm, self);
+				self.fp_registers.set_double(dst, result);
+			}
+			DoubleInstruction::Sqrt { dst, val, rm } => {
+				let result = self.fp_registers.get_double(val).sqrt(rm, self);
+				self.fp_registers.set_double(dst, result);
+			}
+			DoubleInstruction::Min { dst, lhs, rhs } => {
+				let lhs = self.fp_registers.get_double(lhs);
+				let rhs = self.fp_registers.get_double(rhs);
+				let result = lhs.min(&rhs, self);
+				self.fp_registers.set_double(dst, result);
+			}
+			DoubleInstruction::Max { dst, lhs, rhs } => {
+				let lhs = self.fp_registers.get_double(lhs);
+				let rhs = self.fp_registers.get_double(rhs);
+				let result = lhs.max(&rhs, self);
+				self.fp_registers.set_double(dst, result);
+			}
+			// same NaN semantics as the single precision compares
+			DoubleInstruction::Equal { dst, lhs, rhs } => {
+				let lhs = self.fp_registers.get_double(lhs);
+				let rhs = self.fp_registers.get_double(rhs);
+
+				let Some(cmp) = lhs.partial_cmp(&rhs) else {
+					self.registers.set(dst, 0);
+					if lhs.is_snan() || rhs.is_snan() {
+						self.accrue_fflags(ExceptionFlags::FLAG_INVALID);
+					}
+					return;
+				};
+
+				self.registers.set(dst, u64::from(cmp == Ordering::Equal));
+			}
+			DoubleInstruction::LessThan { dst, lhs, rhs } => {
+				let lhs = self.fp_registers.get_double(lhs);
+				let rhs = self.fp_registers.get_double(rhs);
+
+				let Some(cmp) = lhs.partial_cmp(&rhs) else {
+					self.registers.set(dst, 0);
+					self.accrue_fflags(ExceptionFlags::FLAG_INVALID);
+					return;
+				};
+
+				self.registers.set(dst, u64::from(cmp == Ordering::Less));
+			}
+			DoubleInstruction::LessOrEqual { dst, lhs, rhs } => {
+				let lhs = self.fp_registers.get_double(lhs);
+				let rhs = self.fp_registers.get_double(rhs);
+
+				let Some(cmp) = lhs.partial_cmp(&rhs) else {
+					self.registers.set(dst, 0);
+					self.accrue_fflags(ExceptionFlags::FLAG_INVALID);
+					return;
+				};
+
+				self.registers
+					.set(dst, u64::from(matches!(cmp, Ordering::Less | Ordering::Equal)));
+			}
+		}
+	}
+
+	fn execute_h_insn(&mut self, insn: HalfInstruction, _start_pc: u64) {
+		if !self.float_enabled() {
+			self.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+			return;
+		}
+		// a dynamic rounding mode is illegal if frm holds a reserved value
+		if insn.rounding_mode() == Some(RoundingMode::Dynamic) && RoundingMode::from_frm(self).is_none() {
+			self.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+			return;
+		}
+
+		match insn {
+			HalfInstruction::LoadHalf { dst, src, src_offset } => {
+				let offset = self.registers.get(src).wrapping_add_signed(src_offset);
+				let val = read_mem_u16!(self, offset);
+				self.fp_registers.set_half(dst, SoftHalf::from_u16(val));
+			}
+			HalfInstruction::StoreHalf { dst, dst_offset, src } => {
+				let offset = self.registers.get(dst).wrapping_add_signed(dst_offset);
+				let val = self.fp_registers.get_half(src).to_u16();
+				write_mem_u16!(self, offset, val);
+			}
+			HalfInstruction::Add { dst, lhs, rhs, rm } => {
+				let lhs = self.fp_registers.get_half(lhs);
+				let rhs = self.fp_registers.get_half(rhs);
+				let result = lhs.add(&rhs, rm, self);
+				self.fp_registers.set_half(dst, result);
+			}
+			HalfInstruction::Sub { dst, lhs, rhs, rm } => {
+				let lhs = self.fp_registers.get_half(lhs);
+				let rhs = self.fp_registers.get_half(rhs);
+				let result = lhs.sub(&rhs, rm, self);
+				self.fp_registers.set_half(dst, result);
+			}
+			HalfInstruction::MulAdd {
 				dst,
-				_aq,
-				_rl,
+				mul_lhs,
+				mul_rhs,
+				add,
+				rm,
 			} => {
-				let addr = self.registers.get(src1);
-				self.mem
-					.atomic_op_word(addr, |word| {
-						// put (src1) value into rd
-						self.registers.set(dst, u64::from(word));
-
-						// min of src2 value and (src1) (signed)
-						let src2_val = self.registers.get(src2) as i32;
-						let new_val = std::cmp::min(word as i32, src2_val) as u32;
-						Some(new_val)
-					})
-					.expect("addr to be in physmem");
+				let mul_lhs = self.fp_registers.get_half(mul_lhs);
+				let mul_rhs = self.fp_registers.get_half(mul_rhs);
+				let add = self.fp_registers.get_half(add);
+				let result = mul_lhs.mul_add(&mul_rhs, &add, rm, self);
+				self.fp_registers.set_half(dst, result);
 			}
-			AtomicInstruction::MaxWord {
-				src1,
-				src2,
+			HalfInstruction::MulSub {
 				dst,
-				_aq,
-				_rl,
+				mul_lhs,
+				mul_rhs,
+				sub,
+				rm,
 			} => {
-				let addr = self.registers.get(src1);
-				self.mem
-					.atomic_op_word(addr, |word| {
-						// put (src1) value into rd
-						self.registers.set(dst, u64::from(word));
-
-						// max of src2 value and (src1) (signed)
-						let src2_val = self.registers.get(src2) as i32;
-						let new_val = std::cmp::max(word as i32, src2_val) as u32;
-						Some(new_val)
-					})
-					.expect("addr to be in physmem");
+				let mul_lhs = self.fp_registers.get_half(mul_lhs);
+				let mul_rhs = self.fp_registers.get_half(mul_rhs);
+				let sub = self.fp_registers.get_half(sub);
+				let result = mul_lhs.mul_add(&mul_rhs, &sub.neg(), rm, self);
+				self.fp_registers.set_half(dst, result);
 			}
-			AtomicInstruction::MinUnsignedWord {
-				src1,
-				src2,
+			HalfInstruction::NegMulSub {
 				dst,
-				_aq,
-				_rl,
+				mul_lhs,
+				mul_rhs,
+				sub,
+				rm,
 			} => {
-				let addr = self.registers.get(src1);
-				self.mem
-					.atomic_op_word(addr, |word| {
-						// put (src1) value into rd
-						self.registers.set(dst, u64::from(word));
-
-						// min of src2 value and (src1) (unsigned)
-						let src2_val = self.registers.get(src2) as u32;
-						let new_val = std::cmp::min(word, src2_val);
-						Some(new_val)
-					})
-					.expect("addr to be in physmem");
+				let mul_lhs = self.fp_registers.get_half(mul_lhs);
+				let mul_rhs = self.fp_registers.get_half(mul_rhs);
+				let sub = self.fp_registers.get_half(sub);
+				let result = mul_lhs.neg().mul_add(&mul_rhs, &sub, rm, self);
+				self.fp_registers.set_half(dst, result);
 			}
-			AtomicInstruction::MaxUnsignedWord {
-				src1,
-				src2,
+			HalfInstruction::NegMulAdd {
 				dst,
-				_aq,
-				_rl,
+				mul_lhs,
+				mul_rhs,
+				add,
+				rm,
 			} => {
-				let addr = self.registers.get(src1);
-				self.mem
-					.atomic_op_word(addr, |word| {
-						// put (src1) value into rd
-						self.registers.set(dst, u64::from(word));
-
-						// max of src2 value and (src1) (unsigned)
-						let src2_val = self.registers.get(src2) as u32;
-						let new_val = std::cmp::max(word, src2_val);
-						Some(new_val)
-					})
-					.expect("addr to be in physmem");
+				let mul_lhs = self.fp_registers.get_half(mul_lhs);
+				let mul_rhs = self.fp_registers.get_half(mul_rhs);
+				let add = self.fp_registers.get_half(add);
+				let result = mul_lhs.neg().mul_add(&mul_rhs, &add.neg(), rm, self);
+				self.fp_registers.set_half(dst, result);
+			}
+			HalfInstruction::ConvertToFloat { dst, src } => {
+				let result = self.fp_registers.get_half(src).convert_to_float(self);
+				self.fp_registers.set_float(dst, result);
+			}
+			HalfInstruction::ConvertFromFloat { dst, src, rm } => {
+				let result = self.fp_registers.get_float(src).convert_to_half(rm, self);
+				self.fp_registers.set_half(dst, result);
+			}
+			HalfInstruction::ConvertToDouble { dst, src } => {
+				let result = self.fp_registers.get_half(src).convert_to_double(self);
+				self.fp_registers.set_double(dst, result);
+			}
+			HalfInstruction::ConvertFromDouble { dst, src, rm } => {
+				let result = self.fp_registers.get_double(src).convert_to_half(rm, self);
+				self.fp_registers.set_half(dst, result);
+			}
+			HalfInstruction::Classify { dst, src } => {
+				let class = self.fp_registers.get_half(src).fclass();
+				self.registers.set(dst, u64::from(class.to_shift()));
+			}
+			HalfInstruction::MoveToInt { dst, src } => {
+				// the low 16 bits are moved as is, even if the register isn't properly NaN boxed
+				let val = self.fp_registers.get_raw(src) as u16;
+				self.registers.set(dst, val as i16 as i64 as u64);
+			}
+			HalfInstruction::MoveFromInt { dst, src } => {
+				let val = self.registers.get(src) as u16;
+				self.fp_registers.set_half(dst, SoftHalf::from_u16(val));
+			}
+			HalfInstruction::SignInject { dst, lhs, rhs } => {
+				let lhs = self.fp_registers.get_half(lhs).to_u16();
+				let rhs = self.fp_registers.get_half(rhs).to_u16();
+				let sign_bit = 1 << (u16::BITS - 1);
+				let result = (lhs & !sign_bit) | (rhs & sign_bit);
+				self.fp_registers.set_half(dst, SoftHalf::from_u16(result));
+			}
+			HalfInstruction::SignInjectNegate { dst, lhs, rhs } => {
+				let lhs = self.fp_registers.get_half(lhs).to_u16();
+				let rhs = self.fp_registers.get_half(rhs).to_u16();
+				let sign_bit = 1 << (u16::BITS - 1);
+				let result = (lhs & !sign_bit) | (!rhs & sign_bit);
+				self.fp_registers.set_half(dst, SoftHalf::from_u16(result));
+			}
+			HalfInstruction::SignInjectXor { dst, lhs, rhs } => {
+				let lhs = self.fp_registers.get_half(lhs).to_u16();
+				let rhs = self.fp_registers.get_half(rhs).to_u16();
+				let sign_bit = 1 << (u16::BITS - 1);
+				let result = lhs ^ (rhs & sign_bit);
+				self.fp_registers.set_half(dst, SoftHalf::from_u16(result));
+			}
+			HalfInstruction::ConvertToWord { dst, src, rm } => {
+				let val = self.fp_registers.get_half(src).convert_to_i32(rm, self);
+				self.registers.set(dst, val as i64 as u64);
+			}
+			HalfInstruction::ConvertToUnsignedWord { dst, src, rm } => {
+				let val = self.fp_registers.get_half(src).convert_to_u32(rm, self);
+				// the 32bit result is sign extended even for the unsigned variant
+				self.registers.set(dst, val as i32 as i64 as u64);
+			}
+			HalfInstruction::ConvertToDoubleWord { dst, src, rm } => {
+				let val = self.fp_registers.get_half(src).convert_to_i64(rm, self);
+				self.registers.set(dst, val as u64);
+			}
+			HalfInstruction::ConvertToUnsignedDoubleWord { dst, src, rm } => {
+				let val = self.fp_registers.get_half(src).convert_to_u64(rm, self);
+				self.registers.set(dst, val);
+			}
+			HalfInstruction::ConvertFromWord { dst, src, rm } => {
+				let val = self.registers.get(src) as i32;
+				let result = SoftHalf::convert_from_i32(val, rm, self);
+				self.fp_registers.set_half(dst, result);
+			}
+			HalfInstruction::ConvertFromUnsignedWord { dst, src, rm } => {
+				let val = self.registers.get(src) as u32;
+				let result = SoftHalf::convert_from_u32(val, rm, self);
+				self.fp_registers.set_half(dst, result);
+			}
+			HalfInstruction::ConvertFromDoubleWord { dst, src, rm } => {
+				let val = self.registers.get(src) as i64;
+				let result = SoftHalf::convert_from_i64(val, rm, self);
+				self.fp_registers.set_half(dst, result);
+			}
+			HalfInstruction::ConvertFromUnsignedDoubleWord { dst, src, rm } => {
+				let val = self.registers.get(src);
+				let result = SoftHalf::convert_from_u64(val, rm, self);
+				self.fp_registers.set_half(dst, result);
+			}
+			HalfInstruction::Mul { dst, lhs, rhs, rm } => {
+				let lhs = self.fp_registers.get_half(lhs);
+				let rhs = self.fp_registers.get_half(rhs);
+				let result = lhs.mul(&rhs, rm, self);
+				self.fp_registers.set_half(dst, result);
 			}
+			HalfInstruction::Div { dst, lhs, rhs, rm } => {
+				let lhs = self.fp_registers.get_half(lhs);
+				let rhs = self.fp_registers.get_half(rhs);
+				let result = lhs.div(&rhs, rm, self);
+				self.fp_registers.set_half(dst, result);
+			}
+			HalfInstruction::Sqrt { dst, val, rm } => {
+				let result = self.fp_registers.get_half(val).sqrt(rm, self);
+				self.fp_registers.set_half(dst, result);
+			}
+			HalfInstruction::Min { dst, lhs, rhs } => {
+				let lhs = self.fp_registers.get_half(lhs);
+				let rhs = self.fp_registers.get_half(rhs);
+				let result = lhs.min(&rhs, self);
+				self.fp_registers.set_half(dst, result);
+			}
+			HalfInstruction::Max { dst, lhs, rhs } => {
+				let lhs = self.fp_registers.get_half(lhs);
+				let rhs = self.fp_registers.get_half(rhs);
+				let result = lhs.max(&rhs, self);
+				self.fp_registers.set_half(dst, result);
+			}
+			// same NaN semantics as the single precision compares
+			HalfInstruction::Equal { dst, lhs, rhs } => {
+				let lhs = self.fp_registers.get_half(lhs);
+				let rhs = self.fp_registers.get_half(rhs);
 
-			AtomicInstruction::LoadReservedDoubleWord { src, dst, _aq, _rl } => {
-				let addr = self.registers.get(src);
+				let Some(cmp) = lhs.partial_cmp(&rhs) else {
+					self.registers.set(dst, 0);
+					if lhs.is_snan() || rhs.is_snan() {
+						self.accrue_fflags(ExceptionFlags::FLAG_INVALID);
+					}
+					return;
+				};
 
-				let val = self
-					.mem
-					.load_reserved_dword(addr, HART_ID)
-					.expect("addr to be in physmem");
+				self.registers.set(dst, u64::from(cmp == Ordering::Equal));
+			}
+			HalfInstruction::LessThan { dst, lhs, rhs } => {
+				let lhs = self.fp_registers.get_half(lhs);
+				let rhs = self.fp_registers.get_half(rhs);
 
-				self.registers.set(dst, val);
+				let Some(cmp) = lhs.partial_cmp(&rhs) else {
+					self.registers.set(dst, 0);
+					self.accrue_fflags(ExceptionFlags::FLAG_INVALID);
+					return;
+				};
+
+				self.registers.set(dst, u64::from(cmp == Ordering::Less));
 			}
-			AtomicInstruction::StoreConditionalDoubleWord {
-				src1,
-				src2,
-				dst,
-				_aq,
-				_rl,
-			} => {
-				let addr = self.registers.get(src1);
-				let val = self.registers.get(src2);
-				let success = self
-					.mem
-					.store_conditional_dword(addr, HART_ID, val)
-					.expect("addr to be in physmem");
-				if success {
+			HalfInstruction::LessOrEqual { dst, lhs, rhs } => {
+				let lhs = self.fp_registers.get_half(lhs);
+				let rhs = self.fp_registers.get_half(rhs);
+
+				let Some(cmp) = lhs.partial_cmp(&rhs) else {
 					self.registers.set(dst, 0);
-				} else {
-					// TODO: From the little bit I read it says "nonzero code on failure"
-					// We should figure out what that value should be, unless it's arbitrary
-					self.registers.set(dst, 1);
-				}
+					self.accrue_fflags(ExceptionFlags::FLAG_INVALID);
+					return;
+				};
+
+				self.registers
+					.set(dst, u64::from(matches!(cmp, Ordering::Less | Ordering::Equal)));
 			}
-			AtomicInstruction::SwapDoubleWord {
-				src1,
-				src2,
-				dst,
-				_aq,
-				_rl,
-			} => {
-				let addr = self.registers.get(src1);
-				self.mem
-					.atomic_op_dword(addr, |dword| {
-						// put (src1) value into rd
-						self.registers.set(dst, dword);
-
-						// swap src2 to (src1)
-						let src2_val = self.registers.get(src2);
-						Some(src2_val)
-					})
-					.expect("addr to be in physmem");
+		}
+	}
+
+	fn exec_csr(&mut self, insn: CSRInstruction, _start_pc: u64) {
+		// FIXME: ordering of effects on registers and traps???
+		// sources are read up front in case dst is the same register
+		let (dst, csr, update) = match insn {
+			CSRInstruction::CSRReadWrite { dst, src, csr } => {
+				(dst, csr, Some(CSRUpdate::Write(self.registers.get(src))))
 			}
-			AtomicInstruction::AddDoubleWord {
-				src1,
-				src2,
+			// we must not check for writability if the mask register is x0
+			CSRInstruction::CSRReadAndSet { dst, mask, csr } => (
 				dst,
-				_aq,
-				_rl,
-			} => {
-				let addr = self.registers.get(src1);
-				self.mem
-					.atomic_op_dword(addr, |dword| {
-						// put (src1) value into rd
-						self.registers.set(dst, dword);
-
-						// add src2 value to (src1)
-						let src2_val = self.registers.get(src2);
-						let new_val = dword.wrapping_add(src2_val);
-						Some(new_val)
-					})
-					.expect("addr to be in physmem");
+				csr,
+				(mask != GPRegisterIndex::ZERO).then(|| CSRUpdate::Set(self.registers.get(mask))),
+			),
+			CSRInstruction::CSRReadAndClear { dst, mask, csr } => (
+				dst,
+				csr,
+				(mask != GPRegisterIndex::ZERO).then(|| CSRUpdate::Clear(self.registers.get(mask))),
+			),
+			CSRInstruction::CSRReadWriteImm { dst, src, csr } => (dst, csr, Some(CSRUpdate::Write(src))),
+			// we must not check for writability if the mask is 0
+			CSRInstruction::CSRReadAndSetImm { dst, mask, csr } => {
+				(dst, csr, (mask != 0).then_some(CSRUpdate::Set(mask)))
+			}
+			CSRInstruction::CSRReadAndClearImm { dst, mask, csr } => {
+				(dst, csr, (mask != 0).then_some(CSRUpdate::Clear(mask)))
+			}
+		};
+
+		let Some(update) = update else {
+			get_csr!(self, csr);
+			self.registers.set(dst, self.read_csr(csr));
+			return;
+		};
+
+		// reads dont happen when dst is zero, the register write is ignored so that's the same thing
+		get_csr_mut!(self, csr);
+		let old = self.read_csr(csr);
+		let new = match update {
+			CSRUpdate::Write(val) => val,
+			CSRUpdate::Set(mask) => old | mask,
+			CSRUpdate::Clear(mask) => old & !mask,
+		};
+		self.write_csr(csr, new);
+		self.registers.set(dst, old);
+	}
+
+	/// whether the current privilege level is high enough to access a CSR
+	/// the supervisor CSRs don't exist at all without S-mode
+	fn csr_accessible(&self, info: &CSRInfo) -> bool {
+		let required = match info.privilege() {
+			CSRPrivilege::User => PrivilegeMode::User,
+			CSRPrivilege::Supervisor if self.supported_extensions.has(SupportedExtensions::SUPERVISOR) => {
+				PrivilegeMode::Supervisor
+			}
+			CSRPrivilege::Supervisor | CSRPrivilege::Hypervisor => return false,
+			CSRPrivilege::Machine => PrivilegeMode::Machine,
+		};
+		// mstatus.TVM lets M-mode take over the page tables
+		let trapped_by_tvm = info.addr() == ControlStatusRegisters::SATP
+			&& self.privilege == PrivilegeMode::Supervisor
+			&& Mstatus(self.csrs.read_mstatus()).tvm();
+		// the float CSRs go away along with the float registers when FS is off
+		let float_csr = (ControlStatusRegisters::FFLAGS..=ControlStatusRegisters::FCSR).contains(&info.addr());
+		self.privilege >= required
+			&& !trapped_by_tvm
+			&& self.counter_enabled(info.addr())
+			&& (!float_csr || self.float_enabled())
+	}
+
+	/// the user counters are only readable below M-mode when mcounteren enables them,
+	/// and in U-mode scounteren has to enable them too if S-mode exists
+	fn counter_enabled(&self, csr: u16) -> bool {
+		if !(ControlStatusRegisters::CYCLE..=ControlStatusRegisters::HPMCOUNTER31).contains(&csr) {
+			return true;
+		}
+		let bit = 1 << (csr - ControlStatusRegisters::CYCLE);
+		match self.privilege {
+			PrivilegeMode::Machine => true,
+			PrivilegeMode::Supervisor => self.csrs.read_mcounteren() & bit != 0,
+			PrivilegeMode::User => {
+				let supervisor_enabled = !self.supported_extensions.has(SupportedExtensions::SUPERVISOR)
+					|| self.csrs.read_scounteren() & bit != 0;
+				self.csrs.read_mcounteren() & bit != 0 && supervisor_enabled
+			}
+		}
+	}
+
+	/// reads a CSR that has already been checked to exist
+	/// the counters aren't stored in the CSR table and are read from the cpu instead
+	fn read_csr(&self, csr: u16) -> u64 {
+		match csr {
+			ControlStatusRegisters::MCYCLE | ControlStatusRegisters::CYCLE => self.cycles,
+			ControlStatusRegisters::MINSTRET | ControlStatusRegisters::INSTRET => self.instret,
+			ControlStatusRegisters::TIME => self.read_time(),
+			ControlStatusRegisters::MHPMCOUNTER3..=ControlStatusRegisters::MHPMCOUNTER31 => self
+				.hpm
+				.read_counter(usize::from(csr - ControlStatusRegisters::MHPMCOUNTER3)),
+			ControlStatusRegisters::HPMCOUNTER3..=ControlStatusRegisters::HPMCOUNTER31 => self
+				.hpm
+				.read_counter(usize::from(csr - ControlStatusRegisters::HPMCOUNTER3)),
+			ControlStatusRegisters::MHPMEVENT3..=ControlStatusRegisters::MHPMEVENT31 => self
+				.hpm
+				.read_event(usize::from(csr - ControlStatusRegisters::MHPMEVENT3)),
+			ControlStatusRegisters::MHARTID => self.hart_id as u64,
+			ControlStatusRegisters::MIP => self.read_mip(),
+			ControlStatusRegisters::PMPCFG0..=ControlStatusRegisters::PMPCFG14 => {
+				self.pmp.read_cfg(usize::from(csr - ControlStatusRegisters::PMPCFG0))
+			}
+			ControlStatusRegisters::PMPADDR0..=ControlStatusRegisters::PMPADDR63 => {
+				self.pmp.read_addr(usize::from(csr - ControlStatusRegisters::PMPADDR0))
+			}
+			ControlStatusRegisters::SSTATUS => self.csrs.read_mstatus() & Mstatus::SSTATUS_VIEW,
+			ControlStatusRegisters::FFLAGS => self.csrs.read_fcsr() & FCSR_FLAGS_MASK,
+			ControlStatusRegisters::FRM => (self.csrs.read_fcsr() & FCSR_ROUNDING_MODE_MASK) >> 5,
+			// S-mode only sees the interrupts that are delegated to it
+			ControlStatusRegisters::SIE => self.csrs.read_mie() & self.csrs.read_mideleg(),
+			ControlStatusRegisters::SIP => self.read_mip() & self.csrs.read_mideleg(),
+			_ => self.csrs.get(csr).expect("csr existence was already checked").val,
+		}
+	}
+
+	/// reads any CSR the hart implements no matter the privilege level, for the debugger
+	pub fn debug_read_csr(&self, csr: u16) -> Option<u64> {
+		self.csrs.get(csr)?;
+		Some(self.read_csr(csr))
+	}
+
+	/// writes a CSR like the hart would from M-mode, returning false if it doesn't exist or is read-only
+	pub fn debug_write_csr(&mut self, csr: u16, val: u64) -> bool {
+		if !self.csrs.get(csr).is_some_and(CSRInfo::is_rw) {
+			return false;
+		}
+		self.write_csr(csr, val);
+		true
+	}
+
+	/// ticks of the timebase since power on or the last reset, unless software wrote mtime
+	pub fn read_time(&self) -> u64 {
+		self.timer.read()
+	}
+
+	/// writes a CSR that has already been checked to exist and be writable
+	/// CSRs whose writes have side effects or only hold some values are handled here
+	fn write_csr(&mut self, csr: u16, val: u64) {
+		match csr {
+			ControlStatusRegisters::MISA => self.write_misa(val),
+			ControlStatusRegisters::MSTATUS => {
+				let status = self.legalize_mstatus(val);
+				self.csrs.write_mstatus(status.0);
+			}
+			ControlStatusRegisters::MIE => self.csrs.write_mie(val & self.supported_interrupts()),
+			// the machine level bits are driven by devices, only the supervisor bits can be set by software
+			ControlStatusRegisters::MIP => {
+				let writable = self.supported_interrupts() & interrupt::SUPERVISOR;
+				self.csrs.write_mip(val & writable);
+			}
+			// only FIOM is implemented, there's no cache to manage and fences already order I/O
+			ControlStatusRegisters::MENVCFG => {
+				let writable = if self.supported_extensions.has(SupportedExtensions::USER_MODE) {
+					MENVCFG_FIOM
+				} else {
+					0
+				};
+				self.csrs.write_menvcfg(val & writable);
+			}
+			ControlStatusRegisters::MSECCFG => {}
+			ControlStatusRegisters::MEDELEG => {
+				// ecalls from M-mode can't be delegated
+				const DELEGABLE: u64 = 0b1011_0011_1111_1111;
+				let writable = if self.supported_extensions.has(SupportedExtensions::SUPERVISOR) {
+					DELEGABLE
+				} else {
+					0
+				};
+				self.csrs.write_medeleg(val & writable);
+			}
+			ControlStatusRegisters::MIDELEG => {
+				let writable = self.supported_interrupts() & interrupt::SUPERVISOR;
+				self.csrs.write_mideleg(val & writable);
+			}
+			ControlStatusRegisters::SSTATUS => {
+				let old = self.csrs.read_mstatus();
+				let status = self.legalize_mstatus((old & !Mstatus::SSTATUS_VIEW) | (val & Mstatus::SSTATUS_VIEW));
+				self.csrs.write_mstatus(status.0);
+			}
+			ControlStatusRegisters::SIE => {
+				let writable = self.csrs.read_mideleg();
+				let old = self.csrs.read_mie();
+				self.csrs.write_mie((old & !writable) | (val & writable));
+			}
+			// timer and external interrupts are only ever cleared by whatever raised them
+			ControlStatusRegisters::SIP => {
+				let writable = self.csrs.read_mideleg() & interrupt::SUPERVISOR_SOFTWARE;
+				let old = self.csrs.read_mip();
+				self.csrs.write_mip((old & !writable) | (val & writable));
+			}
+			ControlStatusRegisters::PMPCFG0..=ControlStatusRegisters::PMPCFG14 => self
+				.pmp
+				.write_cfg(usize::from(csr - ControlStatusRegisters::PMPCFG0), val),
+			ControlStatusRegisters::PMPADDR0..=ControlStatusRegisters::PMPADDR63 => self
+				.pmp
+				.write_addr(usize::from(csr - ControlStatusRegisters::PMPADDR0), val),
+			// only direct and vectored mode exist, the reserved modes fall back to direct
+			ControlStatusRegisters::MTVEC => self.csrs.write_mtvec(legalize_tvec(val)),
+			ControlStatusRegisters::STVEC => self.csrs.write_stvec(legalize_tvec(val)),
+			// writes selecting a translation mode we don't support are ignored entirely
+			ControlStatusRegisters::SATP => {
+				if Satp(val).mode().is_some_and(|mode| mode <= self.max_translation_mode) {
+					self.csrs.write_satp(val);
+				}
 			}
-			AtomicInstruction::XorDoubleWord {
+			// bit 0 of xepc is always zero, bit 1 is masked off on use depending on whether C is enabled
+			ControlStatusRegisters::MEPC => self.csrs.write_mepc(val & !0b1),
+			ControlStatusRegisters::SEPC => self.csrs.write_sepc(val & !0b1),
+			// there's a bit for each of the 32 user counters
+			ControlStatusRegisters::MCOUNTEREN => self.csrs.write_mcounteren(val & 0xFFFF_FFFF),
+			ControlStatusRegisters::SCOUNTEREN => self.csrs.write_scounteren(val & 0xFFFF_FFFF),
+			// fcsr only has frm and fflags, writing either view leaves the other field alone
+			ControlStatusRegisters::FCSR | ControlStatusRegisters::FFLAGS | ControlStatusRegisters::FRM => {
+				let (mask, val) = match csr {
+					ControlStatusRegisters::FFLAGS => (FCSR_FLAGS_MASK, val),
+					ControlStatusRegisters::FRM => (FCSR_ROUNDING_MODE_MASK, val << 5),
+					_ => (FCSR_ROUNDING_MODE_MASK | FCSR_FLAGS_MASK, val),
+				};
+				let old = self.csrs.read_fcsr();
+				self.csrs.write_fcsr((old & !mask) | (val & mask));
+				self.mark_float_dirty();
+			}
+			ControlStatusRegisters::MCYCLE => self.cycles = val,
+			ControlStatusRegisters::MINSTRET => {
+				self.instret = val;
+				self.instret_written = true;
+			}
+			ControlStatusRegisters::MHPMCOUNTER3..=ControlStatusRegisters::MHPMCOUNTER31 => self
+				.hpm
+				.write_counter(usize::from(csr - ControlStatusRegisters::MHPMCOUNTER3), val),
+			ControlStatusRegisters::MHPMEVENT3..=ControlStatusRegisters::MHPMEVENT31 => self
+				.hpm
+				.write_event(usize::from(csr - ControlStatusRegisters::MHPMEVENT3), val),
+			_ => self.csrs.get_mut(csr).expect("csr existence was already checked").val = val,
+		}
+	}
+
+	/// extensions can be turned off and back on through misa, as long as they're implemented
+	fn write_misa(&mut self, val: u64) {
+		let mut enabled = self.implemented_extensions.with_misa_letters(val);
+		// the base ISA can't be turned off
+		enabled.insert(SupportedExtensions::INTEGER);
+		// D and Zfh build on F
+		if !enabled.has(SupportedExtensions::FLOAT) {
+			enabled.remove(SupportedExtensions::DOUBLE);
+			enabled.remove(SupportedExtensions::ZFH);
+		}
+		// S-mode needs U-mode
+		if !enabled.has(SupportedExtensions::USER_MODE) {
+			enabled.remove(SupportedExtensions::SUPERVISOR);
+		}
+		// turning C off with the next instruction only 2 byte aligned is suppressed
+		if !enabled.has(SupportedExtensions::COMPRESSED) && self.pc % 4 != 0 {
+			enabled.insert(self.supported_extensions & SupportedExtensions::COMPRESSED);
+		}
+
+		self.supported_extensions = enabled;
+		self.csrs.write_misa(enabled.misa());
+	}
+
+	fn exec_compressed_insn(&mut self, insn: CompressedInstruction, _start_pc: u64) {
+		match insn {
+			// this nop is special in that it's designated as an explicit NOP for future standard use
+			// so it cannot be combined into an integer instruction
+			CompressedInstruction::Nop => {}
+			CompressedInstruction::Hint => {}
+		}
+	}
+
+	/// reads the even-odd register pair starting at reg as one value, the pair starting at x0 is always zero
+	fn register_pair(&self, reg: GPRegisterIndex) -> u128 {
+		if reg == GPRegisterIndex::ZERO {
+			return 0;
+		}
+		let hi = GPRegisterIndex::new(reg.as_usize() as u8 + 1).expect("register pairs start on an even register");
+		u128::from(self.registers.get(reg)) | (u128::from(self.registers.get(hi)) << 64)
+	}
+
+	/// writes to the pair starting at x0 are ignored entirely
+	fn set_register_pair(&mut self, reg: GPRegisterIndex, val: u128) {
+		if reg == GPRegisterIndex::ZERO {
+			return;
+		}
+		let hi = GPRegisterIndex::new(reg.as_usize() as u8 + 1).expect("register pairs start on an even register");
+		self.registers.set(reg, val as u64);
+		self.registers.set(hi, (val >> 64) as u64);
+	}
+
+	fn exec_atomic_insn(&mut self, insn: AtomicInstruction, _start_pc: u64) {
+		// TODO: For now we'll be ignoring the aq: _ and rl: _ bits as it requires fencing logic and other things we do-
+		// not currently implement.
+		match insn {
+			AtomicInstruction::CompareAndSwapByte {
 				src1,
 				src2,
 				dst,
 				_aq,
 				_rl,
 			} => {
-				let addr = self.registers.get(src1);
-				self.mem
-					.atomic_op_dword(addr, |dword| {
-						// put (src1) value into rd
-						self.registers.set(dst, dword);
-
-						// xor src2 value with (src1)
-						let src2_val = self.registers.get(src2);
-						let new_val = dword ^ src2_val;
-						Some(new_val)
-					})
-					.expect("addr to be in physmem");
-			}
-			AtomicInstruction::AndDoubleWord {
+				let (vaddr, addr) = atomic_addr!(self, src1, 1, AccessType::Store);
+				let expected = self.registers.get(dst) as u8;
+				let swap = self.registers.get(src2) as u8;
+				let old = atomic_mem!(
+					self,
+					TrapIdx::STORE_ACCESS_FAULT,
+					vaddr,
+					self.mem.atomic_op_byte(addr, |byte| (byte == expected).then_some(swap))
+				);
+				self.watch_access(vaddr, 1, WatchKind::compare_and_swap(old == expected));
+				// the old value is sign extended
+				self.registers.set(dst, old as i8 as u64);
+			}
+			AtomicInstruction::CompareAndSwapHalf {
 				src1,
 				src2,
 				dst,
 				_aq,
 				_rl,
 			} => {
-				let addr = self.registers.get(src1);
-				self.mem
-					.atomic_op_dword(addr, |dword| {
-						// put (src1) value into rd
-						self.registers.set(dst, dword);
-
-						// and src2 value with (src1)
-						let src2_val = self.registers.get(src2);
-						let new_val = dword & src2_val;
-						Some(new_val)
-					})
-					.expect("addr to be in physmem");
-			}
-			AtomicInstruction::OrDoubleWord {
+				let (vaddr, addr) = atomic_addr!(self, src1, 2, AccessType::Store);
+				let expected = self.registers.get(dst) as u16;
+				let swap = self.registers.get(src2) as u16;
+				let old = atomic_mem!(
+					self,
+					TrapIdx::STORE_ACCESS_FAULT,
+					vaddr,
+					self.mem.atomic_op_half(addr, |half| (half == expected).then_some(swap))
+				);
+				self.watch_access(vaddr, 2, WatchKind::compare_and_swap(old == expected));
+				// the old value is sign extended
+				self.registers.set(dst, old as i16 as u64);
+			}
+			AtomicInstruction::CompareAndSwapWord {
 				src1,
 				src2,
 				dst,
 				_aq,
 				_rl,
 			} => {
-				let addr = self.registers.get(src1);
-				self.mem
-					.atomic_op_dword(addr, |dword| {
-						// put (src1) value into rd
-						self.registers.set(dst, dword);
-
-						// or src2 value with (src1)
-						let src2_val = self.registers.get(src2);
-						let new_val = dword | src2_val;
-						Some(new_val)
-					})
-					.expect("addr to be in physmem");
-			}
-			AtomicInstruction::MinDoubleWord {
+				let (vaddr, addr) = atomic_addr!(self, src1, 4, AccessType::Store);
+				let expected = self.registers.get(dst) as u32;
+				let swap = self.registers.get(src2) as u32;
+				let old = atomic_mem!(
+					self,
+					TrapIdx::STORE_ACCESS_FAULT,
+					vaddr,
+					self.mem.atomic_op_word(addr, |word| (word == expected).then_some(swap))
+				);
+				self.watch_access(vaddr, 4, WatchKind::compare_and_swap(old == expected));
+				// the old value is sign extended
+				self.registers.set(dst, old as i32 as u64);
+			}
+			AtomicInstruction::CompareAndSwapDoubleWord {
 				src1,
 				src2,
 				dst,
 				_aq,
 				_rl,
 			} => {
-				let addr = self.registers.get(src1);
-				self.mem
-					.atomic_op_dword(addr, |dword| {
-						// put (src1) value into rd
-						self.registers.set(dst, dword);
-
-						// min of src2 value and (src1) (signed)
-						let src2_val = self.registers.get(src2) as i64;
-						let new_val = std::cmp::min(dword as i64, src2_val) as u64;
-						Some(new_val)
-					})
-					.expect("addr to be in physmem");
-			}
-			AtomicInstruction::MaxDoubleWord {
+				let (vaddr, addr) = atomic_addr!(self, src1, 8, AccessType::Store);
+				let expected = self.registers.get(dst);
+				let swap = self.registers.get(src2);
+				let old = atomic_mem!(
+					self,
+					TrapIdx::STORE_ACCESS_FAULT,
+					vaddr,
+					self.mem
+						.atomic_op_dword(addr, |dword| (dword == expected).then_some(swap))
+				);
+				self.watch_access(vaddr, 8, WatchKind::compare_and_swap(old == expected));
+				self.registers.set(dst, old);
+			}
+			AtomicInstruction::CompareAndSwapQuadWord {
 				src1,
 				src2,
 				dst,
 				_aq,
 				_rl,
 			} => {
-				let addr = self.registers.get(src1);
-				self.mem
-					.atomic_op_dword(addr, |dword| {
-						// put (src1) value into rd
-						self.registers.set(dst, dword);
-
-						// max of src2 value and (src1) (signed)
-						let src2_val = self.registers.get(src2) as i64;
-						let new_val = std::cmp::max(dword as i64, src2_val) as u64;
-						Some(new_val)
-					})
-					.expect("addr to be in physmem");
+				let (vaddr, addr) = atomic_addr!(self, src1, 16, AccessType::Store);
+				let expected = self.register_pair(dst);
+				let swap = self.register_pair(src2);
+				let old = atomic_mem!(
+					self,
+					TrapIdx::STORE_ACCESS_FAULT,
+					vaddr,
+					self.mem
+						.atomic_op_qword(addr, |qword| (qword == expected).then_some(swap))
+				);
+				self.watch_access(vaddr, 16, WatchKind::compare_and_swap(old == expected));
+				self.set_register_pair(dst, old);
+			}
+			AtomicInstruction::LoadReservedWord { src, dst, _aq, _rl } => {
+				let (vaddr, addr) = atomic_addr!(self, src, 4, AccessType::Load);
+
+				let val = atomic_mem!(
+					self,
+					TrapIdx::LOAD_ACCESS_FAULT,
+					vaddr,
+					self.mem.load_reserved_word(addr, self.hart_id)
+				);
+				self.watch_access(vaddr, 4, WatchKind::Read);
+
+				// like every word sized result on RV64 the value is sign extended
+				self.registers.set(dst, val as i32 as u64);
 			}
-			AtomicInstruction::MinUnsignedDoubleWord {
+			AtomicInstruction::StoreConditionalWord {
 				src1,
 				src2,
 				dst,
 				_aq,
 				_rl,
 			} => {
-				let addr = self.registers.get(src1);
-				self.mem
-					.atomic_op_dword(addr, |dword| {
-						// put (src1) value into rd
-						self.registers.set(dst, dword);
-
-						// min of src2 value and (src1) (unsigned)
-						let src2_val = self.registers.get(src2);
-						let new_val = std::cmp::min(dword, src2_val);
-						Some(new_val)
-					})
-					.expect("addr to be in physmem");
+				let (vaddr, addr) = atomic_addr!(self, src1, 4, AccessType::Store);
+				let val = self.registers.get(src2) as u32;
+				let success = atomic_mem!(
+					self,
+					TrapIdx::STORE_ACCESS_FAULT,
+					vaddr,
+					self.mem.store_conditional_word(addr, self.hart_id, val)
+				);
+				if success {
+					self.watch_access(vaddr, 4, WatchKind::Write);
+					self.registers.set(dst, 0);
+				} else {
+					// the spec reserves every other failure code
+					self.registers.set(dst, 1);
+				}
+			}
+			AtomicInstruction::SwapWord { src1, src2, dst, .. } => amo!(self, word, swap, src1, src2, dst),
+			AtomicInstruction::AddWord { src1, src2, dst, .. } => amo!(self, word, add, src1, src2, dst),
+			AtomicInstruction::XorWord { src1, src2, dst, .. } => amo!(self, word, xor, src1, src2, dst),
+			AtomicInstruction::AndWord { src1, src2, dst, .. } => amo!(self, word, and, src1, src2, dst),
+			AtomicInstruction::OrWord { src1, src2, dst, .. } => amo!(self, word, or, src1, src2, dst),
+			AtomicInstruction::MinWord { src1, src2, dst, .. } => amo!(self, word, min, src1, src2, dst),
+			AtomicInstruction::MaxWord { src1, src2, dst, .. } => amo!(self, word, max, src1, src2, dst),
+			AtomicInstruction::MinUnsignedWord { src1, src2, dst, .. } => amo!(self, word, minu, src1, src2, dst),
+			AtomicInstruction::MaxUnsignedWord { src1, src2, dst, .. } => amo!(self, word, maxu, src1, src2, dst),
+			AtomicInstruction::LoadReservedDoubleWord { src, dst, _aq, _rl } => {
+				let (vaddr, addr) = atomic_addr!(self, src, 8, AccessType::Load);
+
+				let val = atomic_mem!(
+					self,
+					TrapIdx::LOAD_ACCESS_FAULT,
+					vaddr,
+					self.mem.load_reserved_dword(addr, self.hart_id)
+				);
+				self.watch_access(vaddr, 8, WatchKind::Read);
+
+				self.registers.set(dst, val);
 			}
-			AtomicInstruction::MaxUnsignedDoubleWord {
+			AtomicInstruction::StoreConditionalDoubleWord {
 				src1,
 				src2,
 				dst,
 				_aq,
 				_rl,
 			} => {
-				let addr = self.registers.get(src1);
-				self.mem
-					.atomic_op_dword(addr, |dword| {
-						// put (src1) value into rd
-						self.registers.set(dst, dword);
-
-						// max of src2 value and (src1) (unsigned)
-						let src2_val = self.registers.get(src2);
-						let new_val = std::cmp::max(dword, src2_val);
-						Some(new_val)
-					})
-					.expect("addr to be in physmem");
+				let (vaddr, addr) = atomic_addr!(self, src1, 8, AccessType::Store);
+				let val = self.registers.get(src2);
+				let success = atomic_mem!(
+					self,
+					TrapIdx::STORE_ACCESS_FAULT,
+					vaddr,
+					self.mem.store_conditional_dword(addr, self.hart_id, val)
+				);
+				if success {
+					self.watch_access(vaddr, 8, WatchKind::Write);
+					self.registers.set(dst, 0);
+				} else {
+					// the spec reserves every other failure code
+					self.registers.set(dst, 1);
+				}
+			}
+			AtomicInstruction::SwapDoubleWord { src1, src2, dst, .. } => amo!(self, dword, swap, src1, src2, dst),
+			AtomicInstruction::AddDoubleWord { src1, src2, dst, .. } => amo!(self, dword, add, src1, src2, dst),
+			AtomicInstruction::XorDoubleWord { src1, src2, dst, .. } => amo!(self, dword, xor, src1, src2, dst),
+			AtomicInstruction::AndDoubleWord { src1, src2, dst, .. } => amo!(self, dword, and, src1, src2, dst),
+			AtomicInstruction::OrDoubleWord { src1, src2, dst, .. } => amo!(self, dword, or, src1, src2, dst),
+			AtomicInstruction::MinDoubleWord { src1, src2, dst, .. } => amo!(self, dword, min, src1, src2, dst),
+			AtomicInstruction::MaxDoubleWord { src1, src2, dst, .. } => amo!(self, dword, max, src1, src2, dst),
+			AtomicInstruction::MinUnsignedDoubleWord { src1, src2, dst, .. } => {
+				amo!(self, dword, minu, src1, src2, dst)
+			}
+			AtomicInstruction::MaxUnsignedDoubleWord { src1, src2, dst, .. } => {
+				amo!(self, dword, maxu, src1, src2, dst)
+			}
+			AtomicInstruction::SwapByte { src1, src2, dst, .. } => amo!(self, byte, swap, src1, src2, dst),
+			AtomicInstruction::AddByte { src1, src2, dst, .. } => amo!(self, byte, add, src1, src2, dst),
+			AtomicInstruction::XorByte { src1, src2, dst, .. } => amo!(self, byte, xor, src1, src2, dst),
+			AtomicInstruction::AndByte { src1, src2, dst, .. } => amo!(self, byte, and, src1, src2, dst),
+			AtomicInstruction::OrByte { src1, src2, dst, .. } => amo!(self, byte, or, src1, src2, dst),
+			AtomicInstruction::MinByte { src1, src2, dst, .. } => amo!(self, byte, min, src1, src2, dst),
+			AtomicInstruction::MaxByte { src1, src2, dst, .. } => amo!(self, byte, max, src1, src2, dst),
+			AtomicInstruction::MinUnsignedByte { src1, src2, dst, .. } => {
+				amo!(self, byte, minu, src1, src2, dst)
+			}
+			AtomicInstruction::MaxUnsignedByte { src1, src2, dst, .. } => {
+				amo!(self, byte, maxu, src1, src2, dst)
+			}
+			AtomicInstruction::SwapHalf { src1, src2, dst, .. } => amo!(self, half, swap, src1, src2, dst),
+			AtomicInstruction::AddHalf { src1, src2, dst, .. } => amo!(self, half, add, src1, src2, dst),
+			AtomicInstruction::XorHalf { src1, src2, dst, .. } => amo!(self, half, xor, src1, src2, dst),
+			AtomicInstruction::AndHalf { src1, src2, dst, .. } => amo!(self, half, and, src1, src2, dst),
+			AtomicInstruction::OrHalf { src1, src2, dst, .. } => amo!(self, half, or, src1, src2, dst),
+			AtomicInstruction::MinHalf { src1, src2, dst, .. } => amo!(self, half, min, src1, src2, dst),
+			AtomicInstruction::MaxHalf { src1, src2, dst, .. } => amo!(self, half, max, src1, src2, dst),
+			AtomicInstruction::MinUnsignedHalf { src1, src2, dst, .. } => {
+				amo!(self, half, minu, src1, src2, dst)
+			}
+			AtomicInstruction::MaxUnsignedHalf { src1, src2, dst, .. } => {
+				amo!(self, half, maxu, src1, src2, dst)
 			}
 		}
 	}
@@ -1377,8 +2963,7 @@ impl WhiskerCpu {
 				let lhs = self.registers.get(lhs) as u32;
 				let rhs = self.registers.get(rhs) as u32;
 
-				// TODO: babygirl is this right? lily is only like, half sure of this.
-				// ty in advance~ <3
+				// the low 32 bits of the product are the same for signed and unsigned, then sign extended
 				let result = ((lhs as i32).wrapping_mul(rhs as i32)) as i64;
 
 				self.registers.set(dst, result as u64);
@@ -1404,7 +2989,8 @@ impl WhiskerCpu {
 				// div by zero returns 0b111111111...
 				let result = if rhs == 0 { u32::MAX } else { lhs.wrapping_div(rhs) };
 
-				self.registers.set(dst, result as u64);
+				// the 32bit result is sign extended even for the unsigned variants
+				self.registers.set(dst, result as i32 as i64 as u64);
 			}
 			MultiplyInstruction::RemainderWord { lhs, rhs, dst } => {
 				let lhs = self.registers.get(lhs) as i32;
@@ -1427,12 +3013,732 @@ impl WhiskerCpu {
 				// rem by zero returns dividend
 				let result = if rhs == 0 { lhs } else { lhs.wrapping_rem(rhs) };
 
-				self.registers.set(dst, result as u64);
+				self.registers.set(dst, result as i32 as i64 as u64);
+			}
+		}
+	}
+
+	fn exec_bitmanip_insn(&mut self, insn: BitmanipInstruction, _start_pc: u64) {
+		match insn {
+			BitmanipInstruction::AddUnsignedWord { dst, lhs, rhs } => {
+				let lhs = self.registers.get(lhs) as u32 as u64;
+				let rhs = self.registers.get(rhs);
+
+				self.registers.set(dst, rhs.wrapping_add(lhs));
+			}
+			BitmanipInstruction::ShiftAdd { dst, lhs, rhs, shift } => {
+				let lhs = self.registers.get(lhs);
+				let rhs = self.registers.get(rhs);
+
+				self.registers.set(dst, rhs.wrapping_add(lhs << shift));
+			}
+			BitmanipInstruction::ShiftAddUnsignedWord { dst, lhs, rhs, shift } => {
+				let lhs = self.registers.get(lhs) as u32 as u64;
+				let rhs = self.registers.get(rhs);
+
+				self.registers.set(dst, rhs.wrapping_add(lhs << shift));
+			}
+			BitmanipInstruction::ShiftLeftLogicalImmediateUnsignedWord { dst, lhs, shift_amt } => {
+				let lhs = self.registers.get(lhs) as u32 as u64;
+
+				self.registers.set(dst, lhs << shift_amt);
+			}
+			BitmanipInstruction::AndNot { dst, lhs, rhs } => {
+				let lhs = self.registers.get(lhs);
+				let rhs = self.registers.get(rhs);
+				self.registers.set(dst, lhs & !rhs);
+			}
+			BitmanipInstruction::OrNot { dst, lhs, rhs } => {
+				let lhs = self.registers.get(lhs);
+				let rhs = self.registers.get(rhs);
+				self.registers.set(dst, lhs | !rhs);
+			}
+			BitmanipInstruction::XorNot { dst, lhs, rhs } => {
+				let lhs = self.registers.get(lhs);
+				let rhs = self.registers.get(rhs);
+				self.registers.set(dst, !(lhs ^ rhs));
+			}
+			BitmanipInstruction::CountLeadingZeros { dst, src } => {
+				let src = self.registers.get(src);
+				self.registers.set(dst, u64::from(src.leading_zeros()));
+			}
+			BitmanipInstruction::CountLeadingZerosWord { dst, src } => {
+				let src = self.registers.get(src) as u32;
+				self.registers.set(dst, u64::from(src.leading_zeros()));
+			}
+			BitmanipInstruction::CountTrailingZeros { dst, src } => {
+				let src = self.registers.get(src);
+				self.registers.set(dst, u64::from(src.trailing_zeros()));
+			}
+			BitmanipInstruction::CountTrailingZerosWord { dst, src } => {
+				let src = self.registers.get(src) as u32;
+				self.registers.set(dst, u64::from(src.trailing_zeros()));
+			}
+			BitmanipInstruction::CountPopulation { dst, src } => {
+				let src = self.registers.get(src);
+				self.registers.set(dst, u64::from(src.count_ones()));
+			}
+			BitmanipInstruction::CountPopulationWord { dst, src } => {
+				let src = self.registers.get(src) as u32;
+				self.registers.set(dst, u64::from(src.count_ones()));
+			}
+			BitmanipInstruction::Min { dst, lhs, rhs } => {
+				let lhs = self.registers.get(lhs) as i64;
+				let rhs = self.registers.get(rhs) as i64;
+				self.registers.set(dst, lhs.min(rhs) as u64);
+			}
+			BitmanipInstruction::MinUnsigned { dst, lhs, rhs } => {
+				let lhs = self.registers.get(lhs);
+				let rhs = self.registers.get(rhs);
+				self.registers.set(dst, lhs.min(rhs));
+			}
+			BitmanipInstruction::Max { dst, lhs, rhs } => {
+				let lhs = self.registers.get(lhs) as i64;
+				let rhs = self.registers.get(rhs) as i64;
+				self.registers.set(dst, lhs.max(rhs) as u64);
+			}
+			BitmanipInstruction::MaxUnsigned { dst, lhs, rhs } => {
+				let lhs = self.registers.get(lhs);
+				let rhs = self.registers.get(rhs);
+				self.registers.set(dst, lhs.max(rhs));
+			}
+			BitmanipInstruction::SignExtendByte { dst, src } => {
+				let src = self.registers.get(src) as i8;
+				self.registers.set(dst, src as i64 as u64);
+			}
+			BitmanipInstruction::SignExtendHalf { dst, src } => {
+				let src = self.registers.get(src) as i16;
+				self.registers.set(dst, src as i64 as u64);
+			}
+			BitmanipInstruction::ZeroExtendHalf { dst, src } => {
+				let src = self.registers.get(src) as u16;
+				self.registers.set(dst, u64::from(src));
+			}
+			BitmanipInstruction::RotateLeft { dst, lhs, rhs } => {
+				let lhs = self.registers.get(lhs);
+				let rhs = self.registers.get(rhs) as u32;
+				// rotate_left only looks at the low bits of the amount
+				self.registers.set(dst, lhs.rotate_left(rhs));
+			}
+			BitmanipInstruction::RotateRight { dst, lhs, rhs } => {
+				let lhs = self.registers.get(lhs);
+				let rhs = self.registers.get(rhs) as u32;
+				self.registers.set(dst, lhs.rotate_right(rhs));
+			}
+			BitmanipInstruction::RotateLeftWord { dst, lhs, rhs } => {
+				let lhs = self.registers.get(lhs) as u32;
+				let rhs = self.registers.get(rhs) as u32;
+				self.registers.set(dst, lhs.rotate_left(rhs) as i32 as i64 as u64);
+			}
+			BitmanipInstruction::RotateRightWord { dst, lhs, rhs } => {
+				let lhs = self.registers.get(lhs) as u32;
+				let rhs = self.registers.get(rhs) as u32;
+				self.registers.set(dst, lhs.rotate_right(rhs) as i32 as i64 as u64);
+			}
+			BitmanipInstruction::RotateRightImmediate { dst, lhs, shift_amt } => {
+				let lhs = self.registers.get(lhs);
+				self.registers.set(dst, lhs.rotate_right(shift_amt));
+			}
+			BitmanipInstruction::RotateRightImmediateWord { dst, lhs, shift_amt } => {
+				let lhs = self.registers.get(lhs) as u32;
+				self.registers
+					.set(dst, lhs.rotate_right(shift_amt) as i32 as i64 as u64);
+			}
+			BitmanipInstruction::OrCombineByte { dst, src } => {
+				let bytes = self
+					.registers
+					.get(src)
+					.to_le_bytes()
+					.map(|b| if b == 0 { 0 } else { 0xFF });
+				self.registers.set(dst, u64::from_le_bytes(bytes));
+			}
+			BitmanipInstruction::ReverseBytes { dst, src } => {
+				let src = self.registers.get(src);
+				self.registers.set(dst, src.swap_bytes());
+			}
+			BitmanipInstruction::CarrylessMul { dst, lhs, rhs } => {
+				let product = carryless_mul(self.registers.get(lhs), self.registers.get(rhs));
+				self.registers.set(dst, product as u64);
+			}
+			BitmanipInstruction::CarrylessMulReversed { dst, lhs, rhs } => {
+				let product = carryless_mul(self.registers.get(lhs), self.registers.get(rhs));
+				self.registers.set(dst, (product >> 63) as u64);
+			}
+			BitmanipInstruction::CarrylessMulHigh { dst, lhs, rhs } => {
+				let product = carryless_mul(self.registers.get(lhs), self.registers.get(rhs));
+				self.registers.set(dst, (product >> 64) as u64);
+			}
+			BitmanipInstruction::BitClear { dst, lhs, rhs } => {
+				let lhs = self.registers.get(lhs);
+				let bit = self.registers.get(rhs) & 63;
+				self.registers.set(dst, lhs & !(1 << bit));
+			}
+			BitmanipInstruction::BitExtract { dst, lhs, rhs } => {
+				let lhs = self.registers.get(lhs);
+				let bit = self.registers.get(rhs) & 63;
+				self.registers.set(dst, (lhs >> bit) & 1);
+			}
+			BitmanipInstruction::BitInvert { dst, lhs, rhs } => {
+				let lhs = self.registers.get(lhs);
+				let bit = self.registers.get(rhs) & 63;
+				self.registers.set(dst, lhs ^ (1 << bit));
+			}
+			BitmanipInstruction::BitSet { dst, lhs, rhs } => {
+				let lhs = self.registers.get(lhs);
+				let bit = self.registers.get(rhs) & 63;
+				self.registers.set(dst, lhs | (1 << bit));
+			}
+			BitmanipInstruction::BitClearImmediate { dst, lhs, shift_amt } => {
+				let lhs = self.registers.get(lhs);
+				self.registers.set(dst, lhs & !(1 << shift_amt));
+			}
+			BitmanipInstruction::BitExtractImmediate { dst, lhs, shift_amt } => {
+				let lhs = self.registers.get(lhs);
+				self.registers.set(dst, (lhs >> shift_amt) & 1);
+			}
+			BitmanipInstruction::BitInvertImmediate { dst, lhs, shift_amt } => {
+				let lhs = self.registers.get(lhs);
+				self.registers.set(dst, lhs ^ (1 << shift_amt));
+			}
+			BitmanipInstruction::BitSetImmediate { dst, lhs, shift_amt } => {
+				let lhs = self.registers.get(lhs);
+				self.registers.set(dst, lhs | (1 << shift_amt));
+			}
+		}
+	}
+
+	fn exec_vector_insn(&mut self, insn: VectorInstruction, _start_pc: u64) {
+		if let VectorInstruction::SetVectorLength { dst, avl, vtype } = insn {
+			self.exec_set_vector_length(dst, avl, vtype);
+			return;
+		}
+
+		// everything other than vset{i}vl{i} is illegal while vill is set
+		let Some(vtype) = VectorType::parse(self.csrs.read_vtype()) else {
+			self.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+			return;
+		};
+		let sew = vtype.sew_bytes;
+		let vl = self.csrs.read_vl() as usize;
+		let vstart = self.csrs.read_vstart() as usize;
+		let group = vtype.group_regs();
+
+		match insn {
+			VectorInstruction::SetVectorLength { .. } => unreachable!(),
+			VectorInstruction::LoadUnitStride {
+				dst,
+				base,
+				eew_bytes,
+				masked,
+				fault_only_first,
+			} => {
+				// a masked load can't overwrite the mask it's reading
+				let Some(emul_group) = Self::vector_emul_group(vtype, eew_bytes) else {
+					self.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+					return;
+				};
+				if !vector_group_aligned(dst, emul_group) || (masked && dst == VectorRegisterIndex::ZERO) {
+					self.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+					return;
+				}
+
+				let base = self.registers.get(base);
+				for idx in vstart..vl {
+					if !self.vector_elem_active(masked, idx) {
+						continue;
+					}
+					let addr = base.wrapping_add((idx * eew_bytes) as u64);
+					let result = if self.traps_misaligned(addr, eew_bytes) {
+						Err((TrapIdx::LOAD_ADDR_MISALIGNED, addr))
+					} else {
+						self.read_mem_sized(addr, eew_bytes)
+					};
+					match result {
+						Ok(val) => self.vec_registers.set_elem(dst, eew_bytes, idx, val),
+						// faults past the first element just shorten vl
+						Err(_) if fault_only_first && idx > 0 => {
+							self.csrs.write_vl(idx as u64);
+							break;
+						}
+						Err((trap, fault_addr)) => {
+							// the trap handler can resume from the faulting element
+							self.csrs.write_vstart(idx as u64);
+							self.request_trap(trap, fault_addr);
+							return;
+						}
+					}
+				}
+			}
+			VectorInstruction::StoreUnitStride {
+				src,
+				base,
+				eew_bytes,
+				masked,
+			} => {
+				let Some(emul_group) = Self::vector_emul_group(vtype, eew_bytes) else {
+					self.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+					return;
+				};
+				if !vector_group_aligned(src, emul_group) {
+					self.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+					return;
+				}
+
+				let base = self.registers.get(base);
+				for idx in vstart..vl {
+					if !self.vector_elem_active(masked, idx) {
+						continue;
+					}
+					let addr = base.wrapping_add((idx * eew_bytes) as u64);
+					if self.traps_misaligned(addr, eew_bytes) {
+						self.csrs.write_vstart(idx as u64);
+						self.request_trap(TrapIdx::STORE_ADDR_MISALIGNED, addr);
+						return;
+					}
+					let val = self.vec_registers.get_elem(src, eew_bytes, idx);
+					if let Err((cause, fault_addr)) = self.write_mem_sized(addr, eew_bytes, val) {
+						self.csrs.write_vstart(idx as u64);
+						self.request_trap(cause, fault_addr);
+						return;
+					}
+				}
+			}
+			VectorInstruction::IntOp {
+				op,
+				dst,
+				lhs,
+				rhs,
+				masked,
+			} => {
+				if !vector_group_aligned(dst, group)
+					|| !vector_group_aligned(lhs, group)
+					|| !vector_operand_aligned(rhs, group)
+				{
+					self.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+					return;
+				}
+
+				for idx in vstart..vl {
+					if !self.vector_elem_active(masked, idx) {
+						continue;
+					}
+					let a = self.vec_registers.get_elem(lhs, sew, idx);
+					let b = self.vector_operand(rhs, sew, idx);
+					self.vec_registers
+						.set_elem(dst, sew, idx, vector_int_op(op, a, b, sew * 8));
+				}
+			}
+			VectorInstruction::Compare {
+				op,
+				dst,
+				lhs,
+				rhs,
+				masked,
+			} => {
+				if !vector_group_aligned(lhs, group) || !vector_operand_aligned(rhs, group) {
+					self.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+					return;
+				}
+
+				// dst may overlap the sources, so compute everything before writing the mask
+				let results = (vstart..vl)
+					.filter(|&idx| self.vector_elem_active(masked, idx))
+					.map(|idx| {
+						let a = self.vec_registers.get_elem(lhs, sew, idx);
+						let b = self.vector_operand(rhs, sew, idx);
+						(idx, vector_cmp_op(op, a, b, sew * 8))
+					})
+					.collect::<Vec<_>>();
+				for (idx, result) in results {
+					self.vec_registers.set_mask(dst, idx, result);
+				}
+			}
+			VectorInstruction::Merge { dst, lhs, rhs } => {
+				if !vector_group_aligned(dst, group)
+					|| !vector_group_aligned(lhs, group)
+					|| !vector_operand_aligned(rhs, group)
+					|| dst == VectorRegisterIndex::ZERO
+				{
+					self.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+					return;
+				}
+
+				for idx in vstart..vl {
+					let val = if self.vec_registers.get_mask(VectorRegisterIndex::ZERO, idx) {
+						self.vector_operand(rhs, sew, idx)
+					} else {
+						self.vec_registers.get_elem(lhs, sew, idx)
+					};
+					self.vec_registers.set_elem(dst, sew, idx, val);
+				}
+			}
+			VectorInstruction::Move { dst, src } => {
+				if !vector_group_aligned(dst, group) || !vector_operand_aligned(src, group) {
+					self.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+					return;
+				}
+
+				for idx in vstart..vl {
+					let val = self.vector_operand(src, sew, idx);
+					self.vec_registers.set_elem(dst, sew, idx, val);
+				}
+			}
+			VectorInstruction::MoveToScalar { dst, src } => {
+				// ignores vl, element 0 is always read
+				let val = self.vec_registers.get_elem(src, sew, 0);
+				self.registers.set(dst, sign_extend(val, sew * 8) as u64);
+			}
+			VectorInstruction::MoveFromScalar { dst, src } => {
+				if vstart < vl {
+					let val = self.registers.get(src);
+					self.vec_registers.set_elem(dst, sew, 0, val);
+				}
+			}
+			VectorInstruction::MaskPopCount { dst, src, masked } => {
+				let count = (0..vl)
+					.filter(|&idx| self.vector_elem_active(masked, idx) && self.vec_registers.get_mask(src, idx))
+					.count();
+				self.registers.set(dst, count as u64);
 			}
+			VectorInstruction::MaskFindFirst { dst, src, masked } => {
+				let first =
+					(0..vl).find(|&idx| self.vector_elem_active(masked, idx) && self.vec_registers.get_mask(src, idx));
+				self.registers.set(dst, first.map_or(u64::MAX, |idx| idx as u64));
+			}
+		}
+
+		self.csrs.write_vstart(0);
+	}
+
+	fn exec_set_vector_length(&mut self, dst: GPRegisterIndex, avl: VectorLengthSource, vtype: VectorTypeSource) {
+		let vtype = match vtype {
+			VectorTypeSource::Register(reg) => self.registers.get(reg),
+			VectorTypeSource::Immediate(vtype) => vtype,
+		};
+
+		// unsupported settings set vill and vl = 0 instead of trapping
+		let Some(vlmax) = VectorType::parse(vtype).map(|ty| ty.vlmax(self.vec_registers.vlenb()) as u64) else {
+			self.csrs.write_vtype(VectorType::ILLEGAL);
+			self.csrs.write_vl(0);
+			self.csrs.write_vstart(0);
+			self.registers.set(dst, 0);
+			return;
+		};
+
+		let avl = match avl {
+			VectorLengthSource::Immediate(avl) => avl,
+			VectorLengthSource::Register(reg) if reg != GPRegisterIndex::ZERO => self.registers.get(reg),
+			// rs1 = x0 with a destination asks for VLMAX
+			VectorLengthSource::Register(_) if dst != GPRegisterIndex::ZERO => u64::MAX,
+			// rs1 = x0 and rd = x0 keeps the current vl
+			VectorLengthSource::Register(_) => self.csrs.read_vl(),
+		};
+
+		let vl = avl.min(vlmax);
+		self.csrs.write_vtype(vtype);
+		self.csrs.write_vl(vl);
+		self.csrs.write_vstart(0);
+		self.registers.set(dst, vl);
+	}
+
+	/// the number of registers in a group of eew_bytes elements, EMUL = (EEW / SEW) * LMUL
+	/// returns None if EMUL is out of range
+	fn vector_emul_group(vtype: VectorType, eew_bytes: usize) -> Option<usize> {
+		let emul_log2 = vtype.lmul_log2 + eew_bytes.trailing_zeros() as i32 - vtype.sew_bytes.trailing_zeros() as i32;
+		if (-3..=3).contains(&emul_log2) {
+			Some(1 << emul_log2.max(0))
+		} else {
+			None
+		}
+	}
+
+	fn vector_elem_active(&self, masked: bool, idx: usize) -> bool {
+		!masked || self.vec_registers.get_mask(VectorRegisterIndex::ZERO, idx)
+	}
+
+	/// the value of the second operand for element idx, truncated to SEW
+	fn vector_operand(&self, operand: VectorOperand, sew_bytes: usize, idx: usize) -> u64 {
+		let sew_mask = u64::MAX >> (64 - sew_bytes * 8);
+		match operand {
+			VectorOperand::Vector(reg) => self.vec_registers.get_elem(reg, sew_bytes, idx),
+			VectorOperand::Scalar(reg) => self.registers.get(reg) & sew_mask,
+			VectorOperand::Immediate(imm) => imm as u64 & sew_mask,
+		}
+	}
+
+	fn traps_misaligned(&self, addr: u64, bytes: usize) -> bool {
+		self.misaligned_access == MisalignedAccess::Trap && addr % bytes as u64 != 0
+	}
+
+	fn read_mem_sized(&mut self, addr: u64, bytes: usize) -> Result<u64, (TrapIdx, u64)> {
+		match bytes {
+			1 => self.read_virt_u8(addr, AccessType::Load).map(u64::from),
+			2 => self.read_virt_u16(addr, AccessType::Load).map(u64::from),
+			4 => self.read_virt_u32(addr, AccessType::Load).map(u64::from),
+			8 => self.read_virt_u64(addr, AccessType::Load),
+			_ => unreachable!(),
+		}
+	}
+
+	fn write_mem_sized(&mut self, addr: u64, bytes: usize, val: u64) -> Result<(), (TrapIdx, u64)> {
+		match bytes {
+			1 => self.write_virt_u8(addr, val as u8),
+			2 => self.write_virt_u16(addr, val as u16),
+			4 => self.write_virt_u32(addr, val as u32),
+			8 => self.write_virt_u64(addr, val),
+			_ => unreachable!(),
 		}
 	}
 
 	fn should_poll(&self) -> bool {
 		self.cycles % 1024 == 0
 	}
+
+	/// sets the pc to the target of a taken branch or jump
+	/// returns false and raises an instruction-address-misaligned trap if the target isn't aligned
+	fn jump_to(&mut self, target: u64) -> bool {
+		let align = if self.supported_extensions.has(SupportedExtensions::COMPRESSED) {
+			2
+		} else {
+			4
+		};
+		if target % align != 0 {
+			self.request_trap(TrapIdx::INSTRUCTION_ADDR_MISALIGNED, target);
+			return false;
+		}
+
+		self.pc = target;
+		true
+	}
+
+	/// the part of MRET and SRET that's shared once the new mstatus has been computed
+	fn exec_trap_return(&mut self, mut status: Mstatus, prev_privilege: PrivilegeMode, epc: u64) {
+		if prev_privilege != PrivilegeMode::Machine {
+			status.set_mprv(false);
+		}
+		self.csrs.write_mstatus(status.0);
+
+		self.privilege = prev_privilege;
+		self.pc = epc & self.epc_mask();
+		// an SC can't succeed on a reservation taken before the return
+		self.mem.release_reservation(self.hart_id);
+	}
+
+	fn least_privileged_mode(&self) -> PrivilegeMode {
+		if self.supported_extensions.has(SupportedExtensions::USER_MODE) {
+			PrivilegeMode::User
+		} else {
+			PrivilegeMode::Machine
+		}
+	}
+
+	/// applies the WARL rules of mstatus to a value being written to it
+	/// fields for modes or extensions we don't support are read-only zero
+	fn legalize_mstatus(&self, val: u64) -> Mstatus {
+		let has_user = self.supported_extensions.has(SupportedExtensions::USER_MODE);
+		let has_supervisor = self.supported_extensions.has(SupportedExtensions::SUPERVISOR);
+		let has_float = self.supported_extensions.has(SupportedExtensions::FLOAT);
+
+		let mut writable = Mstatus::MIE | Mstatus::MPIE;
+		if has_user {
+			writable |= Mstatus::MPRV | Mstatus::TW;
+		}
+		if has_supervisor {
+			writable |=
+				Mstatus::SIE | Mstatus::SPIE | Mstatus::SPP | Mstatus::SUM | Mstatus::MXR | Mstatus::TVM | Mstatus::TSR;
+		}
+		if has_float || has_supervisor {
+			writable |= Mstatus::FS;
+		}
+
+		let old = Mstatus(self.csrs.read_mstatus());
+		let mut status = Mstatus((old.0 & !writable) | (val & writable));
+
+		// MPP only holds modes we support, anything else falls back to M
+		let mpp = match Mstatus(val).mpp() {
+			Some(PrivilegeMode::User) if has_user => PrivilegeMode::User,
+			Some(PrivilegeMode::Supervisor) if has_supervisor => PrivilegeMode::Supervisor,
+			_ => PrivilegeMode::Machine,
+		};
+		status.set_mpp(mpp);
+
+		status.0 &= !(Mstatus::UXL_64 | Mstatus::SXL_64);
+		if has_user {
+			status.0 |= Mstatus::UXL_64;
+		}
+		if has_supervisor {
+			status.0 |= Mstatus::SXL_64;
+		}
+		// recomputes SD
+		status.set_fs(status.fs());
+		status
+	}
+
+	/// float instructions and CSRs are illegal while mstatus.FS is off, this is how OSes save float state lazily
+	fn float_enabled(&self) -> bool {
+		Mstatus(self.csrs.read_mstatus()).fs() != FloatStatus::Off
+	}
+
+	/// anything that changes the float registers or fcsr has to mark FS dirty so the OS knows to save them
+	fn mark_float_dirty(&mut self) {
+		let mut status = Mstatus(self.csrs.read_mstatus());
+		if status.fs() != FloatStatus::Dirty {
+			status.set_fs(FloatStatus::Dirty);
+			self.csrs.write_mstatus(status.0);
+		}
+	}
+
+	/// ORs exception flags into fflags
+	pub fn accrue_fflags(&mut self, flags: u8) {
+		if flags != 0 {
+			let val = self.csrs.read_fcsr() | u64::from(flags);
+			self.csrs.write_fcsr(val);
+			self.mark_float_dirty();
+		}
+	}
+
+	/// xepc can't hold a misaligned address, bit 1 is only masked off when compressed instructions are disabled
+	fn epc_mask(&self) -> u64 {
+		if self.supported_extensions.has(SupportedExtensions::COMPRESSED) {
+			!0b1
+		} else {
+			!0b11
+		}
+	}
+
+	/// whether WFI should stop waiting, this ignores the global interrupt enables
+	fn interrupt_pending(&self) -> bool {
+		self.read_mip() & self.csrs.read_mie() != 0
+	}
+
+	/// the bits software wrote to mip combined with the lines devices are raising
+	fn read_mip(&self) -> u64 {
+		(self.csrs.read_mip() | self.interrupt_lines.get()) & self.supported_interrupts()
+	}
+
+	fn supported_interrupts(&self) -> u64 {
+		if self.supported_extensions.has(SupportedExtensions::SUPERVISOR) {
+			interrupt::MACHINE | interrupt::SUPERVISOR
+		} else {
+			interrupt::MACHINE
+		}
+	}
+
+	/// the highest priority interrupt that's pending, enabled and able to preempt the current privilege level
+	fn pending_interrupt(&self) -> Option<TrapIdx> {
+		// in decreasing priority
+		const PRIORITY: [(u64, TrapIdx); 6] = [
+			(interrupt::MACHINE_EXTERNAL, TrapIdx::MACHINE_EXTERNAL_INTERRUPT),
+			(interrupt::MACHINE_SOFTWARE, TrapIdx::MACHINE_SOFTWARE_INTERRUPT),
+			(interrupt::MACHINE_TIMER, TrapIdx::MACHINE_TIMER_INTERRUPT),
+			(interrupt::SUPERVISOR_EXTERNAL, TrapIdx::SUPERVISOR_EXTERNAL_INTERRUPT),
+			(interrupt::SUPERVISOR_SOFTWARE, TrapIdx::SUPERVISOR_SOFTWARE_INTERRUPT),
+			(interrupt::SUPERVISOR_TIMER, TrapIdx::SUPERVISOR_TIMER_INTERRUPT),
+		];
+
+		// checked first since this runs every cycle and mie is usually zero
+		let enabled = self.csrs.read_mie();
+		if enabled == 0 {
+			return None;
+		}
+		let pending = self.read_mip() & enabled;
+		if pending == 0 {
+			return None;
+		}
+
+		// a mode takes its interrupts from lower modes regardless of its interrupt enable
+		let status = Mstatus(self.csrs.read_mstatus());
+		let delegated = self.csrs.read_mideleg();
+		let machine_enabled = self.privilege < PrivilegeMode::Machine || status.mie();
+		let supervisor_enabled =
+			self.privilege < PrivilegeMode::Supervisor || (self.privilege == PrivilegeMode::Supervisor && status.sie());
+
+		let to_machine = if machine_enabled { pending & !delegated } else { 0 };
+		let to_supervisor = if supervisor_enabled { pending & delegated } else { 0 };
+		// interrupts for M-mode always win over the ones delegated to S-mode
+		let takeable = if to_machine != 0 { to_machine } else { to_supervisor };
+
+		PRIORITY
+			.iter()
+			.find(|(bit, _)| takeable & bit != 0)
+			.map(|(_, cause)| *cause)
+	}
+}
+
+// menvcfg.FIOM, fences on memory also order I/O below M-mode
+const MENVCFG_FIOM: u64 = 1 << 0;
+
+// the low bits of xtvec select direct or vectored mode
+const TVEC_MODE_MASK: u64 = 0b11;
+const TVEC_VECTORED: u64 = 0b01;
+
+fn legalize_tvec(val: u64) -> u64 {
+	if val & TVEC_MODE_MASK > TVEC_VECTORED {
+		val & !TVEC_MODE_MASK
+	} else {
+		val
+	}
+}
+
+/// where a trap goes for a given xtvec, interrupts get their own entry in vectored mode
+fn trap_handler_addr(tvec: u64, cause: TrapIdx) -> u64 {
+	let base = tvec & !TVEC_MODE_MASK;
+	if tvec & TVEC_MODE_MASK == TVEC_VECTORED && cause.kind() == TrapKind::Interrupt {
+		base + 4 * cause.code()
+	} else {
+		base
+	}
+}
+
+fn vector_group_aligned(reg: VectorRegisterIndex, group: usize) -> bool {
+	reg.as_usize() % group == 0
+}
+
+fn vector_operand_aligned(operand: VectorOperand, group: usize) -> bool {
+	match operand {
+		VectorOperand::Vector(reg) => vector_group_aligned(reg, group),
+		_ => true,
+	}
+}
+
+fn sign_extend(val: u64, bits: usize) -> i64 {
+	((val << (64 - bits)) as i64) >> (64 - bits)
+}
+
+/// lhs and rhs are zero extended SEW bit values, the result is truncated when it's written back
+fn vector_int_op(op: VectorIntOp, lhs: u64, rhs: u64, sew_bits: usize) -> u64 {
+	let shift_amt = rhs & (sew_bits as u64 - 1);
+	let signed_lhs = sign_extend(lhs, sew_bits);
+	let signed_rhs = sign_extend(rhs, sew_bits);
+	match op {
+		VectorIntOp::Add => lhs.wrapping_add(rhs),
+		VectorIntOp::Sub => lhs.wrapping_sub(rhs),
+		VectorIntOp::ReverseSub => rhs.wrapping_sub(lhs),
+		VectorIntOp::And => lhs & rhs,
+		VectorIntOp::Or => lhs | rhs,
+		VectorIntOp::Xor => lhs ^ rhs,
+		VectorIntOp::ShiftLeft => lhs << shift_amt,
+		VectorIntOp::ShiftRightLogical => lhs >> shift_amt,
+		VectorIntOp::ShiftRightArithmetic => (signed_lhs >> shift_amt) as u64,
+		VectorIntOp::Min => signed_lhs.min(signed_rhs) as u64,
+		VectorIntOp::MinUnsigned => lhs.min(rhs),
+		VectorIntOp::Max => signed_lhs.max(signed_rhs) as u64,
+		VectorIntOp::MaxUnsigned => lhs.max(rhs),
+		VectorIntOp::Mul => lhs.wrapping_mul(rhs),
+	}
+}
+
+fn vector_cmp_op(op: VectorCmpOp, lhs: u64, rhs: u64, sew_bits: usize) -> bool {
+	let signed_lhs = sign_extend(lhs, sew_bits);
+	let signed_rhs = sign_extend(rhs, sew_bits);
+	match op {
+		VectorCmpOp::Equal => lhs == rhs,
+		VectorCmpOp::NotEqual => lhs != rhs,
+		VectorCmpOp::LessThanUnsigned => lhs < rhs,
+		VectorCmpOp::LessThan => signed_lhs < signed_rhs,
+		VectorCmpOp::LessOrEqualUnsigned => lhs <= rhs,
+		VectorCmpOp::LessOrEqual => signed_lhs <= signed_rhs,
+		VectorCmpOp::GreaterThanUnsigned => lhs > rhs,
+		VectorCmpOp::GreaterThan => signed_lhs > signed_rhs,
+	}
 }
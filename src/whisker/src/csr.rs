@@ -1,6 +1,9 @@
 use std::collections::HashMap;
 use std::fmt::Debug;
 
+use crate::snapshot::{Snapshot, SnapshotReader, SnapshotWriter};
+use crate::ty::PrivilegeMode;
+
 pub const NUM_CSRS: u16 = 4096;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
@@ -116,6 +119,32 @@ impl ControlStatusRegisters {
 	}
 }
 
+impl Snapshot for ControlStatusRegisters {
+	/// every CSR is saved with its address, in order so the same machine always produces the same snapshot
+	fn save(&self, snapshot: &mut SnapshotWriter) {
+		let mut regs: Vec<_> = self.regs.values().collect();
+		regs.sort_by_key(|reg| reg.addr);
+		snapshot.u64(regs.len() as u64);
+		for reg in regs {
+			snapshot.u16(reg.addr);
+			snapshot.u64(reg.val);
+		}
+	}
+
+	fn restore(&mut self, snapshot: &mut SnapshotReader) -> Result<(), String> {
+		for _ in 0..snapshot.u64()? {
+			let addr = snapshot.u16()?;
+			let val = snapshot.u64()?;
+			let reg = self
+				.regs
+				.get_mut(&addr)
+				.ok_or_else(|| format!("the snapshot has CSR {addr:#05x}, which this machine doesn't implement"))?;
+			reg.val = val;
+		}
+		Ok(())
+	}
+}
+
 const RW: bool = true;
 const RO: bool = false;
 
@@ -124,11 +153,355 @@ define_csrs!(
     mvendorid, 0xF11, RO, Machine, 0,
     marchid,   0xF12, RO, Machine, 0,
     mimpid,    0xF13, RO, Machine, 0,
+    // backed by the cpu's hart id
+    mhartid,   0xF14, RO, Machine,
+    // there's no configuration data structure
+    mconfigptr, 0xF15, RO, Machine, 0,
+
+    // sstatus, sie and sip are views of mstatus, mie and mip, their values here are unused
+    sstatus,   0x100, RW, Supervisor,
+    sie,       0x104, RW, Supervisor,
+    stvec,     0x105, RW, Supervisor,
+    scounteren, 0x106, RW, Supervisor,
+    sscratch,  0x140, RW, Supervisor,
+    sepc,      0x141, RW, Supervisor,
+    scause,    0x142, RW, Supervisor,
+    stval,     0x143, RW, Supervisor,
+    sip,       0x144, RW, Supervisor,
+    satp,      0x180, RW, Supervisor,
 
+    mstatus,   0x300, RW, Machine,
+    // filled in by the cpu from the supported extensions
+    misa,      0x301, RW, Machine,
+    medeleg,   0x302, RW, Machine,
+    mideleg,   0x303, RW, Machine,
+    mie,       0x304, RW, Machine,
     mtvec,     0x305, RW, Machine, 0x4000_0000,
+    mcounteren, 0x306, RW, Machine,
+    menvcfg,   0x30A, RW, Machine,
+    mscratch,  0x340, RW, Machine,
     mepc,      0x341, RW, Machine,
     mcause,    0x342, RW, Machine,
     mtval,     0x343, RW, Machine,
+    mip,       0x344, RW, Machine,
+
+    // backed by the cpu's pmp, only the even pmpcfg CSRs exist on RV64
+    pmpcfg0,   0x3A0, RW, Machine,
+    pmpcfg2,   0x3A2, RW, Machine,
+    pmpcfg4,   0x3A4, RW, Machine,
+    pmpcfg6,   0x3A6, RW, Machine,
+    pmpcfg8,   0x3A8, RW, Machine,
+    pmpcfg10,  0x3AA, RW, Machine,
+    pmpcfg12,  0x3AC, RW, Machine,
+    pmpcfg14,  0x3AE, RW, Machine,
+    pmpaddr0,  0x3B0, RW, Machine,
+    pmpaddr1,  0x3B1, RW, Machine,
+    pmpaddr2,  0x3B2, RW, Machine,
+    pmpaddr3,  0x3B3, RW, Machine,
+    pmpaddr4,  0x3B4, RW, Machine,
+    pmpaddr5,  0x3B5, RW, Machine,
+    pmpaddr6,  0x3B6, RW, Machine,
+    pmpaddr7,  0x3B7, RW, Machine,
+    pmpaddr8,  0x3B8, RW, Machine,
+    pmpaddr9,  0x3B9, RW, Machine,
+    pmpaddr10, 0x3BA, RW, Machine,
+    pmpaddr11, 0x3BB, RW, Machine,
+    pmpaddr12, 0x3BC, RW, Machine,
+    pmpaddr13, 0x3BD, RW, Machine,
+    pmpaddr14, 0x3BE, RW, Machine,
+    pmpaddr15, 0x3BF, RW, Machine,
+    pmpaddr16, 0x3C0, RW, Machine,
+    pmpaddr17, 0x3C1, RW, Machine,
+    pmpaddr18, 0x3C2, RW, Machine,
+    pmpaddr19, 0x3C3, RW, Machine,
+    pmpaddr20, 0x3C4, RW, Machine,
+    pmpaddr21, 0x3C5, RW, Machine,
+    pmpaddr22, 0x3C6, RW, Machine,
+    pmpaddr23, 0x3C7, RW, Machine,
+    pmpaddr24, 0x3C8, RW, Machine,
+    pmpaddr25, 0x3C9, RW, Machine,
+    pmpaddr26, 0x3CA, RW, Machine,
+    pmpaddr27, 0x3CB, RW, Machine,
+    pmpaddr28, 0x3CC, RW, Machine,
+    pmpaddr29, 0x3CD, RW, Machine,
+    pmpaddr30, 0x3CE, RW, Machine,
+    pmpaddr31, 0x3CF, RW, Machine,
+    pmpaddr32, 0x3D0, RW, Machine,
+    pmpaddr33, 0x3D1, RW, Machine,
+    pmpaddr34, 0x3D2, RW, Machine,
+    pmpaddr35, 0x3D3, RW, Machine,
+    pmpaddr36, 0x3D4, RW, Machine,
+    pmpaddr37, 0x3D5, RW, Machine,
+    pmpaddr38, 0x3D6, RW, Machine,
+    pmpaddr39, 0x3D7, RW, Machine,
+    pmpaddr40, 0x3D8, RW, Machine,
+    pmpaddr41, 0x3D9, RW, Machine,
+    pmpaddr42, 0x3DA, RW, Machine,
+    pmpaddr43, 0x3DB, RW, Machine,
+    pmpaddr44, 0x3DC, RW, Machine,
+    pmpaddr45, 0x3DD, RW, Machine,
+    pmpaddr46, 0x3DE, RW, Machine,
+    pmpaddr47, 0x3DF, RW, Machine,
+    pmpaddr48, 0x3E0, RW, Machine,
+    pmpaddr49, 0x3E1, RW, Machine,
+    pmpaddr50, 0x3E2, RW, Machine,
+    pmpaddr51, 0x3E3, RW, Machine,
+    pmpaddr52, 0x3E4, RW, Machine,
+    pmpaddr53, 0x3E5, RW, Machine,
+    pmpaddr54, 0x3E6, RW, Machine,
+    pmpaddr55, 0x3E7, RW, Machine,
+    pmpaddr56, 0x3E8, RW, Machine,
+    pmpaddr57, 0x3E9, RW, Machine,
+    pmpaddr58, 0x3EA, RW, Machine,
+    pmpaddr59, 0x3EB, RW, Machine,
+    pmpaddr60, 0x3EC, RW, Machine,
+    pmpaddr61, 0x3ED, RW, Machine,
+    pmpaddr62, 0x3EE, RW, Machine,
+    pmpaddr63, 0x3EF, RW, Machine,
+
+    // backed by the cpu's hpm counters
+    mhpmevent3,    0x323, RW, Machine,
+    mhpmevent4,    0x324, RW, Machine,
+    mhpmevent5,    0x325, RW, Machine,
+    mhpmevent6,    0x326, RW, Machine,
+    mhpmevent7,    0x327, RW, Machine,
+    mhpmevent8,    0x328, RW, Machine,
+    mhpmevent9,    0x329, RW, Machine,
+    mhpmevent10,   0x32A, RW, Machine,
+    mhpmevent11,   0x32B, RW, Machine,
+    mhpmevent12,   0x32C, RW, Machine,
+    mhpmevent13,   0x32D, RW, Machine,
+    mhpmevent14,   0x32E, RW, Machine,
+    mhpmevent15,   0x32F, RW, Machine,
+    mhpmevent16,   0x330, RW, Machine,
+    mhpmevent17,   0x331, RW, Machine,
+    mhpmevent18,   0x332, RW, Machine,
+    mhpmevent19,   0x333, RW, Machine,
+    mhpmevent20,   0x334, RW, Machine,
+    mhpmevent21,   0x335, RW, Machine,
+    mhpmevent22,   0x336, RW, Machine,
+    mhpmevent23,   0x337, RW, Machine,
+    mhpmevent24,   0x338, RW, Machine,
+    mhpmevent25,   0x339, RW, Machine,
+    mhpmevent26,   0x33A, RW, Machine,
+    mhpmevent27,   0x33B, RW, Machine,
+    mhpmevent28,   0x33C, RW, Machine,
+    mhpmevent29,   0x33D, RW, Machine,
+    mhpmevent30,   0x33E, RW, Machine,
+    mhpmevent31,   0x33F, RW, Machine,
+    // none of the Smepmp bits are implemented
+    mseccfg,   0x747, RW, Machine,
+
+    // the counters are backed by the cpu, their values here are unused
+    mcycle,    0xB00, RW, Machine,
+    minstret,  0xB02, RW, Machine,
+    mhpmcounter3,  0xB03, RW, Machine,
+    mhpmcounter4,  0xB04, RW, Machine,
+    mhpmcounter5,  0xB05, RW, Machine,
+    mhpmcounter6,  0xB06, RW, Machine,
+    mhpmcounter7,  0xB07, RW, Machine,
+    mhpmcounter8,  0xB08, RW, Machine,
+    mhpmcounter9,  0xB09, RW, Machine,
+    mhpmcounter10, 0xB0A, RW, Machine,
+    mhpmcounter11, 0xB0B, RW, Machine,
+    mhpmcounter12, 0xB0C, RW, Machine,
+    mhpmcounter13, 0xB0D, RW, Machine,
+    mhpmcounter14, 0xB0E, RW, Machine,
+    mhpmcounter15, 0xB0F, RW, Machine,
+    mhpmcounter16, 0xB10, RW, Machine,
+    mhpmcounter17, 0xB11, RW, Machine,
+    mhpmcounter18, 0xB12, RW, Machine,
+    mhpmcounter19, 0xB13, RW, Machine,
+    mhpmcounter20, 0xB14, RW, Machine,
+    mhpmcounter21, 0xB15, RW, Machine,
+    mhpmcounter22, 0xB16, RW, Machine,
+    mhpmcounter23, 0xB17, RW, Machine,
+    mhpmcounter24, 0xB18, RW, Machine,
+    mhpmcounter25, 0xB19, RW, Machine,
+    mhpmcounter26, 0xB1A, RW, Machine,
+    mhpmcounter27, 0xB1B, RW, Machine,
+    mhpmcounter28, 0xB1C, RW, Machine,
+    mhpmcounter29, 0xB1D, RW, Machine,
+    mhpmcounter30, 0xB1E, RW, Machine,
+    mhpmcounter31, 0xB1F, RW, Machine,
+    cycle,     0xC00, RO, User,
+    time,      0xC01, RO, User,
+    instret,   0xC02, RO, User,
+    hpmcounter3,   0xC03, RO, User,
+    hpmcounter4,   0xC04, RO, User,
+    hpmcounter5,   0xC05, RO, User,
+    hpmcounter6,   0xC06, RO, User,
+    hpmcounter7,   0xC07, RO, User,
+    hpmcounter8,   0xC08, RO, User,
+    hpmcounter9,   0xC09, RO, User,
+    hpmcounter10,  0xC0A, RO, User,
+    hpmcounter11,  0xC0B, RO, User,
+    hpmcounter12,  0xC0C, RO, User,
+    hpmcounter13,  0xC0D, RO, User,
+    hpmcounter14,  0xC0E, RO, User,
+    hpmcounter15,  0xC0F, RO, User,
+    hpmcounter16,  0xC10, RO, User,
+    hpmcounter17,  0xC11, RO, User,
+    hpmcounter18,  0xC12, RO, User,
+    hpmcounter19,  0xC13, RO, User,
+    hpmcounter20,  0xC14, RO, User,
+    hpmcounter21,  0xC15, RO, User,
+    hpmcounter22,  0xC16, RO, User,
+    hpmcounter23,  0xC17, RO, User,
+    hpmcounter24,  0xC18, RO, User,
+    hpmcounter25,  0xC19, RO, User,
+    hpmcounter26,  0xC1A, RO, User,
+    hpmcounter27,  0xC1B, RO, User,
+    hpmcounter28,  0xC1C, RO, User,
+    hpmcounter29,  0xC1D, RO, User,
+    hpmcounter30,  0xC1E, RO, User,
+    hpmcounter31,  0xC1F, RO, User,
 
+    // fflags and frm are views of fcsr, their values here are unused
+    fflags,    0x001, RW, User,
+    frm,       0x002, RW, User,
     fcsr,      0x003, RW, User,
+
+    vstart,    0x008, RW, User,
+    vxsat,     0x009, RW, User,
+    vxrm,      0x00A, RW, User,
+    vcsr,      0x00F, RW, User,
+    vl,        0xC20, RO, User,
+    // starts out with vill set until the first vsetvl
+    vtype,     0xC21, RO, User, 0x8000_0000_0000_0000,
+    // filled in by the cpu from the configured VLEN
+    vlenb,     0xC22, RO, User,
 );
+
+/// bits in mip and mie
+pub mod interrupt {
+	pub const SUPERVISOR_SOFTWARE: u64 = 1 << 1;
+	pub const MACHINE_SOFTWARE: u64 = 1 << 3;
+	pub const SUPERVISOR_TIMER: u64 = 1 << 5;
+	pub const MACHINE_TIMER: u64 = 1 << 7;
+	pub const SUPERVISOR_EXTERNAL: u64 = 1 << 9;
+	pub const MACHINE_EXTERNAL: u64 = 1 << 11;
+
+	pub const MACHINE: u64 = MACHINE_SOFTWARE | MACHINE_TIMER | MACHINE_EXTERNAL;
+	pub const SUPERVISOR: u64 = SUPERVISOR_SOFTWARE | SUPERVISOR_TIMER | SUPERVISOR_EXTERNAL;
+}
+
+/// the state of the floating point unit as tracked by mstatus.FS
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FloatStatus {
+	Off = 0b00,
+	Initial = 0b01,
+	Clean = 0b10,
+	Dirty = 0b11,
+}
+
+/// mstatus with accessors for its fields, the cpu makes sure only legal values get written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mstatus(pub u64);
+
+macro_rules! status_flags {
+	($($name:ident, $mask:ident;)*) => {
+		paste::paste! {
+			$(
+				pub fn $name(self) -> bool {
+					self.0 & Self::$mask != 0
+				}
+
+				pub fn [< set_ $name >](&mut self, val: bool) {
+					if val {
+						self.0 |= Self::$mask;
+					} else {
+						self.0 &= !Self::$mask;
+					}
+				}
+			)*
+		}
+	};
+}
+
+#[allow(unused)]
+impl Mstatus {
+	pub const SIE: u64 = 1 << 1;
+	pub const MIE: u64 = 1 << 3;
+	pub const SPIE: u64 = 1 << 5;
+	pub const MPIE: u64 = 1 << 7;
+	pub const SPP: u64 = 1 << 8;
+	const MPP_SHIFT: u64 = 11;
+	pub const MPP: u64 = 0b11 << Self::MPP_SHIFT;
+	const FS_SHIFT: u64 = 13;
+	pub const FS: u64 = 0b11 << Self::FS_SHIFT;
+	pub const MPRV: u64 = 1 << 17;
+	pub const SUM: u64 = 1 << 18;
+	pub const MXR: u64 = 1 << 19;
+	pub const TVM: u64 = 1 << 20;
+	pub const TW: u64 = 1 << 21;
+	// traps SRET in S-mode
+	pub const TSR: u64 = 1 << 22;
+	// UXL and SXL are fixed at 64 bits when U and S are supported
+	pub const UXL_64: u64 = 2 << 32;
+	pub const SXL_64: u64 = 2 << 34;
+	// set when any of the extension state fields are dirty
+	pub const SD: u64 = 1 << 63;
+
+	/// the fields of mstatus that can be seen through sstatus
+	pub const SSTATUS_VIEW: u64 =
+		Self::SIE | Self::SPIE | Self::SPP | Self::FS | Self::SUM | Self::MXR | Self::UXL_64 | Self::SD;
+
+	status_flags! {
+		sie, SIE;
+		mie, MIE;
+		spie, SPIE;
+		mpie, MPIE;
+		mprv, MPRV;
+		sum, SUM;
+		mxr, MXR;
+		tvm, TVM;
+		tw, TW;
+		tsr, TSR;
+	}
+
+	/// the mode SRET returns to
+	pub fn spp(self) -> PrivilegeMode {
+		if self.0 & Self::SPP != 0 {
+			PrivilegeMode::Supervisor
+		} else {
+			PrivilegeMode::User
+		}
+	}
+
+	/// SPP can only hold U or S
+	pub fn set_spp(&mut self, mode: PrivilegeMode) {
+		debug_assert_ne!(mode, PrivilegeMode::Machine);
+		self.0 &= !Self::SPP;
+		if mode == PrivilegeMode::Supervisor {
+			self.0 |= Self::SPP;
+		}
+	}
+
+	/// the mode MRET returns to, None if MPP holds the reserved encoding
+	pub fn mpp(self) -> Option<PrivilegeMode> {
+		PrivilegeMode::from_bits((self.0 & Self::MPP) >> Self::MPP_SHIFT)
+	}
+
+	pub fn set_mpp(&mut self, mode: PrivilegeMode) {
+		self.0 = (self.0 & !Self::MPP) | ((mode as u64) << Self::MPP_SHIFT);
+	}
+
+	pub fn fs(self) -> FloatStatus {
+		match (self.0 & Self::FS) >> Self::FS_SHIFT {
+			0b00 => FloatStatus::Off,
+			0b01 => FloatStatus::Initial,
+			0b10 => FloatStatus::Clean,
+			_ => FloatStatus::Dirty,
+		}
+	}
+
+	/// also keeps SD in sync
+	pub fn set_fs(&mut self, fs: FloatStatus) {
+		self.0 = (self.0 & !Self::FS) | ((fs as u64) << Self::FS_SHIFT);
+		self.0 &= !Self::SD;
+		if fs == FloatStatus::Dirty {
+			self.0 |= Self::SD;
+		}
+	}
+}
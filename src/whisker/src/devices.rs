@@ -0,0 +1,28 @@
+pub mod clint;
+pub mod framebuffer;
+pub mod plic;
+pub mod serial;
+pub mod test_finisher;
+pub mod uart;
+
+// MMIO accesses can be narrower than a device's registers, these pick out or replace the bytes an access covers
+
+fn size_mask(size: usize) -> u64 {
+	if size >= 8 {
+		u64::MAX
+	} else {
+		(1 << (size * 8)) - 1
+	}
+}
+
+/// the size bytes of reg starting at byte
+pub fn extract_bytes(reg: u64, byte: u64, size: usize) -> u64 {
+	reg.checked_shr(byte as u32 * 8).unwrap_or(0) & size_mask(size)
+}
+
+/// reg with the size bytes starting at byte replaced by val
+pub fn insert_bytes(reg: u64, byte: u64, size: usize, val: u64) -> u64 {
+	let shift = byte as u32 * 8;
+	let mask = size_mask(size).checked_shl(shift).unwrap_or(0);
+	(reg & !mask) | (val.checked_shl(shift).unwrap_or(0) & mask)
+}
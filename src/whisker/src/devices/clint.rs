@@ -0,0 +1,118 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use crate::cpu::{InterruptLines, Timer};
+use crate::csr::interrupt;
+use crate::devices::{extract_bytes, insert_bytes};
+use crate::mem::MmioRegion;
+use crate::snapshot::{Snapshot, SnapshotReader, SnapshotWriter};
+
+/// where the CLINT sits on the bus on the virt machine and most SiFive parts
+pub const CLINT_BASE: u64 = 0x0200_0000;
+pub const CLINT_SIZE: u64 = 0x1_0000;
+
+// register offsets for hart 0
+const MSIP: u64 = 0x0000;
+const MTIMECMP: u64 = 0x4000;
+const MTIME: u64 = 0xBFF8;
+
+/// the core local interruptor, holds the machine timer and software interrupt registers for the hart
+#[derive(Debug, Clone)]
+pub struct Clint(Arc<ClintState>);
+
+#[derive(Debug)]
+struct ClintState {
+	timer: Timer,
+	lines: InterruptLines,
+	msip: AtomicU64,
+	mtimecmp: AtomicU64,
+}
+
+impl Clint {
+	pub fn new(timer: Timer, lines: InterruptLines) -> Self {
+		Self(Arc::new(ClintState {
+			timer,
+			lines,
+			msip: AtomicU64::new(0),
+			mtimecmp: AtomicU64::new(u64::MAX),
+		}))
+	}
+
+	/// mtimecmp resets to its maximum so the timer interrupt doesn't fire before software sets it up
+	pub fn reset(&self) {
+		self.set_software_interrupt(false);
+		self.0.mtimecmp.store(u64::MAX, Ordering::Release);
+		self.update_timer_interrupt();
+	}
+
+	/// drives MSIP, this is the msip register so software sees the change when it reads it back
+	pub fn set_software_interrupt(&self, pending: bool) {
+		self.0.msip.store(u64::from(pending), Ordering::Release);
+		self.0.lines.set(interrupt::MACHINE_SOFTWARE, pending);
+	}
+
+	/// raises MTIP while mtime >= mtimecmp, the execution loop calls this every cycle since time moves on by itself
+	pub fn update_timer_interrupt(&self) {
+		let pending = self.0.timer.read() >= self.0.mtimecmp.load(Ordering::Acquire);
+		self.0.lines.set(interrupt::MACHINE_TIMER, pending);
+	}
+
+	/// the MMIO region covering the CLINT's registers
+	pub fn mmio_region(&self, base: u64) -> MmioRegion {
+		let read = self.clone();
+		let write = self.clone();
+		MmioRegion::new(
+			base,
+			CLINT_SIZE,
+			move |offset, size| read.read(offset, size),
+			move |offset, size, val| write.write(offset, size, val),
+		)
+	}
+
+	// RV64 software accesses mtimecmp and mtime in one go, RV32 software uses two 32 bit halves
+	fn read(&self, offset: u64, size: usize) -> u64 {
+		let (reg, byte) = match offset {
+			MSIP..=0x0003 => (self.0.msip.load(Ordering::Acquire), offset - MSIP),
+			MTIMECMP..=0x4007 => (self.0.mtimecmp.load(Ordering::Acquire), offset - MTIMECMP),
+			MTIME..=0xBFFF => (self.0.timer.read(), offset - MTIME),
+			_ => return 0,
+		};
+		extract_bytes(reg, byte, size)
+	}
+
+	fn write(&self, offset: u64, size: usize, val: u64) {
+		match offset {
+			// only bit 0 of msip is writable
+			MSIP => self.set_software_interrupt(val & 1 != 0),
+			MTIMECMP..=0x4007 => {
+				let reg = self.0.mtimecmp.load(Ordering::Acquire);
+				self.0
+					.mtimecmp
+					.store(insert_bytes(reg, offset - MTIMECMP, size, val), Ordering::Release);
+				// software clears the interrupt by moving mtimecmp forward, it has to drop right away
+				self.update_timer_interrupt();
+			}
+			MTIME..=0xBFFF => {
+				let reg = self.0.timer.read();
+				self.0.timer.write(insert_bytes(reg, offset - MTIME, size, val));
+				self.update_timer_interrupt();
+			}
+			_ => {}
+		}
+	}
+}
+
+impl Snapshot for Clint {
+	fn save(&self, snapshot: &mut SnapshotWriter) {
+		snapshot.bool(self.0.msip.load(Ordering::Acquire) != 0);
+		snapshot.u64(self.0.mtimecmp.load(Ordering::Acquire));
+	}
+
+	/// mtime lives in the timer, which the cpu restores
+	fn restore(&mut self, snapshot: &mut SnapshotReader) -> Result<(), String> {
+		self.set_software_interrupt(snapshot.bool()?);
+		self.0.mtimecmp.store(snapshot.u64()?, Ordering::Release);
+		self.update_timer_interrupt();
+		Ok(())
+	}
+}
@@ -0,0 +1,153 @@
+use std::sync::{Arc, Mutex};
+
+use crate::devices::{extract_bytes, insert_bytes};
+use crate::mem::MmioRegion;
+use crate::snapshot::{expect_eq, Snapshot, SnapshotReader, SnapshotWriter};
+
+/// where the framebuffer sits on the bus, nothing else on the virt machine uses this range
+pub const FRAMEBUFFER_BASE: u64 = 0x5000_0000;
+pub const DEFAULT_REFRESH_RATE: u32 = 60;
+
+const BYTES_PER_PIXEL: u64 = 4;
+
+/// a linear framebuffer, each pixel is a little endian 32 bit 0x00RRGGBB word and rows are packed with no padding
+#[derive(Clone)]
+pub struct Framebuffer(Arc<FramebufferInner>);
+
+struct FramebufferInner {
+	width: usize,
+	height: usize,
+	pixels: Mutex<Vec<u32>>,
+}
+
+impl std::fmt::Debug for Framebuffer {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("Framebuffer")
+			.field("width", &self.0.width)
+			.field("height", &self.0.height)
+			.finish_non_exhaustive()
+	}
+}
+
+impl Framebuffer {
+	pub fn new(width: usize, height: usize) -> Self {
+		Self(Arc::new(FramebufferInner {
+			width,
+			height,
+			pixels: Mutex::new(vec![0; width * height]),
+		}))
+	}
+
+	pub fn width(&self) -> usize {
+		self.0.width
+	}
+
+	pub fn height(&self) -> usize {
+		self.0.height
+	}
+
+	/// bytes per row
+	pub fn stride(&self) -> u64 {
+		self.0.width as u64 * BYTES_PER_PIXEL
+	}
+
+	/// the size of the framebuffer in bytes
+	pub fn size(&self) -> u64 {
+		self.stride() * self.0.height as u64
+	}
+
+	/// the screen goes black on reset
+	pub fn reset(&self) {
+		self.0.pixels.lock().expect("framebuffer lock poisoned").fill(0);
+	}
+
+	/// the MMIO region covering the pixels
+	pub fn mmio_region(&self, base: u64) -> MmioRegion {
+		let read = self.clone();
+		let write = self.clone();
+		MmioRegion::new(
+			base,
+			self.size(),
+			move |offset, size| read.read(offset, size),
+			move |offset, size, val| write.write(offset, size, val),
+		)
+	}
+
+	// accesses can cover part of a pixel or span two, so they're done a byte at a time under one lock
+	fn read(&self, offset: u64, size: usize) -> u64 {
+		let pixels = self.0.pixels.lock().expect("framebuffer lock poisoned");
+		(0..size as u64).fold(0, |acc, byte| {
+			let offset = offset + byte;
+			let pixel = pixels.get((offset / BYTES_PER_PIXEL) as usize).copied().unwrap_or(0);
+			acc | (extract_bytes(u64::from(pixel), offset % BYTES_PER_PIXEL, 1) << (byte * 8))
+		})
+	}
+
+	fn write(&self, offset: u64, size: usize, val: u64) {
+		let mut pixels = self.0.pixels.lock().expect("framebuffer lock poisoned");
+		for byte in 0..size as u64 {
+			let offset = offset + byte;
+			let Some(pixel) = pixels.get_mut((offset / BYTES_PER_PIXEL) as usize) else {
+				continue;
+			};
+			let val = extract_bytes(val, byte, 1);
+			*pixel = insert_bytes(u64::from(*pixel), offset % BYTES_PER_PIXEL, 1, val) as u32;
+		}
+	}
+
+	/// shows the framebuffer in a host window from a background thread, redrawing refresh_rate times a second
+	/// closing the window only stops the redraws, the guest keeps running
+	#[cfg(feature = "window")]
+	pub fn spawn_window(&self, refresh_rate: u32) {
+		use std::time::Duration;
+
+		use minifb::{Window, WindowOptions};
+
+		let fb = self.clone();
+		std::thread::spawn(move || {
+			let mut window = match Window::new("whisker", fb.0.width, fb.0.height, WindowOptions::default()) {
+				Ok(window) => window,
+				Err(err) => {
+					tracing::error!("could not open the framebuffer window: {err}");
+					return;
+				}
+			};
+			let frame = Duration::from_secs(1) / refresh_rate.max(1);
+			let mut buffer = vec![0; fb.0.width * fb.0.height];
+			while window.is_open() {
+				buffer.copy_from_slice(&fb.0.pixels.lock().expect("framebuffer lock poisoned"));
+				if let Err(err) = window.update_with_buffer(&buffer, fb.0.width, fb.0.height) {
+					tracing::error!("could not update the framebuffer window: {err}");
+					return;
+				}
+				std::thread::sleep(frame);
+			}
+		});
+	}
+
+	#[cfg(not(feature = "window"))]
+	pub fn spawn_window(&self, _refresh_rate: u32) {
+		tracing::warn!("whisker was built without the window feature, the framebuffer won't be shown");
+	}
+}
+
+impl Snapshot for Framebuffer {
+	fn save(&self, snapshot: &mut SnapshotWriter) {
+		let pixels = self.0.pixels.lock().expect("framebuffer lock poisoned");
+		snapshot.u64(self.0.width as u64);
+		snapshot.u64(self.0.height as u64);
+		for &pixel in pixels.iter() {
+			snapshot.u32(pixel);
+		}
+	}
+
+	fn restore(&mut self, snapshot: &mut SnapshotReader) -> Result<(), String> {
+		let resolution = (snapshot.u64()? as usize, snapshot.u64()? as usize);
+		expect_eq("framebuffer resolution", (self.0.width, self.0.height), resolution)?;
+		let mut pixels = self.0.pixels.lock().expect("framebuffer lock poisoned");
+		for pixel in pixels.iter_mut() {
+			*pixel = snapshot.u32()?;
+		}
+		Ok(())
+	}
+}
@@ -0,0 +1,258 @@
+use std::sync::{Arc, Mutex};
+
+use crate::cpu::InterruptLines;
+use crate::csr::interrupt;
+use crate::devices::extract_bytes;
+use crate::mem::MmioRegion;
+use crate::snapshot::{Snapshot, SnapshotReader, SnapshotWriter};
+
+/// where the PLIC sits on the bus on the virt machine
+pub const PLIC_BASE: u64 = 0x0C00_0000;
+
+/// source 0 doesn't exist, so this allows sources 1 to 63
+pub const PLIC_SOURCES: u32 = 64;
+/// the registers end after the last context
+pub const PLIC_SIZE: u64 = CONTEXT + CONTEXTS.len() as u64 * CONTEXT_STRIDE;
+
+// register offsets, everything is per context past the enables
+const PRIORITY: u64 = 0x00_0000;
+const PENDING: u64 = 0x00_1000;
+const ENABLE: u64 = 0x00_2000;
+const ENABLE_STRIDE: u64 = 0x80;
+const CONTEXT: u64 = 0x20_0000;
+const CONTEXT_STRIDE: u64 = 0x1000;
+const THRESHOLD: u64 = 0x0;
+const CLAIM: u64 = 0x4;
+
+// priorities and thresholds are 3 bits
+const PRIORITY_MASK: u8 = 0b111;
+
+/// the hart's two contexts, M-mode drives MEIP and S-mode drives SEIP
+const CONTEXTS: [u64; 2] = [interrupt::MACHINE_EXTERNAL, interrupt::SUPERVISOR_EXTERNAL];
+
+/// the platform level interrupt controller, routes device interrupts to the hart's external interrupt lines
+#[derive(Debug, Clone)]
+pub struct Plic(Arc<PlicInner>);
+
+#[derive(Debug)]
+struct PlicInner {
+	lines: InterruptLines,
+	state: Mutex<PlicState>,
+}
+
+#[derive(Debug)]
+struct PlicState {
+	priority: [u8; PLIC_SOURCES as usize],
+	// one bit per source in all of these
+	levels: u64,
+	pending: u64,
+	// claimed and not completed yet, these can't become pending again until they are
+	claimed: u64,
+	enable: [u64; CONTEXTS.len()],
+	threshold: [u8; CONTEXTS.len()],
+}
+
+impl Default for PlicState {
+	fn default() -> Self {
+		Self {
+			priority: [0; PLIC_SOURCES as usize],
+			levels: 0,
+			pending: 0,
+			claimed: 0,
+			enable: [0; CONTEXTS.len()],
+			threshold: [0; CONTEXTS.len()],
+		}
+	}
+}
+
+impl PlicState {
+	/// the highest priority source that's pending, enabled and above the context's threshold, the lowest id wins ties
+	fn best(&self, context: usize) -> Option<u32> {
+		let candidates = self.pending & self.enable[context];
+		(1..PLIC_SOURCES)
+			.filter(|&source| candidates & (1 << source) != 0)
+			.filter(|&source| self.priority[source as usize] > self.threshold[context])
+			.min_by_key(|&source| std::cmp::Reverse(self.priority[source as usize]))
+	}
+}
+
+impl Plic {
+	pub fn new(lines: InterruptLines) -> Self {
+		Self(Arc::new(PlicInner {
+			lines,
+			state: Mutex::new(PlicState::default()),
+		}))
+	}
+
+	/// everything is disabled and nothing is pending on reset, the levels devices are driving are kept
+	pub fn reset(&self) {
+		let mut state = self.0.state.lock().expect("plic lock poisoned");
+		let levels = state.levels;
+		*state = PlicState {
+			levels,
+			pending: levels,
+			..Default::default()
+		};
+		self.update(&state);
+	}
+
+	/// drives a source's level, the interrupts are level triggered so a raised source stays pending until it's lowered
+	pub fn set_level(&self, source: u32, raised: bool) {
+		assert!(source != 0 && source < PLIC_SOURCES, "invalid PLIC source {source}");
+		let mut state = self.0.state.lock().expect("plic lock poisoned");
+		let bit = 1 << source;
+		if raised {
+			state.levels |= bit;
+			if state.claimed & bit == 0 {
+				state.pending |= bit;
+			}
+		} else {
+			state.levels &= !bit;
+			state.pending &= !bit;
+		}
+		self.update(&state);
+	}
+
+	fn update(&self, state: &PlicState) {
+		for (context, line) in CONTEXTS.into_iter().enumerate() {
+			self.0.lines.set(line, state.best(context).is_some());
+		}
+	}
+
+	/// the MMIO region covering every register, the gaps between them read as zero
+	pub fn mmio_region(&self, base: u64) -> MmioRegion {
+		let read = self.clone();
+		let write = self.clone();
+		MmioRegion::new(
+			base,
+			PLIC_SIZE,
+			move |offset, size| read.read(offset, size),
+			move |offset, size, val| write.write(offset, size, val),
+		)
+	}
+
+	/// the context a claim/complete or threshold register belongs to, and its offset in the context
+	fn context_reg(offset: u64) -> Option<(usize, u64)> {
+		let context = offset.checked_sub(CONTEXT)? / CONTEXT_STRIDE;
+		(context < CONTEXTS.len() as u64).then_some((context as usize, offset % CONTEXT_STRIDE))
+	}
+
+	/// which context and word of the enable bits an offset is in
+	fn enable_word(offset: u64) -> Option<(usize, u64)> {
+		let context = offset.checked_sub(ENABLE)? / ENABLE_STRIDE;
+		let word = (offset % ENABLE_STRIDE) / 4;
+		(context < CONTEXTS.len() as u64 && word < 2).then_some((context as usize, word))
+	}
+
+	// every register is 32 bits, 64 bit accesses are split in two and narrower reads see part of the register
+	fn read(&self, offset: u64, size: usize) -> u64 {
+		if size == 8 {
+			return u64::from(self.read_word(offset)) | (u64::from(self.read_word(offset + 4)) << 32);
+		}
+		extract_bytes(u64::from(self.read_word(offset & !3)), offset % 4, size)
+	}
+
+	// narrower writes are ignored like on QEMU
+	fn write(&self, offset: u64, size: usize, val: u64) {
+		match size {
+			8 => {
+				self.write_word(offset, val as u32);
+				self.write_word(offset + 4, (val >> 32) as u32);
+			}
+			4 => self.write_word(offset, val as u32),
+			_ => {}
+		}
+	}
+
+	fn read_word(&self, offset: u64) -> u32 {
+		let mut state = self.0.state.lock().expect("plic lock poisoned");
+		match offset {
+			PRIORITY..PENDING => {
+				let source = (offset - PRIORITY) / 4;
+				state.priority.get(source as usize).copied().map_or(0, u32::from)
+			}
+			PENDING..=0x1007 => (state.pending >> ((offset - PENDING) * 8)) as u32,
+			_ => {
+				if let Some((context, word)) = Self::enable_word(offset) {
+					return (state.enable[context] >> (word * 32)) as u32;
+				}
+				match Self::context_reg(offset) {
+					Some((context, THRESHOLD)) => u32::from(state.threshold[context]),
+					Some((context, CLAIM)) => {
+						let Some(source) = state.best(context) else {
+							return 0;
+						};
+						state.pending &= !(1 << source);
+						state.claimed |= 1 << source;
+						self.update(&state);
+						source
+					}
+					_ => 0,
+				}
+			}
+		}
+	}
+
+	fn write_word(&self, offset: u64, val: u32) {
+		let mut state = self.0.state.lock().expect("plic lock poisoned");
+		match offset {
+			// source 0 doesn't exist so its priority is hardwired to zero
+			PRIORITY..PENDING => {
+				let source = (offset - PRIORITY) / 4;
+				if source != 0 {
+					if let Some(priority) = state.priority.get_mut(source as usize) {
+						*priority = val as u8 & PRIORITY_MASK;
+					}
+				}
+			}
+			// pending bits are read-only
+			PENDING..ENABLE => {}
+			_ => {
+				if let Some((context, word)) = Self::enable_word(offset) {
+					let shift = word * 32;
+					let enable = (state.enable[context] & !(0xFFFF_FFFF << shift)) | (u64::from(val) << shift);
+					// source 0 can't be enabled
+					state.enable[context] = enable & !1;
+				} else {
+					match Self::context_reg(offset) {
+						Some((context, THRESHOLD)) => state.threshold[context] = val as u8 & PRIORITY_MASK,
+						Some((_, CLAIM)) => {
+							// completing a source lets it become pending again if it's still raised
+							let bit = 1_u64.checked_shl(val).unwrap_or(0);
+							if state.claimed & bit != 0 {
+								state.claimed &= !bit;
+								state.pending |= state.levels & bit;
+							}
+						}
+						_ => return,
+					}
+				}
+			}
+		}
+		self.update(&state);
+	}
+}
+
+impl Snapshot for Plic {
+	fn save(&self, snapshot: &mut SnapshotWriter) {
+		let state = self.0.state.lock().expect("plic lock poisoned");
+		snapshot.bytes(&state.priority);
+		snapshot.u64(state.levels);
+		snapshot.u64(state.pending);
+		snapshot.u64(state.claimed);
+		snapshot.u64s(&state.enable);
+		snapshot.bytes(&state.threshold);
+	}
+
+	fn restore(&mut self, snapshot: &mut SnapshotReader) -> Result<(), String> {
+		let mut state = self.0.state.lock().expect("plic lock poisoned");
+		snapshot.bytes_into("PLIC source count", &mut state.priority)?;
+		state.levels = snapshot.u64()?;
+		state.pending = snapshot.u64()?;
+		state.claimed = snapshot.u64()?;
+		snapshot.u64s(&mut state.enable)?;
+		snapshot.bytes_into("PLIC context count", &mut state.threshold)?;
+		self.update(&state);
+		Ok(())
+	}
+}
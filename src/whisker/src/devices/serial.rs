@@ -0,0 +1,249 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use crate::devices::uart::Uart;
+
+/// where the UART's console is attached on the host, like QEMU's -serial
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum SerialBackend {
+	/// output goes to stdout and input comes from stdin
+	#[default]
+	Stdio,
+	/// output is appended to a file and there's no input
+	File(PathBuf),
+	/// a pseudo-terminal whose path is printed on startup, connect to it with screen, minicom, etc.
+	Pty,
+	/// listens on an address and attaches one client at a time, output is dropped while nobody is connected
+	Tcp(String),
+}
+
+impl FromStr for SerialBackend {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s.split_once(':') {
+			None if s == "stdio" => Ok(Self::Stdio),
+			None if s == "pty" => Ok(Self::Pty),
+			Some(("file", path)) if !path.is_empty() => Ok(Self::File(path.into())),
+			Some(("tcp", addr)) if !addr.is_empty() => Ok(Self::Tcp(addr.to_string())),
+			_ => Err(format!(
+				"unknown serial backend {s:?}, expected stdio, file:<path>, pty or tcp:<addr>"
+			)),
+		}
+	}
+}
+
+impl SerialBackend {
+	/// connects the UART to the backend, input is read on background threads
+	/// with a log everything the guest transmits is copied to it too
+	/// stdin only has one reader, with host_stdin it's handed back for semihosting and htif instead of going to the UART
+	pub fn attach(&self, uart: &Uart, log: Option<ConsoleLog>, host_stdin: bool) -> io::Result<Option<Receiver<u8>>> {
+		let tee = |output: Box<dyn Write + Send>| -> Box<dyn Write + Send> {
+			match log {
+				Some(log) => Box::new(Tee { output, log }),
+				None => output,
+			}
+		};
+		match self {
+			Self::Stdio => {
+				uart.set_output(tee(Box::new(io::stdout())));
+				if !host_stdin {
+					spawn_reader(uart.clone(), io::stdin());
+				}
+			}
+			Self::File(path) => {
+				let file = OpenOptions::new().create(true).append(true).open(path)?;
+				uart.set_output(tee(Box::new(file)));
+			}
+			Self::Pty => {
+				let (master, path) = open_pty()?;
+				eprintln!("Serial console attached to {}", path.display());
+				uart.set_output(tee(Box::new(master.try_clone()?)));
+				spawn_reader(uart.clone(), master);
+			}
+			Self::Tcp(addr) => {
+				let listener = TcpListener::bind(addr)?;
+				eprintln!("Serial console listening on {}", listener.local_addr()?);
+				let client = Arc::new(Mutex::new(None));
+				uart.set_output(tee(Box::new(TcpOutput(client.clone()))));
+				let uart = uart.clone();
+				std::thread::spawn(move || {
+					for stream in listener.incoming() {
+						let Ok(stream) = stream else {
+							continue;
+						};
+						let Ok(output) = stream.try_clone() else {
+							continue;
+						};
+						*client.lock().expect("serial client lock poisoned") = Some(output);
+						receive_all(&uart, stream);
+						*client.lock().expect("serial client lock poisoned") = None;
+					}
+				});
+			}
+		}
+		Ok(host_stdin.then(read_stdin))
+	}
+}
+
+/// host calls poll the console, so stdin is read on a background thread
+fn read_stdin() -> Receiver<u8> {
+	let (sender, input) = mpsc::channel();
+	std::thread::spawn(move || {
+		let mut buf = [0; 256];
+		// stop on EOF or any error, the receiver sees the disconnect
+		while let Ok(len @ 1..) = io::stdin().read(&mut buf) {
+			if buf[..len].iter().any(|&byte| sender.send(byte).is_err()) {
+				return;
+			}
+		}
+	});
+	input
+}
+
+fn spawn_reader(uart: Uart, input: impl Read + Send + 'static) {
+	std::thread::spawn(move || receive_all(&uart, input));
+}
+
+/// feeds everything read into the UART's receive FIFO
+fn receive_all(uart: &Uart, mut input: impl Read) {
+	let mut buf = [0; 256];
+	// stop on EOF or any error, there's nothing more to receive either way
+	while let Ok(len @ 1..) = input.read(&mut buf) {
+		uart.receive(&buf[..len]);
+	}
+}
+
+/// a copy of the console in a file, each line can start with the seconds since it was opened like dmesg
+pub struct ConsoleLog {
+	file: File,
+	started: Option<Instant>,
+	at_line_start: bool,
+}
+
+impl ConsoleLog {
+	pub fn create(path: &Path, timestamps: bool) -> io::Result<Self> {
+		Ok(Self {
+			file: File::create(path)?,
+			started: timestamps.then(Instant::now),
+			at_line_start: true,
+		})
+	}
+
+	fn record(&mut self, buf: &[u8]) -> io::Result<()> {
+		let Some(started) = self.started else {
+			return self.file.write_all(buf);
+		};
+		for line in buf.split_inclusive(|&byte| byte == b'\n') {
+			if self.at_line_start {
+				let elapsed = started.elapsed();
+				write!(self.file, "[{:>5}.{:06}] ", elapsed.as_secs(), elapsed.subsec_micros())?;
+			}
+			self.file.write_all(line)?;
+			self.at_line_start = line.ends_with(b"\n");
+		}
+		Ok(())
+	}
+}
+
+/// writes to the backend and copies whatever it took to the log
+struct Tee {
+	output: Box<dyn Write + Send>,
+	log: ConsoleLog,
+}
+
+impl Write for Tee {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		let len = self.output.write(buf)?;
+		// the log is best effort, the guest's output still has to reach the backend
+		let _ = self.log.record(&buf[..len]);
+		Ok(len)
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		self.output.flush()
+	}
+}
+
+/// writes to the connected TCP client, if there is one
+struct TcpOutput(Arc<Mutex<Option<TcpStream>>>);
+
+impl Write for TcpOutput {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		let mut client = self.0.lock().expect("serial client lock poisoned");
+		if let Some(stream) = client.as_mut() {
+			// a failed write means the client went away, the reader thread notices too
+			if stream.write_all(buf).is_err() {
+				*client = None;
+			}
+		}
+		Ok(buf.len())
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		Ok(())
+	}
+}
+
+#[cfg(unix)]
+fn open_pty() -> io::Result<(File, PathBuf)> {
+	use std::ffi::{c_char, c_int, CStr, OsStr};
+	use std::os::fd::FromRawFd;
+	use std::os::unix::ffi::OsStrExt;
+
+	// std already links libc, the termios struct is only passed by pointer so it can stay opaque
+	extern "C" {
+		fn posix_openpt(flags: c_int) -> c_int;
+		fn grantpt(fd: c_int) -> c_int;
+		fn unlockpt(fd: c_int) -> c_int;
+		fn ptsname(fd: c_int) -> *const c_char;
+		fn tcgetattr(fd: c_int, termios: *mut u8) -> c_int;
+		fn tcsetattr(fd: c_int, optional_actions: c_int, termios: *const u8) -> c_int;
+		fn cfmakeraw(termios: *mut u8);
+	}
+	const O_RDWR: c_int = 2;
+	const TCSANOW: c_int = 0;
+
+	let check = |ret: c_int| {
+		if ret < 0 {
+			Err(io::Error::last_os_error())
+		} else {
+			Ok(ret)
+		}
+	};
+
+	// SAFETY: the fd is owned by the File as soon as it's open, ptsname's buffer is copied before anything else runs
+	unsafe {
+		let fd = check(posix_openpt(O_RDWR))?;
+		let master = File::from_raw_fd(fd);
+		check(grantpt(fd))?;
+		check(unlockpt(fd))?;
+
+		// the guest does its own line editing and echoing, so the terminal has to pass bytes through untouched
+		let mut termios = [0_u8; 256];
+		check(tcgetattr(fd, termios.as_mut_ptr()))?;
+		cfmakeraw(termios.as_mut_ptr());
+		check(tcsetattr(fd, TCSANOW, termios.as_ptr()))?;
+
+		let name = ptsname(fd);
+		if name.is_null() {
+			return Err(io::Error::last_os_error());
+		}
+		let path = PathBuf::from(OsStr::from_bytes(CStr::from_ptr(name).to_bytes()));
+		Ok((master, path))
+	}
+}
+
+#[cfg(not(unix))]
+fn open_pty() -> io::Result<(File, PathBuf)> {
+	Err(io::Error::new(
+		io::ErrorKind::Unsupported,
+		"pseudo-terminals are only supported on unix hosts",
+	))
+}
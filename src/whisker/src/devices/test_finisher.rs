@@ -0,0 +1,55 @@
+use std::sync::Arc;
+
+use crate::cpu::{PowerOffLine, ResetLine};
+use crate::mem::MmioRegion;
+
+/// where the finisher sits on the bus on the virt machine
+pub const TEST_FINISHER_BASE: u64 = 0x0010_0000;
+pub const TEST_FINISHER_SIZE: u64 = 0x1000;
+
+// the low half of the written word is the command, FAIL takes the exit code from the high half
+const COMMAND_MASK: u32 = 0xFFFF;
+const FAIL: u32 = 0x3333;
+pub const TEST_FINISHER_PASS: u32 = 0x5555;
+pub const TEST_FINISHER_RESET: u32 = 0x7777;
+
+/// sifive_test, the test finisher QEMU's virt machine has, which Linux drives through syscon-poweroff and syscon-reboot
+#[derive(Debug, Clone)]
+pub struct TestFinisher(Arc<TestFinisherState>);
+
+#[derive(Debug)]
+struct TestFinisherState {
+	reset: ResetLine,
+	power_off: PowerOffLine,
+}
+
+impl TestFinisher {
+	pub fn new(reset: ResetLine, power_off: PowerOffLine) -> Self {
+		Self(Arc::new(TestFinisherState { reset, power_off }))
+	}
+
+	/// the MMIO region covering the finisher's register
+	pub fn mmio_region(&self, base: u64) -> MmioRegion {
+		let write = self.clone();
+		MmioRegion::new(
+			base,
+			TEST_FINISHER_SIZE,
+			// the register is write-only
+			|_, _| 0,
+			move |offset, _, val| write.write(offset, val as u32),
+		)
+	}
+
+	/// narrower stores are zero-extended like on QEMU
+	fn write(&self, offset: u64, word: u32) {
+		if offset != 0 {
+			return;
+		}
+		match word & COMMAND_MASK {
+			FAIL => self.0.power_off.request((word >> 16) as i32),
+			TEST_FINISHER_PASS => self.0.power_off.request(0),
+			TEST_FINISHER_RESET => self.0.reset.request(),
+			_ => {}
+		}
+	}
+}
@@ -0,0 +1,240 @@
+use std::collections::VecDeque;
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+
+use crate::devices::plic::Plic;
+use crate::mem::MmioRegion;
+use crate::snapshot::{Snapshot, SnapshotReader, SnapshotWriter};
+
+/// where the UART sits on the bus on the virt machine
+pub const UART_BASE: u64 = 0x1000_0000;
+/// the PLIC source the UART's interrupt is wired to
+pub const UART_IRQ: u32 = 10;
+/// the registers only take 8 bytes, but the UART is given more room like on QEMU
+pub const UART_SIZE: u64 = 0x100;
+
+// register offsets, some of them mean something else when read, written or with LCR.DLAB set
+const RBR_THR_DLL: u64 = 0;
+const IER_DLM: u64 = 1;
+const IIR_FCR: u64 = 2;
+const LCR: u64 = 3;
+const MCR: u64 = 4;
+const LSR: u64 = 5;
+const MSR: u64 = 6;
+const SCR: u64 = 7;
+
+const IER_RX_AVAILABLE: u8 = 1 << 0;
+const IER_THR_EMPTY: u8 = 1 << 1;
+const IER_MASK: u8 = 0b1111;
+
+const IIR_NONE: u8 = 0b0001;
+const IIR_THR_EMPTY: u8 = 0b0010;
+const IIR_RX_AVAILABLE: u8 = 0b0100;
+const IIR_FIFOS_ENABLED: u8 = 0b1100_0000;
+
+const FCR_ENABLE_FIFOS: u8 = 1 << 0;
+const FCR_CLEAR_RX: u8 = 1 << 1;
+const FCR_CLEAR_TX: u8 = 1 << 2;
+
+const LCR_DLAB: u8 = 1 << 7;
+
+const MCR_MASK: u8 = 0b1_1111;
+
+const LSR_DATA_READY: u8 = 1 << 0;
+const LSR_THR_EMPTY: u8 = 1 << 5;
+const LSR_TRANSMITTER_EMPTY: u8 = 1 << 6;
+
+// the modem is always ready, CTS, DSR and DCD are set
+const MSR_CONNECTED: u8 = 0b1011_0000;
+
+/// a 16550A compatible UART, transmitted bytes go straight to the output and received bytes are queued with `receive`
+/// there's no baud rate, so transmitting is instant and the transmit holding register is always empty
+#[derive(Clone)]
+pub struct Uart(Arc<UartInner>);
+
+struct UartInner {
+	plic: Plic,
+	output: Mutex<Box<dyn Write + Send>>,
+	state: Mutex<UartState>,
+}
+
+#[derive(Debug, Default)]
+struct UartState {
+	rx: VecDeque<u8>,
+	ier: u8,
+	lcr: u8,
+	mcr: u8,
+	scr: u8,
+	fcr: u8,
+	divisor: u16,
+	// the THR empty interrupt is cleared by reading IIR or writing THR, so it needs its own flag
+	thr_empty_pending: bool,
+}
+
+impl UartState {
+	/// the highest priority interrupt the UART is raising
+	fn iir(&self) -> u8 {
+		if self.ier & IER_RX_AVAILABLE != 0 && !self.rx.is_empty() {
+			IIR_RX_AVAILABLE
+		} else if self.ier & IER_THR_EMPTY != 0 && self.thr_empty_pending {
+			IIR_THR_EMPTY
+		} else {
+			IIR_NONE
+		}
+	}
+}
+
+impl std::fmt::Debug for Uart {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("Uart").finish_non_exhaustive()
+	}
+}
+
+impl Uart {
+	/// transmitted bytes are dropped until an output is attached
+	pub fn new(plic: Plic) -> Self {
+		Self(Arc::new(UartInner {
+			plic,
+			output: Mutex::new(Box::new(io::sink())),
+			state: Mutex::new(UartState::default()),
+		}))
+	}
+
+	/// everything but received bytes that haven't been read yet is cleared on reset
+	pub fn reset(&self) {
+		let mut state = self.0.state.lock().expect("uart lock poisoned");
+		let rx = std::mem::take(&mut state.rx);
+		*state = UartState {
+			rx,
+			..Default::default()
+		};
+		self.update(&state);
+	}
+
+	/// queues bytes as if they came in over the wire
+	pub fn receive(&self, bytes: &[u8]) {
+		let mut state = self.0.state.lock().expect("uart lock poisoned");
+		state.rx.extend(bytes);
+		self.update(&state);
+	}
+
+	/// replaces where transmitted bytes go
+	pub fn set_output(&self, output: Box<dyn Write + Send>) {
+		*self.0.output.lock().expect("uart output lock poisoned") = output;
+	}
+
+	fn update(&self, state: &UartState) {
+		self.0.plic.set_level(UART_IRQ, state.iir() != IIR_NONE);
+	}
+
+	/// the MMIO region covering the UART's registers, every register is a byte wide
+	pub fn mmio_region(&self, base: u64) -> MmioRegion {
+		let read = self.clone();
+		let write = self.clone();
+		MmioRegion::byte_wide(
+			base,
+			UART_SIZE,
+			move |offset| read.read_byte(offset),
+			move |offset, val| write.write_byte(offset, val),
+		)
+	}
+
+	fn read_byte(&self, offset: u64) -> u8 {
+		let mut state = self.0.state.lock().expect("uart lock poisoned");
+		let dlab = state.lcr & LCR_DLAB != 0;
+		let val = match offset {
+			RBR_THR_DLL if dlab => state.divisor as u8,
+			RBR_THR_DLL => state.rx.pop_front().unwrap_or(0),
+			IER_DLM if dlab => (state.divisor >> 8) as u8,
+			IER_DLM => state.ier,
+			IIR_FCR => {
+				let iir = state.iir();
+				// reading IIR acknowledges the THR empty interrupt
+				if iir == IIR_THR_EMPTY {
+					state.thr_empty_pending = false;
+				}
+				let fifos = if state.fcr & FCR_ENABLE_FIFOS != 0 {
+					IIR_FIFOS_ENABLED
+				} else {
+					0
+				};
+				iir | fifos
+			}
+			LCR => state.lcr,
+			MCR => state.mcr,
+			LSR => {
+				let ready = if state.rx.is_empty() { 0 } else { LSR_DATA_READY };
+				ready | LSR_THR_EMPTY | LSR_TRANSMITTER_EMPTY
+			}
+			MSR => MSR_CONNECTED,
+			SCR => state.scr,
+			_ => 0,
+		};
+		self.update(&state);
+		val
+	}
+
+	fn write_byte(&self, offset: u64, val: u8) {
+		let mut state = self.0.state.lock().expect("uart lock poisoned");
+		let dlab = state.lcr & LCR_DLAB != 0;
+		match offset {
+			RBR_THR_DLL if dlab => state.divisor = (state.divisor & 0xFF00) | u16::from(val),
+			RBR_THR_DLL => {
+				let mut output = self.0.output.lock().expect("uart output lock poisoned");
+				// the guest has no way to find out about host I/O errors, so they're dropped like a disconnected line
+				let _ = output.write_all(&[val]).and_then(|_| output.flush());
+				// the byte went out immediately, so THR is empty again
+				state.thr_empty_pending = true;
+			}
+			IER_DLM if dlab => state.divisor = (state.divisor & 0x00FF) | (u16::from(val) << 8),
+			IER_DLM => {
+				// enabling the THR empty interrupt raises it right away since THR is always empty
+				if val & IER_THR_EMPTY != 0 && state.ier & IER_THR_EMPTY == 0 {
+					state.thr_empty_pending = true;
+				}
+				state.ier = val & IER_MASK;
+			}
+			IIR_FCR => {
+				if val & FCR_CLEAR_RX != 0 {
+					state.rx.clear();
+				}
+				// the clear bits are self-clearing, and there's nothing to clear in the transmit FIFO
+				state.fcr = val & !(FCR_CLEAR_RX | FCR_CLEAR_TX);
+			}
+			LCR => state.lcr = val,
+			MCR => state.mcr = val & MCR_MASK,
+			SCR => state.scr = val,
+			_ => {}
+		}
+		self.update(&state);
+	}
+}
+
+impl Snapshot for Uart {
+	/// received bytes that haven't been read yet are part of the state, the output isn't
+	fn save(&self, snapshot: &mut SnapshotWriter) {
+		let state = self.0.state.lock().expect("uart lock poisoned");
+		snapshot.bytes(&state.rx.iter().copied().collect::<Vec<_>>());
+		snapshot.u8(state.ier);
+		snapshot.u8(state.lcr);
+		snapshot.u8(state.mcr);
+		snapshot.u8(state.scr);
+		snapshot.u8(state.fcr);
+		snapshot.u16(state.divisor);
+		snapshot.bool(state.thr_empty_pending);
+	}
+
+	fn restore(&mut self, snapshot: &mut SnapshotReader) -> Result<(), String> {
+		let mut state = self.0.state.lock().expect("uart lock poisoned");
+		state.rx = snapshot.bytes()?.iter().copied().collect();
+		state.ier = snapshot.u8()?;
+		state.lcr = snapshot.u8()?;
+		state.mcr = snapshot.u8()?;
+		state.scr = snapshot.u8()?;
+		state.fcr = snapshot.u8()?;
+		state.divisor = snapshot.u16()?;
+		state.thr_empty_pending = snapshot.bool()?;
+		self.update(&state);
+		Ok(())
+	}
+}
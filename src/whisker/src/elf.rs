@@ -0,0 +1,206 @@
+const ELF_MAGIC: &[u8; 4] = b"\x7fELF";
+const ELFCLASS64: u8 = 2;
+const ELFDATA2LSB: u8 = 1;
+const EM_RISCV: u16 = 243;
+
+const PT_LOAD: u32 = 1;
+const SHT_SYMTAB: u32 = 2;
+const STT_SECTION: u8 = 3;
+const STT_FILE: u8 = 4;
+const SHN_UNDEF: u16 = 0;
+
+const PROGRAM_HEADER_SIZE: usize = 56;
+const SECTION_HEADER_SIZE: usize = 64;
+const SYMBOL_SIZE: usize = 24;
+
+/// whether the file starts with the ELF magic, anything else is treated as a flat binary
+pub fn is_elf(bytes: &[u8]) -> bool {
+	bytes.starts_with(ELF_MAGIC)
+}
+
+/// a PT_LOAD segment, already padded with zeroes up to its size in memory
+#[derive(Debug)]
+pub struct Segment {
+	pub addr: u64,
+	pub data: Vec<u8>,
+}
+
+/// the parts of a RISC-V ELF64 executable whisker cares about
+#[derive(Debug)]
+pub struct ElfImage {
+	pub entry: u64,
+	pub segments: Vec<Segment>,
+	pub symbols: SymbolTable,
+}
+
+impl ElfImage {
+	/// segments are placed at their physical addresses, which is what linker scripts set with AT()
+	pub fn parse(bytes: &[u8]) -> Result<Self, String> {
+		if !is_elf(bytes) {
+			return Err("not an ELF file".to_string());
+		}
+		if read_u8(bytes, 4)? != ELFCLASS64 {
+			return Err("only 64 bit ELF files are supported".to_string());
+		}
+		if read_u8(bytes, 5)? != ELFDATA2LSB {
+			return Err("only little endian ELF files are supported".to_string());
+		}
+		let machine = read_u16(bytes, 0x12)?;
+		if machine != EM_RISCV {
+			return Err(format!("the ELF file is for machine {machine}, not RISC-V"));
+		}
+
+		let entry = read_u64(bytes, 0x18)?;
+		let phoff = read_u64(bytes, 0x20)? as usize;
+		let phnum = read_u16(bytes, 0x38)? as usize;
+
+		let mut segments = Vec::new();
+		for idx in 0..phnum {
+			let header = phoff + idx * PROGRAM_HEADER_SIZE;
+			if read_u32(bytes, header)? != PT_LOAD {
+				continue;
+			}
+			let offset = read_u64(bytes, header + 0x08)? as usize;
+			let addr = read_u64(bytes, header + 0x18)?;
+			let file_size = read_u64(bytes, header + 0x20)? as usize;
+			let mem_size = read_u64(bytes, header + 0x28)? as usize;
+			if mem_size == 0 {
+				continue;
+			}
+			if file_size > mem_size {
+				return Err(format!(
+					"segment at {addr:#x} has more bytes in the file than in memory"
+				));
+			}
+			let mut data = slice(bytes, offset, file_size)?.to_vec();
+			// the rest is bss
+			data.resize(mem_size, 0);
+			segments.push(Segment { addr, data });
+		}
+
+		Ok(Self {
+			entry,
+			segments,
+			symbols: SymbolTable::parse(bytes)?,
+		})
+	}
+}
+
+#[derive(Debug, Clone)]
+struct Symbol {
+	name: String,
+	addr: u64,
+	size: u64,
+}
+
+/// the named symbols of the loaded images, sorted by address so addresses can be mapped back to them
+#[derive(Debug, Clone, Default)]
+pub struct SymbolTable(Vec<Symbol>);
+
+impl SymbolTable {
+	/// stripped files just have an empty symbol table
+	fn parse(bytes: &[u8]) -> Result<Self, String> {
+		let shoff = read_u64(bytes, 0x28)? as usize;
+		let shnum = read_u16(bytes, 0x3C)? as usize;
+		let section = |idx: usize| shoff + idx * SECTION_HEADER_SIZE;
+
+		let mut symbols = Vec::new();
+		for idx in 0..shnum {
+			let header = section(idx);
+			if read_u32(bytes, header + 0x04)? != SHT_SYMTAB {
+				continue;
+			}
+			let offset = read_u64(bytes, header + 0x18)? as usize;
+			let size = read_u64(bytes, header + 0x20)? as usize;
+			// the symbol names live in the string table section the symbol table links to
+			let strtab = section(read_u32(bytes, header + 0x28)? as usize);
+			let strtab_offset = read_u64(bytes, strtab + 0x18)? as usize;
+			let strtab_size = read_u64(bytes, strtab + 0x20)? as usize;
+			let strings = slice(bytes, strtab_offset, strtab_size)?;
+
+			for symbol in slice(bytes, offset, size)?.chunks_exact(SYMBOL_SIZE) {
+				let name = read_u32(symbol, 0x00)? as usize;
+				let kind = read_u8(symbol, 0x04)? & 0xF;
+				let section = read_u16(symbol, 0x06)?;
+				if section == SHN_UNDEF || matches!(kind, STT_SECTION | STT_FILE) {
+					continue;
+				}
+				let name = strings.get(name..).unwrap_or_default();
+				let name = &name[..name.iter().position(|&byte| byte == 0).unwrap_or(name.len())];
+				if name.is_empty() {
+					continue;
+				}
+				symbols.push(Symbol {
+					name: String::from_utf8_lossy(name).into_owned(),
+					addr: read_u64(symbol, 0x08)?,
+					size: read_u64(symbol, 0x10)?,
+				});
+			}
+		}
+
+		let mut table = Self(symbols);
+		table.sort();
+		Ok(table)
+	}
+
+	pub fn extend(&mut self, other: SymbolTable) {
+		self.0.extend(other.0);
+		self.sort();
+	}
+
+	fn sort(&mut self) {
+		self.0.sort_by_key(|symbol| symbol.addr);
+	}
+
+	/// the symbol covering addr and how far into it addr is
+	/// symbols without a size, like assembly labels, cover everything up to the next one
+	pub fn lookup(&self, addr: u64) -> Option<(&str, u64)> {
+		let idx = self.0.partition_point(|symbol| symbol.addr <= addr).checked_sub(1)?;
+		let symbol = &self.0[idx];
+		let offset = addr - symbol.addr;
+		if symbol.size != 0 && offset >= symbol.size {
+			return None;
+		}
+		Some((&symbol.name, offset))
+	}
+
+	/// where the first symbol called name is
+	pub fn address_of(&self, name: &str) -> Option<u64> {
+		self.0
+			.iter()
+			.find(|symbol| symbol.name == name)
+			.map(|symbol| symbol.addr)
+	}
+
+	/// formats an address like gdb does, 0x80000010 <main+0x10>
+	pub fn describe(&self, addr: u64) -> String {
+		match self.lookup(addr) {
+			Some((name, 0)) => format!("{addr:#018X} <{name}>"),
+			Some((name, offset)) => format!("{addr:#018X} <{name}+{offset:#x}>"),
+			None => format!("{addr:#018X}"),
+		}
+	}
+}
+
+fn slice(bytes: &[u8], offset: usize, len: usize) -> Result<&[u8], String> {
+	offset
+		.checked_add(len)
+		.and_then(|end| bytes.get(offset..end))
+		.ok_or_else(|| format!("the ELF file is truncated, {len} bytes at {offset:#x} are missing"))
+}
+
+fn read_u8(bytes: &[u8], offset: usize) -> Result<u8, String> {
+	Ok(slice(bytes, offset, 1)?[0])
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Result<u16, String> {
+	Ok(u16::from_le_bytes(slice(bytes, offset, 2)?.try_into().unwrap()))
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Result<u32, String> {
+	Ok(u32::from_le_bytes(slice(bytes, offset, 4)?.try_into().unwrap()))
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> Result<u64, String> {
+	Ok(u64::from_le_bytes(slice(bytes, offset, 8)?.try_into().unwrap()))
+}
@@ -0,0 +1,287 @@
+use crate::devices::clint::CLINT_SIZE;
+use crate::devices::framebuffer::Framebuffer;
+use crate::devices::plic::{PLIC_SIZE, PLIC_SOURCES};
+use crate::devices::test_finisher::{TEST_FINISHER_PASS, TEST_FINISHER_RESET, TEST_FINISHER_SIZE};
+use crate::devices::uart::{UART_IRQ, UART_SIZE};
+use crate::machine::MachineLayout;
+use crate::mmu::TranslationMode;
+use crate::ty::SupportedExtensions;
+
+const FDT_MAGIC: u32 = 0xD00D_FEED;
+const FDT_VERSION: u32 = 17;
+const FDT_LAST_COMPATIBLE_VERSION: u32 = 16;
+const FDT_HEADER_SIZE: usize = 40;
+// a single empty entry terminates the memory reservation block
+const FDT_RESERVE_MAP_SIZE: usize = 16;
+
+const FDT_BEGIN_NODE: u32 = 1;
+const FDT_END_NODE: u32 = 2;
+const FDT_PROP: u32 = 3;
+const FDT_END: u32 = 9;
+
+// phandles other nodes refer to the interrupt controllers by
+const CPU_INTC_PHANDLE: u32 = 1;
+const PLIC_PHANDLE: u32 = 2;
+const TEST_FINISHER_PHANDLE: u32 = 3;
+
+// the interrupt numbers the CLINT and PLIC are wired to on the hart's local interrupt controller
+const IRQ_M_SOFTWARE: u32 = 3;
+const IRQ_M_TIMER: u32 = 7;
+const IRQ_S_EXTERNAL: u32 = 9;
+const IRQ_M_EXTERNAL: u32 = 11;
+
+/// the 16550A is clocked like the one on QEMU's virt machine, whisker ignores the divisor anyway
+const UART_CLOCK_FREQ: u32 = 3_686_400;
+
+/// whether a blob starts with the device tree magic
+pub fn is_fdt(bytes: &[u8]) -> bool {
+	bytes.starts_with(&FDT_MAGIC.to_be_bytes())
+}
+
+/// everything about the machine the device tree describes
+#[derive(Debug)]
+pub struct MachineDescription<'a> {
+	pub extensions: SupportedExtensions,
+	pub translation_mode: TranslationMode,
+	pub timebase_freq: u64,
+	pub layout: &'a MachineLayout,
+	pub framebuffer: Option<&'a Framebuffer>,
+}
+
+/// builds the flattened device tree blob that's handed to the guest in a1
+pub fn generate(machine: &MachineDescription) -> Vec<u8> {
+	let devices = &machine.layout.devices;
+	let dram = &machine.layout.dram;
+	let mut fdt = FdtWriter::default();
+	fdt.begin_node("");
+	fdt.property_u32("#address-cells", 2);
+	fdt.property_u32("#size-cells", 2);
+	fdt.property_string("compatible", "whisker,virt");
+	fdt.property_string("model", "whisker");
+
+	fdt.begin_node("chosen");
+	fdt.property_string("stdout-path", &format!("/soc/serial@{:x}", devices.uart));
+	fdt.end_node();
+
+	fdt.begin_node(&format!("memory@{:x}", dram.base));
+	fdt.property_string("device_type", "memory");
+	fdt.property_u64s("reg", &[dram.base, dram.size]);
+	fdt.end_node();
+
+	fdt.begin_node("cpus");
+	fdt.property_u32("#address-cells", 1);
+	fdt.property_u32("#size-cells", 0);
+	fdt.property_u32("timebase-frequency", machine.timebase_freq as u32);
+	// whisker only emulates hart 0
+	fdt.begin_node("cpu@0");
+	fdt.property_string("device_type", "cpu");
+	fdt.property_u32("reg", 0);
+	fdt.property_string("status", "okay");
+	fdt.property_string("compatible", "riscv");
+	fdt.property_string("riscv,isa", &machine.extensions.isa_string());
+	fdt.property_string("riscv,isa-base", "rv64i");
+	fdt.property_strings("riscv,isa-extensions", &machine.extensions.isa_extensions());
+	let mmu_type = match machine.translation_mode {
+		TranslationMode::Bare => "riscv,none",
+		TranslationMode::Sv39 => "riscv,sv39",
+		TranslationMode::Sv48 => "riscv,sv48",
+		TranslationMode::Sv57 => "riscv,sv57",
+	};
+	if machine.extensions.has(SupportedExtensions::SUPERVISOR) {
+		fdt.property_string("mmu-type", mmu_type);
+	}
+	fdt.begin_node("interrupt-controller");
+	fdt.property_u32("#interrupt-cells", 1);
+	fdt.property_empty("interrupt-controller");
+	fdt.property_string("compatible", "riscv,cpu-intc");
+	fdt.property_u32("phandle", CPU_INTC_PHANDLE);
+	fdt.end_node();
+	fdt.end_node();
+	fdt.end_node();
+
+	fdt.begin_node("soc");
+	fdt.property_u32("#address-cells", 2);
+	fdt.property_u32("#size-cells", 2);
+	fdt.property_string("compatible", "simple-bus");
+	fdt.property_empty("ranges");
+
+	fdt.begin_node(&format!("clint@{:x}", devices.clint));
+	fdt.property_strings("compatible", &["sifive,clint0", "riscv,clint0"]);
+	fdt.property_u64s("reg", &[devices.clint, CLINT_SIZE]);
+	fdt.property_u32s(
+		"interrupts-extended",
+		&[CPU_INTC_PHANDLE, IRQ_M_SOFTWARE, CPU_INTC_PHANDLE, IRQ_M_TIMER],
+	);
+	fdt.end_node();
+
+	fdt.begin_node(&format!("plic@{:x}", devices.plic));
+	fdt.property_strings("compatible", &["sifive,plic-1.0.0", "riscv,plic0"]);
+	fdt.property_u64s("reg", &[devices.plic, PLIC_SIZE]);
+	fdt.property_u32("#address-cells", 0);
+	fdt.property_u32("#interrupt-cells", 1);
+	fdt.property_empty("interrupt-controller");
+	// source 0 doesn't exist
+	fdt.property_u32("riscv,ndev", PLIC_SOURCES - 1);
+	// context 0 is M-mode and context 1 is S-mode
+	fdt.property_u32s(
+		"interrupts-extended",
+		&[CPU_INTC_PHANDLE, IRQ_M_EXTERNAL, CPU_INTC_PHANDLE, IRQ_S_EXTERNAL],
+	);
+	fdt.property_u32("phandle", PLIC_PHANDLE);
+	fdt.end_node();
+
+	fdt.begin_node(&format!("serial@{:x}", devices.uart));
+	fdt.property_string("compatible", "ns16550a");
+	fdt.property_u64s("reg", &[devices.uart, UART_SIZE]);
+	fdt.property_u32("clock-frequency", UART_CLOCK_FREQ);
+	fdt.property_u32("interrupt-parent", PLIC_PHANDLE);
+	fdt.property_u32("interrupts", UART_IRQ);
+	fdt.end_node();
+
+	fdt.begin_node(&format!("test@{:x}", devices.test_finisher));
+	fdt.property_strings("compatible", &["sifive,test1", "sifive,test0", "syscon"]);
+	fdt.property_u64s("reg", &[devices.test_finisher, TEST_FINISHER_SIZE]);
+	fdt.property_u32("phandle", TEST_FINISHER_PHANDLE);
+	fdt.end_node();
+
+	fdt.begin_node("poweroff");
+	fdt.property_string("compatible", "syscon-poweroff");
+	fdt.property_u32("regmap", TEST_FINISHER_PHANDLE);
+	fdt.property_u32("offset", 0);
+	fdt.property_u32("value", TEST_FINISHER_PASS);
+	fdt.end_node();
+
+	fdt.begin_node("reboot");
+	fdt.property_string("compatible", "syscon-reboot");
+	fdt.property_u32("regmap", TEST_FINISHER_PHANDLE);
+	fdt.property_u32("offset", 0);
+	fdt.property_u32("value", TEST_FINISHER_RESET);
+	fdt.end_node();
+
+	if let Some(framebuffer) = machine.framebuffer {
+		fdt.begin_node(&format!("framebuffer@{:x}", devices.framebuffer));
+		fdt.property_string("compatible", "simple-framebuffer");
+		fdt.property_u64s("reg", &[devices.framebuffer, framebuffer.size()]);
+		fdt.property_u32("width", framebuffer.width() as u32);
+		fdt.property_u32("height", framebuffer.height() as u32);
+		fdt.property_u32("stride", framebuffer.stride() as u32);
+		fdt.property_string("format", "x8r8g8b8");
+		fdt.end_node();
+	}
+
+	fdt.end_node();
+	fdt.end_node();
+	fdt.finish()
+}
+
+/// writes the structure block and strings block of a device tree blob
+#[derive(Debug, Default)]
+struct FdtWriter {
+	structure: Vec<u8>,
+	strings: Vec<u8>,
+}
+
+impl FdtWriter {
+	fn begin_node(&mut self, name: &str) {
+		self.token(FDT_BEGIN_NODE);
+		self.structure.extend_from_slice(name.as_bytes());
+		self.structure.push(0);
+		self.align();
+	}
+
+	fn end_node(&mut self) {
+		self.token(FDT_END_NODE);
+	}
+
+	fn property(&mut self, name: &str, value: &[u8]) {
+		let name_offset = self.string_offset(name);
+		self.token(FDT_PROP);
+		self.token(value.len() as u32);
+		self.token(name_offset);
+		self.structure.extend_from_slice(value);
+		self.align();
+	}
+
+	fn property_empty(&mut self, name: &str) {
+		self.property(name, &[]);
+	}
+
+	fn property_u32(&mut self, name: &str, val: u32) {
+		self.property_u32s(name, &[val]);
+	}
+
+	/// cells are big endian
+	fn property_u32s(&mut self, name: &str, vals: &[u32]) {
+		let value: Vec<u8> = vals.iter().flat_map(|val| val.to_be_bytes()).collect();
+		self.property(name, &value);
+	}
+
+	/// each value takes two cells, which is what #address-cells and #size-cells are set to outside of /cpus
+	fn property_u64s(&mut self, name: &str, vals: &[u64]) {
+		let value: Vec<u8> = vals.iter().flat_map(|val| val.to_be_bytes()).collect();
+		self.property(name, &value);
+	}
+
+	fn property_string(&mut self, name: &str, val: &str) {
+		self.property_strings(name, &[val]);
+	}
+
+	/// a string list is every string with its nul terminator, back to back
+	fn property_strings(&mut self, name: &str, vals: &[&str]) {
+		let value: Vec<u8> = vals.iter().flat_map(|val| val.bytes().chain([0])).collect();
+		self.property(name, &value);
+	}
+
+	/// property names are stored once in the strings block and referred to by offset
+	fn string_offset(&mut self, name: &str) -> u32 {
+		let mut offset = 0;
+		for string in self.strings.split(|&byte| byte == 0) {
+			if string == name.as_bytes() {
+				return offset as u32;
+			}
+			offset += string.len() + 1;
+		}
+		let offset = self.strings.len();
+		self.strings.extend(name.bytes().chain([0]));
+		offset as u32
+	}
+
+	fn token(&mut self, val: u32) {
+		self.structure.extend_from_slice(&val.to_be_bytes());
+	}
+
+	fn align(&mut self) {
+		while self.structure.len() % 4 != 0 {
+			self.structure.push(0);
+		}
+	}
+
+	/// the header, an empty memory reservation block, the structure block and then the strings block
+	fn finish(mut self) -> Vec<u8> {
+		self.token(FDT_END);
+
+		let reserve_map_offset = FDT_HEADER_SIZE;
+		let structure_offset = reserve_map_offset + FDT_RESERVE_MAP_SIZE;
+		let strings_offset = structure_offset + self.structure.len();
+		let total_size = strings_offset + self.strings.len();
+
+		let header = [
+			FDT_MAGIC,
+			total_size as u32,
+			structure_offset as u32,
+			strings_offset as u32,
+			reserve_map_offset as u32,
+			FDT_VERSION,
+			FDT_LAST_COMPATIBLE_VERSION,
+			// boot_cpuid_phys
+			0,
+			self.strings.len() as u32,
+			self.structure.len() as u32,
+		];
+		let mut blob: Vec<u8> = header.iter().flat_map(|val| val.to_be_bytes()).collect();
+		blob.extend_from_slice(&[0; FDT_RESERVE_MAP_SIZE]);
+		blob.extend_from_slice(&self.structure);
+		blob.extend_from_slice(&self.strings);
+		blob
+	}
+}
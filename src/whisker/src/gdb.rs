@@ -1,36 +1,272 @@
-use std::net::{TcpListener, TcpStream};
+use std::cell::Cell;
+use std::io::{self, Read, Stdout, Write};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
 
 use gdbstub::arch::{Arch, Registers};
 use gdbstub::target::TargetError;
 use gdbstub::{
-	common::Signal,
-	conn::ConnectionExt,
+	common::{Pid, Signal},
+	conn::{Connection, ConnectionExt},
 	stub::{
 		run_blocking::{BlockingEventLoop, Event, WaitForStopReasonError},
-		SingleThreadStopReason,
+		state_machine::GdbStubStateMachine,
+		DisconnectReason, GdbStub, GdbStubError, SingleThreadStopReason,
 	},
 	target::{
 		ext::{
+			base::single_register_access::SingleRegisterAccess,
 			base::singlethread::{SingleThreadBase, SingleThreadResume, SingleThreadSingleStep},
-			breakpoints::{Breakpoints, SwBreakpoint},
+			breakpoints::{Breakpoints, HwBreakpoint, HwWatchpoint, SwBreakpoint, WatchKind as GdbWatchKind},
+			extended_mode::{Args, AttachKind, CurrentActivePid, ExtendedMode, ShouldTerminate},
+			memory_map::MemoryMap,
+			monitor_cmd::{outputln, ConsoleOutput, MonitorCmd},
 		},
 		Target,
 	},
 };
 use gdbstub_arch::riscv::reg::id::RiscvRegId;
+use tracing::warn;
 
-use crate::cpu::{WhiskerExecState, WhiskerExecStatus};
+use crate::breakpoint::Breakpoint;
+use crate::cpu::{WatchKind, WhiskerExecState, WhiskerExecStatus};
+use crate::mem::{Permissions, RegionKind};
+use crate::ty::{FPRegisterIndex, GPRegisterIndex, PrivilegeMode, TrapIdx};
+use crate::util::parse_number;
 use crate::WhiskerCpu;
 
-pub fn wait_for_tcp() -> Result<TcpStream, std::io::Error> {
-	let sockaddr = format!("127.0.0.1:{}", 2424);
-	eprintln!("Waiting for a GDB connection on {:?}...", sockaddr);
+pub const DEFAULT_GDB_TRANSPORT: &str = "tcp:127.0.0.1:2424";
+
+/// how gdb connects to the stub
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GdbTransport {
+	/// listens on an address and waits for gdb to connect
+	Tcp(String),
+	/// listens on a unix domain socket at this path, it must not exist yet
+	Unix(PathBuf),
+	/// speaks the protocol over stdin and stdout, for `target remote | whisker run --gdb stdio ...`
+	Stdio,
+}
+
+impl FromStr for GdbTransport {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s.split_once(':') {
+			None if s == "stdio" => Ok(Self::Stdio),
+			Some(("tcp", addr)) if !addr.is_empty() => Ok(Self::Tcp(addr.to_string())),
+			Some(("unix", path)) if !path.is_empty() => Ok(Self::Unix(path.into())),
+			_ => Err(format!(
+				"unknown gdb transport {s:?}, expected tcp:<addr>, unix:<path> or stdio"
+			)),
+		}
+	}
+}
+
+impl GdbTransport {
+	/// starts listening, gdb can connect once accept is called
+	pub fn listen(&self) -> io::Result<GdbListener> {
+		match self {
+			Self::Tcp(addr) => Ok(GdbListener::Tcp(TcpListener::bind(addr)?)),
+			#[cfg(unix)]
+			Self::Unix(path) => Ok(GdbListener::Unix(
+				std::os::unix::net::UnixListener::bind(path)?,
+				path.clone(),
+			)),
+			#[cfg(not(unix))]
+			Self::Unix(_) => Err(io::Error::new(
+				io::ErrorKind::Unsupported,
+				"unix domain sockets are only supported on unix hosts",
+			)),
+			Self::Stdio => Ok(GdbListener::Stdio(Cell::new(false))),
+		}
+	}
+}
+
+pub type GdbConnection = Box<dyn ConnectionExt<Error = io::Error>>;
+
+/// waits for gdb to connect, it stays open so gdb can attach again after detaching
+pub enum GdbListener {
+	Tcp(TcpListener),
+	#[cfg(unix)]
+	Unix(std::os::unix::net::UnixListener, PathBuf),
+	/// stdin and stdout can only be used by one gdb, it can't come back after detaching
+	/// this is whether they've been handed out
+	Stdio(Cell<bool>),
+}
+
+impl GdbListener {
+	/// blocks until gdb connects
+	pub fn accept(&self) -> io::Result<GdbConnection> {
+		match self {
+			Self::Tcp(listener) => eprintln!("Waiting for a GDB connection on {}...", listener.local_addr()?),
+			#[cfg(unix)]
+			Self::Unix(_, path) => eprintln!("Waiting for a GDB connection on {}...", path.display()),
+			Self::Stdio(_) => {}
+		}
+		self.accept_conn(true)?
+			.ok_or_else(|| io::Error::new(io::ErrorKind::Unsupported, "gdb can't attach again over stdio"))
+	}
+
+	/// returns None right away if gdb isn't trying to connect
+	pub fn try_accept(&self) -> io::Result<Option<GdbConnection>> {
+		self.accept_conn(false)
+	}
+
+	fn accept_conn(&self, blocking: bool) -> io::Result<Option<GdbConnection>> {
+		let conn: GdbConnection = match self {
+			Self::Tcp(listener) => {
+				listener.set_nonblocking(!blocking)?;
+				let (stream, addr) = match listener.accept() {
+					Ok(conn) => conn,
+					Err(err) if err.kind() == io::ErrorKind::WouldBlock => return Ok(None),
+					Err(err) => return Err(err),
+				};
+				// some hosts hand out connections that inherit the listener's non-blocking mode
+				stream.set_nonblocking(false)?;
+				eprintln!("Debugger connected from {}", addr);
+				Box::new(stream)
+			}
+			#[cfg(unix)]
+			Self::Unix(listener, _) => {
+				listener.set_nonblocking(!blocking)?;
+				let (stream, _) = match listener.accept() {
+					Ok(conn) => conn,
+					Err(err) if err.kind() == io::ErrorKind::WouldBlock => return Ok(None),
+					Err(err) => return Err(err),
+				};
+				stream.set_nonblocking(false)?;
+				eprintln!("Debugger connected");
+				Box::new(stream)
+			}
+			Self::Stdio(taken) => {
+				if taken.replace(true) {
+					return Ok(None);
+				}
+				Box::new(StdioConnection::new())
+			}
+		};
+		Ok(Some(conn))
+	}
+}
+
+// set by the SIGINT handler, Ctrl-C pauses the machine while it's set up
+static CTRL_C: AtomicBool = AtomicBool::new(false);
 
-	let sock = TcpListener::bind(sockaddr)?;
-	let (stream, addr) = sock.accept()?;
-	eprintln!("Debugger connected from {}", addr);
+#[cfg(unix)]
+mod sigint {
+	use std::ffi::c_int;
+	use std::sync::atomic::Ordering;
+
+	// std already links libc
+	extern "C" {
+		fn signal(signum: c_int, handler: usize) -> usize;
+	}
+	const SIGINT: c_int = 2;
+	const SIG_DFL: usize = 0;
 
-	Ok(stream)
+	extern "C" fn on_sigint(_signum: c_int) {
+		super::CTRL_C.store(true, Ordering::Relaxed);
+	}
+
+	pub fn catch(catch: bool) {
+		let handler = if catch { on_sigint as usize } else { SIG_DFL };
+		// SAFETY: the handler only stores to an atomic, which is async-signal-safe
+		unsafe {
+			signal(SIGINT, handler);
+		}
+	}
+}
+
+#[cfg(not(unix))]
+mod sigint {
+	pub fn catch(_catch: bool) {}
+}
+
+/// whether Ctrl-C pauses the machine, otherwise it kills whisker like usual
+/// it's only caught while something can respond to it, not during blocking waits
+pub fn catch_ctrl_c(catch: bool) {
+	CTRL_C.store(false, Ordering::Relaxed);
+	sigint::catch(catch);
+}
+
+pub fn ctrl_c_pending() -> bool {
+	CTRL_C.load(Ordering::Relaxed)
+}
+
+pub fn take_ctrl_c() -> bool {
+	CTRL_C.swap(false, Ordering::Relaxed)
+}
+
+/// gdb on the other end of stdin and stdout
+/// stdin is read on a background thread so the event loop can check for data without blocking
+struct StdioConnection {
+	input: Receiver<u8>,
+	peeked: Option<u8>,
+	output: Stdout,
+}
+
+impl StdioConnection {
+	fn new() -> Self {
+		let (sender, input) = mpsc::channel();
+		std::thread::spawn(move || {
+			let mut buf = [0; 256];
+			// stop on EOF or any error, the receiver sees the disconnect
+			while let Ok(len @ 1..) = io::stdin().read(&mut buf) {
+				if buf[..len].iter().any(|&byte| sender.send(byte).is_err()) {
+					return;
+				}
+			}
+		});
+		Self {
+			input,
+			peeked: None,
+			output: io::stdout(),
+		}
+	}
+}
+
+fn disconnected() -> io::Error {
+	io::Error::new(io::ErrorKind::UnexpectedEof, "gdb closed stdin")
+}
+
+impl Connection for StdioConnection {
+	type Error = io::Error;
+
+	fn write(&mut self, byte: u8) -> io::Result<()> {
+		self.output.write_all(&[byte])
+	}
+
+	fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+		self.output.write_all(buf)
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		self.output.flush()
+	}
+}
+
+impl ConnectionExt for StdioConnection {
+	fn read(&mut self) -> io::Result<u8> {
+		match self.peeked.take() {
+			Some(byte) => Ok(byte),
+			None => self.input.recv().map_err(|_| disconnected()),
+		}
+	}
+
+	fn peek(&mut self) -> io::Result<Option<u8>> {
+		if self.peeked.is_none() {
+			self.peeked = match self.input.try_recv() {
+				Ok(byte) => Some(byte),
+				Err(TryRecvError::Empty) => None,
+				Err(TryRecvError::Disconnected) => return Err(disconnected()),
+			};
+		}
+		Ok(self.peeked)
+	}
 }
 
 pub struct Rv64Arch;
@@ -136,6 +372,169 @@ impl Target for WhiskerCpu {
 	fn use_target_description_xml(&self) -> bool {
 		true
 	}
+
+	fn support_monitor_cmd(&mut self) -> Option<gdbstub::target::ext::monitor_cmd::MonitorCmdOps<'_, Self>> {
+		Some(self)
+	}
+
+	fn support_extended_mode(&mut self) -> Option<gdbstub::target::ext::extended_mode::ExtendedModeOps<'_, Self>> {
+		Some(self)
+	}
+
+	fn support_memory_map(&mut self) -> Option<gdbstub::target::ext::memory_map::MemoryMapOps<'_, Self>> {
+		Some(self)
+	}
+}
+
+// the machine is the only process, so it always has this pid
+const MACHINE_PID: Pid = match Pid::new(1) {
+	Some(pid) => pid,
+	None => unreachable!(),
+};
+
+/// `run` and `start` in extended-remote mode restart the machine instead of launching a program
+impl ExtendedMode for WhiskerCpu {
+	fn on_start(&mut self) -> Result<(), Self::Error> {
+		self.gdb_extended_mode = true;
+		Ok(())
+	}
+
+	fn run(&mut self, filename: Option<&[u8]>, _args: Args<'_, '_>) -> gdbstub::target::TargetResult<Pid, Self> {
+		if let Some(filename) = filename.filter(|filename| !filename.is_empty()) {
+			warn!(
+				"gdb asked to run {}, but whisker can only restart the machine it was started with",
+				String::from_utf8_lossy(filename)
+			);
+		}
+		self.restart();
+		self.exec_state = WhiskerExecState::Paused;
+		Ok(MACHINE_PID)
+	}
+
+	fn attach(&mut self, _pid: Pid) -> gdbstub::target::TargetResult<(), Self> {
+		self.exec_state = WhiskerExecState::Paused;
+		Ok(())
+	}
+
+	fn query_if_attached(&mut self, _pid: Pid) -> gdbstub::target::TargetResult<AttachKind, Self> {
+		// gdb didn't start whisker, so quitting gdb should detach and leave the machine running
+		Ok(AttachKind::Attach)
+	}
+
+	fn kill(&mut self, _pid: Option<Pid>) -> gdbstub::target::TargetResult<ShouldTerminate, Self> {
+		if !self.gdb_extended_mode {
+			return Ok(ShouldTerminate::Yes);
+		}
+		self.exec_state = WhiskerExecState::Paused;
+		Ok(ShouldTerminate::No)
+	}
+
+	fn restart(&mut self) -> Result<(), Self::Error> {
+		WhiskerCpu::restart(self);
+		self.exec_state = WhiskerExecState::Paused;
+		Ok(())
+	}
+
+	fn support_current_active_pid(
+		&mut self,
+	) -> Option<gdbstub::target::ext::extended_mode::CurrentActivePidOps<'_, Self>> {
+		Some(self)
+	}
+}
+
+impl CurrentActivePid for WhiskerCpu {
+	fn current_active_pid(&mut self) -> Result<Pid, Self::Error> {
+		Ok(MACHINE_PID)
+	}
+}
+
+impl MemoryMap for WhiskerCpu {
+	fn memory_map_xml(&self, offset: u64, length: usize, buf: &mut [u8]) -> gdbstub::target::TargetResult<usize, Self> {
+		let xml = memory_map_xml(self);
+		let start = (offset as usize).min(xml.len());
+		let end = (start + length).min(xml.len()).min(start + buf.len());
+		buf[..end - start].copy_from_slice(&xml.as_bytes()[start..end]);
+		Ok(end - start)
+	}
+}
+
+/// memory the guest can't write is rom so gdb uses hardware breakpoints there
+/// device registers are left out, gdb won't touch unlisted addresses so it can't set off their side effects
+fn memory_map_xml(cpu: &WhiskerCpu) -> String {
+	let mut xml = String::from(
+		r#"<?xml version="1.0"?>
+<!DOCTYPE memory-map PUBLIC "+//IDN gnu.org//DTD GDB Memory Map V1.0//EN" "http://sourceware.org/gdb/gdb-memory-map.dtd">
+<memory-map>
+"#,
+	);
+	for (region, perms) in cpu.mem.regions() {
+		if region.kind == RegionKind::Mmio {
+			continue;
+		}
+		let kind = if perms.has(Permissions::WRITE) { "ram" } else { "rom" };
+		xml += &format!(
+			"\t<memory type=\"{kind}\" start=\"{:#x}\" length=\"{:#x}\"/>\n",
+			region.base, region.size
+		);
+	}
+	xml += "</memory-map>\n";
+	xml
+}
+
+impl MonitorCmd for WhiskerCpu {
+	fn handle_monitor_cmd(&mut self, cmd: &[u8], mut out: ConsoleOutput<'_>) -> Result<(), Self::Error> {
+		let cmd = String::from_utf8_lossy(cmd);
+		let cmd = cmd.trim();
+		let (name, args) = cmd.split_once(char::is_whitespace).unwrap_or((cmd, ""));
+		match name {
+			"reset" => {
+				self.reset();
+				outputln!(out, "machine reset, pc={:#018X}", self.pc);
+			}
+			"break" => match Breakpoint::parse_command(args) {
+				Ok((addr, bp)) => {
+					outputln!(out, "breakpoint at {}", self.symbols.describe(addr));
+					self.breakpoints.insert(addr, bp);
+				}
+				Err(e) => outputln!(out, "{e}"),
+			},
+			"delete" => match parse_number(args.trim()) {
+				Ok(addr) if self.breakpoints.remove(&addr).is_some() => {
+					outputln!(out, "deleted breakpoint at {}", self.symbols.describe(addr));
+				}
+				Ok(addr) => outputln!(out, "no breakpoint at {addr:#018X}"),
+				Err(e) => outputln!(out, "invalid address {args:?}: {e}"),
+			},
+			"breakpoints" => {
+				let mut addrs = self.breakpoints.keys().copied().collect::<Vec<_>>();
+				addrs.sort_unstable();
+				for addr in addrs {
+					let bp = &self.breakpoints[&addr];
+					let owner = if bp.sticky { "monitor" } else { "gdb" };
+					let mut line = format!("{} ({owner}) hits={}", self.symbols.describe(addr), bp.hits);
+					if bp.ignore != 0 {
+						line += &format!(" ignore={}", bp.ignore);
+					}
+					if let Some(condition) = &bp.condition {
+						line += &format!(" if {condition}");
+					}
+					outputln!(out, "{line}");
+				}
+			}
+			"" | "help" => {
+				outputln!(out, "whisker monitor commands:");
+				outputln!(out, "  reset - reset the machine and jump to the reset vector");
+				outputln!(
+					out,
+					"  break ADDR [if REG OP VALUE] [ignore N] - breakpoint checked by whisker, kept until deleted"
+				);
+				outputln!(out, "  delete ADDR - remove the breakpoint at ADDR");
+				outputln!(out, "  breakpoints - list breakpoints with their hit counts");
+			}
+			_ => outputln!(out, "unknown monitor command: {cmd}"),
+		}
+		Ok(())
+	}
 }
 
 impl SingleThreadBase for WhiskerCpu {
@@ -165,7 +564,7 @@ impl SingleThreadBase for WhiskerCpu {
 		start_addr: <Self::Arch as gdbstub::arch::Arch>::Usize,
 		data: &mut [u8],
 	) -> gdbstub::target::TargetResult<usize, Self> {
-		match self.mem.read_slice(start_addr, data) {
+		match self.mem.debug_read_slice(start_addr, data) {
 			Ok(()) => Ok(data.len()),
 			// FIXME: does this do what we want
 			Err(addr) => Ok((addr - start_addr) as usize),
@@ -177,7 +576,7 @@ impl SingleThreadBase for WhiskerCpu {
 		start_addr: <Self::Arch as gdbstub::arch::Arch>::Usize,
 		data: &[u8],
 	) -> gdbstub::target::TargetResult<(), Self> {
-		match self.mem.write_slice(start_addr, data) {
+		match self.mem.debug_write_slice(start_addr, data) {
 			Ok(()) => Ok(()),
 			// EREMOTEIO - causes gdb to report "cannot access memory at <start_addr>"
 			Err(_addr) => Err(TargetError::Errno(121)),
@@ -187,6 +586,72 @@ impl SingleThreadBase for WhiskerCpu {
 	fn support_resume(&mut self) -> Option<gdbstub::target::ext::base::singlethread::SingleThreadResumeOps<'_, Self>> {
 		Some(self)
 	}
+
+	fn support_single_register_access(
+		&mut self,
+	) -> Option<gdbstub::target::ext::base::single_register_access::SingleRegisterAccessOps<'_, (), Self>> {
+		Some(self)
+	}
+}
+
+// EINVAL - gdb reports the register as unavailable
+const REGISTER_UNAVAILABLE: TargetError<()> = TargetError::Errno(22);
+
+/// lets gdb read and write the CSRs and the privilege level, which aren't part of the g packet
+impl SingleRegisterAccess<()> for WhiskerCpu {
+	fn read_register(
+		&mut self,
+		_tid: (),
+		reg_id: RiscvRegId<u64>,
+		buf: &mut [u8],
+	) -> gdbstub::target::TargetResult<usize, Self> {
+		let val = match reg_id {
+			RiscvRegId::Gpr(idx) => self.registers.regs()[usize::from(idx)],
+			RiscvRegId::Fpr(idx) => self.fp_registers.get_all_raw()[usize::from(idx)],
+			RiscvRegId::Pc => self.pc,
+			RiscvRegId::Csr(csr) => self.debug_read_csr(csr).ok_or(REGISTER_UNAVAILABLE)?,
+			RiscvRegId::Priv => {
+				buf[0] = self.privilege as u8;
+				return Ok(1);
+			}
+			_ => return Err(REGISTER_UNAVAILABLE),
+		};
+		buf[..8].copy_from_slice(&val.to_le_bytes());
+		Ok(8)
+	}
+
+	fn write_register(
+		&mut self,
+		_tid: (),
+		reg_id: RiscvRegId<u64>,
+		val: &[u8],
+	) -> gdbstub::target::TargetResult<(), Self> {
+		let mut bytes = [0; 8];
+		let len = val.len().min(8);
+		bytes[..len].copy_from_slice(&val[..len]);
+		let val = u64::from_le_bytes(bytes);
+		match reg_id {
+			RiscvRegId::Gpr(idx) => self
+				.registers
+				.set(GPRegisterIndex::new(idx).ok_or(REGISTER_UNAVAILABLE)?, val),
+			RiscvRegId::Fpr(idx) => self
+				.fp_registers
+				.set_raw(FPRegisterIndex::new(idx).ok_or(REGISTER_UNAVAILABLE)?, val),
+			RiscvRegId::Pc => self.pc = val,
+			RiscvRegId::Csr(csr) => {
+				if !self.debug_write_csr(csr, val) {
+					return Err(REGISTER_UNAVAILABLE);
+				}
+			}
+			RiscvRegId::Priv => {
+				self.privilege = PrivilegeMode::from_bits(val).ok_or(REGISTER_UNAVAILABLE)?;
+				// translations were cached for the old privilege level
+				self.tlb.flush(None, None);
+			}
+			_ => return Err(REGISTER_UNAVAILABLE),
+		}
+		Ok(())
+	}
 }
 
 impl SingleThreadResume for WhiskerCpu {
@@ -215,11 +680,11 @@ impl Breakpoints for WhiskerCpu {
 	}
 
 	fn support_hw_breakpoint(&mut self) -> Option<gdbstub::target::ext::breakpoints::HwBreakpointOps<'_, Self>> {
-		None
+		Some(self)
 	}
 
 	fn support_hw_watchpoint(&mut self) -> Option<gdbstub::target::ext::breakpoints::HwWatchpointOps<'_, Self>> {
-		None
+		Some(self)
 	}
 }
 
@@ -229,7 +694,7 @@ impl SwBreakpoint for WhiskerCpu {
 		addr: <Self::Arch as gdbstub::arch::Arch>::Usize,
 		_kind: <Self::Arch as gdbstub::arch::Arch>::BreakpointKind,
 	) -> gdbstub::target::TargetResult<bool, Self> {
-		self.breakpoints.insert(addr);
+		self.breakpoints.entry(addr).or_default();
 		Ok(true)
 	}
 
@@ -238,11 +703,134 @@ impl SwBreakpoint for WhiskerCpu {
 		addr: <Self::Arch as gdbstub::arch::Arch>::Usize,
 		_kind: <Self::Arch as gdbstub::arch::Arch>::BreakpointKind,
 	) -> gdbstub::target::TargetResult<bool, Self> {
-		self.breakpoints.remove(&addr);
+		if self.breakpoints.get(&addr).is_some_and(|bp| !bp.sticky) {
+			self.breakpoints.remove(&addr);
+		}
 		Ok(true)
 	}
 }
 
+// both kinds only compare the pc before each fetch, so neither patches memory and both work in ROM
+impl HwBreakpoint for WhiskerCpu {
+	fn add_hw_breakpoint(
+		&mut self,
+		addr: <Self::Arch as gdbstub::arch::Arch>::Usize,
+		_kind: <Self::Arch as gdbstub::arch::Arch>::BreakpointKind,
+	) -> gdbstub::target::TargetResult<bool, Self> {
+		self.hw_breakpoints.insert(addr);
+		Ok(true)
+	}
+
+	fn remove_hw_breakpoint(
+		&mut self,
+		addr: <Self::Arch as gdbstub::arch::Arch>::Usize,
+		_kind: <Self::Arch as gdbstub::arch::Arch>::BreakpointKind,
+	) -> gdbstub::target::TargetResult<bool, Self> {
+		Ok(self.hw_breakpoints.remove(&addr))
+	}
+}
+
+impl From<GdbWatchKind> for WatchKind {
+	fn from(kind: GdbWatchKind) -> Self {
+		match kind {
+			GdbWatchKind::Write => WatchKind::Write,
+			GdbWatchKind::Read => WatchKind::Read,
+			GdbWatchKind::ReadWrite => WatchKind::ReadWrite,
+		}
+	}
+}
+
+impl From<WatchKind> for GdbWatchKind {
+	fn from(kind: WatchKind) -> Self {
+		match kind {
+			WatchKind::Write => GdbWatchKind::Write,
+			WatchKind::Read => GdbWatchKind::Read,
+			WatchKind::ReadWrite => GdbWatchKind::ReadWrite,
+		}
+	}
+}
+
+impl HwWatchpoint for WhiskerCpu {
+	fn add_hw_watchpoint(
+		&mut self,
+		addr: <Self::Arch as gdbstub::arch::Arch>::Usize,
+		len: <Self::Arch as gdbstub::arch::Arch>::Usize,
+		kind: GdbWatchKind,
+	) -> gdbstub::target::TargetResult<bool, Self> {
+		Ok(self.add_watchpoint(addr, len, kind.into()))
+	}
+
+	fn remove_hw_watchpoint(
+		&mut self,
+		addr: <Self::Arch as gdbstub::arch::Arch>::Usize,
+		len: <Self::Arch as gdbstub::arch::Arch>::Usize,
+		kind: GdbWatchKind,
+	) -> gdbstub::target::TargetResult<bool, Self> {
+		Ok(self.remove_watchpoint(addr, len, kind.into()))
+	}
+}
+
+/// like GdbStub::run_blocking, except the session outlives the machine powering off in extended mode
+/// so gdb can run it again, on_exit is called with the exit code every time that happens
+/// errors are already formatted for printing
+pub fn run_session(
+	cpu: &mut WhiskerCpu,
+	conn: GdbConnection,
+	mut on_exit: impl FnMut(&WhiskerCpu, u8),
+) -> Result<DisconnectReason, String> {
+	let mut gdb = GdbStub::new(conn).run_state_machine(cpu).map_err(describe_error)?;
+	loop {
+		gdb = match gdb {
+			GdbStubStateMachine::Idle(mut gdb) => {
+				let byte = gdb.borrow_conn().read().map_err(describe_connection_error)?;
+				gdb.incoming_data(cpu, byte)
+			}
+			GdbStubStateMachine::Disconnected(gdb) => match gdb.get_reason() {
+				DisconnectReason::TargetExited(code) if cpu.gdb_extended_mode => {
+					on_exit(cpu, code);
+					Ok(gdb.return_to_idle())
+				}
+				reason => return Ok(reason),
+			},
+			GdbStubStateMachine::CtrlCInterrupt(gdb) => {
+				let stop_reason = WhiskerEventLoop::on_interrupt(cpu).map_err(describe_target_error)?;
+				gdb.interrupt_handled(cpu, stop_reason)
+			}
+			GdbStubStateMachine::Running(mut gdb) => {
+				match WhiskerEventLoop::wait_for_stop_reason(cpu, gdb.borrow_conn()) {
+					Ok(Event::TargetStopped(stop_reason)) => gdb.report_stop(cpu, stop_reason),
+					Ok(Event::IncomingData(byte)) => gdb.incoming_data(cpu, byte),
+					Err(WaitForStopReasonError::Target(err)) => return Err(describe_target_error(err)),
+					Err(WaitForStopReasonError::Connection(err)) => return Err(describe_connection_error(err)),
+				}
+			}
+		}
+		.map_err(describe_error)?;
+	}
+}
+
+fn describe_target_error(err: ()) -> String {
+	format!("target encountered a fatal error: {err:?}")
+}
+
+fn describe_connection_error(err: io::Error) -> String {
+	format!("connection error: {err:?}")
+}
+
+fn describe_error(err: GdbStubError<(), io::Error>) -> String {
+	if err.is_target_error() {
+		format!(
+			"target encountered a fatal error: {:?}",
+			err.into_target_error().unwrap()
+		)
+	} else if err.is_connection_error() {
+		let (err, kind) = err.into_connection_error().unwrap();
+		format!("connection error: {kind:?} - {err:?}")
+	} else {
+		format!("gdbstub encountered a fatal error: {err:?}")
+	}
+}
+
 impl BlockingEventLoop for WhiskerEventLoop {
 	type Target = WhiskerCpu;
 
@@ -260,21 +848,40 @@ impl BlockingEventLoop for WhiskerEventLoop {
 			<Self::Connection as gdbstub::conn::Connection>::Error,
 		>,
 	> {
-		let poll_incoming_data = || conn.peek().map(|b| b.is_some()).unwrap_or(true);
-		match target.exec_gdb(poll_incoming_data) {
-			None => {
-				let data = conn.read().map_err(WaitForStopReasonError::Connection)?;
-				Ok(Event::IncomingData(data))
+		// Ctrl-C in whisker's terminal only pauses while the machine runs, the rest of the time it quits like usual
+		catch_ctrl_c(true);
+		let poll_incoming_data = || ctrl_c_pending() || conn.peek().map(|b| b.is_some()).unwrap_or(true);
+		let event = match target.exec_gdb(poll_incoming_data) {
+			// it pauses like Ctrl-C in gdb does
+			None if take_ctrl_c() => {
+				target.exec_state = WhiskerExecState::Paused;
+				Ok(Event::TargetStopped(SingleThreadStopReason::Signal(Signal::SIGINT)))
 			}
+			None => conn
+				.read()
+				.map(Event::IncomingData)
+				.map_err(WaitForStopReasonError::Connection),
 			Some(res) => {
 				let reason = match res {
 					WhiskerExecStatus::Stepped => SingleThreadStopReason::DoneStep,
 					WhiskerExecStatus::Paused => SingleThreadStopReason::Signal(Signal::SIGINT),
 					WhiskerExecStatus::HitBreakpoint => SingleThreadStopReason::SwBreak(()),
+					WhiskerExecStatus::HitHwBreakpoint => SingleThreadStopReason::HwBreak(()),
+					WhiskerExecStatus::HitWatchpoint(kind, addr) => SingleThreadStopReason::Watch {
+						tid: (),
+						kind: kind.into(),
+						addr,
+					},
+					WhiskerExecStatus::HitPoison => SingleThreadStopReason::Signal(Signal::SIGSEGV),
+					WhiskerExecStatus::FatalTrap(cause) => SingleThreadStopReason::Signal(trap_signal(cause)),
+					// gdb only gets the low byte of the exit code, like a host process's parent would
+					WhiskerExecStatus::Exited(code) => SingleThreadStopReason::Exited(code as u8),
 				};
 				Ok(Event::TargetStopped(reason))
 			}
-		}
+		};
+		catch_ctrl_c(false);
+		event
 	}
 
 	fn on_interrupt(
@@ -284,3 +891,22 @@ impl BlockingEventLoop for WhiskerEventLoop {
 		Ok(Some(SingleThreadStopReason::Signal(Signal::SIGINT)))
 	}
 }
+
+/// the signal a host process would get for the same exception
+fn trap_signal(cause: TrapIdx) -> Signal {
+	match cause {
+		TrapIdx::ILLEGAL_INSTRUCTION => Signal::SIGILL,
+		TrapIdx::BREAKPOINT => Signal::SIGTRAP,
+		TrapIdx::INSTRUCTION_ADDR_MISALIGNED | TrapIdx::LOAD_ADDR_MISALIGNED | TrapIdx::STORE_ADDR_MISALIGNED => {
+			Signal::SIGBUS
+		}
+		TrapIdx::INSTRUCTION_ACCESS_FAULT
+		| TrapIdx::LOAD_ACCESS_FAULT
+		| TrapIdx::STORE_ACCESS_FAULT
+		| TrapIdx::INSTRUCTION_PAGE_FAULT
+		| TrapIdx::LOAD_PAGE_FAULT
+		| TrapIdx::STORE_PAGE_FAULT => Signal::SIGSEGV,
+		TrapIdx::ECALL_UMODE | TrapIdx::ECALL_SMODE | TrapIdx::ECALL_MMODE => Signal::SIGSYS,
+		_ => Signal::SIGTRAP,
+	}
+}
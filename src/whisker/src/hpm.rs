@@ -0,0 +1,166 @@
+use crate::insn::atomic::AtomicInstruction;
+use crate::insn::double::DoubleInstruction;
+use crate::insn::float::FloatInstruction;
+use crate::insn::half::HalfInstruction;
+use crate::insn::int::IntInstruction;
+use crate::insn::vector::VectorInstruction;
+use crate::insn::Instruction;
+use crate::snapshot::{Snapshot, SnapshotReader, SnapshotWriter};
+
+/// mhpmcounter3 through mhpmcounter31
+pub const HPM_COUNTERS: usize = 29;
+
+/// the events mhpmeventN can select, each counts retired instructions of some kind
+pub mod event {
+	pub const NONE: u64 = 0;
+	pub const INTEGER: u64 = 1;
+	pub const FLOAT: u64 = 2;
+	pub const VECTOR: u64 = 3;
+	pub const ATOMIC: u64 = 4;
+	pub const CSR: u64 = 5;
+	pub const LOAD: u64 = 6;
+	pub const STORE: u64 = 7;
+	pub const BRANCH: u64 = 8;
+	pub const BRANCH_TAKEN: u64 = 9;
+
+	pub const LAST: u64 = BRANCH_TAKEN;
+}
+
+/// a set of events, bit N is event N
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HpmEvents(u64);
+
+impl HpmEvents {
+	pub fn add(&mut self, event: u64) {
+		self.0 |= 1 << event;
+	}
+
+	pub fn has(self, event: u64) -> bool {
+		event != event::NONE && self.0 & (1 << event) != 0
+	}
+
+	/// the events an instruction counts towards when it retires, except BRANCH_TAKEN which depends on the outcome
+	pub fn of(inst: &Instruction) -> Self {
+		let mut events = Self::default();
+		let class = match inst {
+			Instruction::IntExtension(_)
+			| Instruction::CompressedExtension(_)
+			| Instruction::MultiplyInstruction(_)
+			| Instruction::BitmanipExtension(_) => event::INTEGER,
+			Instruction::FloatExtension(_) | Instruction::DoubleExtension(_) | Instruction::HalfExtension(_) => {
+				event::FLOAT
+			}
+			Instruction::VectorExtension(_) => event::VECTOR,
+			Instruction::AtomicExtension(_) => event::ATOMIC,
+			Instruction::Csr(_) => event::CSR,
+		};
+		events.add(class);
+
+		let access = match inst {
+			Instruction::IntExtension(insn) => match insn {
+				IntInstruction::LoadByte { .. }
+				| IntInstruction::LoadHalf { .. }
+				| IntInstruction::LoadWord { .. }
+				| IntInstruction::LoadDoubleWord { .. }
+				| IntInstruction::LoadByteZeroExtend { .. }
+				| IntInstruction::LoadHalfZeroExtend { .. }
+				| IntInstruction::LoadWordZeroExtend { .. } => Some(event::LOAD),
+				IntInstruction::StoreByte { .. }
+				| IntInstruction::StoreHalf { .. }
+				| IntInstruction::StoreWord { .. }
+				| IntInstruction::StoreDoubleWord { .. } => Some(event::STORE),
+				IntInstruction::BranchEqual { .. }
+				| IntInstruction::BranchNotEqual { .. }
+				| IntInstruction::BranchLessThan { .. }
+				| IntInstruction::BranchGreaterEqual { .. }
+				| IntInstruction::BranchLessThanUnsigned { .. }
+				| IntInstruction::BranchGreaterEqualUnsigned { .. } => Some(event::BRANCH),
+				_ => None,
+			},
+			Instruction::FloatExtension(FloatInstruction::LoadWord { .. })
+			| Instruction::DoubleExtension(DoubleInstruction::LoadDoubleWord { .. })
+			| Instruction::HalfExtension(HalfInstruction::LoadHalf { .. })
+			| Instruction::VectorExtension(VectorInstruction::LoadUnitStride { .. }) => Some(event::LOAD),
+			Instruction::FloatExtension(FloatInstruction::StoreWord { .. })
+			| Instruction::DoubleExtension(DoubleInstruction::StoreDoubleWord { .. })
+			| Instruction::HalfExtension(HalfInstruction::StoreHalf { .. })
+			| Instruction::VectorExtension(VectorInstruction::StoreUnitStride { .. }) => Some(event::STORE),
+			// LR is a load and SC is a store, AMOs are both
+			Instruction::AtomicExtension(insn) => match insn {
+				AtomicInstruction::LoadReservedWord { .. } | AtomicInstruction::LoadReservedDoubleWord { .. } => {
+					Some(event::LOAD)
+				}
+				AtomicInstruction::StoreConditionalWord { .. }
+				| AtomicInstruction::StoreConditionalDoubleWord { .. } => Some(event::STORE),
+				_ => {
+					events.add(event::LOAD);
+					Some(event::STORE)
+				}
+			},
+			_ => None,
+		};
+		if let Some(access) = access {
+			events.add(access);
+		}
+		events
+	}
+}
+
+/// the programmable counters, each counts retired instructions matching the event its mhpmevent selects
+#[derive(Debug, Clone)]
+pub struct HpmCounters {
+	event: [u64; HPM_COUNTERS],
+	counter: [u64; HPM_COUNTERS],
+}
+
+impl HpmCounters {
+	pub fn new() -> Self {
+		Self {
+			event: [event::NONE; HPM_COUNTERS],
+			counter: [0; HPM_COUNTERS],
+		}
+	}
+
+	pub fn reset(&mut self) {
+		*self = Self::new();
+	}
+
+	/// idx is 0 for mhpmevent3
+	pub fn read_event(&self, idx: usize) -> u64 {
+		self.event[idx]
+	}
+
+	/// events we don't count read back as zero
+	pub fn write_event(&mut self, idx: usize, val: u64) {
+		self.event[idx] = if val <= event::LAST { val } else { event::NONE };
+	}
+
+	/// idx is 0 for mhpmcounter3
+	pub fn read_counter(&self, idx: usize) -> u64 {
+		self.counter[idx]
+	}
+
+	pub fn write_counter(&mut self, idx: usize, val: u64) {
+		self.counter[idx] = val;
+	}
+
+	pub fn retire(&mut self, events: HpmEvents) {
+		for (counter, &event) in self.counter.iter_mut().zip(&self.event) {
+			if events.has(event) {
+				*counter = counter.wrapping_add(1);
+			}
+		}
+	}
+}
+
+impl Snapshot for HpmCounters {
+	fn save(&self, snapshot: &mut SnapshotWriter) {
+		snapshot.u64s(&self.event);
+		snapshot.u64s(&self.counter);
+	}
+
+	fn restore(&mut self, snapshot: &mut SnapshotReader) -> Result<(), String> {
+		snapshot.u64s(&mut self.event)?;
+		snapshot.u64s(&mut self.counter)
+	}
+}
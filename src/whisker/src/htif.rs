@@ -0,0 +1,343 @@
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::fs::OpenOptions;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::rc::Rc;
+
+use tracing::warn;
+
+use crate::cpu::WhiskerCpu;
+use crate::mem::MemoryHook;
+use crate::semihosting::{errno, ConsoleInput, GuestMemory, HostFile, EBADF, EINVAL, ENOSYS};
+
+// commands hold the device in the top byte, the command in the next one and a 48 bit payload
+const DEV_SYSCALL: u8 = 0;
+const DEV_CONSOLE: u8 = 1;
+const CONSOLE_GETCHAR: u8 = 0;
+const CONSOLE_PUTCHAR: u8 = 1;
+const PAYLOAD_MASK: u64 = (1 << 48) - 1;
+
+// the proxied syscalls use linux's numbers, like riscv-pk's frontend
+const SYS_OPENAT: u64 = 56;
+const SYS_CLOSE: u64 = 57;
+const SYS_LSEEK: u64 = 62;
+const SYS_READ: u64 = 63;
+const SYS_WRITE: u64 = 64;
+const SYS_PREAD: u64 = 67;
+const SYS_PWRITE: u64 = 68;
+const SYS_EXIT: u64 = 93;
+const SYS_GETMAINVARS: u64 = 2011;
+
+const O_ACCMODE: u64 = 0x3;
+const O_CREAT: u64 = 0x40;
+const O_EXCL: u64 = 0x80;
+const O_TRUNC: u64 = 0x200;
+const O_APPEND: u64 = 0x400;
+const AT_FDCWD: i64 = -100;
+
+// htif addresses are never translated
+const PHYS: GuestMemory = GuestMemory::Physical;
+
+const ENOMEM: i32 = 12;
+const ESPIPE: i32 = 29;
+
+/// the host side of the host-target interface spike and riscv-pk use, riscv-tests report their result through it
+/// the guest writes a command to tohost and gets responses in fromhost, both are physical addresses
+/// proxied syscalls reach any file whisker can, so only run trusted programs with it
+pub struct Htif {
+	tohost: u64,
+	/// without it commands are still carried out, the guest just never hears back
+	fromhost: Option<u64>,
+	/// set by a memory hook when the guest writes tohost
+	written: Rc<Cell<bool>>,
+	/// responses waiting for the guest to empty fromhost
+	responses: VecDeque<u64>,
+	/// the guest asked for a console character and hasn't got one yet
+	reading: bool,
+	input: ConsoleInput,
+	// the index is the guest's file descriptor
+	files: Vec<Option<HostFile>>,
+	/// what getmainvars hands the guest as argv
+	args: Vec<String>,
+	console: Box<dyn Write>,
+}
+
+impl std::fmt::Debug for Htif {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("Htif")
+			.field("tohost", &self.tohost)
+			.field("fromhost", &self.fromhost)
+			.field("responses", &self.responses)
+			.field("reading", &self.reading)
+			.field("files", &self.files)
+			.field("args", &self.args)
+			.finish_non_exhaustive()
+	}
+}
+
+impl Htif {
+	/// console output goes to console, which is stdout unless something else owns it
+	pub fn new(
+		tohost: u64,
+		fromhost: Option<u64>,
+		args: Vec<String>,
+		console: Box<dyn Write>,
+		input: ConsoleInput,
+	) -> Self {
+		Self {
+			tohost,
+			fromhost,
+			written: Rc::default(),
+			responses: VecDeque::new(),
+			reading: false,
+			input,
+			files: vec![Some(HostFile::Stdin), Some(HostFile::Stdout), Some(HostFile::Stderr)],
+			args,
+			console,
+		}
+	}
+
+	/// whether the guest sent a command or is waiting on a response
+	pub fn busy(&self) -> bool {
+		self.written.get() || self.reading || !self.responses.is_empty()
+	}
+
+	fn respond(&mut self, dev: u8, cmd: u8, payload: u64) {
+		if self.fromhost.is_some() {
+			self.responses
+				.push_back(u64::from(dev) << 56 | u64::from(cmd) << 48 | payload & PAYLOAD_MASK);
+		}
+	}
+
+	fn command(&mut self, cpu: &mut WhiskerCpu, command: u64) {
+		let dev = (command >> 56) as u8;
+		let cmd = (command >> 48) as u8;
+		let payload = command & PAYLOAD_MASK;
+		match (dev, cmd) {
+			// an odd payload is the exit code shifted left by one, riscv-tests put the failing test's number there
+			(DEV_SYSCALL, _) if payload & 1 != 0 => cpu.power_off_line.request((payload >> 1) as i32),
+			(DEV_SYSCALL, _) => {
+				self.syscall(cpu, payload);
+				self.respond(dev, cmd, 1);
+			}
+			(DEV_CONSOLE, CONSOLE_PUTCHAR) => {
+				if let Err(err) = self.write_console(&[payload as u8]) {
+					warn!("could not write the guest's htif console output: {err}");
+				}
+			}
+			(DEV_CONSOLE, CONSOLE_GETCHAR) => self.reading = true,
+			_ => warn!("the guest sent an unsupported htif command {command:#x}"),
+		}
+	}
+
+	fn poll_console(&mut self) {
+		if !self.reading {
+			return;
+		}
+		if let Some(byte) = self.input.try_read() {
+			self.reading = false;
+			self.respond(DEV_CONSOLE, CONSOLE_GETCHAR, 0x100 | u64::from(byte));
+		}
+	}
+
+	fn write_console(&mut self, bytes: &[u8]) -> io::Result<()> {
+		self.console.write_all(bytes)?;
+		self.console.flush()
+	}
+
+	/// magic_mem holds the syscall number and its arguments, the result is written over the number
+	fn syscall(&mut self, cpu: &mut WhiskerCpu, magic_mem: u64) {
+		let Ok([num, a0, a1, a2, a3, ..]) = PHYS.words::<8>(cpu, magic_mem) else {
+			warn!("the guest's htif syscall block at {magic_mem:#x} isn't in memory");
+			return;
+		};
+		let result = match num {
+			SYS_OPENAT => self.openat(cpu, a0 as i64, a1, a2, a3),
+			SYS_CLOSE => self.close(a0),
+			SYS_LSEEK => self.lseek(a0, a1 as i64, a2),
+			SYS_READ => self.read(cpu, a0, a1, a2, None),
+			SYS_WRITE => self.write(cpu, a0, a1, a2, None),
+			SYS_PREAD => self.read(cpu, a0, a1, a2, Some(a3)),
+			SYS_PWRITE => self.write(cpu, a0, a1, a2, Some(a3)),
+			SYS_EXIT => {
+				cpu.power_off_line.request(a0 as i32);
+				Ok(0)
+			}
+			SYS_GETMAINVARS => self.getmainvars(cpu, a0, a1),
+			_ => {
+				warn!("the guest made an unsupported htif syscall {num}");
+				Err(ENOSYS)
+			}
+		};
+		let result = result.unwrap_or_else(|errno| -i64::from(errno));
+		// the block was just read, so this can't fail
+		let _ = PHYS.write(cpu, magic_mem, &result.to_le_bytes());
+	}
+
+	fn file(&mut self, fd: u64) -> Result<&mut HostFile, i32> {
+		self.files.get_mut(fd as usize).and_then(Option::as_mut).ok_or(EBADF)
+	}
+
+	/// the mode is ignored, new files get whisker's default permissions
+	fn openat(&mut self, cpu: &mut WhiskerCpu, dirfd: i64, name: u64, len: u64, flags: u64) -> Result<i64, i32> {
+		// the length counts the terminating nul
+		let path = PHYS.path(cpu, name, len)?;
+		// paths relative to a directory the guest opened aren't supported
+		if dirfd != AT_FDCWD && !path.starts_with('/') {
+			return Err(ENOSYS);
+		}
+
+		let mut options = OpenOptions::new();
+		match flags & O_ACCMODE {
+			0 => options.read(true),
+			1 => options.write(true),
+			2 => options.read(true).write(true),
+			_ => return Err(EINVAL),
+		};
+		options.append(flags & O_APPEND != 0).truncate(flags & O_TRUNC != 0);
+		if flags & O_CREAT != 0 {
+			if flags & O_EXCL != 0 {
+				options.create_new(true);
+			} else {
+				options.create(true);
+			}
+		}
+		let file = HostFile::File(options.open(path).map_err(errno)?);
+
+		let fd = match self.files.iter().position(Option::is_none) {
+			Some(fd) => fd,
+			None => {
+				self.files.push(None);
+				self.files.len() - 1
+			}
+		};
+		self.files[fd] = Some(file);
+		Ok(fd as i64)
+	}
+
+	fn close(&mut self, fd: u64) -> Result<i64, i32> {
+		self.file(fd)?;
+		self.files[fd as usize] = None;
+		Ok(0)
+	}
+
+	fn lseek(&mut self, fd: u64, offset: i64, whence: u64) -> Result<i64, i32> {
+		let pos = match whence {
+			0 => SeekFrom::Start(offset as u64),
+			1 => SeekFrom::Current(offset),
+			2 => SeekFrom::End(offset),
+			_ => return Err(EINVAL),
+		};
+		match self.file(fd)? {
+			HostFile::File(file) => Ok(file.seek(pos).map_err(errno)? as i64),
+			_ => Err(ESPIPE),
+		}
+	}
+
+	/// reads at offset without moving the file position when it's given, like pread
+	fn read(&mut self, cpu: &mut WhiskerCpu, fd: u64, buf: u64, len: u64, offset: Option<u64>) -> Result<i64, i32> {
+		let read = match (self.file(fd)?, offset) {
+			(HostFile::File(file), None) => PHYS.copy_in(cpu, buf, len, |data| file.read(data).map_err(errno))?,
+			(HostFile::File(file), Some(offset)) => at_offset(file, offset, |file| {
+				PHYS.copy_in(cpu, buf, len, |data| file.read(data).map_err(errno))
+			})?,
+			(HostFile::Stdin, None) => PHYS.copy_in(cpu, buf, len, |data| Ok(self.input.read(data)))?,
+			(HostFile::Stdin, Some(_)) => return Err(ESPIPE),
+			(HostFile::Stdout | HostFile::Stderr, _) => return Err(EBADF),
+		};
+		Ok(read as i64)
+	}
+
+	/// writes at offset without moving the file position when it's given, like pwrite
+	fn write(&mut self, cpu: &mut WhiskerCpu, fd: u64, buf: u64, len: u64, offset: Option<u64>) -> Result<i64, i32> {
+		match (self.file(fd)?, offset) {
+			(HostFile::File(file), None) => PHYS.copy_out(cpu, buf, len, |data| file.write_all(data).map_err(errno))?,
+			(HostFile::File(file), Some(offset)) => at_offset(file, offset, |file| {
+				PHYS.copy_out(cpu, buf, len, |data| file.write_all(data).map_err(errno))
+			})?,
+			(HostFile::Stdout, None) => {
+				PHYS.copy_out(cpu, buf, len, |data| self.write_console(data).map_err(errno))?;
+			}
+			(HostFile::Stderr, None) => {
+				PHYS.copy_out(cpu, buf, len, |data| io::stderr().write_all(data).map_err(errno))?;
+			}
+			(HostFile::Stdout | HostFile::Stderr, Some(_)) => return Err(ESPIPE),
+			(HostFile::Stdin, _) => return Err(EBADF),
+		}
+		Ok(len as i64)
+	}
+
+	/// fills buf with argc, the argv pointers, an empty envp and then the strings, like riscv-pk expects
+	fn getmainvars(&mut self, cpu: &mut WhiskerCpu, buf: u64, limit: u64) -> Result<i64, i32> {
+		let words = self.args.len() as u64 + 3;
+		let mut block = Vec::new();
+		block.extend_from_slice(&(self.args.len() as u64).to_le_bytes());
+		let mut string_addr = buf + words * 8;
+		for arg in &self.args {
+			block.extend_from_slice(&string_addr.to_le_bytes());
+			string_addr += arg.len() as u64 + 1;
+		}
+		// the null ending argv and the one ending envp
+		block.extend_from_slice(&[0; 16]);
+		for arg in &self.args {
+			block.extend_from_slice(arg.as_bytes());
+			block.push(0);
+		}
+		if block.len() as u64 > limit {
+			return Err(ENOMEM);
+		}
+		PHYS.write(cpu, buf, &block)?;
+		Ok(0)
+	}
+}
+
+impl WhiskerCpu {
+	/// starts watching tohost, commands are carried out after the instruction that wrote it
+	pub fn attach_htif(&mut self, htif: Htif) {
+		let written = htif.written.clone();
+		self.mem
+			.add_hook(MemoryHook::new(htif.tohost, 8).on_write(move |_, _, _| written.set(true)));
+		self.htif = Some(htif);
+	}
+
+	/// carries out a command the guest wrote to tohost and hands it whatever response fits in fromhost
+	pub fn service_htif(&mut self) {
+		let Some(mut htif) = self.htif.take() else {
+			return;
+		};
+		if htif.written.take() {
+			// riscv-tests write the two halves separately, so a zero is a half written command and left alone
+			if let Some([command]) = PHYS
+				.words::<1>(self, htif.tohost)
+				.ok()
+				.filter(|&[command]| command != 0)
+			{
+				// emptying tohost tells the guest the command was taken
+				let _ = self.mem.debug_write_slice(htif.tohost, &[0; 8]);
+				htif.command(self, command);
+			}
+		}
+		htif.poll_console();
+		if let Some(fromhost) = htif.fromhost {
+			// the guest empties fromhost once it has taken a response
+			if !htif.responses.is_empty() && PHYS.words::<1>(self, fromhost) == Ok([0]) {
+				let response = htif.responses.pop_front().unwrap();
+				let _ = self.mem.debug_write_slice(fromhost, &response.to_le_bytes());
+			}
+		}
+		self.htif = Some(htif);
+	}
+}
+
+/// runs op with the file at offset and puts the position back afterwards
+fn at_offset<T>(
+	file: &mut std::fs::File,
+	offset: u64,
+	op: impl FnOnce(&mut std::fs::File) -> Result<T, i32>,
+) -> Result<T, i32> {
+	let pos = file.stream_position().map_err(errno)?;
+	file.seek(SeekFrom::Start(offset)).map_err(errno)?;
+	let result = op(file);
+	file.seek(SeekFrom::Start(pos)).map_err(errno)?;
+	result
+}
@@ -1,17 +1,26 @@
 pub mod atomic;
+pub mod bitmanip;
 pub mod compressed;
 pub mod csr;
+pub mod double;
 pub mod float;
+pub mod half;
 pub mod int;
 pub mod multiply;
+pub mod vector;
 
 use atomic::AtomicInstruction;
+use bitmanip::BitmanipInstruction;
 use compressed::CompressedInstruction;
+use double::DoubleInstruction;
 use float::FloatInstruction;
+use half::HalfInstruction;
 use int::IntInstruction;
 use multiply::MultiplyInstruction;
+use vector::VectorInstruction;
 
 use crate::insn::csr::CSRInstruction;
+use crate::mmu::AccessType;
 use crate::ty::{SupportedExtensions, TrapIdx};
 use crate::util::extract_bits_16;
 use crate::{insn16, insn32, WhiskerCpu};
@@ -20,22 +29,27 @@ use crate::{insn16, insn32, WhiskerCpu};
 pub enum Instruction {
 	IntExtension(IntInstruction),
 	FloatExtension(FloatInstruction),
+	DoubleExtension(DoubleInstruction),
+	HalfExtension(HalfInstruction),
 	Csr(CSRInstruction),
 	CompressedExtension(CompressedInstruction),
 	AtomicExtension(AtomicInstruction),
 	MultiplyInstruction(MultiplyInstruction),
+	BitmanipExtension(BitmanipInstruction),
+	VectorExtension(VectorInstruction),
 }
 
 impl Instruction {
 	/// tries to fetch an instruction, or returns Err if a trap happened during the fetch
-	pub fn fetch_instruction(cpu: &mut WhiskerCpu) -> Result<(Instruction, u64), ()> {
+	/// returns the instruction with its raw bits and size in bytes
+	pub fn fetch_instruction(cpu: &mut WhiskerCpu) -> Result<(Instruction, u32, u64), ()> {
 		let pc = cpu.pc;
 		let support_compressed = cpu.supported_extensions.has(SupportedExtensions::COMPRESSED);
 
-		let parcel1 = match cpu.mem.read_u16(pc) {
+		let parcel1 = match cpu.read_virt_u16(pc, AccessType::Fetch) {
 			Ok(parcel1) => parcel1,
-			Err(addr) => {
-				cpu.request_trap(TrapIdx::INSTRUCTION_PAGE_FAULT, addr);
+			Err((cause, addr)) => {
+				cpu.request_trap(cause, addr);
 				return Err(());
 			}
 		};
@@ -43,37 +57,28 @@ impl Instruction {
 		if extract_bits_16(parcel1, 0, 1) != 0b11 {
 			if support_compressed {
 				let insn = insn16::parse(cpu, parcel1)?;
-				Ok((insn.into(), 2))
+				Ok((insn.into(), u32::from(parcel1), 2))
 			} else {
-				cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, pc);
+				cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, u64::from(parcel1));
 				Err(())
 			}
 		} else if extract_bits_16(parcel1, 2, 4) != 0b111 {
-			let full_parcel = match cpu.mem.read_u32(pc) {
+			let full_parcel = match cpu.read_virt_u32(pc, AccessType::Fetch) {
 				Ok(p) => p,
-				Err(addr) => {
-					cpu.request_trap(TrapIdx::INSTRUCTION_PAGE_FAULT, addr);
+				Err((cause, addr)) => {
+					cpu.request_trap(cause, addr);
 					return Err(());
 				}
 			};
 			let insn = insn32::parse(cpu, full_parcel)?;
-			Ok((insn, 4))
-		} else if extract_bits_16(parcel1, 0, 5) == 0b011111 {
-			if support_compressed {
-				todo!("implement 48bit instruction")
-			} else {
-				cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, pc);
-				Err(())
-			}
-		} else if extract_bits_16(parcel1, 0, 6) == 0b0111111 {
-			if support_compressed {
-				todo!("implement 64bit instruction")
-			} else {
-				cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, pc);
-				Err(())
-			}
+			Ok((insn, full_parcel, 4))
 		} else {
-			cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, pc);
+			// 48 bit, 64 bit and longer formats have no standard instructions yet
+			// mtval only holds the first ILEN (32) bits, or just the first parcel if the rest can't be read
+			let bits = cpu
+				.read_virt_u32(pc, AccessType::Fetch)
+				.map_or(u64::from(parcel1), u64::from);
+			cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, bits);
 			Err(())
 		}
 	}
@@ -157,6 +157,172 @@ pub enum AtomicInstruction {
 		_aq: bool,
 		_rl: bool,
 	},
+
+	// Zacas, dst holds the value to compare against and gets the old value, src2 holds the value to swap in
+	CompareAndSwapByte {
+		src1: GPRegisterIndex,
+		src2: GPRegisterIndex,
+		dst: GPRegisterIndex,
+		_aq: bool,
+		_rl: bool,
+	},
+	CompareAndSwapHalf {
+		src1: GPRegisterIndex,
+		src2: GPRegisterIndex,
+		dst: GPRegisterIndex,
+		_aq: bool,
+		_rl: bool,
+	},
+	CompareAndSwapWord {
+		src1: GPRegisterIndex,
+		src2: GPRegisterIndex,
+		dst: GPRegisterIndex,
+		_aq: bool,
+		_rl: bool,
+	},
+	CompareAndSwapDoubleWord {
+		src1: GPRegisterIndex,
+		src2: GPRegisterIndex,
+		dst: GPRegisterIndex,
+		_aq: bool,
+		_rl: bool,
+	},
+	// dst and src2 are the first of an even-odd register pair, the low half is in the even register
+	CompareAndSwapQuadWord {
+		src1: GPRegisterIndex,
+		src2: GPRegisterIndex,
+		dst: GPRegisterIndex,
+		_aq: bool,
+		_rl: bool,
+	},
+
+	// Zabha, there's no LR or SC for bytes and halfwords
+	SwapByte {
+		src1: GPRegisterIndex,
+		src2: GPRegisterIndex,
+		dst: GPRegisterIndex,
+		_aq: bool,
+		_rl: bool,
+	},
+	AddByte {
+		src1: GPRegisterIndex,
+		src2: GPRegisterIndex,
+		dst: GPRegisterIndex,
+		_aq: bool,
+		_rl: bool,
+	},
+	XorByte {
+		src1: GPRegisterIndex,
+		src2: GPRegisterIndex,
+		dst: GPRegisterIndex,
+		_aq: bool,
+		_rl: bool,
+	},
+	AndByte {
+		src1: GPRegisterIndex,
+		src2: GPRegisterIndex,
+		dst: GPRegisterIndex,
+		_aq: bool,
+		_rl: bool,
+	},
+	OrByte {
+		src1: GPRegisterIndex,
+		src2: GPRegisterIndex,
+		dst: GPRegisterIndex,
+		_aq: bool,
+		_rl: bool,
+	},
+	MinByte {
+		src1: GPRegisterIndex,
+		src2: GPRegisterIndex,
+		dst: GPRegisterIndex,
+		_aq: bool,
+		_rl: bool,
+	},
+	MaxByte {
+		src1: GPRegisterIndex,
+		src2: GPRegisterIndex,
+		dst: GPRegisterIndex,
+		_aq: bool,
+		_rl: bool,
+	},
+	MinUnsignedByte {
+		src1: GPRegisterIndex,
+		src2: GPRegisterIndex,
+		dst: GPRegisterIndex,
+		_aq: bool,
+		_rl: bool,
+	},
+	MaxUnsignedByte {
+		src1: GPRegisterIndex,
+		src2: GPRegisterIndex,
+		dst: GPRegisterIndex,
+		_aq: bool,
+		_rl: bool,
+	},
+	SwapHalf {
+		src1: GPRegisterIndex,
+		src2: GPRegisterIndex,
+		dst: GPRegisterIndex,
+		_aq: bool,
+		_rl: bool,
+	},
+	AddHalf {
+		src1: GPRegisterIndex,
+		src2: GPRegisterIndex,
+		dst: GPRegisterIndex,
+		_aq: bool,
+		_rl: bool,
+	},
+	XorHalf {
+		src1: GPRegisterIndex,
+		src2: GPRegisterIndex,
+		dst: GPRegisterIndex,
+		_aq: bool,
+		_rl: bool,
+	},
+	AndHalf {
+		src1: GPRegisterIndex,
+		src2: GPRegisterIndex,
+		dst: GPRegisterIndex,
+		_aq: bool,
+		_rl: bool,
+	},
+	OrHalf {
+		src1: GPRegisterIndex,
+		src2: GPRegisterIndex,
+		dst: GPRegisterIndex,
+		_aq: bool,
+		_rl: bool,
+	},
+	MinHalf {
+		src1: GPRegisterIndex,
+		src2: GPRegisterIndex,
+		dst: GPRegisterIndex,
+		_aq: bool,
+		_rl: bool,
+	},
+	MaxHalf {
+		src1: GPRegisterIndex,
+		src2: GPRegisterIndex,
+		dst: GPRegisterIndex,
+		_aq: bool,
+		_rl: bool,
+	},
+	MinUnsignedHalf {
+		src1: GPRegisterIndex,
+		src2: GPRegisterIndex,
+		dst: GPRegisterIndex,
+		_aq: bool,
+		_rl: bool,
+	},
+	MaxUnsignedHalf {
+		src1: GPRegisterIndex,
+		src2: GPRegisterIndex,
+		dst: GPRegisterIndex,
+		_aq: bool,
+		_rl: bool,
+	},
 }
 
 impl Into<Instruction> for AtomicInstruction {
@@ -0,0 +1,238 @@
+use crate::ty::GPRegisterIndex;
+
+use super::Instruction;
+
+#[derive(Debug)]
+pub enum BitmanipInstruction {
+	// ADD.UW: rhs + zext32(lhs)
+	AddUnsignedWord {
+		dst: GPRegisterIndex,
+		lhs: GPRegisterIndex,
+		rhs: GPRegisterIndex,
+	},
+	// SH1ADD/SH2ADD/SH3ADD: rhs + (lhs << shift)
+	ShiftAdd {
+		dst: GPRegisterIndex,
+		lhs: GPRegisterIndex,
+		rhs: GPRegisterIndex,
+		shift: u32,
+	},
+	// SH1ADD.UW/SH2ADD.UW/SH3ADD.UW: rhs + (zext32(lhs) << shift)
+	ShiftAddUnsignedWord {
+		dst: GPRegisterIndex,
+		lhs: GPRegisterIndex,
+		rhs: GPRegisterIndex,
+		shift: u32,
+	},
+	// SLLI.UW: zext32(lhs) << shift_amt
+	ShiftLeftLogicalImmediateUnsignedWord {
+		dst: GPRegisterIndex,
+		lhs: GPRegisterIndex,
+		shift_amt: u32,
+	},
+
+	// ANDN: lhs & !rhs
+	AndNot {
+		dst: GPRegisterIndex,
+		lhs: GPRegisterIndex,
+		rhs: GPRegisterIndex,
+	},
+	// ORN: lhs | !rhs
+	OrNot {
+		dst: GPRegisterIndex,
+		lhs: GPRegisterIndex,
+		rhs: GPRegisterIndex,
+	},
+	// XNOR: !(lhs ^ rhs)
+	XorNot {
+		dst: GPRegisterIndex,
+		lhs: GPRegisterIndex,
+		rhs: GPRegisterIndex,
+	},
+
+	// CLZ
+	CountLeadingZeros {
+		dst: GPRegisterIndex,
+		src: GPRegisterIndex,
+	},
+	// CLZW, counts in the low 32 bits
+	CountLeadingZerosWord {
+		dst: GPRegisterIndex,
+		src: GPRegisterIndex,
+	},
+	// CTZ
+	CountTrailingZeros {
+		dst: GPRegisterIndex,
+		src: GPRegisterIndex,
+	},
+	// CTZW, counts in the low 32 bits
+	CountTrailingZerosWord {
+		dst: GPRegisterIndex,
+		src: GPRegisterIndex,
+	},
+	// CPOP: number of set bits
+	CountPopulation {
+		dst: GPRegisterIndex,
+		src: GPRegisterIndex,
+	},
+	// CPOPW, counts in the low 32 bits
+	CountPopulationWord {
+		dst: GPRegisterIndex,
+		src: GPRegisterIndex,
+	},
+
+	Min {
+		dst: GPRegisterIndex,
+		lhs: GPRegisterIndex,
+		rhs: GPRegisterIndex,
+	},
+	MinUnsigned {
+		dst: GPRegisterIndex,
+		lhs: GPRegisterIndex,
+		rhs: GPRegisterIndex,
+	},
+	Max {
+		dst: GPRegisterIndex,
+		lhs: GPRegisterIndex,
+		rhs: GPRegisterIndex,
+	},
+	MaxUnsigned {
+		dst: GPRegisterIndex,
+		lhs: GPRegisterIndex,
+		rhs: GPRegisterIndex,
+	},
+
+	// SEXT.B
+	SignExtendByte {
+		dst: GPRegisterIndex,
+		src: GPRegisterIndex,
+	},
+	// SEXT.H
+	SignExtendHalf {
+		dst: GPRegisterIndex,
+		src: GPRegisterIndex,
+	},
+	// ZEXT.H
+	ZeroExtendHalf {
+		dst: GPRegisterIndex,
+		src: GPRegisterIndex,
+	},
+
+	RotateLeft {
+		dst: GPRegisterIndex,
+		lhs: GPRegisterIndex,
+		rhs: GPRegisterIndex,
+	},
+	RotateRight {
+		dst: GPRegisterIndex,
+		lhs: GPRegisterIndex,
+		rhs: GPRegisterIndex,
+	},
+	RotateLeftWord {
+		dst: GPRegisterIndex,
+		lhs: GPRegisterIndex,
+		rhs: GPRegisterIndex,
+	},
+	RotateRightWord {
+		dst: GPRegisterIndex,
+		lhs: GPRegisterIndex,
+		rhs: GPRegisterIndex,
+	},
+	// RORI
+	RotateRightImmediate {
+		dst: GPRegisterIndex,
+		lhs: GPRegisterIndex,
+		shift_amt: u32,
+	},
+	// RORIW
+	RotateRightImmediateWord {
+		dst: GPRegisterIndex,
+		lhs: GPRegisterIndex,
+		shift_amt: u32,
+	},
+
+	// ORC.B: each byte becomes 0xFF if any of its bits are set, 0 otherwise
+	OrCombineByte {
+		dst: GPRegisterIndex,
+		src: GPRegisterIndex,
+	},
+	// REV8
+	ReverseBytes {
+		dst: GPRegisterIndex,
+		src: GPRegisterIndex,
+	},
+
+	// CLMUL: low 64 bits of the carry-less product
+	CarrylessMul {
+		dst: GPRegisterIndex,
+		lhs: GPRegisterIndex,
+		rhs: GPRegisterIndex,
+	},
+	// CLMULR: bits 126..63 of the carry-less product
+	CarrylessMulReversed {
+		dst: GPRegisterIndex,
+		lhs: GPRegisterIndex,
+		rhs: GPRegisterIndex,
+	},
+	// CLMULH: high 64 bits of the carry-less product
+	CarrylessMulHigh {
+		dst: GPRegisterIndex,
+		lhs: GPRegisterIndex,
+		rhs: GPRegisterIndex,
+	},
+
+	// BCLR: lhs with bit (rhs & 63) cleared
+	BitClear {
+		dst: GPRegisterIndex,
+		lhs: GPRegisterIndex,
+		rhs: GPRegisterIndex,
+	},
+	// BEXT: bit (rhs & 63) of lhs
+	BitExtract {
+		dst: GPRegisterIndex,
+		lhs: GPRegisterIndex,
+		rhs: GPRegisterIndex,
+	},
+	// BINV: lhs with bit (rhs & 63) flipped
+	BitInvert {
+		dst: GPRegisterIndex,
+		lhs: GPRegisterIndex,
+		rhs: GPRegisterIndex,
+	},
+	// BSET: lhs with bit (rhs & 63) set
+	BitSet {
+		dst: GPRegisterIndex,
+		lhs: GPRegisterIndex,
+		rhs: GPRegisterIndex,
+	},
+	// BCLRI
+	BitClearImmediate {
+		dst: GPRegisterIndex,
+		lhs: GPRegisterIndex,
+		shift_amt: u32,
+	},
+	// BEXTI
+	BitExtractImmediate {
+		dst: GPRegisterIndex,
+		lhs: GPRegisterIndex,
+		shift_amt: u32,
+	},
+	// BINVI
+	BitInvertImmediate {
+		dst: GPRegisterIndex,
+		lhs: GPRegisterIndex,
+		shift_amt: u32,
+	},
+	// BSETI
+	BitSetImmediate {
+		dst: GPRegisterIndex,
+		lhs: GPRegisterIndex,
+		shift_amt: u32,
+	},
+}
+
+impl From<BitmanipInstruction> for Instruction {
+	fn from(insn: BitmanipInstruction) -> Self {
+		Instruction::BitmanipExtension(insn)
+	}
+}
@@ -3,6 +3,8 @@ use super::Instruction;
 #[derive(Debug)]
 pub enum CompressedInstruction {
 	Nop,
+	// encodings set aside for hints, none are implemented so they have no architectural effect
+	Hint,
 }
 
 impl Into<Instruction> for CompressedInstruction {
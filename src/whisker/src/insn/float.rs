@@ -82,6 +82,139 @@ pub enum FloatInstruction {
 		add: FPRegisterIndex,
 		rm: RoundingMode,
 	},
+	// (mul_lhs * mul_rhs) - sub
+	MulSub {
+		dst: FPRegisterIndex,
+		mul_lhs: FPRegisterIndex,
+		mul_rhs: FPRegisterIndex,
+		sub: FPRegisterIndex,
+		rm: RoundingMode,
+	},
+	// -(mul_lhs * mul_rhs) + sub
+	NegMulSub {
+		dst: FPRegisterIndex,
+		mul_lhs: FPRegisterIndex,
+		mul_rhs: FPRegisterIndex,
+		sub: FPRegisterIndex,
+		rm: RoundingMode,
+	},
+	// -(mul_lhs * mul_rhs) - add
+	NegMulAdd {
+		dst: FPRegisterIndex,
+		mul_lhs: FPRegisterIndex,
+		mul_rhs: FPRegisterIndex,
+		add: FPRegisterIndex,
+		rm: RoundingMode,
+	},
+	// FSGNJ.S: magnitude of lhs with the sign of rhs
+	SignInject {
+		dst: FPRegisterIndex,
+		lhs: FPRegisterIndex,
+		rhs: FPRegisterIndex,
+	},
+	// FSGNJN.S: magnitude of lhs with the opposite sign of rhs
+	SignInjectNegate {
+		dst: FPRegisterIndex,
+		lhs: FPRegisterIndex,
+		rhs: FPRegisterIndex,
+	},
+	// FSGNJX.S: magnitude of lhs with the xor of both signs
+	SignInjectXor {
+		dst: FPRegisterIndex,
+		lhs: FPRegisterIndex,
+		rhs: FPRegisterIndex,
+	},
+
+	// FCLASS.S
+	Classify {
+		dst: GPRegisterIndex,
+		src: FPRegisterIndex,
+	},
+	// FMV.X.W, moves the raw bits
+	MoveToInt {
+		dst: GPRegisterIndex,
+		src: FPRegisterIndex,
+	},
+	// FMV.W.X, moves the raw bits
+	MoveFromInt {
+		dst: FPRegisterIndex,
+		src: GPRegisterIndex,
+	},
+
+	// FCVT.W.S
+	ConvertToWord {
+		dst: GPRegisterIndex,
+		src: FPRegisterIndex,
+		rm: RoundingMode,
+	},
+	// FCVT.WU.S
+	ConvertToUnsignedWord {
+		dst: GPRegisterIndex,
+		src: FPRegisterIndex,
+		rm: RoundingMode,
+	},
+	// FCVT.L.S
+	ConvertToDoubleWord {
+		dst: GPRegisterIndex,
+		src: FPRegisterIndex,
+		rm: RoundingMode,
+	},
+	// FCVT.LU.S
+	ConvertToUnsignedDoubleWord {
+		dst: GPRegisterIndex,
+		src: FPRegisterIndex,
+		rm: RoundingMode,
+	},
+	// FCVT.S.W
+	ConvertFromWord {
+		dst: FPRegisterIndex,
+		src: GPRegisterIndex,
+		rm: RoundingMode,
+	},
+	// FCVT.S.WU
+	ConvertFromUnsignedWord {
+		dst: FPRegisterIndex,
+		src: GPRegisterIndex,
+		rm: RoundingMode,
+	},
+	// FCVT.S.L
+	ConvertFromDoubleWord {
+		dst: FPRegisterIndex,
+		src: GPRegisterIndex,
+		rm: RoundingMode,
+	},
+	// FCVT.S.LU
+	ConvertFromUnsignedDoubleWord {
+		dst: FPRegisterIndex,
+		src: GPRegisterIndex,
+		rm: RoundingMode,
+	},
+}
+
+impl FloatInstruction {
+	/// the rounding mode encoded in the instruction, if it has one
+	pub fn rounding_mode(&self) -> Option<RoundingMode> {
+		match self {
+			Self::Add { rm, .. }
+			| Self::Sub { rm, .. }
+			| Self::Mul { rm, .. }
+			| Self::Div { rm, .. }
+			| Self::Sqrt { rm, .. }
+			| Self::MulAdd { rm, .. }
+			| Self::MulSub { rm, .. }
+			| Self::NegMulSub { rm, .. }
+			| Self::NegMulAdd { rm, .. }
+			| Self::ConvertToWord { rm, .. }
+			| Self::ConvertToUnsignedWord { rm, .. }
+			| Self::ConvertToDoubleWord { rm, .. }
+			| Self::ConvertToUnsignedDoubleWord { rm, .. }
+			| Self::ConvertFromWord { rm, .. }
+			| Self::ConvertFromUnsignedWord { rm, .. }
+			| Self::ConvertFromDoubleWord { rm, .. }
+			| Self::ConvertFromUnsignedDoubleWord { rm, .. } => Some(*rm),
+			_ => None,
+		}
+	}
 }
 
 impl Into<Instruction> for FloatInstruction {
@@ -0,0 +1,248 @@
+use crate::{
+	soft::RoundingMode,
+	ty::{FPRegisterIndex, GPRegisterIndex},
+};
+
+use super::Instruction;
+
+#[derive(Debug)]
+pub enum HalfInstruction {
+	LoadHalf {
+		dst: FPRegisterIndex,
+		src: GPRegisterIndex,
+		src_offset: i64,
+	},
+	StoreHalf {
+		dst: GPRegisterIndex,
+		dst_offset: i64,
+		src: FPRegisterIndex,
+	},
+
+	Add {
+		dst: FPRegisterIndex,
+		lhs: FPRegisterIndex,
+		rhs: FPRegisterIndex,
+		rm: RoundingMode,
+	},
+	Sub {
+		dst: FPRegisterIndex,
+		lhs: FPRegisterIndex,
+		rhs: FPRegisterIndex,
+		rm: RoundingMode,
+	},
+	Mul {
+		dst: FPRegisterIndex,
+		lhs: FPRegisterIndex,
+		rhs: FPRegisterIndex,
+		rm: RoundingMode,
+	},
+	Div {
+		dst: FPRegisterIndex,
+		lhs: FPRegisterIndex,
+		rhs: FPRegisterIndex,
+		rm: RoundingMode,
+	},
+	Sqrt {
+		dst: FPRegisterIndex,
+		val: FPRegisterIndex,
+		rm: RoundingMode,
+	},
+
+	Min {
+		dst: FPRegisterIndex,
+		lhs: FPRegisterIndex,
+		rhs: FPRegisterIndex,
+	},
+	Max {
+		dst: FPRegisterIndex,
+		lhs: FPRegisterIndex,
+		rhs: FPRegisterIndex,
+	},
+
+	Equal {
+		dst: GPRegisterIndex,
+		lhs: FPRegisterIndex,
+		rhs: FPRegisterIndex,
+	},
+	LessThan {
+		dst: GPRegisterIndex,
+		lhs: FPRegisterIndex,
+		rhs: FPRegisterIndex,
+	},
+	LessOrEqual {
+		dst: GPRegisterIndex,
+		lhs: FPRegisterIndex,
+		rhs: FPRegisterIndex,
+	},
+
+	MulAdd {
+		dst: FPRegisterIndex,
+		mul_lhs: FPRegisterIndex,
+		mul_rhs: FPRegisterIndex,
+		add: FPRegisterIndex,
+		rm: RoundingMode,
+	},
+	// (mul_lhs * mul_rhs) - sub
+	MulSub {
+		dst: FPRegisterIndex,
+		mul_lhs: FPRegisterIndex,
+		mul_rhs: FPRegisterIndex,
+		sub: FPRegisterIndex,
+		rm: RoundingMode,
+	},
+	// -(mul_lhs * mul_rhs) + sub
+	NegMulSub {
+		dst: FPRegisterIndex,
+		mul_lhs: FPRegisterIndex,
+		mul_rhs: FPRegisterIndex,
+		sub: FPRegisterIndex,
+		rm: RoundingMode,
+	},
+	// -(mul_lhs * mul_rhs) - add
+	NegMulAdd {
+		dst: FPRegisterIndex,
+		mul_lhs: FPRegisterIndex,
+		mul_rhs: FPRegisterIndex,
+		add: FPRegisterIndex,
+		rm: RoundingMode,
+	},
+	// FCVT.S.H, this is always exact so the rounding mode is unused
+	ConvertToFloat {
+		dst: FPRegisterIndex,
+		src: FPRegisterIndex,
+	},
+	// FCVT.H.S
+	ConvertFromFloat {
+		dst: FPRegisterIndex,
+		src: FPRegisterIndex,
+		rm: RoundingMode,
+	},
+	// FCVT.H.H, this is always exact so the rounding mode is unused
+	ConvertToDouble {
+		dst: FPRegisterIndex,
+		src: FPRegisterIndex,
+	},
+	// FCVT.H.H
+	ConvertFromDouble {
+		dst: FPRegisterIndex,
+		src: FPRegisterIndex,
+		rm: RoundingMode,
+	},
+	// FSGNJ.H: magnitude of lhs with the sign of rhs
+	SignInject {
+		dst: FPRegisterIndex,
+		lhs: FPRegisterIndex,
+		rhs: FPRegisterIndex,
+	},
+	// FSGNJN.H: magnitude of lhs with the opposite sign of rhs
+	SignInjectNegate {
+		dst: FPRegisterIndex,
+		lhs: FPRegisterIndex,
+		rhs: FPRegisterIndex,
+	},
+	// FSGNJX.H: magnitude of lhs with the xor of both signs
+	SignInjectXor {
+		dst: FPRegisterIndex,
+		lhs: FPRegisterIndex,
+		rhs: FPRegisterIndex,
+	},
+
+	// FCLASS.H
+	Classify {
+		dst: GPRegisterIndex,
+		src: FPRegisterIndex,
+	},
+	// FMV.X.H, moves the raw bits
+	MoveToInt {
+		dst: GPRegisterIndex,
+		src: FPRegisterIndex,
+	},
+	// FMV.H.X, moves the raw bits
+	MoveFromInt {
+		dst: FPRegisterIndex,
+		src: GPRegisterIndex,
+	},
+
+	// FCVT.W.H
+	ConvertToWord {
+		dst: GPRegisterIndex,
+		src: FPRegisterIndex,
+		rm: RoundingMode,
+	},
+	// FCVT.WU.H
+	ConvertToUnsignedWord {
+		dst: GPRegisterIndex,
+		src: FPRegisterIndex,
+		rm: RoundingMode,
+	},
+	// FCVT.L.H
+	ConvertToDoubleWord {
+		dst: GPRegisterIndex,
+		src: FPRegisterIndex,
+		rm: RoundingMode,
+	},
+	// FCVT.LU.H
+	ConvertToUnsignedDoubleWord {
+		dst: GPRegisterIndex,
+		src: FPRegisterIndex,
+		rm: RoundingMode,
+	},
+	// FCVT.H.W
+	ConvertFromWord {
+		dst: FPRegisterIndex,
+		src: GPRegisterIndex,
+		rm: RoundingMode,
+	},
+	// FCVT.H.WU
+	ConvertFromUnsignedWord {
+		dst: FPRegisterIndex,
+		src: GPRegisterIndex,
+		rm: RoundingMode,
+	},
+	// FCVT.H.L
+	ConvertFromDoubleWord {
+		dst: FPRegisterIndex,
+		src: GPRegisterIndex,
+		rm: RoundingMode,
+	},
+	// FCVT.H.LU
+	ConvertFromUnsignedDoubleWord {
+		dst: FPRegisterIndex,
+		src: GPRegisterIndex,
+		rm: RoundingMode,
+	},
+}
+
+impl HalfInstruction {
+	/// the rounding mode encoded in the instruction, if it has one
+	pub fn rounding_mode(&self) -> Option<RoundingMode> {
+		match self {
+			Self::Add { rm, .. }
+			| Self::Sub { rm, .. }
+			| Self::Mul { rm, .. }
+			| Self::Div { rm, .. }
+			| Self::Sqrt { rm, .. }
+			| Self::MulAdd { rm, .. }
+			| Self::MulSub { rm, .. }
+			| Self::NegMulSub { rm, .. }
+			| Self::NegMulAdd { rm, .. }
+			| Self::ConvertFromFloat { rm, .. }
+			| Self::ConvertFromDouble { rm, .. }
+			| Self::ConvertToWord { rm, .. }
+			| Self::ConvertToUnsignedWord { rm, .. }
+			| Self::ConvertToDoubleWord { rm, .. }
+			| Self::ConvertToUnsignedDoubleWord { rm, .. }
+			| Self::ConvertFromWord { rm, .. }
+			| Self::ConvertFromUnsignedWord { rm, .. }
+			| Self::ConvertFromDoubleWord { rm, .. }
+			| Self::ConvertFromUnsignedDoubleWord { rm, .. } => Some(*rm),
+			_ => None,
+		}
+	}
+}
+
+impl From<HalfInstruction> for Instruction {
+	fn from(insn: HalfInstruction) -> Self {
+		Instruction::HalfExtension(insn)
+	}
+}
@@ -118,6 +118,18 @@ pub enum IntInstruction {
 		lhs: GPRegisterIndex,
 		rhs: GPRegisterIndex,
 	},
+	// CZERO.EQZ: 0 if rhs is zero, otherwise lhs
+	ConditionalZeroIfEqualZero {
+		dst: GPRegisterIndex,
+		lhs: GPRegisterIndex,
+		rhs: GPRegisterIndex,
+	},
+	// CZERO.NEZ: 0 if rhs is not zero, otherwise lhs
+	ConditionalZeroIfNotEqualZero {
+		dst: GPRegisterIndex,
+		lhs: GPRegisterIndex,
+		rhs: GPRegisterIndex,
+	},
 	AddImmediate {
 		dst: GPRegisterIndex,
 		lhs: GPRegisterIndex,
@@ -249,11 +261,40 @@ pub enum IntInstruction {
 		dst: GPRegisterIndex,
 	},
 
+	// ============
+	// MISC-MEM
+	// ============
+	Fence {
+		// IORW bits
+		_pred: u8,
+		_succ: u8,
+	},
+	FenceTso,
+	// a hint that the hart is in a spin-wait loop, decoded as a plain fence without Zihintpause
+	Pause,
+	FenceInstruction,
+
 	// =========
 	// SYSTEM
 	// =========
 	ECall,
 	EBreak,
+	// stalls the hart until an interrupt is pending
+	WaitForInterrupt,
+	// wrs.nto and wrs.sto, stalls the hart until its reservation is lost or an interrupt is pending
+	// the short timeout variant gives up after a short while even if neither happened
+	WaitOnReservationSet {
+		short_timeout: bool,
+	},
+	// mret
+	MachineReturn,
+	// sret
+	SupervisorReturn,
+	// sfence.vma, x0 for either operand means all addresses or all address spaces
+	SupervisorFenceVirtualMemory {
+		vaddr: GPRegisterIndex,
+		asid: GPRegisterIndex,
+	},
 }
 
 impl Into<Instruction> for IntInstruction {
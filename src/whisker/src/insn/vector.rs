@@ -0,0 +1,138 @@
+use crate::ty::{GPRegisterIndex, VectorRegisterIndex};
+
+use super::Instruction;
+
+/// the second source operand of an OP-V instruction, selected by func3
+#[derive(Debug, Clone, Copy)]
+pub enum VectorOperand {
+	Vector(VectorRegisterIndex),
+	Scalar(GPRegisterIndex),
+	Immediate(i64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VectorIntOp {
+	Add,
+	Sub,
+	// rhs - lhs
+	ReverseSub,
+	And,
+	Or,
+	Xor,
+	ShiftLeft,
+	ShiftRightLogical,
+	ShiftRightArithmetic,
+	Min,
+	MinUnsigned,
+	Max,
+	MaxUnsigned,
+	Mul,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VectorCmpOp {
+	Equal,
+	NotEqual,
+	LessThanUnsigned,
+	LessThan,
+	LessOrEqualUnsigned,
+	LessOrEqual,
+	GreaterThanUnsigned,
+	GreaterThan,
+}
+
+/// when masked is true the instruction only touches elements whose bit in v0 is set
+#[derive(Debug)]
+pub enum VectorInstruction {
+	// vsetvli, vsetivli and vsetvl
+	SetVectorLength {
+		dst: GPRegisterIndex,
+		avl: VectorLengthSource,
+		vtype: VectorTypeSource,
+	},
+
+	// vle<eew>.v and vle<eew>ff.v
+	LoadUnitStride {
+		dst: VectorRegisterIndex,
+		base: GPRegisterIndex,
+		eew_bytes: usize,
+		masked: bool,
+		// a fault after the first element shrinks vl instead of trapping
+		fault_only_first: bool,
+	},
+	// vse<eew>.v
+	StoreUnitStride {
+		src: VectorRegisterIndex,
+		base: GPRegisterIndex,
+		eew_bytes: usize,
+		masked: bool,
+	},
+
+	// dst[i] = lhs[i] op rhs[i]
+	IntOp {
+		op: VectorIntOp,
+		dst: VectorRegisterIndex,
+		lhs: VectorRegisterIndex,
+		rhs: VectorOperand,
+		masked: bool,
+	},
+	// dst.mask[i] = lhs[i] op rhs[i]
+	Compare {
+		op: VectorCmpOp,
+		dst: VectorRegisterIndex,
+		lhs: VectorRegisterIndex,
+		rhs: VectorOperand,
+		masked: bool,
+	},
+	// vmerge: dst[i] = v0.mask[i] ? rhs[i] : lhs[i]
+	Merge {
+		dst: VectorRegisterIndex,
+		lhs: VectorRegisterIndex,
+		rhs: VectorOperand,
+	},
+	// vmv.v.v, vmv.v.x and vmv.v.i
+	Move {
+		dst: VectorRegisterIndex,
+		src: VectorOperand,
+	},
+	// vmv.x.s: sign extended element 0
+	MoveToScalar {
+		dst: GPRegisterIndex,
+		src: VectorRegisterIndex,
+	},
+	// vmv.s.x
+	MoveFromScalar {
+		dst: VectorRegisterIndex,
+		src: GPRegisterIndex,
+	},
+	// vcpop.m
+	MaskPopCount {
+		dst: GPRegisterIndex,
+		src: VectorRegisterIndex,
+		masked: bool,
+	},
+	// vfirst.m: index of the first set mask bit, or -1
+	MaskFindFirst {
+		dst: GPRegisterIndex,
+		src: VectorRegisterIndex,
+		masked: bool,
+	},
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum VectorLengthSource {
+	Register(GPRegisterIndex),
+	Immediate(u64),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum VectorTypeSource {
+	Register(GPRegisterIndex),
+	Immediate(u64),
+}
+
+impl From<VectorInstruction> for Instruction {
+	fn from(insn: VectorInstruction) -> Self {
+		Instruction::VectorExtension(insn)
+	}
+}
@@ -3,9 +3,9 @@ use tracing::trace;
 use crate::insn16::ty::CWideImmType;
 use crate::{
 	cpu::WhiskerCpu,
-	insn::{compressed::CompressedInstruction, int::IntInstruction, Instruction},
+	insn::{compressed::CompressedInstruction, double::DoubleInstruction, int::IntInstruction, Instruction},
 	insn16::ty::{CAType, CBArithType, CBranchType, CImmType, CJType, CLoadType, CRType, CStackStoreType, CStoreType},
-	ty::{GPRegisterIndex, TrapIdx},
+	ty::{GPRegisterIndex, SupportedExtensions, TrapIdx},
 	util::extract_bits_16,
 };
 
@@ -30,7 +30,19 @@ impl CompressedInstruction {
 				}
 			}
 			FLD => {
-				todo!("FLD (D ext)")
+				if !cpu.supported_extensions.has(SupportedExtensions::DOUBLE) {
+					cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+					return Err(());
+				}
+
+				let cl = CLoadType::parse(parcel);
+				Ok(DoubleInstruction::LoadDoubleWord {
+					dst: cl.dst().to_fp(),
+					src: cl.src(),
+					// the immediate was zero extended so this will never do a sign extension
+					src_offset: cl.imm().cast_signed(),
+				}
+				.into())
 			}
 			LOAD_WORD => {
 				let cl = CLoadType::parse(parcel);
@@ -57,7 +69,19 @@ impl CompressedInstruction {
 				Err(())
 			}
 			FSD => {
-				todo!("FSD (D ext)")
+				if !cpu.supported_extensions.has(SupportedExtensions::DOUBLE) {
+					cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+					return Err(());
+				}
+
+				let cs = CStoreType::parse(parcel);
+				Ok(DoubleInstruction::StoreDoubleWord {
+					dst: cs.dst(),
+					// the immediate was zero extended so this will never do a sign extension
+					dst_offset: cs.imm().cast_signed(),
+					src: cs.src().to_fp(),
+				}
+				.into())
 			}
 			STORE_WORD => {
 				let cs = CStoreType::parse(parcel);
@@ -92,6 +116,9 @@ impl CompressedInstruction {
 				let im = CImmType::parse(parcel);
 				if im.reg() == GPRegisterIndex::ZERO && im.imm() == 0 {
 					Ok(CompressedInstruction::Nop.into())
+				} else if im.reg() == GPRegisterIndex::ZERO || im.imm() == 0 {
+					// C.NOP with a non-zero imm and C.ADDI with a zero imm are hints
+					Ok(CompressedInstruction::Hint.into())
 				} else {
 					Ok(IntInstruction::AddImmediate {
 						dst: im.reg(),
@@ -119,7 +146,7 @@ impl CompressedInstruction {
 			LI => {
 				let im = CImmType::parse(parcel);
 				if im.reg() == GPRegisterIndex::ZERO {
-					todo!("HINT")
+					Ok(CompressedInstruction::Hint.into())
 				} else {
 					Ok(IntInstruction::AddImmediate {
 						dst: im.reg(),
@@ -136,7 +163,7 @@ impl CompressedInstruction {
 					cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
 					Err(())
 				} else if im.reg() == GPRegisterIndex::ZERO {
-					todo!("HINT")
+					Ok(CompressedInstruction::Hint.into())
 				} else if im.reg().as_usize() == 2 {
 					Ok(IntInstruction::AddImmediate {
 						dst: im.reg(),
@@ -185,7 +212,7 @@ impl CompressedInstruction {
 				match func2 {
 					func2::SRLI => {
 						if cb.imm() == 0 {
-							todo!("HINT")
+							Ok(CompressedInstruction::Hint.into())
 						} else {
 							Ok(IntInstruction::ShiftRightLogicalImmediate {
 								dst: cb.reg(),
@@ -197,7 +224,7 @@ impl CompressedInstruction {
 					}
 					func2::SRAI => {
 						if cb.imm() == 0 {
-							todo!("HINT")
+							Ok(CompressedInstruction::Hint.into())
 						} else {
 							Ok(IntInstruction::ShiftRightArithmeticImmediate {
 								dst: cb.reg(),
@@ -219,12 +246,18 @@ impl CompressedInstruction {
 						let sub_func2 = extract_bits_16(parcel, 5, 6) as u8;
 						if is_word {
 							match sub_func2 {
-								SUBW => {
-									todo!("SUBW (OP-32)")
+								SUBW => Ok(IntInstruction::SubWord {
+									dst: ca.src1(),
+									lhs: ca.src1(),
+									rhs: ca.src2(),
 								}
-								ADDW => {
-									todo!("ADDW (OP-32)")
+								.into()),
+								ADDW => Ok(IntInstruction::AddWord {
+									dst: ca.src1(),
+									lhs: ca.src1(),
+									rhs: ca.src2(),
 								}
+								.into()),
 								_ => {
 									// RESERVED
 									cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
@@ -275,10 +308,8 @@ impl CompressedInstruction {
 		match func3 {
 			SLLI => {
 				let im = CImmType::parse(parcel);
-				if im.reg() == GPRegisterIndex::ZERO {
-					todo!("HINT")
-				} else if im.imm() == 0 {
-					todo!("HINT")
+				if im.reg() == GPRegisterIndex::ZERO || im.imm() == 0 {
+					Ok(CompressedInstruction::Hint.into())
 				} else {
 					Ok(IntInstruction::ShiftLeftLogicalImmediate {
 						dst: im.reg(),
@@ -289,7 +320,19 @@ impl CompressedInstruction {
 				}
 			}
 			FLDSP => {
-				todo!("FLDSP (F extension)")
+				if !cpu.supported_extensions.has(SupportedExtensions::DOUBLE) {
+					cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+					return Err(());
+				}
+
+				// unlike C.LDSP, any destination register is valid here
+				let im = CImmType::parse(parcel);
+				Ok(DoubleInstruction::LoadDoubleWord {
+					dst: im.reg().to_fp(),
+					src: GPRegisterIndex::SP,
+					src_offset: im.imm(),
+				}
+				.into())
 			}
 			LWSP => {
 				let im = CImmType::parse(parcel);
@@ -332,9 +375,7 @@ impl CompressedInstruction {
 								cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
 								Err(())
 							}
-							(GPRegisterIndex::ZERO, _rs2) => {
-								todo!("HINT")
-							}
+							(GPRegisterIndex::ZERO, _rs2) => Ok(CompressedInstruction::Hint.into()),
 							(rs1, GPRegisterIndex::ZERO) => Ok(IntInstruction::JumpAndLinkRegister {
 								link_reg: GPRegisterIndex::ZERO,
 								jmp_reg: rs1,
@@ -351,9 +392,7 @@ impl CompressedInstruction {
 					}
 					JALR_EBREAK_ADD => match (crtype.src1(), crtype.src2()) {
 						(GPRegisterIndex::ZERO, GPRegisterIndex::ZERO) => Ok(IntInstruction::EBreak.into()),
-						(GPRegisterIndex::ZERO, _rs2) => {
-							todo!("HINT")
-						}
+						(GPRegisterIndex::ZERO, _rs2) => Ok(CompressedInstruction::Hint.into()),
 						(rs1, GPRegisterIndex::ZERO) => Ok(IntInstruction::JumpAndLinkRegister {
 							link_reg: GPRegisterIndex::LINK_REG,
 							jmp_reg: rs1,
@@ -371,7 +410,18 @@ impl CompressedInstruction {
 				}
 			}
 			FSDSP => {
-				todo!("FSDSP (D extension)")
+				if !cpu.supported_extensions.has(SupportedExtensions::DOUBLE) {
+					cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+					return Err(());
+				}
+
+				let ss = CStackStoreType::parse(parcel);
+				Ok(DoubleInstruction::StoreDoubleWord {
+					dst: GPRegisterIndex::SP,
+					dst_offset: ss.imm(),
+					src: ss.src().to_fp(),
+				}
+				.into())
 			}
 			SWSP => {
 				let ss = CStackStoreType::parse(parcel);
@@ -401,8 +451,10 @@ pub fn parse(cpu: &mut WhiskerCpu, parcel: u16) -> Result<Instruction, ()> {
 
 	let opcode_ty = extract_bits_16(parcel, 0, 1) as u8;
 	trace!("(C-ext) parcel={parcel:#018b}");
-	if parcel.count_zeros() == u16::BITS {
-		panic!("Invalid 16-bit instruction. Cannot be all zero bits");
+	// the all zero parcel is defined to be illegal, so running into zeroed memory traps
+	if parcel == 0 {
+		cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+		return Err(());
 	}
 	match opcode_ty {
 		C0 => CompressedInstruction::parse_c0(cpu, parcel),
@@ -621,12 +673,11 @@ mod ty {
 					let imm_3_5 = extract_bits_16(parcel, 10, 12);
 					(imm_6 << 6 | imm_3_5 << 3 | imm_2 << 2) as u64
 				}
-				LOAD_DOUBLE_WORD => {
+				LOAD_DOUBLE_WORD | FLD => {
 					let imm_6_7 = extract_bits_16(parcel, 5, 6);
 					let imm_3_5 = extract_bits_16(parcel, 10, 12);
 					(imm_6_7 << 6 | imm_3_5 << 3) as u64
 				}
-				// TODO: C.FLD not yet implemented
 				_ => unreachable!("invalid CLoadType func3 {func:#05b}"),
 			};
 
@@ -671,12 +722,11 @@ mod ty {
 					let imm_3_5 = extract_bits_16(parcel, 10, 12);
 					(imm_6 << 6 | imm_3_5 << 3 | imm_2 << 2) as u64
 				}
-				STORE_DOUBLE_WORD => {
+				STORE_DOUBLE_WORD | FSD => {
 					let imm_6_7 = extract_bits_16(parcel, 5, 6);
 					let imm_3_5 = extract_bits_16(parcel, 10, 12);
 					(imm_6_7 << 6 | imm_3_5 << 3) as u64
 				}
-				// TODO: C.FSD not yet implemented
 				_ => unreachable!("invalid CStoreType func3 {func:#05b}"),
 			};
 
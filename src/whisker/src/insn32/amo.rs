@@ -99,6 +99,19 @@ impl AtomicInstruction {
 				_aq: aq,
 				_rl: rl,
 			},
+			COMPARE_AND_SWAP => {
+				if !cpu.supported_extensions.has(SupportedExtensions::ZACAS) {
+					cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+					return Err(());
+				}
+				Self::CompareAndSwapWord {
+					src1: rtype.src1().to_gp(),
+					src2: rtype.src2().to_gp(),
+					dst: rtype.dst().to_gp(),
+					_aq: aq,
+					_rl: rl,
+				}
+			}
 			_ => unreachable!(),
 		})
 	}
@@ -195,9 +208,232 @@ impl AtomicInstruction {
 				_aq: aq,
 				_rl: rl,
 			},
+			COMPARE_AND_SWAP => {
+				if !cpu.supported_extensions.has(SupportedExtensions::ZACAS) {
+					cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+					return Err(());
+				}
+				Self::CompareAndSwapDoubleWord {
+					src1: rtype.src1().to_gp(),
+					src2: rtype.src2().to_gp(),
+					dst: rtype.dst().to_gp(),
+					_aq: aq,
+					_rl: rl,
+				}
+			}
 			_ => unreachable!(),
 		})
 	}
+	pub fn parse_byte_insn(cpu: &mut WhiskerCpu, rtype: RType) -> Result<Self, ()> {
+		use consts::*;
+
+		let rl = extract_bits_8(rtype.func7(), 0, 0) != 0;
+		let aq = extract_bits_8(rtype.func7(), 1, 1) != 0;
+
+		let func5 = extract_bits_8(rtype.func7(), 2, 7);
+
+		Ok(match func5 {
+			SWAP => Self::SwapByte {
+				src1: rtype.src1().to_gp(),
+				src2: rtype.src2().to_gp(),
+				dst: rtype.dst().to_gp(),
+				_aq: aq,
+				_rl: rl,
+			},
+			ADD => Self::AddByte {
+				src1: rtype.src1().to_gp(),
+				src2: rtype.src2().to_gp(),
+				dst: rtype.dst().to_gp(),
+				_aq: aq,
+				_rl: rl,
+			},
+			XOR => Self::XorByte {
+				src1: rtype.src1().to_gp(),
+				src2: rtype.src2().to_gp(),
+				dst: rtype.dst().to_gp(),
+				_aq: aq,
+				_rl: rl,
+			},
+			AND => Self::AndByte {
+				src1: rtype.src1().to_gp(),
+				src2: rtype.src2().to_gp(),
+				dst: rtype.dst().to_gp(),
+				_aq: aq,
+				_rl: rl,
+			},
+			OR => Self::OrByte {
+				src1: rtype.src1().to_gp(),
+				src2: rtype.src2().to_gp(),
+				dst: rtype.dst().to_gp(),
+				_aq: aq,
+				_rl: rl,
+			},
+			MIN => Self::MinByte {
+				src1: rtype.src1().to_gp(),
+				src2: rtype.src2().to_gp(),
+				dst: rtype.dst().to_gp(),
+				_aq: aq,
+				_rl: rl,
+			},
+			MAX => Self::MaxByte {
+				src1: rtype.src1().to_gp(),
+				src2: rtype.src2().to_gp(),
+				dst: rtype.dst().to_gp(),
+				_aq: aq,
+				_rl: rl,
+			},
+			MIN_UNSIGNED => Self::MinUnsignedByte {
+				src1: rtype.src1().to_gp(),
+				src2: rtype.src2().to_gp(),
+				dst: rtype.dst().to_gp(),
+				_aq: aq,
+				_rl: rl,
+			},
+			MAX_UNSIGNED => Self::MaxUnsignedByte {
+				src1: rtype.src1().to_gp(),
+				src2: rtype.src2().to_gp(),
+				dst: rtype.dst().to_gp(),
+				_aq: aq,
+				_rl: rl,
+			},
+			COMPARE_AND_SWAP => {
+				if !cpu.supported_extensions.has(SupportedExtensions::ZACAS) {
+					cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+					return Err(());
+				}
+				Self::CompareAndSwapByte {
+					src1: rtype.src1().to_gp(),
+					src2: rtype.src2().to_gp(),
+					dst: rtype.dst().to_gp(),
+					_aq: aq,
+					_rl: rl,
+				}
+			}
+			_ => {
+				cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+				return Err(());
+			}
+		})
+	}
+
+	pub fn parse_half_insn(cpu: &mut WhiskerCpu, rtype: RType) -> Result<Self, ()> {
+		use consts::*;
+
+		let rl = extract_bits_8(rtype.func7(), 0, 0) != 0;
+		let aq = extract_bits_8(rtype.func7(), 1, 1) != 0;
+
+		let func5 = extract_bits_8(rtype.func7(), 2, 7);
+
+		Ok(match func5 {
+			SWAP => Self::SwapHalf {
+				src1: rtype.src1().to_gp(),
+				src2: rtype.src2().to_gp(),
+				dst: rtype.dst().to_gp(),
+				_aq: aq,
+				_rl: rl,
+			},
+			ADD => Self::AddHalf {
+				src1: rtype.src1().to_gp(),
+				src2: rtype.src2().to_gp(),
+				dst: rtype.dst().to_gp(),
+				_aq: aq,
+				_rl: rl,
+			},
+			XOR => Self::XorHalf {
+				src1: rtype.src1().to_gp(),
+				src2: rtype.src2().to_gp(),
+				dst: rtype.dst().to_gp(),
+				_aq: aq,
+				_rl: rl,
+			},
+			AND => Self::AndHalf {
+				src1: rtype.src1().to_gp(),
+				src2: rtype.src2().to_gp(),
+				dst: rtype.dst().to_gp(),
+				_aq: aq,
+				_rl: rl,
+			},
+			OR => Self::OrHalf {
+				src1: rtype.src1().to_gp(),
+				src2: rtype.src2().to_gp(),
+				dst: rtype.dst().to_gp(),
+				_aq: aq,
+				_rl: rl,
+			},
+			MIN => Self::MinHalf {
+				src1: rtype.src1().to_gp(),
+				src2: rtype.src2().to_gp(),
+				dst: rtype.dst().to_gp(),
+				_aq: aq,
+				_rl: rl,
+			},
+			MAX => Self::MaxHalf {
+				src1: rtype.src1().to_gp(),
+				src2: rtype.src2().to_gp(),
+				dst: rtype.dst().to_gp(),
+				_aq: aq,
+				_rl: rl,
+			},
+			MIN_UNSIGNED => Self::MinUnsignedHalf {
+				src1: rtype.src1().to_gp(),
+				src2: rtype.src2().to_gp(),
+				dst: rtype.dst().to_gp(),
+				_aq: aq,
+				_rl: rl,
+			},
+			MAX_UNSIGNED => Self::MaxUnsignedHalf {
+				src1: rtype.src1().to_gp(),
+				src2: rtype.src2().to_gp(),
+				dst: rtype.dst().to_gp(),
+				_aq: aq,
+				_rl: rl,
+			},
+			COMPARE_AND_SWAP => {
+				if !cpu.supported_extensions.has(SupportedExtensions::ZACAS) {
+					cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+					return Err(());
+				}
+				Self::CompareAndSwapHalf {
+					src1: rtype.src1().to_gp(),
+					src2: rtype.src2().to_gp(),
+					dst: rtype.dst().to_gp(),
+					_aq: aq,
+					_rl: rl,
+				}
+			}
+			_ => {
+				cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+				return Err(());
+			}
+		})
+	}
+}
+
+impl AtomicInstruction {
+	/// the only quadword AMO is amocas.q
+	pub fn parse_quad_word_insn(cpu: &mut WhiskerCpu, rtype: RType) -> Result<Self, ()> {
+		use consts::*;
+
+		let rl = extract_bits_8(rtype.func7(), 0, 0) != 0;
+		let aq = extract_bits_8(rtype.func7(), 1, 1) != 0;
+
+		let func5 = extract_bits_8(rtype.func7(), 2, 7);
+
+		// the register pairs have to start on an even register
+		let odd_pair = rtype.dst().as_usize() % 2 != 0 || rtype.src2().as_usize() % 2 != 0;
+		if func5 != COMPARE_AND_SWAP || odd_pair || !cpu.supported_extensions.has(SupportedExtensions::ZACAS) {
+			cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+			return Err(());
+		}
+
+		Ok(Self::CompareAndSwapQuadWord {
+			src1: rtype.src1().to_gp(),
+			src2: rtype.src2().to_gp(),
+			dst: rtype.dst().to_gp(),
+			_aq: aq,
+			_rl: rl,
+		})
+	}
 }
 
 pub fn parse_amo(cpu: &mut WhiskerCpu, parcel: u32) -> Result<Instruction, ()> {
@@ -211,19 +447,30 @@ pub fn parse_amo(cpu: &mut WhiskerCpu, parcel: u32) -> Result<Instruction, ()> {
 	let rtype = RType::parse(parcel);
 
 	match rtype.func3() {
+		BYTE | HALF if !cpu.supported_extensions.has(SupportedExtensions::ZABHA) => {
+			cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+			Err(())
+		}
+		BYTE => Ok(AtomicInstruction::parse_byte_insn(cpu, rtype).map(AtomicInstruction::into)?),
+		HALF => Ok(AtomicInstruction::parse_half_insn(cpu, rtype).map(AtomicInstruction::into)?),
 		WORD => Ok(AtomicInstruction::parse_word_insn(cpu, rtype).map(AtomicInstruction::into)?),
 		DWORD => Ok(AtomicInstruction::parse_double_word_insn(cpu, rtype).map(AtomicInstruction::into)?),
+		QWORD => Ok(AtomicInstruction::parse_quad_word_insn(cpu, rtype).map(AtomicInstruction::into)?),
 		_ => unreachable!(),
 	}
 }
 
 pub mod consts {
+	pub const BYTE: u8 = 0b000;
+	pub const HALF: u8 = 0b001;
 	pub const WORD: u8 = 0b010;
 	pub const DWORD: u8 = 0b011;
+	pub const QWORD: u8 = 0b100;
 
 	pub const LOAD_RESERVED: u8 = 0b00010;
 	pub const STORE_CONDITIONAL: u8 = 0b00011;
 	pub const SWAP: u8 = 0b00001;
+	pub const COMPARE_AND_SWAP: u8 = 0b00101;
 	pub const ADD: u8 = 0b00000;
 	pub const XOR: u8 = 0b00100;
 	pub const AND: u8 = 0b01100;
@@ -0,0 +1,251 @@
+use crate::{
+	cpu::WhiskerCpu,
+	insn::bitmanip::BitmanipInstruction,
+	ty::{SupportedExtensions, TrapIdx},
+	util::extract_bits_32,
+};
+
+use super::{IType, RType};
+
+macro_rules! define_op_bitmanip {
+	($rtype:ident, $($const:ident, $inst:ident),*) => {
+		match $rtype.func() {
+			$( $const => Self::$inst { dst: $rtype.dst().to_gp(), lhs: $rtype.src1().to_gp(), rhs: $rtype.src2().to_gp() }, )*
+			_ => unreachable!(),
+		}
+	};
+}
+
+impl BitmanipInstruction {
+	pub fn parse_op(cpu: &mut WhiskerCpu, rtype: RType) -> Result<Self, ()> {
+		use crate::insn32::op::consts::*;
+		match rtype.func() {
+			SH1ADD | SH2ADD | SH3ADD => {
+				require(cpu, SupportedExtensions::ZBA)?;
+				Ok(Self::ShiftAdd {
+					dst: rtype.dst().to_gp(),
+					lhs: rtype.src1().to_gp(),
+					rhs: rtype.src2().to_gp(),
+					shift: shift_add_amount(rtype.func3()),
+				})
+			}
+			AND_NOT | OR_NOT | XOR_NOT | MIN | MIN_UNSIGNED | MAX | MAX_UNSIGNED | ROTATE_LEFT | ROTATE_RIGHT => {
+				require(cpu, SupportedExtensions::ZBB)?;
+				Ok(define_op_bitmanip!(
+					rtype,
+					AND_NOT,
+					AndNot,
+					OR_NOT,
+					OrNot,
+					XOR_NOT,
+					XorNot,
+					MIN,
+					Min,
+					MIN_UNSIGNED,
+					MinUnsigned,
+					MAX,
+					Max,
+					MAX_UNSIGNED,
+					MaxUnsigned,
+					ROTATE_LEFT,
+					RotateLeft,
+					ROTATE_RIGHT,
+					RotateRight
+				))
+			}
+			CARRYLESS_MUL | CARRYLESS_MUL_REVERSED | CARRYLESS_MUL_HIGH => {
+				require(cpu, SupportedExtensions::ZBC)?;
+				Ok(define_op_bitmanip!(
+					rtype,
+					CARRYLESS_MUL,
+					CarrylessMul,
+					CARRYLESS_MUL_REVERSED,
+					CarrylessMulReversed,
+					CARRYLESS_MUL_HIGH,
+					CarrylessMulHigh
+				))
+			}
+			BIT_CLEAR | BIT_EXTRACT | BIT_INVERT | BIT_SET => {
+				require(cpu, SupportedExtensions::ZBS)?;
+				Ok(define_op_bitmanip!(
+					rtype,
+					BIT_CLEAR,
+					BitClear,
+					BIT_EXTRACT,
+					BitExtract,
+					BIT_INVERT,
+					BitInvert,
+					BIT_SET,
+					BitSet
+				))
+			}
+			_ => unreachable!(),
+		}
+	}
+
+	pub fn parse_op_32(cpu: &mut WhiskerCpu, rtype: RType) -> Result<Self, ()> {
+		use crate::insn32::op_32::consts::*;
+		match rtype.func() {
+			ADD_UNSIGNED_WORD => {
+				require(cpu, SupportedExtensions::ZBA)?;
+				Ok(Self::AddUnsignedWord {
+					dst: rtype.dst().to_gp(),
+					lhs: rtype.src1().to_gp(),
+					rhs: rtype.src2().to_gp(),
+				})
+			}
+			SH1ADD_UNSIGNED_WORD | SH2ADD_UNSIGNED_WORD | SH3ADD_UNSIGNED_WORD => {
+				require(cpu, SupportedExtensions::ZBA)?;
+				Ok(Self::ShiftAddUnsignedWord {
+					dst: rtype.dst().to_gp(),
+					lhs: rtype.src1().to_gp(),
+					rhs: rtype.src2().to_gp(),
+					shift: shift_add_amount(rtype.func3()),
+				})
+			}
+			// ZEXT.H is only defined with rs2 = x0
+			ZERO_EXTEND_HALF if rtype.src2().as_usize() == 0 => {
+				require(cpu, SupportedExtensions::ZBB)?;
+				Ok(Self::ZeroExtendHalf {
+					dst: rtype.dst().to_gp(),
+					src: rtype.src1().to_gp(),
+				})
+			}
+			ROTATE_LEFT_WORD | ROTATE_RIGHT_WORD => {
+				require(cpu, SupportedExtensions::ZBB)?;
+				Ok(define_op_bitmanip!(
+					rtype,
+					ROTATE_LEFT_WORD,
+					RotateLeftWord,
+					ROTATE_RIGHT_WORD,
+					RotateRightWord
+				))
+			}
+			_ => {
+				cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+				Err(())
+			}
+		}
+	}
+
+	pub fn parse_op_imm(cpu: &mut WhiskerCpu, itype: IType) -> Result<Self, ()> {
+		use crate::insn32::op_imm::consts::*;
+		let imm = extract_bits_32(itype.imm() as u32, 0, 11) as u16;
+		let shift_kind = extract_bits_32(itype.imm() as u32, 6, 11) as u8;
+		let shift_amt = extract_bits_32(itype.imm() as u32, 0, 5);
+		let dst = itype.dst().to_gp();
+		let src = itype.src().to_gp();
+		let (ext, insn) = match (itype.func(), imm) {
+			(SHIFT_LEFT_IMM, COUNT_LEADING_ZEROS) => (SupportedExtensions::ZBB, Self::CountLeadingZeros { dst, src }),
+			(SHIFT_LEFT_IMM, COUNT_TRAILING_ZEROS) => (SupportedExtensions::ZBB, Self::CountTrailingZeros { dst, src }),
+			(SHIFT_LEFT_IMM, COUNT_POPULATION) => (SupportedExtensions::ZBB, Self::CountPopulation { dst, src }),
+			(SHIFT_LEFT_IMM, SIGN_EXTEND_BYTE) => (SupportedExtensions::ZBB, Self::SignExtendByte { dst, src }),
+			(SHIFT_LEFT_IMM, SIGN_EXTEND_HALF) => (SupportedExtensions::ZBB, Self::SignExtendHalf { dst, src }),
+			(SHIFT_RIGHT_IMM, OR_COMBINE_BYTE) => (SupportedExtensions::ZBB, Self::OrCombineByte { dst, src }),
+			(SHIFT_RIGHT_IMM, REVERSE_BYTES) => (SupportedExtensions::ZBB, Self::ReverseBytes { dst, src }),
+			(SHIFT_RIGHT_IMM, _) if shift_kind == SHIFT_ROTATE => (
+				SupportedExtensions::ZBB,
+				Self::RotateRightImmediate {
+					dst,
+					lhs: src,
+					shift_amt,
+				},
+			),
+			(SHIFT_LEFT_IMM, _) if shift_kind == BIT_CLEAR_IMM => (
+				SupportedExtensions::ZBS,
+				Self::BitClearImmediate {
+					dst,
+					lhs: src,
+					shift_amt,
+				},
+			),
+			(SHIFT_RIGHT_IMM, _) if shift_kind == BIT_EXTRACT_IMM => (
+				SupportedExtensions::ZBS,
+				Self::BitExtractImmediate {
+					dst,
+					lhs: src,
+					shift_amt,
+				},
+			),
+			(SHIFT_LEFT_IMM, _) if shift_kind == BIT_INVERT_IMM => (
+				SupportedExtensions::ZBS,
+				Self::BitInvertImmediate {
+					dst,
+					lhs: src,
+					shift_amt,
+				},
+			),
+			(SHIFT_LEFT_IMM, _) if shift_kind == BIT_SET_IMM => (
+				SupportedExtensions::ZBS,
+				Self::BitSetImmediate {
+					dst,
+					lhs: src,
+					shift_amt,
+				},
+			),
+			_ => {
+				cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+				return Err(());
+			}
+		};
+		require(cpu, ext)?;
+		Ok(insn)
+	}
+
+	pub fn parse_op_imm_32(cpu: &mut WhiskerCpu, itype: IType) -> Result<Self, ()> {
+		use crate::insn32::op_imm_32::consts::*;
+		let imm = extract_bits_32(itype.imm() as u32, 0, 11) as u16;
+		let shift_kind = extract_bits_32(itype.imm() as u32, 6, 11) as u8;
+		let dst = itype.dst().to_gp();
+		let src = itype.src().to_gp();
+		match (itype.func(), imm) {
+			(SHIFT_LEFT_IMM_WORD, _) if shift_kind == SHIFT_LEFT_UNSIGNED_WORD => {
+				require(cpu, SupportedExtensions::ZBA)?;
+				Ok(Self::ShiftLeftLogicalImmediateUnsignedWord {
+					dst,
+					lhs: src,
+					shift_amt: extract_bits_32(itype.imm() as u32, 0, 5),
+				})
+			}
+			(SHIFT_LEFT_IMM_WORD, COUNT_LEADING_ZEROS_WORD) => {
+				require(cpu, SupportedExtensions::ZBB)?;
+				Ok(Self::CountLeadingZerosWord { dst, src })
+			}
+			(SHIFT_LEFT_IMM_WORD, COUNT_TRAILING_ZEROS_WORD) => {
+				require(cpu, SupportedExtensions::ZBB)?;
+				Ok(Self::CountTrailingZerosWord { dst, src })
+			}
+			(SHIFT_LEFT_IMM_WORD, COUNT_POPULATION_WORD) => {
+				require(cpu, SupportedExtensions::ZBB)?;
+				Ok(Self::CountPopulationWord { dst, src })
+			}
+			// the word variant only has a 5 bit shift amount, bit 5 must be clear
+			(SHIFT_RIGHT_IMM_WORD, _) if shift_kind == SHIFT_ROTATE && imm & (1 << 5) == 0 => {
+				require(cpu, SupportedExtensions::ZBB)?;
+				Ok(Self::RotateRightImmediateWord {
+					dst,
+					lhs: src,
+					shift_amt: extract_bits_32(itype.imm() as u32, 0, 4),
+				})
+			}
+			_ => {
+				cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+				Err(())
+			}
+		}
+	}
+}
+
+fn require(cpu: &mut WhiskerCpu, ext: SupportedExtensions) -> Result<(), ()> {
+	if cpu.supported_extensions.has(ext) {
+		Ok(())
+	} else {
+		cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+		Err(())
+	}
+}
+
+// func3 is 0b010, 0b100 or 0b110 for a shift of 1, 2 or 3
+fn shift_add_amount(func3: u8) -> u32 {
+	u32::from(func3 >> 1)
+}
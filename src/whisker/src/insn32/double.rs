@@ -0,0 +1,245 @@
+use crate::{
+	cpu::WhiskerCpu,
+	insn::double::DoubleInstruction,
+	soft::RoundingMode,
+	ty::{RegisterIndex, TrapIdx},
+};
+
+use super::{IType, RType, SType};
+
+impl DoubleInstruction {
+	pub fn parse_load_fp(cpu: &mut WhiskerCpu, itype: IType) -> Result<DoubleInstruction, ()> {
+		use crate::insn32::load_fp::consts::*;
+		match itype.func() {
+			FLOAT_LOAD_DOUBLE_WORD => Ok(DoubleInstruction::LoadDoubleWord {
+				dst: itype.dst().to_fp(),
+				src: itype.src().to_gp(),
+				src_offset: itype.imm(),
+			}),
+			_ => {
+				cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+				Err(())
+			}
+		}
+	}
+
+	pub fn parse_store_fp(cpu: &mut WhiskerCpu, stype: SType) -> Result<DoubleInstruction, ()> {
+		use crate::insn32::store_fp::consts::*;
+		match stype.func() {
+			FLOAT_STORE_DOUBLE_WORD => Ok(DoubleInstruction::StoreDoubleWord {
+				dst: stype.src1().to_gp(),
+				dst_offset: stype.imm(),
+				src: stype.src2().to_fp(),
+			}),
+			_ => {
+				cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+				Err(())
+			}
+		}
+	}
+
+	pub fn parse_op_fp(cpu: &mut WhiskerCpu, rtype: RType, rm: RoundingMode) -> Result<DoubleInstruction, ()> {
+		use crate::insn32::op_fp::consts::*;
+		match rtype.func7() {
+			ADD_DOUBLE => Ok(DoubleInstruction::Add {
+				dst: rtype.dst().into(),
+				lhs: rtype.src1().into(),
+				rhs: rtype.src2().into(),
+				rm,
+			}),
+			SUB_DOUBLE => Ok(DoubleInstruction::Sub {
+				dst: rtype.dst().into(),
+				lhs: rtype.src1().into(),
+				rhs: rtype.src2().into(),
+				rm,
+			}),
+			MUL_DOUBLE => Ok(DoubleInstruction::Mul {
+				dst: rtype.dst().into(),
+				lhs: rtype.src1().into(),
+				rhs: rtype.src2().into(),
+				rm,
+			}),
+			DIV_DOUBLE => Ok(DoubleInstruction::Div {
+				dst: rtype.dst().into(),
+				lhs: rtype.src1().into(),
+				rhs: rtype.src2().into(),
+				rm,
+			}),
+			SQRT_DOUBLE => {
+				if rtype.src2() != RegisterIndex::ZERO {
+					cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+					Err(())
+				} else {
+					Ok(DoubleInstruction::Sqrt {
+						dst: rtype.dst().to_fp(),
+						val: rtype.src1().to_fp(),
+						rm,
+					})
+				}
+			}
+			MIN_MAX_DOUBLE => match rtype.func3() {
+				min_max::MIN => Ok(DoubleInstruction::Min {
+					dst: rtype.dst().into(),
+					lhs: rtype.src1().into(),
+					rhs: rtype.src2().into(),
+				}),
+				min_max::MAX => Ok(DoubleInstruction::Max {
+					dst: rtype.dst().into(),
+					lhs: rtype.src1().into(),
+					rhs: rtype.src2().into(),
+				}),
+				_ => {
+					cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+					Err(())
+				}
+			},
+			CMP_DOUBLE => match rtype.func3() {
+				cmp::EQ => Ok(DoubleInstruction::Equal {
+					dst: rtype.dst().to_gp(),
+					lhs: rtype.src1().to_fp(),
+					rhs: rtype.src2().to_fp(),
+				}),
+				cmp::LESS_EQ => Ok(DoubleInstruction::LessOrEqual {
+					dst: rtype.dst().to_gp(),
+					lhs: rtype.src1().to_fp(),
+					rhs: rtype.src2().to_fp(),
+				}),
+				cmp::LESS_THAN => Ok(DoubleInstruction::LessThan {
+					dst: rtype.dst().to_gp(),
+					lhs: rtype.src1().to_fp(),
+					rhs: rtype.src2().to_fp(),
+				}),
+				_ => {
+					cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+					Err(())
+				}
+			},
+
+			MV_INT_CLASS_DOUBLE => match (rtype.func3(), rtype.src2().as_usize()) {
+				(mv_class::MV, 0) => Ok(DoubleInstruction::MoveToInt {
+					dst: rtype.dst().to_gp(),
+					src: rtype.src1().to_fp(),
+				}),
+				(mv_class::CLASS, 0) => Ok(DoubleInstruction::Classify {
+					dst: rtype.dst().to_gp(),
+					src: rtype.src1().to_fp(),
+				}),
+				_ => {
+					cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+					Err(())
+				}
+			},
+			MV_DOUBLE_INT => match (rtype.func3(), rtype.src2().as_usize()) {
+				(mv_class::MV, 0) => Ok(DoubleInstruction::MoveFromInt {
+					dst: rtype.dst().to_fp(),
+					src: rtype.src1().to_gp(),
+				}),
+				_ => {
+					cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+					Err(())
+				}
+			},
+			SIGN_INJECT_DOUBLE => match rtype.func3() {
+				sign_inject::NORMAL => Ok(DoubleInstruction::SignInject {
+					dst: rtype.dst().to_fp(),
+					lhs: rtype.src1().to_fp(),
+					rhs: rtype.src2().to_fp(),
+				}),
+				sign_inject::NEGATE => Ok(DoubleInstruction::SignInjectNegate {
+					dst: rtype.dst().to_fp(),
+					lhs: rtype.src1().to_fp(),
+					rhs: rtype.src2().to_fp(),
+				}),
+				sign_inject::XOR => Ok(DoubleInstruction::SignInjectXor {
+					dst: rtype.dst().to_fp(),
+					lhs: rtype.src1().to_fp(),
+					rhs: rtype.src2().to_fp(),
+				}),
+				_ => {
+					cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+					Err(())
+				}
+			},
+			CVT_SINGLE_DOUBLE => {
+				if rtype.src2().as_usize() as u8 != cvt::FROM_DOUBLE {
+					cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+					Err(())
+				} else {
+					Ok(DoubleInstruction::ConvertToFloat {
+						dst: rtype.dst().to_fp(),
+						src: rtype.src1().to_fp(),
+						rm,
+					})
+				}
+			}
+			CVT_DOUBLE_SINGLE => {
+				if rtype.src2().as_usize() as u8 != cvt::FROM_SINGLE {
+					cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+					Err(())
+				} else {
+					Ok(DoubleInstruction::ConvertFromFloat {
+						dst: rtype.dst().to_fp(),
+						src: rtype.src1().to_fp(),
+					})
+				}
+			}
+			CVT_INT_DOUBLE => match rtype.src2().as_usize() as u8 {
+				cvt::WORD => Ok(DoubleInstruction::ConvertToWord {
+					dst: rtype.dst().to_gp(),
+					src: rtype.src1().to_fp(),
+					rm,
+				}),
+				cvt::UNSIGNED_WORD => Ok(DoubleInstruction::ConvertToUnsignedWord {
+					dst: rtype.dst().to_gp(),
+					src: rtype.src1().to_fp(),
+					rm,
+				}),
+				cvt::DOUBLE_WORD => Ok(DoubleInstruction::ConvertToDoubleWord {
+					dst: rtype.dst().to_gp(),
+					src: rtype.src1().to_fp(),
+					rm,
+				}),
+				cvt::UNSIGNED_DOUBLE_WORD => Ok(DoubleInstruction::ConvertToUnsignedDoubleWord {
+					dst: rtype.dst().to_gp(),
+					src: rtype.src1().to_fp(),
+					rm,
+				}),
+				_ => {
+					cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+					Err(())
+				}
+			},
+			CVT_DOUBLE_INT => match rtype.src2().as_usize() as u8 {
+				cvt::WORD => Ok(DoubleInstruction::ConvertFromWord {
+					dst: rtype.dst().to_fp(),
+					src: rtype.src1().to_gp(),
+					rm,
+				}),
+				cvt::UNSIGNED_WORD => Ok(DoubleInstruction::ConvertFromUnsignedWord {
+					dst: rtype.dst().to_fp(),
+					src: rtype.src1().to_gp(),
+					rm,
+				}),
+				cvt::DOUBLE_WORD => Ok(DoubleInstruction::ConvertFromDoubleWord {
+					dst: rtype.dst().to_fp(),
+					src: rtype.src1().to_gp(),
+					rm,
+				}),
+				cvt::UNSIGNED_DOUBLE_WORD => Ok(DoubleInstruction::ConvertFromUnsignedDoubleWord {
+					dst: rtype.dst().to_fp(),
+					src: rtype.src1().to_gp(),
+					rm,
+				}),
+				_ => {
+					cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+					Err(())
+				}
+			},
+
+			_ => {
+				cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+				Err(())
+			}
+		}
+	}
+}
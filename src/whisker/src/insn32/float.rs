@@ -108,6 +108,103 @@ impl FloatInstruction {
 					Err(())
 				}
 			},
+			MV_INT_CLASS_SINGLE => match (rtype.func3(), rtype.src2().as_usize()) {
+				(mv_class::MV, 0) => Ok(FloatInstruction::MoveToInt {
+					dst: rtype.dst().to_gp(),
+					src: rtype.src1().to_fp(),
+				}),
+				(mv_class::CLASS, 0) => Ok(FloatInstruction::Classify {
+					dst: rtype.dst().to_gp(),
+					src: rtype.src1().to_fp(),
+				}),
+				_ => {
+					cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+					Err(())
+				}
+			},
+			MV_SINGLE_INT => match (rtype.func3(), rtype.src2().as_usize()) {
+				(mv_class::MV, 0) => Ok(FloatInstruction::MoveFromInt {
+					dst: rtype.dst().to_fp(),
+					src: rtype.src1().to_gp(),
+				}),
+				_ => {
+					cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+					Err(())
+				}
+			},
+			SIGN_INJECT_SINGLE => match rtype.func3() {
+				sign_inject::NORMAL => Ok(FloatInstruction::SignInject {
+					dst: rtype.dst().to_fp(),
+					lhs: rtype.src1().to_fp(),
+					rhs: rtype.src2().to_fp(),
+				}),
+				sign_inject::NEGATE => Ok(FloatInstruction::SignInjectNegate {
+					dst: rtype.dst().to_fp(),
+					lhs: rtype.src1().to_fp(),
+					rhs: rtype.src2().to_fp(),
+				}),
+				sign_inject::XOR => Ok(FloatInstruction::SignInjectXor {
+					dst: rtype.dst().to_fp(),
+					lhs: rtype.src1().to_fp(),
+					rhs: rtype.src2().to_fp(),
+				}),
+				_ => {
+					cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+					Err(())
+				}
+			},
+			CVT_INT_SINGLE => match rtype.src2().as_usize() as u8 {
+				cvt::WORD => Ok(FloatInstruction::ConvertToWord {
+					dst: rtype.dst().to_gp(),
+					src: rtype.src1().to_fp(),
+					rm,
+				}),
+				cvt::UNSIGNED_WORD => Ok(FloatInstruction::ConvertToUnsignedWord {
+					dst: rtype.dst().to_gp(),
+					src: rtype.src1().to_fp(),
+					rm,
+				}),
+				cvt::DOUBLE_WORD => Ok(FloatInstruction::ConvertToDoubleWord {
+					dst: rtype.dst().to_gp(),
+					src: rtype.src1().to_fp(),
+					rm,
+				}),
+				cvt::UNSIGNED_DOUBLE_WORD => Ok(FloatInstruction::ConvertToUnsignedDoubleWord {
+					dst: rtype.dst().to_gp(),
+					src: rtype.src1().to_fp(),
+					rm,
+				}),
+				_ => {
+					cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+					Err(())
+				}
+			},
+			CVT_SINGLE_INT => match rtype.src2().as_usize() as u8 {
+				cvt::WORD => Ok(FloatInstruction::ConvertFromWord {
+					dst: rtype.dst().to_fp(),
+					src: rtype.src1().to_gp(),
+					rm,
+				}),
+				cvt::UNSIGNED_WORD => Ok(FloatInstruction::ConvertFromUnsignedWord {
+					dst: rtype.dst().to_fp(),
+					src: rtype.src1().to_gp(),
+					rm,
+				}),
+				cvt::DOUBLE_WORD => Ok(FloatInstruction::ConvertFromDoubleWord {
+					dst: rtype.dst().to_fp(),
+					src: rtype.src1().to_gp(),
+					rm,
+				}),
+				cvt::UNSIGNED_DOUBLE_WORD => Ok(FloatInstruction::ConvertFromUnsignedDoubleWord {
+					dst: rtype.dst().to_fp(),
+					src: rtype.src1().to_gp(),
+					rm,
+				}),
+				_ => {
+					cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+					Err(())
+				}
+			},
 
 			_ => unimplemented!("OP-FP func7={:#09b}", rtype.func7()),
 		}
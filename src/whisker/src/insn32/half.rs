@@ -0,0 +1,254 @@
+use crate::{
+	cpu::WhiskerCpu,
+	insn::half::HalfInstruction,
+	soft::RoundingMode,
+	ty::{RegisterIndex, SupportedExtensions, TrapIdx},
+};
+
+use super::{IType, RType, SType};
+
+impl HalfInstruction {
+	pub fn parse_load_fp(cpu: &mut WhiskerCpu, itype: IType) -> Result<HalfInstruction, ()> {
+		use crate::insn32::load_fp::consts::*;
+		match itype.func() {
+			FLOAT_LOAD_HALF => Ok(HalfInstruction::LoadHalf {
+				dst: itype.dst().to_fp(),
+				src: itype.src().to_gp(),
+				src_offset: itype.imm(),
+			}),
+			_ => {
+				cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+				Err(())
+			}
+		}
+	}
+
+	pub fn parse_store_fp(cpu: &mut WhiskerCpu, stype: SType) -> Result<HalfInstruction, ()> {
+		use crate::insn32::store_fp::consts::*;
+		match stype.func() {
+			FLOAT_STORE_HALF => Ok(HalfInstruction::StoreHalf {
+				dst: stype.src1().to_gp(),
+				dst_offset: stype.imm(),
+				src: stype.src2().to_fp(),
+			}),
+			_ => {
+				cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+				Err(())
+			}
+		}
+	}
+
+	pub fn parse_op_fp(cpu: &mut WhiskerCpu, rtype: RType, rm: RoundingMode) -> Result<HalfInstruction, ()> {
+		use crate::insn32::op_fp::consts::*;
+		match rtype.func7() {
+			ADD_HALF => Ok(HalfInstruction::Add {
+				dst: rtype.dst().into(),
+				lhs: rtype.src1().into(),
+				rhs: rtype.src2().into(),
+				rm,
+			}),
+			SUB_HALF => Ok(HalfInstruction::Sub {
+				dst: rtype.dst().into(),
+				lhs: rtype.src1().into(),
+				rhs: rtype.src2().into(),
+				rm,
+			}),
+			MUL_HALF => Ok(HalfInstruction::Mul {
+				dst: rtype.dst().into(),
+				lhs: rtype.src1().into(),
+				rhs: rtype.src2().into(),
+				rm,
+			}),
+			DIV_HALF => Ok(HalfInstruction::Div {
+				dst: rtype.dst().into(),
+				lhs: rtype.src1().into(),
+				rhs: rtype.src2().into(),
+				rm,
+			}),
+			SQRT_HALF => {
+				if rtype.src2() != RegisterIndex::ZERO {
+					cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+					Err(())
+				} else {
+					Ok(HalfInstruction::Sqrt {
+						dst: rtype.dst().to_fp(),
+						val: rtype.src1().to_fp(),
+						rm,
+					})
+				}
+			}
+			MIN_MAX_HALF => match rtype.func3() {
+				min_max::MIN => Ok(HalfInstruction::Min {
+					dst: rtype.dst().into(),
+					lhs: rtype.src1().into(),
+					rhs: rtype.src2().into(),
+				}),
+				min_max::MAX => Ok(HalfInstruction::Max {
+					dst: rtype.dst().into(),
+					lhs: rtype.src1().into(),
+					rhs: rtype.src2().into(),
+				}),
+				_ => {
+					cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+					Err(())
+				}
+			},
+			CMP_HALF => match rtype.func3() {
+				cmp::EQ => Ok(HalfInstruction::Equal {
+					dst: rtype.dst().to_gp(),
+					lhs: rtype.src1().to_fp(),
+					rhs: rtype.src2().to_fp(),
+				}),
+				cmp::LESS_EQ => Ok(HalfInstruction::LessOrEqual {
+					dst: rtype.dst().to_gp(),
+					lhs: rtype.src1().to_fp(),
+					rhs: rtype.src2().to_fp(),
+				}),
+				cmp::LESS_THAN => Ok(HalfInstruction::LessThan {
+					dst: rtype.dst().to_gp(),
+					lhs: rtype.src1().to_fp(),
+					rhs: rtype.src2().to_fp(),
+				}),
+				_ => {
+					cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+					Err(())
+				}
+			},
+
+			MV_INT_CLASS_HALF => match (rtype.func3(), rtype.src2().as_usize()) {
+				(mv_class::MV, 0) => Ok(HalfInstruction::MoveToInt {
+					dst: rtype.dst().to_gp(),
+					src: rtype.src1().to_fp(),
+				}),
+				(mv_class::CLASS, 0) => Ok(HalfInstruction::Classify {
+					dst: rtype.dst().to_gp(),
+					src: rtype.src1().to_fp(),
+				}),
+				_ => {
+					cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+					Err(())
+				}
+			},
+			MV_HALF_INT => match (rtype.func3(), rtype.src2().as_usize()) {
+				(mv_class::MV, 0) => Ok(HalfInstruction::MoveFromInt {
+					dst: rtype.dst().to_fp(),
+					src: rtype.src1().to_gp(),
+				}),
+				_ => {
+					cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+					Err(())
+				}
+			},
+			SIGN_INJECT_HALF => match rtype.func3() {
+				sign_inject::NORMAL => Ok(HalfInstruction::SignInject {
+					dst: rtype.dst().to_fp(),
+					lhs: rtype.src1().to_fp(),
+					rhs: rtype.src2().to_fp(),
+				}),
+				sign_inject::NEGATE => Ok(HalfInstruction::SignInjectNegate {
+					dst: rtype.dst().to_fp(),
+					lhs: rtype.src1().to_fp(),
+					rhs: rtype.src2().to_fp(),
+				}),
+				sign_inject::XOR => Ok(HalfInstruction::SignInjectXor {
+					dst: rtype.dst().to_fp(),
+					lhs: rtype.src1().to_fp(),
+					rhs: rtype.src2().to_fp(),
+				}),
+				_ => {
+					cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+					Err(())
+				}
+			},
+			CVT_SINGLE_DOUBLE => Ok(HalfInstruction::ConvertToFloat {
+				dst: rtype.dst().to_fp(),
+				src: rtype.src1().to_fp(),
+			}),
+			CVT_DOUBLE_SINGLE => {
+				if !cpu.supported_extensions.has(SupportedExtensions::DOUBLE) {
+					cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+					return Err(());
+				}
+				Ok(HalfInstruction::ConvertToDouble {
+					dst: rtype.dst().to_fp(),
+					src: rtype.src1().to_fp(),
+				})
+			}
+			CVT_HALF_FLOAT => match rtype.src2().as_usize() as u8 {
+				cvt::FROM_SINGLE => Ok(HalfInstruction::ConvertFromFloat {
+					dst: rtype.dst().to_fp(),
+					src: rtype.src1().to_fp(),
+					rm,
+				}),
+				cvt::FROM_DOUBLE if cpu.supported_extensions.has(SupportedExtensions::DOUBLE) => {
+					Ok(HalfInstruction::ConvertFromDouble {
+						dst: rtype.dst().to_fp(),
+						src: rtype.src1().to_fp(),
+						rm,
+					})
+				}
+				_ => {
+					cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+					Err(())
+				}
+			},
+			CVT_INT_HALF => match rtype.src2().as_usize() as u8 {
+				cvt::WORD => Ok(HalfInstruction::ConvertToWord {
+					dst: rtype.dst().to_gp(),
+					src: rtype.src1().to_fp(),
+					rm,
+				}),
+				cvt::UNSIGNED_WORD => Ok(HalfInstruction::ConvertToUnsignedWord {
+					dst: rtype.dst().to_gp(),
+					src: rtype.src1().to_fp(),
+					rm,
+				}),
+				cvt::DOUBLE_WORD => Ok(HalfInstruction::ConvertToDoubleWord {
+					dst: rtype.dst().to_gp(),
+					src: rtype.src1().to_fp(),
+					rm,
+				}),
+				cvt::UNSIGNED_DOUBLE_WORD => Ok(HalfInstruction::ConvertToUnsignedDoubleWord {
+					dst: rtype.dst().to_gp(),
+					src: rtype.src1().to_fp(),
+					rm,
+				}),
+				_ => {
+					cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+					Err(())
+				}
+			},
+			CVT_HALF_INT => match rtype.src2().as_usize() as u8 {
+				cvt::WORD => Ok(HalfInstruction::ConvertFromWord {
+					dst: rtype.dst().to_fp(),
+					src: rtype.src1().to_gp(),
+					rm,
+				}),
+				cvt::UNSIGNED_WORD => Ok(HalfInstruction::ConvertFromUnsignedWord {
+					dst: rtype.dst().to_fp(),
+					src: rtype.src1().to_gp(),
+					rm,
+				}),
+				cvt::DOUBLE_WORD => Ok(HalfInstruction::ConvertFromDoubleWord {
+					dst: rtype.dst().to_fp(),
+					src: rtype.src1().to_gp(),
+					rm,
+				}),
+				cvt::UNSIGNED_DOUBLE_WORD => Ok(HalfInstruction::ConvertFromUnsignedDoubleWord {
+					dst: rtype.dst().to_fp(),
+					src: rtype.src1().to_gp(),
+					rm,
+				}),
+				_ => {
+					cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+					Err(())
+				}
+			},
+
+			_ => {
+				cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+				Err(())
+			}
+		}
+	}
+}
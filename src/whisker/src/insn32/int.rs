@@ -216,7 +216,9 @@ impl IntInstruction {
     		OR, Or,
     		XOR, Xor,
     		SET_LESS_THAN, SetLessThan,
-    		SET_LESS_THAN_UNSIGNED, SetLessThanUnsigned
+    		SET_LESS_THAN_UNSIGNED, SetLessThanUnsigned,
+    		CONDITIONAL_ZERO_EQUAL_ZERO, ConditionalZeroIfEqualZero,
+    		CONDITIONAL_ZERO_NOT_EQUAL_ZERO, ConditionalZeroIfNotEqualZero
     	)
 	}
 
@@ -1,12 +1,15 @@
 use crate::{
 	cpu::WhiskerCpu,
-	insn::{float::FloatInstruction, Instruction},
+	insn::{double::DoubleInstruction, float::FloatInstruction, half::HalfInstruction, Instruction},
 	insn32::R4Type,
 	soft::RoundingMode,
 	ty::{SupportedExtensions, TrapIdx},
+	util::extract_bits_32,
 };
 
+/// parses all 4 fused multiply-add opcodes (MADD, MSUB, NMSUB and NMADD) since they share a format
 pub fn parse_madd(cpu: &mut WhiskerCpu, parcel: u32) -> Result<Instruction, ()> {
+	use crate::insn32::consts::opcode::*;
 	use crate::insn32::consts::*;
 
 	// MADD type is reserved for standard F extension only
@@ -23,17 +26,129 @@ pub fn parse_madd(cpu: &mut WhiskerCpu, parcel: u32) -> Result<Instruction, ()>
 		return Err(());
 	};
 
+	let dst = r4type.dst().to_fp();
+	let mul_lhs = r4type.src1().to_fp();
+	let mul_rhs = r4type.src2().to_fp();
+	let add = r4type.src3().to_fp();
+	let opcode = extract_bits_32(parcel, 2, 6);
+
 	let fmt = r4type.func2();
 
 	match fmt {
-		SINGLE_PRECISION => Ok(FloatInstruction::MulAdd {
-			dst: r4type.dst().to_fp(),
-			mul_lhs: r4type.src1().to_fp(),
-			mul_rhs: r4type.src2().to_fp(),
-			add: r4type.src3().to_fp(),
-			rm,
+		SINGLE_PRECISION => Ok(match opcode {
+			MADD => FloatInstruction::MulAdd {
+				dst,
+				mul_lhs,
+				mul_rhs,
+				add,
+				rm,
+			},
+			MSUB => FloatInstruction::MulSub {
+				dst,
+				mul_lhs,
+				mul_rhs,
+				sub: add,
+				rm,
+			},
+			NMSUB => FloatInstruction::NegMulSub {
+				dst,
+				mul_lhs,
+				mul_rhs,
+				sub: add,
+				rm,
+			},
+			NMADD => FloatInstruction::NegMulAdd {
+				dst,
+				mul_lhs,
+				mul_rhs,
+				add,
+				rm,
+			},
+			_ => unreachable!(),
 		}
 		.into()),
-		_ => unimplemented!("op-madd func2:{fmt:#04b}"),
+		DOUBLE_PRECISION => {
+			if cpu.supported_extensions.has(SupportedExtensions::DOUBLE) {
+				Ok(match opcode {
+					MADD => DoubleInstruction::MulAdd {
+						dst,
+						mul_lhs,
+						mul_rhs,
+						add,
+						rm,
+					},
+					MSUB => DoubleInstruction::MulSub {
+						dst,
+						mul_lhs,
+						mul_rhs,
+						sub: add,
+						rm,
+					},
+					NMSUB => DoubleInstruction::NegMulSub {
+						dst,
+						mul_lhs,
+						mul_rhs,
+						sub: add,
+						rm,
+					},
+					NMADD => DoubleInstruction::NegMulAdd {
+						dst,
+						mul_lhs,
+						mul_rhs,
+						add,
+						rm,
+					},
+					_ => unreachable!(),
+				}
+				.into())
+			} else {
+				cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+				Err(())
+			}
+		}
+		HALF_PRECISION => {
+			if cpu.supported_extensions.has(SupportedExtensions::ZFH) {
+				Ok(match opcode {
+					MADD => HalfInstruction::MulAdd {
+						dst,
+						mul_lhs,
+						mul_rhs,
+						add,
+						rm,
+					},
+					MSUB => HalfInstruction::MulSub {
+						dst,
+						mul_lhs,
+						mul_rhs,
+						sub: add,
+						rm,
+					},
+					NMSUB => HalfInstruction::NegMulSub {
+						dst,
+						mul_lhs,
+						mul_rhs,
+						sub: add,
+						rm,
+					},
+					NMADD => HalfInstruction::NegMulAdd {
+						dst,
+						mul_lhs,
+						mul_rhs,
+						add,
+						rm,
+					},
+					_ => unreachable!(),
+				}
+				.into())
+			} else {
+				cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+				Err(())
+			}
+		}
+		// quad precision
+		_ => {
+			cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, u64::from(parcel));
+			Err(())
+		}
 	}
 }
@@ -0,0 +1,49 @@
+use crate::{
+	cpu::WhiskerCpu,
+	insn::{int::IntInstruction, Instruction},
+	insn32::IType,
+	ty::{SupportedExtensions, TrapIdx},
+	util::extract_bits_32,
+};
+
+pub fn parse_misc_mem(cpu: &mut WhiskerCpu, parcel: u32) -> Result<Instruction, ()> {
+	use consts::*;
+
+	let itype = IType::parse(parcel);
+	match itype.func() {
+		FENCE => {
+			let fm = extract_bits_32(parcel, 28, 31) as u8;
+			let pred = extract_bits_32(parcel, 24, 27) as u8;
+			let succ = extract_bits_32(parcel, 20, 23) as u8;
+			let is_pause =
+				fm == 0 && pred == ORDER_W && succ == 0 && itype.dst().as_usize() == 0 && itype.src().as_usize() == 0;
+			// unknown fm values must be treated as a regular fence
+			if fm == FM_TSO && pred == ORDER_RW && succ == ORDER_RW {
+				Ok(IntInstruction::FenceTso.into())
+			} else if is_pause && cpu.supported_extensions.has(SupportedExtensions::ZIHINTPAUSE) {
+				Ok(IntInstruction::Pause.into())
+			} else {
+				Ok(IntInstruction::Fence {
+					_pred: pred,
+					_succ: succ,
+				}
+				.into())
+			}
+		}
+		FENCE_I => Ok(IntInstruction::FenceInstruction.into()),
+		_ => {
+			cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+			Err(())
+		}
+	}
+}
+
+pub mod consts {
+	pub const FENCE: u8 = 0b000;
+	pub const FENCE_I: u8 = 0b001;
+
+	pub const FM_TSO: u8 = 0b1000;
+	// predecessor/successor bits are IORW from high to low
+	pub const ORDER_RW: u8 = 0b0011;
+	pub const ORDER_W: u8 = 0b0001;
+}
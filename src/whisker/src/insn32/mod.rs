@@ -1,27 +1,33 @@
 pub mod amo;
+pub mod bitmanip;
 pub mod branch;
+pub mod double;
 pub mod float;
+pub mod half;
 pub mod int;
 pub mod jalr;
 pub mod load;
 pub mod load_fp;
 pub mod madd;
+pub mod misc_mem;
 pub mod multiply;
 pub mod op;
 pub mod op_32;
 pub mod op_fp;
 pub mod op_imm;
 pub mod op_imm_32;
+pub mod op_v;
 pub mod store;
 pub mod store_fp;
 pub mod system;
+pub mod vector;
 
 pub use ty::*;
 
 use crate::{
 	cpu::WhiskerCpu,
 	insn::{int::IntInstruction, Instruction},
-	ty::{RegisterIndex, UnknownRegisterIndex},
+	ty::{RegisterIndex, TrapIdx, UnknownRegisterIndex},
 	util::extract_bits_32,
 };
 
@@ -31,8 +37,7 @@ pub fn parse(cpu: &mut WhiskerCpu, parcel: u32) -> Result<Instruction, ()> {
 	match opcode_ty {
 		LOAD => load::parse_load(cpu, parcel),
 		LOAD_FP => load_fp::parse_load_fp(cpu, parcel),
-		CUSTOM_0 => todo!("CUSTOM_0"),
-		MISC_MEM => todo!("MISC_MEM"),
+		MISC_MEM => misc_mem::parse_misc_mem(cpu, parcel),
 		OP_IMM => op_imm::parse_op_imm(cpu, parcel),
 		AUIPC => {
 			let utype = UType::parse(parcel);
@@ -43,10 +48,8 @@ pub fn parse(cpu: &mut WhiskerCpu, parcel: u32) -> Result<Instruction, ()> {
 			.into())
 		}
 		OP_IMM_32 => op_imm_32::parse_op_imm_32(cpu, parcel),
-		UNK_48B => todo!("UNK_48B"),
 		STORE => store::parse_store(cpu, parcel),
 		STORE_FP => store_fp::parse_store_fp(cpu, parcel),
-		CUSTOM_1 => todo!("CUSTOM_1"),
 		AMO => amo::parse_amo(cpu, parcel),
 		OP => op::parse_op(cpu, parcel),
 		LUI => {
@@ -58,18 +61,12 @@ pub fn parse(cpu: &mut WhiskerCpu, parcel: u32) -> Result<Instruction, ()> {
 			.into())
 		}
 		OP_32 => op_32::parse_op_32(cpu, parcel),
-		UNK_64B => todo!("UNK_64B"),
 		MADD => madd::parse_madd(cpu, parcel),
-		MSUB => todo!("MSUB"),
-		NMSUB => todo!("NMSUB"),
-		NMADD => todo!("NMADD"),
+		MSUB | NMSUB | NMADD => madd::parse_madd(cpu, parcel),
 		OP_FP => op_fp::parse_op_fp(cpu, parcel),
-		OP_V => todo!("OP_V"),
-		CUSTOM_2 => todo!("CUSTOM_2"),
-		UNK_48B2 => todo!("UNK_48B2"),
+		OP_V => op_v::parse_op_v(cpu, parcel),
 		BRANCH => branch::parse_branch(cpu, parcel),
 		JALR => jalr::parse_jalr(cpu, parcel),
-		RESERVED => todo!("RESERVED"),
 		JAL => {
 			let jtype = JType::parse(parcel);
 			Ok(IntInstruction::JumpAndLink {
@@ -79,9 +76,11 @@ pub fn parse(cpu: &mut WhiskerCpu, parcel: u32) -> Result<Instruction, ()> {
 			.into())
 		}
 		SYSTEM => system::parse_system(cpu, parcel),
-		OP_VE => todo!("OP_VE"),
-		CUSTOM_3 => todo!("CUSTOM_3"),
-		UNK_80B => todo!("UNK_80B"),
+		// reserved and custom opcodes, and the wide formats which fetch never hands to us
+		CUSTOM_0 | CUSTOM_1 | CUSTOM_2 | CUSTOM_3 | RESERVED | OP_VE | UNK_48B | UNK_64B | UNK_48B2 | UNK_80B => {
+			cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, u64::from(parcel));
+			Err(())
+		}
 		// should have exhaustively matched all possible opcode types
 		_ => unreachable!(),
 	}
@@ -387,9 +386,7 @@ mod ty {
 
 mod consts {
 	pub const SINGLE_PRECISION: u8 = 0b00;
-	#[allow(unused)]
 	pub const DOUBLE_PRECISION: u8 = 0b01;
-	#[allow(unused)]
 	pub const HALF_PRECISION: u8 = 0b10;
 	#[allow(unused)]
 	pub const QUAD_PRECISION: u8 = 0b11;
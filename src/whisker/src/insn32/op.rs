@@ -1,6 +1,6 @@
 use crate::{
 	cpu::WhiskerCpu,
-	insn::{int::IntInstruction, multiply::MultiplyInstruction, Instruction},
+	insn::{bitmanip::BitmanipInstruction, int::IntInstruction, multiply::MultiplyInstruction, Instruction},
 	insn32::RType,
 	ty::{SupportedExtensions, TrapIdx},
 };
@@ -29,6 +29,16 @@ pub fn parse_op(cpu: &mut WhiskerCpu, parcel: u32) -> Result<Instruction, ()> {
 			}
 		}
 
+		CONDITIONAL_ZERO_EQUAL_ZERO | CONDITIONAL_ZERO_NOT_EQUAL_ZERO => {
+			if cpu.supported_extensions.has(SupportedExtensions::ZICOND) {
+				let insn = IntInstruction::parse_op(rtype);
+				Ok(insn.into())
+			} else {
+				cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+				Err(())
+			}
+		}
+
 		MUL | MULH | MULHSU | MULHU | DIV | DIVU | REM | REMU => {
 			if cpu.supported_extensions.has(SupportedExtensions::MULTIPLY) {
 				let insn = MultiplyInstruction::parse_op(cpu, rtype)?;
@@ -38,6 +48,30 @@ pub fn parse_op(cpu: &mut WhiskerCpu, parcel: u32) -> Result<Instruction, ()> {
 				Err(())
 			}
 		}
+
+		SH1ADD
+		| SH2ADD
+		| SH3ADD
+		| AND_NOT
+		| OR_NOT
+		| XOR_NOT
+		| MIN
+		| MIN_UNSIGNED
+		| MAX
+		| MAX_UNSIGNED
+		| ROTATE_LEFT
+		| ROTATE_RIGHT
+		| CARRYLESS_MUL
+		| CARRYLESS_MUL_REVERSED
+		| CARRYLESS_MUL_HIGH
+		| BIT_CLEAR
+		| BIT_EXTRACT
+		| BIT_INVERT
+		| BIT_SET => {
+			// the bitmanip parser checks which of the B sub-extensions is required
+			let insn = BitmanipInstruction::parse_op(cpu, rtype)?;
+			Ok(insn.into())
+		}
 		_ => unimplemented!("OP func={:#014b} | {:#X}", rtype.func(), cpu.pc),
 	}
 }
@@ -62,4 +96,30 @@ pub mod consts {
 	pub const DIVU: u16 = 0b0000001101;
 	pub const REM: u16 = 0b0000001110;
 	pub const REMU: u16 = 0b0000001111;
+
+	pub const CONDITIONAL_ZERO_EQUAL_ZERO: u16 = 0b0000111101;
+	pub const CONDITIONAL_ZERO_NOT_EQUAL_ZERO: u16 = 0b0000111111;
+
+	pub const SH1ADD: u16 = 0b0010000010;
+	pub const SH2ADD: u16 = 0b0010000100;
+	pub const SH3ADD: u16 = 0b0010000110;
+
+	pub const AND_NOT: u16 = 0b0100000111;
+	pub const OR_NOT: u16 = 0b0100000110;
+	pub const XOR_NOT: u16 = 0b0100000100;
+	pub const MIN: u16 = 0b0000101100;
+	pub const MIN_UNSIGNED: u16 = 0b0000101101;
+	pub const MAX: u16 = 0b0000101110;
+	pub const MAX_UNSIGNED: u16 = 0b0000101111;
+	pub const ROTATE_LEFT: u16 = 0b0110000001;
+	pub const ROTATE_RIGHT: u16 = 0b0110000101;
+
+	pub const CARRYLESS_MUL: u16 = 0b0000101001;
+	pub const CARRYLESS_MUL_REVERSED: u16 = 0b0000101010;
+	pub const CARRYLESS_MUL_HIGH: u16 = 0b0000101011;
+
+	pub const BIT_CLEAR: u16 = 0b0100100001;
+	pub const BIT_EXTRACT: u16 = 0b0100100101;
+	pub const BIT_INVERT: u16 = 0b0110100001;
+	pub const BIT_SET: u16 = 0b0010100001;
 }
@@ -1,6 +1,6 @@
 use crate::{
 	cpu::WhiskerCpu,
-	insn::{int::IntInstruction, multiply::MultiplyInstruction, Instruction},
+	insn::{bitmanip::BitmanipInstruction, int::IntInstruction, multiply::MultiplyInstruction, Instruction},
 	insn32::RType,
 	ty::{SupportedExtensions, TrapIdx},
 };
@@ -29,7 +29,17 @@ pub fn parse_op_32(cpu: &mut WhiskerCpu, parcel: u32) -> Result<Instruction, ()>
 				Err(())
 			}
 		}
-		_ => unimplemented!("OP-32 func={:#014b} | {:#X}", rtype.func(), cpu.pc),
+
+		ADD_UNSIGNED_WORD | SH1ADD_UNSIGNED_WORD | SH2ADD_UNSIGNED_WORD | SH3ADD_UNSIGNED_WORD | ZERO_EXTEND_HALF
+		| ROTATE_LEFT_WORD | ROTATE_RIGHT_WORD => {
+			// the bitmanip parser checks which of the B sub-extensions is required
+			let insn = BitmanipInstruction::parse_op_32(cpu, rtype)?;
+			Ok(insn.into())
+		}
+		_ => {
+			cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+			Err(())
+		}
 	}
 }
 
@@ -45,4 +55,13 @@ pub mod consts {
 	pub const DIV_UNSIGNED_WORD: u16 = 0b0000001101;
 	pub const REM_WORD: u16 = 0b0000001110;
 	pub const REM_UNSIGNED_WORD: u16 = 0b0000001111;
+
+	pub const ADD_UNSIGNED_WORD: u16 = 0b0000100000;
+	pub const SH1ADD_UNSIGNED_WORD: u16 = 0b0010000010;
+	pub const SH2ADD_UNSIGNED_WORD: u16 = 0b0010000100;
+	pub const SH3ADD_UNSIGNED_WORD: u16 = 0b0010000110;
+	// rs2 must be x0
+	pub const ZERO_EXTEND_HALF: u16 = 0b0000100100;
+	pub const ROTATE_LEFT_WORD: u16 = 0b0110000001;
+	pub const ROTATE_RIGHT_WORD: u16 = 0b0110000101;
 }
@@ -1,6 +1,6 @@
 use crate::{
 	cpu::WhiskerCpu,
-	insn::{float::FloatInstruction, Instruction},
+	insn::{double::DoubleInstruction, float::FloatInstruction, half::HalfInstruction, Instruction},
 	insn32::RType,
 	soft::RoundingMode,
 	ty::{SupportedExtensions, TrapIdx},
@@ -24,10 +24,43 @@ pub fn parse_op_fp(cpu: &mut WhiskerCpu, parcel: u32) -> Result<Instruction, ()>
 	};
 	let func7 = rtype.func7();
 	match func7 {
-		ADD_SINGLE | SUB_SINGLE | MUL_SINGLE | DIV_SINGLE | SQRT_SINGLE | MIN_MAX | CMP_SINGLE => {
+		// conversions from half precision share their func7 with the other conversions
+		CVT_SINGLE_DOUBLE | CVT_DOUBLE_SINGLE if rtype.src2().as_usize() as u8 == cvt::FROM_HALF => {
+			if cpu.supported_extensions.has(SupportedExtensions::ZFH) {
+				HalfInstruction::parse_op_fp(cpu, rtype, rm).map(|i| i.into())
+			} else {
+				cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+				Err(())
+			}
+		}
+		ADD_SINGLE | SUB_SINGLE | MUL_SINGLE | DIV_SINGLE | SQRT_SINGLE | MIN_MAX | CMP_SINGLE | CVT_INT_SINGLE
+		| CVT_SINGLE_INT | SIGN_INJECT_SINGLE | MV_INT_CLASS_SINGLE | MV_SINGLE_INT => {
 			FloatInstruction::parse_op_fp(cpu, rtype, rm).map(|i| i.into())
 		}
-		_ => unimplemented!("op-fp func7={func7:#09b}"),
+		ADD_DOUBLE | SUB_DOUBLE | MUL_DOUBLE | DIV_DOUBLE | SQRT_DOUBLE | MIN_MAX_DOUBLE | CMP_DOUBLE
+		| CVT_INT_DOUBLE | CVT_DOUBLE_INT | CVT_SINGLE_DOUBLE | CVT_DOUBLE_SINGLE | SIGN_INJECT_DOUBLE
+		| MV_INT_CLASS_DOUBLE | MV_DOUBLE_INT => {
+			if cpu.supported_extensions.has(SupportedExtensions::DOUBLE) {
+				DoubleInstruction::parse_op_fp(cpu, rtype, rm).map(|i| i.into())
+			} else {
+				cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+				Err(())
+			}
+		}
+		ADD_HALF | SUB_HALF | MUL_HALF | DIV_HALF | SQRT_HALF | MIN_MAX_HALF | CMP_HALF | CVT_INT_HALF
+		| CVT_HALF_INT | CVT_HALF_FLOAT | SIGN_INJECT_HALF | MV_INT_CLASS_HALF | MV_HALF_INT => {
+			if cpu.supported_extensions.has(SupportedExtensions::ZFH) {
+				HalfInstruction::parse_op_fp(cpu, rtype, rm).map(|i| i.into())
+			} else {
+				cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+				Err(())
+			}
+		}
+		// quad precision and reserved encodings
+		_ => {
+			cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, u64::from(parcel));
+			Err(())
+		}
 	}
 }
 
@@ -40,6 +73,31 @@ pub mod consts {
 	pub const SQRT_SINGLE: u8 = 0b0101100;
 	pub const MIN_MAX: u8 = 0b0010100;
 	pub const CMP_SINGLE: u8 = 0b1010000;
+	pub const SIGN_INJECT_SINGLE: u8 = 0b0010000;
+	// FMV.X.W and FCLASS.S, selected by func3
+	pub const MV_INT_CLASS_SINGLE: u8 = 0b1110000;
+	pub const MV_SINGLE_INT: u8 = 0b1111000;
+	// float -> int
+	pub const CVT_INT_SINGLE: u8 = 0b1100000;
+	// int -> float
+	pub const CVT_SINGLE_INT: u8 = 0b1101000;
+
+	pub const ADD_DOUBLE: u8 = 0b0000001;
+	pub const SUB_DOUBLE: u8 = 0b0000101;
+	pub const MUL_DOUBLE: u8 = 0b0001001;
+	pub const DIV_DOUBLE: u8 = 0b0001101;
+	pub const SQRT_DOUBLE: u8 = 0b0101101;
+	pub const MIN_MAX_DOUBLE: u8 = 0b0010101;
+	pub const CMP_DOUBLE: u8 = 0b1010001;
+	pub const SIGN_INJECT_DOUBLE: u8 = 0b0010001;
+	pub const MV_INT_CLASS_DOUBLE: u8 = 0b1110001;
+	pub const MV_DOUBLE_INT: u8 = 0b1111001;
+	pub const CVT_INT_DOUBLE: u8 = 0b1100001;
+	pub const CVT_DOUBLE_INT: u8 = 0b1101001;
+	// double -> single, rs2 selects the source format
+	pub const CVT_SINGLE_DOUBLE: u8 = 0b0100000;
+	// single -> double
+	pub const CVT_DOUBLE_SINGLE: u8 = 0b0100001;
 
 	pub mod min_max {
 		pub const MIN: u8 = 0b000;
@@ -51,4 +109,42 @@ pub mod consts {
 		pub const LESS_THAN: u8 = 0b001;
 		pub const EQ: u8 = 0b010;
 	}
+
+	pub const ADD_HALF: u8 = 0b0000010;
+	pub const SUB_HALF: u8 = 0b0000110;
+	pub const MUL_HALF: u8 = 0b0001010;
+	pub const DIV_HALF: u8 = 0b0001110;
+	pub const SQRT_HALF: u8 = 0b0101110;
+	pub const MIN_MAX_HALF: u8 = 0b0010110;
+	pub const CMP_HALF: u8 = 0b1010010;
+	pub const SIGN_INJECT_HALF: u8 = 0b0010010;
+	pub const MV_INT_CLASS_HALF: u8 = 0b1110010;
+	pub const MV_HALF_INT: u8 = 0b1111010;
+	pub const CVT_INT_HALF: u8 = 0b1100010;
+	pub const CVT_HALF_INT: u8 = 0b1101010;
+	// single/double -> half
+	pub const CVT_HALF_FLOAT: u8 = 0b0100010;
+
+	pub mod mv_class {
+		pub const MV: u8 = 0b000;
+		pub const CLASS: u8 = 0b001;
+	}
+
+	pub mod sign_inject {
+		pub const NORMAL: u8 = 0b000;
+		pub const NEGATE: u8 = 0b001;
+		pub const XOR: u8 = 0b010;
+	}
+
+	// selected by rs2
+	pub mod cvt {
+		pub const FROM_SINGLE: u8 = 0b00000;
+		pub const FROM_DOUBLE: u8 = 0b00001;
+		pub const FROM_HALF: u8 = 0b00010;
+
+		pub const WORD: u8 = 0b00000;
+		pub const UNSIGNED_WORD: u8 = 0b00001;
+		pub const DOUBLE_WORD: u8 = 0b00010;
+		pub const UNSIGNED_DOUBLE_WORD: u8 = 0b00011;
+	}
 }
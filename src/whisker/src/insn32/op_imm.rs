@@ -1,8 +1,9 @@
 use crate::{
 	cpu::WhiskerCpu,
-	insn::{int::IntInstruction, Instruction},
+	insn::{bitmanip::BitmanipInstruction, int::IntInstruction, Instruction},
 	insn32::IType,
 	ty::{SupportedExtensions, TrapIdx},
+	util::extract_bits_32,
 };
 
 pub fn parse_op_imm(cpu: &mut WhiskerCpu, parcel: u32) -> Result<Instruction, ()> {
@@ -10,6 +11,15 @@ pub fn parse_op_imm(cpu: &mut WhiskerCpu, parcel: u32) -> Result<Instruction, ()
 
 	let itype = IType::parse(parcel);
 	match itype.func() {
+		// the bitmanip extensions share func3 with the shifts, but use the other shift kinds
+		SHIFT_LEFT_IMM | SHIFT_RIGHT_IMM
+			if !matches!(
+				extract_bits_32(itype.imm() as u32, 6, 11) as u8,
+				SHIFT_LOGICAL | SHIFT_ARITHMETIC
+			) =>
+		{
+			Ok(BitmanipInstruction::parse_op_imm(cpu, itype)?.into())
+		}
 		ADD_IMM
 		| XOR_IMM
 		| OR_IMM
@@ -42,4 +52,19 @@ pub mod consts {
 
 	pub const SHIFT_LOGICAL: u8 = 0b000000;
 	pub const SHIFT_ARITHMETIC: u8 = 0b010000;
+	pub const SHIFT_ROTATE: u8 = 0b011000;
+	// Zbs, the low 6 bits of the immediate are the bit index
+	pub const BIT_CLEAR_IMM: u8 = 0b010010;
+	pub const BIT_EXTRACT_IMM: u8 = 0b010010;
+	pub const BIT_INVERT_IMM: u8 = 0b011010;
+	pub const BIT_SET_IMM: u8 = 0b001010;
+
+	// selected by the whole immediate
+	pub const COUNT_LEADING_ZEROS: u16 = 0x600;
+	pub const COUNT_TRAILING_ZEROS: u16 = 0x601;
+	pub const COUNT_POPULATION: u16 = 0x602;
+	pub const SIGN_EXTEND_BYTE: u16 = 0x604;
+	pub const SIGN_EXTEND_HALF: u16 = 0x605;
+	pub const OR_COMBINE_BYTE: u16 = 0x287;
+	pub const REVERSE_BYTES: u16 = 0x6B8;
 }
@@ -1,8 +1,9 @@
 use crate::{
 	cpu::WhiskerCpu,
-	insn::{int::IntInstruction, Instruction},
+	insn::{bitmanip::BitmanipInstruction, int::IntInstruction, Instruction},
 	insn32::IType,
 	ty::{SupportedExtensions, TrapIdx},
+	util::extract_bits_32,
 };
 
 pub fn parse_op_imm_32(cpu: &mut WhiskerCpu, parcel: u32) -> Result<Instruction, ()> {
@@ -10,6 +11,15 @@ pub fn parse_op_imm_32(cpu: &mut WhiskerCpu, parcel: u32) -> Result<Instruction,
 
 	let itype = IType::parse(parcel);
 	match itype.func() {
+		// the bitmanip extensions share func3 with the shifts, but use the other shift kinds
+		SHIFT_LEFT_IMM_WORD | SHIFT_RIGHT_IMM_WORD
+			if !matches!(
+				extract_bits_32(itype.imm() as u32, 6, 11) as u8,
+				SHIFT_LOGICAL | SHIFT_ARITHMETIC
+			) =>
+		{
+			Ok(BitmanipInstruction::parse_op_imm_32(cpu, itype)?.into())
+		}
 		ADD_IMM_WORD | SHIFT_LEFT_IMM_WORD | SHIFT_RIGHT_IMM_WORD => {
 			if cpu.supported_extensions.has(SupportedExtensions::INTEGER) {
 				Ok(IntInstruction::parse_op_imm_32(itype).into())
@@ -29,4 +39,11 @@ pub mod consts {
 
 	pub const SHIFT_LOGICAL: u8 = 0b000000;
 	pub const SHIFT_ARITHMETIC: u8 = 0b010000;
+	pub const SHIFT_LEFT_UNSIGNED_WORD: u8 = 0b000010;
+	pub const SHIFT_ROTATE: u8 = 0b011000;
+
+	// selected by the whole immediate
+	pub const COUNT_LEADING_ZEROS_WORD: u16 = 0x600;
+	pub const COUNT_TRAILING_ZEROS_WORD: u16 = 0x601;
+	pub const COUNT_POPULATION_WORD: u16 = 0x602;
 }
@@ -0,0 +1,73 @@
+use crate::{
+	cpu::WhiskerCpu,
+	insn::{vector::VectorInstruction, Instruction},
+	ty::{SupportedExtensions, TrapIdx},
+};
+
+pub fn parse_op_v(cpu: &mut WhiskerCpu, parcel: u32) -> Result<Instruction, ()> {
+	if cpu.supported_extensions.has(SupportedExtensions::VECTOR) {
+		VectorInstruction::parse_op_v(cpu, parcel).map(|i| i.into())
+	} else {
+		cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+		Err(())
+	}
+}
+
+pub mod consts {
+	// func3 selects the operand kinds
+	pub const OPIVV: u8 = 0b000;
+	pub const OPFVV: u8 = 0b001;
+	pub const OPMVV: u8 = 0b010;
+	pub const OPIVI: u8 = 0b011;
+	pub const OPIVX: u8 = 0b100;
+	pub const OPFVF: u8 = 0b101;
+	pub const OPMVX: u8 = 0b110;
+	// vsetvli, vsetivli and vsetvl
+	pub const OPCFG: u8 = 0b111;
+
+	// func6 for the OPI* formats
+	pub mod int {
+		pub const ADD: u8 = 0b000000;
+		pub const SUB: u8 = 0b000010;
+		pub const REVERSE_SUB: u8 = 0b000011;
+		pub const MIN_UNSIGNED: u8 = 0b000100;
+		pub const MIN: u8 = 0b000101;
+		pub const MAX_UNSIGNED: u8 = 0b000110;
+		pub const MAX: u8 = 0b000111;
+		pub const AND: u8 = 0b001001;
+		pub const OR: u8 = 0b001010;
+		pub const XOR: u8 = 0b001011;
+		// vmerge when masked, vmv.v otherwise
+		pub const MERGE_MOVE: u8 = 0b010111;
+		pub const SET_EQ: u8 = 0b011000;
+		pub const SET_NE: u8 = 0b011001;
+		pub const SET_LESS_THAN_UNSIGNED: u8 = 0b011010;
+		pub const SET_LESS_THAN: u8 = 0b011011;
+		pub const SET_LESS_EQ_UNSIGNED: u8 = 0b011100;
+		pub const SET_LESS_EQ: u8 = 0b011101;
+		pub const SET_GREATER_THAN_UNSIGNED: u8 = 0b011110;
+		pub const SET_GREATER_THAN: u8 = 0b011111;
+		pub const SHIFT_LEFT: u8 = 0b100101;
+		pub const SHIFT_RIGHT_LOGICAL: u8 = 0b101000;
+		pub const SHIFT_RIGHT_ARITHMETIC: u8 = 0b101001;
+	}
+
+	// func6 for the OPM* formats
+	pub mod mask {
+		// VWXUNARY0 for OPMVV, VRXUNARY0 for OPMVX
+		pub const UNARY0: u8 = 0b010000;
+		pub const MUL: u8 = 0b100101;
+
+		// VWXUNARY0 is selected by vs1
+		pub const MOVE_TO_SCALAR: u8 = 0b00000;
+		pub const POP_COUNT: u8 = 0b10000;
+		pub const FIND_FIRST: u8 = 0b10001;
+	}
+
+	// vector loads and stores share LOAD-FP and STORE-FP, their widths are in load_fp::consts
+	pub mod mem {
+		pub const MOP_UNIT_STRIDE: u8 = 0b00;
+		pub const UNIT_STRIDE: u8 = 0b00000;
+		pub const UNIT_STRIDE_FAULT_FIRST: u8 = 0b10000;
+	}
+}
@@ -1,6 +1,9 @@
 use crate::{
 	cpu::WhiskerCpu,
-	insn::{float::FloatInstruction, Instruction},
+	insn::{
+		double::DoubleInstruction, float::FloatInstruction, half::HalfInstruction, vector::VectorInstruction,
+		Instruction,
+	},
 	insn32::SType,
 	ty::{SupportedExtensions, TrapIdx},
 };
@@ -10,6 +13,14 @@ pub fn parse_store_fp(cpu: &mut WhiskerCpu, parcel: u32) -> Result<Instruction,
 
 	let stype = SType::parse(parcel);
 	match stype.func() {
+		FLOAT_STORE_HALF => {
+			if cpu.supported_extensions.has(SupportedExtensions::ZFH) {
+				HalfInstruction::parse_store_fp(cpu, stype).map(|i| i.into())
+			} else {
+				cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+				Err(())
+			}
+		}
 		FLOAT_STORE_WORD => {
 			if cpu.supported_extensions.has(SupportedExtensions::FLOAT) {
 				Ok(FloatInstruction::parse_store_fp(stype).into())
@@ -18,10 +29,37 @@ pub fn parse_store_fp(cpu: &mut WhiskerCpu, parcel: u32) -> Result<Instruction,
 				Err(())
 			}
 		}
-		_ => unreachable!("LOAD-FP func={:#05b}", stype.func()),
+		FLOAT_STORE_DOUBLE_WORD => {
+			if cpu.supported_extensions.has(SupportedExtensions::DOUBLE) {
+				DoubleInstruction::parse_store_fp(cpu, stype).map(|i| i.into())
+			} else {
+				cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+				Err(())
+			}
+		}
+		VECTOR_8 | VECTOR_16 | VECTOR_32 | VECTOR_64 => {
+			if cpu.supported_extensions.has(SupportedExtensions::VECTOR) {
+				VectorInstruction::parse_store_fp(cpu, parcel).map(|i| i.into())
+			} else {
+				cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+				Err(())
+			}
+		}
+		_ => {
+			cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+			Err(())
+		}
 	}
 }
 
 pub mod consts {
+	pub const FLOAT_STORE_HALF: u8 = 0b001;
 	pub const FLOAT_STORE_WORD: u8 = 0b010;
+	pub const FLOAT_STORE_DOUBLE_WORD: u8 = 0b011;
+
+	// vector accesses, the element width is encoded separately from the float widths
+	pub const VECTOR_8: u8 = 0b000;
+	pub const VECTOR_16: u8 = 0b101;
+	pub const VECTOR_32: u8 = 0b110;
+	pub const VECTOR_64: u8 = 0b111;
 }
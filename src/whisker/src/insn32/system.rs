@@ -1,7 +1,7 @@
 use crate::{
 	cpu::WhiskerCpu,
 	insn::{csr::CSRInstruction, int::IntInstruction, Instruction},
-	insn32::IType,
+	insn32::{IType, RType},
 	ty::{SupportedExtensions, TrapIdx},
 };
 
@@ -10,9 +10,28 @@ pub fn parse_system(cpu: &mut WhiskerCpu, parcel: u32) -> Result<Instruction, ()
 
 	let itype = IType::parse(parcel);
 	match itype.func() {
+		// sfence.vma is the only R-type instruction here
+		funcs::E_CALL_BREAK if RType::parse(parcel).func7() == SFENCE_VMA_FUNC7 => {
+			let rtype = RType::parse(parcel);
+			if cpu.supported_extensions.has(SupportedExtensions::SUPERVISOR) && rtype.dst().as_usize() == 0 {
+				Ok(IntInstruction::SupervisorFenceVirtualMemory {
+					vaddr: rtype.src1().to_gp(),
+					asid: rtype.src2().to_gp(),
+				}
+				.into())
+			} else {
+				cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+				Err(())
+			}
+		}
 		funcs::E_CALL_BREAK => {
-			if cpu.supported_extensions.has(SupportedExtensions::INTEGER) {
-				Ok(parse_call_break(itype).into())
+			let insn = parse_privileged(itype);
+			let supported = match insn {
+				IntInstruction::WaitOnReservationSet { .. } => SupportedExtensions::ZAWRS,
+				_ => SupportedExtensions::INTEGER,
+			};
+			if cpu.supported_extensions.has(supported) {
+				Ok(insn.into())
 			} else {
 				cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
 				Err(())
@@ -27,11 +46,16 @@ pub fn parse_system(cpu: &mut WhiskerCpu, parcel: u32) -> Result<Instruction, ()
 	}
 }
 
-fn parse_call_break(itype: IType) -> IntInstruction {
+fn parse_privileged(itype: IType) -> IntInstruction {
 	match (itype.dst().to_gp().as_usize(), itype.src().to_gp().as_usize()) {
 		(0, 0) => match itype.imm() {
 			0b000000000000 => IntInstruction::ECall,
 			0b000000000001 => IntInstruction::EBreak,
+			0b000100000101 => IntInstruction::WaitForInterrupt,
+			0b000000001101 => IntInstruction::WaitOnReservationSet { short_timeout: false },
+			0b000000011101 => IntInstruction::WaitOnReservationSet { short_timeout: true },
+			0b000100000010 => IntInstruction::SupervisorReturn,
+			0b001100000010 => IntInstruction::MachineReturn,
 			imm => unimplemented!("SYSTEM func=0b000 rd=0b00000 rs1=0b00000 imm={imm:#014b}"),
 		},
 		(rd, rs1) => unimplemented!("SYSTEM func=0b000 rd={rd:#07b} rs1={rs1:#07b}"),
@@ -90,4 +114,6 @@ pub mod consts {
 		pub const CSRRSI: u8 = 0b110;
 		pub const CSRRCI: u8 = 0b111;
 	}
+
+	pub const SFENCE_VMA_FUNC7: u8 = 0b0001001;
 }
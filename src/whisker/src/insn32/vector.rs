@@ -0,0 +1,245 @@
+use crate::{
+	cpu::WhiskerCpu,
+	insn::vector::{VectorCmpOp, VectorInstruction, VectorIntOp, VectorLengthSource, VectorOperand, VectorTypeSource},
+	ty::TrapIdx,
+	util::{extract_bits_32, sign_ext_imm},
+};
+
+use super::{extract_dst, extract_src1, extract_src2};
+
+macro_rules! illegal {
+	($cpu:ident) => {{
+		$cpu.request_trap(TrapIdx::ILLEGAL_INSTRUCTION, 0);
+		return Err(());
+	}};
+}
+
+impl VectorInstruction {
+	pub fn parse_load_fp(cpu: &mut WhiskerCpu, parcel: u32) -> Result<Self, ()> {
+		use crate::insn32::op_v::consts::mem::*;
+
+		let Some(eew_bytes) = element_width(parcel) else {
+			illegal!(cpu)
+		};
+		let fault_only_first = match extract_bits_32(parcel, 20, 24) as u8 {
+			UNIT_STRIDE => false,
+			UNIT_STRIDE_FAULT_FIRST => true,
+			// whole register and mask loads aren't supported yet
+			_ => illegal!(cpu),
+		};
+		if !is_unit_stride(parcel) {
+			illegal!(cpu)
+		}
+
+		Ok(Self::LoadUnitStride {
+			dst: extract_dst(parcel).to_vec(),
+			base: extract_src1(parcel).to_gp(),
+			eew_bytes,
+			masked: is_masked(parcel),
+			fault_only_first,
+		})
+	}
+
+	pub fn parse_store_fp(cpu: &mut WhiskerCpu, parcel: u32) -> Result<Self, ()> {
+		use crate::insn32::op_v::consts::mem::*;
+
+		let Some(eew_bytes) = element_width(parcel) else {
+			illegal!(cpu)
+		};
+		if extract_bits_32(parcel, 20, 24) as u8 != UNIT_STRIDE || !is_unit_stride(parcel) {
+			illegal!(cpu)
+		}
+
+		Ok(Self::StoreUnitStride {
+			// vs3 lives where rd would be
+			src: extract_dst(parcel).to_vec(),
+			base: extract_src1(parcel).to_gp(),
+			eew_bytes,
+			masked: is_masked(parcel),
+		})
+	}
+
+	pub fn parse_op_v(cpu: &mut WhiskerCpu, parcel: u32) -> Result<Self, ()> {
+		use crate::insn32::op_v::consts::*;
+
+		let func3 = extract_bits_32(parcel, 12, 14) as u8;
+		if func3 == OPCFG {
+			return Ok(Self::parse_set_vector_length(parcel));
+		}
+
+		let func6 = extract_bits_32(parcel, 26, 31) as u8;
+		let dst = extract_dst(parcel).to_vec();
+		let src1 = extract_src1(parcel);
+		let lhs = extract_src2(parcel).to_vec();
+		let masked = is_masked(parcel);
+
+		match func3 {
+			OPIVV | OPIVX | OPIVI => {
+				use int::*;
+
+				let is_shift = matches!(func6, SHIFT_LEFT | SHIFT_RIGHT_LOGICAL | SHIFT_RIGHT_ARITHMETIC);
+				let rhs = match func3 {
+					OPIVV => VectorOperand::Vector(src1.to_vec()),
+					OPIVX => VectorOperand::Scalar(src1.to_gp()),
+					// shift amounts are unsigned, everything else is sign extended
+					_ if is_shift => VectorOperand::Immediate(src1.as_usize() as i64),
+					_ => VectorOperand::Immediate(sign_ext_imm(src1.as_usize() as u32, 4)),
+				};
+
+				let int_op = match func6 {
+					ADD => Some(VectorIntOp::Add),
+					SUB if func3 != OPIVI => Some(VectorIntOp::Sub),
+					REVERSE_SUB if func3 != OPIVV => Some(VectorIntOp::ReverseSub),
+					MIN_UNSIGNED if func3 != OPIVI => Some(VectorIntOp::MinUnsigned),
+					MIN if func3 != OPIVI => Some(VectorIntOp::Min),
+					MAX_UNSIGNED if func3 != OPIVI => Some(VectorIntOp::MaxUnsigned),
+					MAX if func3 != OPIVI => Some(VectorIntOp::Max),
+					AND => Some(VectorIntOp::And),
+					OR => Some(VectorIntOp::Or),
+					XOR => Some(VectorIntOp::Xor),
+					SHIFT_LEFT => Some(VectorIntOp::ShiftLeft),
+					SHIFT_RIGHT_LOGICAL => Some(VectorIntOp::ShiftRightLogical),
+					SHIFT_RIGHT_ARITHMETIC => Some(VectorIntOp::ShiftRightArithmetic),
+					_ => None,
+				};
+				if let Some(op) = int_op {
+					return Ok(Self::IntOp {
+						op,
+						dst,
+						lhs,
+						rhs,
+						masked,
+					});
+				}
+
+				let cmp_op = match func6 {
+					SET_EQ => Some(VectorCmpOp::Equal),
+					SET_NE => Some(VectorCmpOp::NotEqual),
+					SET_LESS_THAN_UNSIGNED if func3 != OPIVI => Some(VectorCmpOp::LessThanUnsigned),
+					SET_LESS_THAN if func3 != OPIVI => Some(VectorCmpOp::LessThan),
+					SET_LESS_EQ_UNSIGNED => Some(VectorCmpOp::LessOrEqualUnsigned),
+					SET_LESS_EQ => Some(VectorCmpOp::LessOrEqual),
+					SET_GREATER_THAN_UNSIGNED if func3 != OPIVV => Some(VectorCmpOp::GreaterThanUnsigned),
+					SET_GREATER_THAN if func3 != OPIVV => Some(VectorCmpOp::GreaterThan),
+					_ => None,
+				};
+				if let Some(op) = cmp_op {
+					return Ok(Self::Compare {
+						op,
+						dst,
+						lhs,
+						rhs,
+						masked,
+					});
+				}
+
+				match func6 {
+					MERGE_MOVE if masked => Ok(Self::Merge { dst, lhs, rhs }),
+					// vmv.v.* requires vs2 to be v0
+					MERGE_MOVE if lhs.as_usize() == 0 => Ok(Self::Move { dst, src: rhs }),
+					_ => illegal!(cpu),
+				}
+			}
+			OPMVV => {
+				use mask::*;
+
+				match func6 {
+					MUL => Ok(Self::IntOp {
+						op: VectorIntOp::Mul,
+						dst,
+						lhs,
+						rhs: VectorOperand::Vector(src1.to_vec()),
+						masked,
+					}),
+					UNARY0 => match src1.as_usize() as u8 {
+						MOVE_TO_SCALAR if !masked => Ok(Self::MoveToScalar {
+							dst: extract_dst(parcel).to_gp(),
+							src: lhs,
+						}),
+						POP_COUNT => Ok(Self::MaskPopCount {
+							dst: extract_dst(parcel).to_gp(),
+							src: lhs,
+							masked,
+						}),
+						FIND_FIRST => Ok(Self::MaskFindFirst {
+							dst: extract_dst(parcel).to_gp(),
+							src: lhs,
+							masked,
+						}),
+						_ => illegal!(cpu),
+					},
+					_ => illegal!(cpu),
+				}
+			}
+			OPMVX => {
+				use mask::*;
+
+				match func6 {
+					MUL => Ok(Self::IntOp {
+						op: VectorIntOp::Mul,
+						dst,
+						lhs,
+						rhs: VectorOperand::Scalar(src1.to_gp()),
+						masked,
+					}),
+					// vmv.s.x requires vs2 to be v0
+					UNARY0 if !masked && lhs.as_usize() == 0 => Ok(Self::MoveFromScalar { dst, src: src1.to_gp() }),
+					_ => illegal!(cpu),
+				}
+			}
+			// vector floating point isn't supported
+			OPFVV | OPFVF => illegal!(cpu),
+			_ => unreachable!(),
+		}
+	}
+
+	fn parse_set_vector_length(parcel: u32) -> Self {
+		let dst = extract_dst(parcel).to_gp();
+		let src1 = extract_src1(parcel);
+		if extract_bits_32(parcel, 31, 31) == 0 {
+			// vsetvli
+			Self::SetVectorLength {
+				dst,
+				avl: VectorLengthSource::Register(src1.to_gp()),
+				vtype: VectorTypeSource::Immediate(u64::from(extract_bits_32(parcel, 20, 30))),
+			}
+		} else if extract_bits_32(parcel, 30, 30) == 1 {
+			// vsetivli, the avl is an immediate in the rs1 field
+			Self::SetVectorLength {
+				dst,
+				avl: VectorLengthSource::Immediate(src1.as_usize() as u64),
+				vtype: VectorTypeSource::Immediate(u64::from(extract_bits_32(parcel, 20, 29))),
+			}
+		} else {
+			// vsetvl
+			Self::SetVectorLength {
+				dst,
+				avl: VectorLengthSource::Register(src1.to_gp()),
+				vtype: VectorTypeSource::Register(extract_src2(parcel).to_gp()),
+			}
+		}
+	}
+}
+
+// vm is active low, a 0 means the instruction is masked by v0
+fn is_masked(parcel: u32) -> bool {
+	extract_bits_32(parcel, 25, 25) == 0
+}
+
+// no segments (nf = 0), mew = 0 and a unit-stride mop
+fn is_unit_stride(parcel: u32) -> bool {
+	use crate::insn32::op_v::consts::mem::*;
+	extract_bits_32(parcel, 28, 31) == 0 && extract_bits_32(parcel, 26, 27) as u8 == MOP_UNIT_STRIDE
+}
+
+fn element_width(parcel: u32) -> Option<usize> {
+	// the store widths are the same
+	use crate::insn32::load_fp::consts::*;
+	match extract_bits_32(parcel, 12, 14) as u8 {
+		VECTOR_8 => Some(1),
+		VECTOR_16 => Some(2),
+		VECTOR_32 => Some(4),
+		VECTOR_64 => Some(8),
+		_ => None,
+	}
+}
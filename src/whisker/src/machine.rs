@@ -0,0 +1,131 @@
+use std::path::Path;
+
+use serde::{Deserialize, Deserializer};
+
+use crate::devices::clint::CLINT_BASE;
+use crate::devices::framebuffer::FRAMEBUFFER_BASE;
+use crate::devices::plic::PLIC_BASE;
+use crate::devices::test_finisher::TEST_FINISHER_BASE;
+use crate::devices::uart::UART_BASE;
+use crate::mem::{MemoryBuilder, PageBase, Permissions};
+
+// THESE MUST BE IN SYNC WITH LINKER SCRIPTS
+const BOOTROM_BASE: u64 = 0x00001000;
+const DRAM_BASE: u64 = 0x8000_0000;
+const DRAM_SIZE: u64 = 0x1000_0000;
+
+/// where everything is on the bus, read from a TOML file or the built-in virt-like layout
+/// anything left out of the file falls back to the built-in layout, except memory regions need a base and size
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MachineLayout {
+	/// where the bootrom is mapped
+	pub bootrom: u64,
+	/// the main memory, the device tree describes it and the kernel and device tree are loaded into it
+	pub dram: RamRegion,
+	/// more memory that's only mapped, like SRAM or a flash window
+	pub regions: Vec<RamRegion>,
+	pub devices: DeviceLayout,
+	pub load: LoadLayout,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RamRegion {
+	pub base: u64,
+	pub size: u64,
+	#[serde(default = "all_permissions", deserialize_with = "deserialize_permissions")]
+	pub permissions: Permissions,
+}
+
+/// the base address of each device's registers
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct DeviceLayout {
+	pub clint: u64,
+	pub plic: u64,
+	pub uart: u64,
+	pub test_finisher: u64,
+	pub framebuffer: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoadLayout {
+	/// where a flat kernel is copied to, ELF kernels go where they were linked
+	pub kernel: u64,
+	/// where the generated device tree goes, by default it's at the end of DRAM
+	pub fdt: Option<u64>,
+}
+
+impl Default for MachineLayout {
+	fn default() -> Self {
+		Self {
+			bootrom: BOOTROM_BASE,
+			dram: RamRegion {
+				base: DRAM_BASE,
+				size: DRAM_SIZE,
+				permissions: Permissions::ALL,
+			},
+			regions: Vec::new(),
+			devices: DeviceLayout::default(),
+			load: LoadLayout::default(),
+		}
+	}
+}
+
+impl Default for DeviceLayout {
+	fn default() -> Self {
+		Self {
+			clint: CLINT_BASE,
+			plic: PLIC_BASE,
+			uart: UART_BASE,
+			test_finisher: TEST_FINISHER_BASE,
+			framebuffer: FRAMEBUFFER_BASE,
+		}
+	}
+}
+
+impl Default for LoadLayout {
+	fn default() -> Self {
+		Self {
+			kernel: DRAM_BASE,
+			fdt: None,
+		}
+	}
+}
+
+impl MachineLayout {
+	pub fn from_file(path: &Path) -> Result<Self, String> {
+		let text = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+		toml::from_str(&text).map_err(|err| err.to_string())
+	}
+
+	/// maps DRAM and the other memory regions, the bootrom and devices are added by the caller
+	/// every region gets its own part of physical memory, back to back
+	pub fn memory(&self) -> MemoryBuilder {
+		let regions = std::iter::once(&self.dram).chain(&self.regions);
+		let mut mem = MemoryBuilder::default();
+		let mut phys = 0;
+		for region in regions {
+			mem = mem.phys_mapping(
+				PageBase::from_addr(region.base),
+				PageBase::from_addr(phys),
+				region.size,
+				region.permissions,
+			);
+			phys += region.size;
+		}
+		mem.physical_size(phys)
+	}
+}
+
+fn all_permissions() -> Permissions {
+	Permissions::ALL
+}
+
+/// permissions are written like "rx"
+fn deserialize_permissions<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Permissions, D::Error> {
+	let perms = String::deserialize(deserializer)?;
+	perms.parse().map_err(serde::de::Error::custom)
+}
@@ -1,33 +1,72 @@
+mod batch;
+mod breakpoint;
 mod cpu;
 mod csr;
+mod devices;
+mod elf;
+mod fdt;
 mod gdb;
+mod hpm;
+mod htif;
 mod insn;
 mod insn16;
 mod insn32;
+mod machine;
 mod mem;
+mod mmu;
+mod pmp;
 mod regs;
+mod semihosting;
+mod snapshot;
 mod soft;
+mod stats;
+mod trace;
 mod ty;
 mod util;
 
 #[cfg(not(target_pointer_width = "64"))]
 compile_error!("whisker only supports 64bit architectures");
 
-use std::io::Write as _;
-use std::path::PathBuf;
-use std::{fs, io};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
 
-use clap::{command, Parser, Subcommand};
-use gdbstub::conn::ConnectionExt;
-use gdbstub::stub::GdbStub;
+use clap::error::ErrorKind;
+use clap::{command, Args, CommandFactory, Parser, Subcommand};
+use gdbstub::stub::DisconnectReason;
+use serde::{Deserialize, Serialize};
 use tracing::level_filters::LevelFilter;
+use tracing::{info, warn};
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
 use tracing_subscriber::layer::SubscriberExt as _;
 use tracing_subscriber::util::SubscriberInitExt as _;
 
-use crate::cpu::{WhiskerCpu, WhiskerExecState};
-use crate::gdb::WhiskerEventLoop;
-use crate::mem::{MemoryBuilder, PageBase, PageEntry};
+use crate::cpu::{
+	InterruptLines, MisalignedAccess, PowerOffLine, ResetLine, Timer, WhiskerCpu, WhiskerExecState, WhiskerExecStatus,
+	DEFAULT_TIMEBASE_FREQ,
+};
+use crate::devices::clint::Clint;
+use crate::devices::framebuffer::{Framebuffer, DEFAULT_REFRESH_RATE};
+use crate::devices::plic::Plic;
+use crate::devices::serial::{ConsoleLog, SerialBackend};
+use crate::devices::test_finisher::TestFinisher;
+use crate::devices::uart::Uart;
+use crate::elf::{ElfImage, SymbolTable};
+use crate::fdt::MachineDescription;
+use crate::gdb::{GdbConnection, GdbListener, GdbTransport, DEFAULT_GDB_TRANSPORT};
+use crate::htif::Htif;
+use crate::machine::MachineLayout;
+use crate::mem::{MemoryHook, PageBase, Permissions, DEFAULT_RESERVATION_GRANULE, PAGE_SIZE};
+use crate::mmu::TranslationMode;
+use crate::pmp::{Pmp, DEFAULT_PMP_ENTRIES};
+use crate::regs::VectorRegisters;
+use crate::semihosting::{ConsoleInput, Semihosting};
+use crate::stats::{OpcodeStats, PerfSummary};
+use crate::trace::{TraceFormat, Tracer};
 use crate::ty::SupportedExtensions;
+use crate::util::parse_number;
 
 #[derive(Debug, Parser)]
 #[command(version)]
@@ -38,21 +77,191 @@ struct CliArgs {
 
 #[derive(Debug, Subcommand)]
 enum Commands {
-	Run {
-		#[arg(long)]
-		logfile: Option<PathBuf>,
-		#[arg(short = 'g', long)]
-		use_gdb: bool,
+	Run(Box<RunArgs>),
+	/// run every test listed in a TOML file, each in its own whisker, and print a summary
+	/// see examples/batch.toml, exits with 1 if any test didn't pass
+	Batch {
+		/// print the console output of the tests that didn't pass
+		#[arg(short, long)]
+		verbose: bool,
 		#[arg()]
-		bootrom: PathBuf,
-		#[arg()]
-		kernel: PathBuf,
+		file: PathBuf,
+	},
+	/// continue from a snapshot taken with --snapshot-every, using the options of the run that took it
+	/// anything after the snapshot is added to those options, like -g to attach gdb
+	Resume {
+		snapshot: PathBuf,
+		#[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+		extra: Vec<String>,
 	},
 }
 
+/// giving an option again overrides it, so `resume` can change what the snapshots were taken with
+#[derive(Debug, Args)]
+#[command(args_override_self = true)]
+struct RunArgs {
+	#[arg(long)]
+	logfile: Option<PathBuf>,
+	/// wait for gdb to connect on 127.0.0.1:2424 before running
+	#[arg(short = 'g', long)]
+	use_gdb: bool,
+	/// wait for gdb on tcp:<addr>, unix:<path> or stdio instead, stdio needs the serial console elsewhere
+	#[arg(long, value_name = "TRANSPORT")]
+	gdb: Option<GdbTransport>,
+	/// wipe memory and reload the bootrom and kernel when the machine resets
+	/// by default memory is preserved across resets like a warm reboot
+	#[arg(long)]
+	reload_on_reset: bool,
+	/// let the guest write to the bootrom, by default it's read-only like real ROM
+	#[arg(long)]
+	writable_bootrom: bool,
+	/// the extensions to enable, like rv64imac_zicsr, by default everything whisker implements is
+	/// S and U mode are always available, extensions whisker doesn't implement are rejected
+	#[arg(long, value_name = "ISA")]
+	isa: Option<SupportedExtensions>,
+	/// width of the vector registers in bits, a power of two between 64 and 65536
+	#[arg(long, default_value_t = 128, value_parser = parse_vlen)]
+	vlen: usize,
+	/// whether misaligned loads and stores are emulated or raise address-misaligned traps
+	#[arg(long, value_enum, default_value_t)]
+	misaligned: MisalignedAccess,
+	/// the largest virtual address space satp can select
+	#[arg(long, value_enum, default_value_t)]
+	max_satp_mode: TranslationMode,
+	/// the number of PMP entries, 0, 16 or 64
+	#[arg(long, default_value_t = DEFAULT_PMP_ENTRIES, value_parser = parse_pmp_entries)]
+	pmp_entries: usize,
+	/// frequency of the time CSR in Hz
+	#[arg(long, default_value_t = DEFAULT_TIMEBASE_FREQ)]
+	timebase_freq: u64,
+	/// where the UART is attached, stdio, file:<path>, pty or tcp:<addr>
+	#[arg(long, default_value = "stdio")]
+	serial: SerialBackend,
+	/// copy everything the guest prints on the serial console to this file too
+	#[arg(long, value_name = "PATH")]
+	console_log: Option<PathBuf>,
+	/// start each line of the console log with the seconds since whisker started
+	#[arg(long, requires = "console_log")]
+	console_log_timestamps: bool,
+	/// map a framebuffer of this size, like 640x480, and show it in a window
+	#[arg(long, value_parser = parse_resolution)]
+	framebuffer: Option<(usize, usize)>,
+	/// how many times a second the framebuffer window is redrawn
+	#[arg(long, default_value_t = DEFAULT_REFRESH_RATE)]
+	refresh_rate: u32,
+	/// copy a file into memory at an address like initrd.img@0x88000000, can be repeated
+	/// and loading a device tree blob replaces the generated one
+	#[arg(long = "load", value_name = "PATH@ADDR")]
+	loads: Vec<LoadSpec>,
+	/// log every guest access to a range of memory, like 0x80001000+0x100, can be repeated
+	#[arg(long, value_name = "ADDR+SIZE", value_parser = parse_range)]
+	trace_mem: Vec<(u64, u64)>,
+	/// write a line for every instruction executed to this file, with the registers it changed
+	#[arg(long, value_name = "PATH")]
+	trace_out: Option<PathBuf>,
+	/// how each line of the trace is written
+	#[arg(long, value_enum, default_value_t)]
+	trace_format: TraceFormat,
+	/// only keep the last N instructions, they're written when the machine stops
+	#[arg(long, value_name = "N", requires = "trace_out")]
+	trace_last: Option<usize>,
+	/// only trace instructions in a range of addresses, like 0x80000000+0x1000, can be repeated
+	#[arg(long, value_name = "ADDR+SIZE", value_parser = parse_range, requires = "trace_out")]
+	trace_pc: Vec<(u64, u64)>,
+	/// print how many times each instruction was executed when whisker exits
+	#[arg(long)]
+	opcode_stats: bool,
+	/// write the instruction counts to this file as JSON when whisker exits
+	#[arg(long, value_name = "PATH")]
+	opcode_stats_json: Option<PathBuf>,
+	/// print how many instructions ran, how long it took and the MIPS when whisker exits
+	#[arg(long)]
+	perf: bool,
+	/// also print the MIPS every this many seconds while the guest runs without gdb
+	#[arg(long, value_name = "SECONDS", value_parser = parse_timeout, requires = "perf")]
+	perf_interval: Option<Duration>,
+	/// stop after this many cycles, like 100M, one per instruction or trap, and exit with 124
+	#[arg(long, value_name = "N", value_parser = parse_count, conflicts_with_all = ["use_gdb", "gdb"])]
+	max_cycles: Option<u64>,
+	/// stop after this many seconds of wall-clock time, like 2.5, and exit with 124
+	#[arg(long, value_name = "SECONDS", value_parser = parse_timeout, conflicts_with_all = ["use_gdb", "gdb"])]
+	timeout: Option<Duration>,
+	/// end the program at an ebreak and exit with a0 as the code, for tests that report their result that way
+	#[arg(long)]
+	exit_on_ebreak: bool,
+	/// stop with a report when the guest touches a range of memory, like 0x0+0x1000, can be repeated
+	#[arg(long, value_name = "ADDR+SIZE", value_parser = parse_range)]
+	poison: Vec<(u64, u64)>,
+	/// save a snapshot of the whole machine to this file when it powers off
+	#[arg(long, value_name = "PATH")]
+	save_on_exit: Option<PathBuf>,
+	/// save a snapshot every N cycles, like 100M, into --snapshot-dir as snap1, snap2 and so on
+	/// `whisker resume DIR/snapN` picks up from one with the options of this run
+	#[arg(long, value_name = "N", value_parser = parse_count, requires = "snapshot_dir", conflicts_with_all = ["use_gdb", "gdb"])]
+	snapshot_every: Option<u64>,
+	#[arg(long, value_name = "DIR", requires = "snapshot_every")]
+	snapshot_dir: Option<PathBuf>,
+	/// the size in bytes of the reservation set LR takes, a power of two of at least 8
+	#[arg(long, default_value_t = DEFAULT_RESERVATION_GRANULE, value_parser = parse_reservation_granule)]
+	reservation_granule: u64,
+	/// keep guest RAM in this file instead of the heap, it's overwritten and left behind after exit
+	/// DRAM starts at the beginning of the file and extra memory regions follow it in order
+	#[arg(long, value_name = "PATH")]
+	ram_file: Option<PathBuf>,
+	/// resume from a snapshot taken on a machine started with the same options
+	#[arg(long, value_name = "PATH")]
+	restore: Option<PathBuf>,
+	/// service semihosting calls, letting the guest print and use host files with whisker's permissions
+	/// stdin goes to the guest's semihosting and htif console instead of the serial console
+	#[arg(long)]
+	semihosting: bool,
+	/// where the guest writes htif commands, like riscv-tests and riscv-pk do
+	/// only needed for flat images, htif is used whenever an ELF has a tohost symbol
+	/// like with --semihosting, stdin then goes to the htif console instead of the serial console
+	#[arg(long, value_name = "ADDR", value_parser = parse_addr)]
+	tohost: Option<u64>,
+	/// where htif responses go, by default the fromhost symbol
+	#[arg(long, value_name = "ADDR", value_parser = parse_addr)]
+	fromhost: Option<u64>,
+	/// an argument for the program riscv-pk runs, argv[0] is the kernel's path, can be given more than once
+	#[arg(long, value_name = "ARG")]
+	htif_arg: Vec<String>,
+	/// a TOML file describing where memory and devices are, see examples/machine.toml
+	/// anything it leaves out keeps the built-in layout
+	#[arg(long, value_name = "PATH", value_parser = parse_machine)]
+	machine: Option<MachineLayout>,
+	/// where a flat kernel is copied to and the bootrom jumps, overriding the machine file
+	#[arg(long, value_name = "ADDR", value_parser = parse_addr)]
+	kernel_addr: Option<u64>,
+	/// where the bootrom is mapped and a flat one starts running, overriding the machine file
+	/// it has to be page aligned and can't overlap memory or a device
+	#[arg(long, value_name = "ADDR", value_parser = parse_bootrom_offset)]
+	bootrom_offset: Option<u64>,
+	#[arg()]
+	bootrom: PathBuf,
+	/// a small bare-metal program can run as the bootrom alone
+	#[arg()]
+	kernel: Option<PathBuf>,
+	/// the options as they were given, recorded next to periodic snapshots so they can be resumed
+	#[arg(skip)]
+	argv: Vec<String>,
+}
+
 fn main() {
+	let mut cli = CliArgs::parse();
+	match &mut cli.command {
+		Commands::Run(args) => args.argv = std::env::args().skip(2).collect(),
+		Commands::Resume { snapshot, extra } => cli.command = Commands::Run(resume_args(snapshot, extra)),
+		Commands::Batch { .. } => {}
+	}
+
+	// gdb owns stdout when it's attached through stdio, so logs have to go somewhere else
+	let writer = match &cli.command {
+		Commands::Run(args) if args.gdb == Some(GdbTransport::Stdio) => BoxMakeWriter::new(io::stderr),
+		Commands::Run(_) | Commands::Batch { .. } | Commands::Resume { .. } => BoxMakeWriter::new(io::stdout),
+	};
 	tracing_subscriber::registry()
-		.with(tracing_subscriber::fmt::layer().without_time())
+		.with(tracing_subscriber::fmt::layer().without_time().with_writer(writer))
 		.with(
 			tracing_subscriber::EnvFilter::builder()
 				.with_default_directive(LevelFilter::INFO.into())
@@ -60,112 +269,734 @@ fn main() {
 		)
 		.init();
 
-	let cli = CliArgs::parse();
-
 	match cli.command {
-		Commands::Run {
-			use_gdb: gdb,
-			bootrom,
-			kernel,
-			logfile,
-		} => {
-			let cpu = init_cpu(bootrom, kernel, logfile);
-			if gdb {
-				run_gdb(cpu);
+		Commands::Run(args) => {
+			let RunArgs {
+				use_gdb,
+				gdb,
+				bootrom,
+				kernel,
+				logfile,
+				reload_on_reset,
+				writable_bootrom,
+				isa,
+				vlen,
+				misaligned,
+				max_satp_mode,
+				pmp_entries,
+				timebase_freq,
+				serial,
+				console_log,
+				console_log_timestamps,
+				framebuffer,
+				refresh_rate,
+				loads,
+				trace_mem,
+				trace_out,
+				trace_format,
+				trace_last,
+				trace_pc,
+				opcode_stats,
+				opcode_stats_json,
+				perf,
+				perf_interval,
+				max_cycles,
+				timeout,
+				exit_on_ebreak,
+				poison,
+				reservation_granule,
+				ram_file,
+				save_on_exit,
+				snapshot_every,
+				snapshot_dir,
+				argv,
+				restore,
+				semihosting,
+				tohost,
+				fromhost,
+				htif_arg,
+				machine,
+				kernel_addr,
+				bootrom_offset,
+			} = *args;
+			if gdb == Some(GdbTransport::Stdio) && serial == SerialBackend::Stdio {
+				CliArgs::command()
+					.error(
+						ErrorKind::ArgumentConflict,
+						"gdb and the serial console can't both use stdio, move the console with --serial",
+					)
+					.exit();
+			}
+			let framebuffer = framebuffer.map(|(width, height)| Framebuffer::new(width, height));
+			let mut layout = machine.unwrap_or_default();
+			if let Some(addr) = kernel_addr {
+				layout.load.kernel = addr;
+			}
+			if let Some(addr) = bootrom_offset {
+				layout.bootrom = addr;
+			}
+			let htif_argv0 = kernel.as_ref().unwrap_or(&bootrom).display().to_string();
+			let mut cpu = init_cpu(MachineConfig {
+				bootrom,
+				kernel,
+				logfile,
+				reload_on_reset,
+				writable_bootrom,
+				extensions: isa.unwrap_or(SupportedExtensions::IMPLEMENTED),
+				vlen,
+				timebase_freq,
+				framebuffer: framebuffer.clone(),
+				layout,
+				poison,
+				reservation_granule,
+				ram_file,
+			});
+			let tohost = tohost.or_else(|| cpu.symbols.address_of("tohost"));
+			// a guest using host calls reads its console through them, unless gdb has stdin
+			let host_stdin = (semihosting || tohost.is_some()) && gdb != Some(GdbTransport::Stdio);
+			if host_stdin && serial == SerialBackend::Stdio {
+				info!("stdin goes to semihosting and htif, the serial console doesn't get input");
+			}
+			// attached once the cpu exists but before anything runs, so no output is lost
+			let uart = cpu.uart.as_ref().expect("the uart is always present");
+			let console_log = console_log.map(|path| {
+				ConsoleLog::create(&path, console_log_timestamps)
+					.unwrap_or_else(|err| panic!("could not create console log {}: {err}", path.display()))
+			});
+			let console_input = serial
+				.attach(uart, console_log, host_stdin)
+				.unwrap_or_else(|err| panic!("could not attach the serial console to {serial:?}: {err}"))
+				.map(ConsoleInput::new)
+				.unwrap_or_default();
+			if let Some(framebuffer) = framebuffer {
+				framebuffer.spawn_window(refresh_rate);
+			}
+			cpu.misaligned_access = misaligned;
+			cpu.exit_on_ebreak = exit_on_ebreak;
+			cpu.max_translation_mode = max_satp_mode;
+			cpu.pmp = Pmp::new(pmp_entries);
+			// like the logs, the guest's console output can't go to stdout when gdb is using it
+			let guest_console = || -> Box<dyn io::Write> {
+				match gdb {
+					Some(GdbTransport::Stdio) => Box::new(io::stderr()),
+					_ => Box::new(io::stdout()),
+				}
+			};
+			if semihosting {
+				cpu.semihosting = Some(Semihosting::new(guest_console(), console_input.clone()));
+			}
+			if let Some(tohost) = tohost {
+				let fromhost = fromhost.or_else(|| cpu.symbols.address_of("fromhost"));
+				let args = std::iter::once(htif_argv0).chain(htif_arg).collect();
+				cpu.attach_htif(Htif::new(tohost, fromhost, args, guest_console(), console_input));
+			}
+			match load_blobs(&mut cpu, &loads) {
+				Some(fdt_addr) => cpu.set_fdt_addr(fdt_addr),
+				None => load_device_tree(&mut cpu, timebase_freq),
+			}
+			for (start, size) in trace_mem {
+				cpu.mem.add_hook(
+					MemoryHook::new(start, size)
+						.on_read(|addr, size, val| info!("read {size} bytes at {addr:#018X}: {val:#x}"))
+						.on_write(|addr, size, val| info!("wrote {size} bytes at {addr:#018X}: {val:#x}")),
+				);
+			}
+			if let Some(path) = trace_out {
+				let tracer = Tracer::create(&path, trace_format, trace_pc, trace_last)
+					.unwrap_or_else(|err| panic!("could not create trace file {}: {err}", path.display()));
+				cpu.tracer = Some(tracer);
+			}
+			if opcode_stats || opcode_stats_json.is_some() {
+				cpu.opcode_stats = Some(OpcodeStats::new(opcode_stats, opcode_stats_json));
+			}
+			if let Some(path) = restore {
+				let snapshot =
+					fs::read(&path).unwrap_or_else(|_| panic!("could not read snapshot file {}", path.display()));
+				cpu.restore_snapshot(&snapshot)
+					.unwrap_or_else(|err| panic!("could not restore snapshot {}: {err}", path.display()));
+			}
+			// started last so loading and restoring aren't counted
+			if perf {
+				cpu.perf = Some(PerfSummary::new(perf_interval, cpu.instret));
+			}
+			if let Some(transport) = gdb.or(use_gdb.then(|| DEFAULT_GDB_TRANSPORT.parse().unwrap())) {
+				run_gdb(cpu, &transport, save_on_exit);
 			} else {
-				run_normal(cpu);
+				let snapshots = snapshot_every.zip(snapshot_dir).map(|(every, dir)| {
+					record_run(&dir, &argv);
+					PeriodicSnapshots { every, dir }
+				});
+				run_normal(cpu, save_on_exit, RunLimits { max_cycles, timeout }, snapshots);
 			}
 		}
+		Commands::Resume { .. } => unreachable!("resume is turned into run before this"),
+		Commands::Batch { verbose, file } => match batch::run_batch(&file, verbose) {
+			Ok(all_passed) => std::process::exit(if all_passed { 0 } else { 1 }),
+			Err(err) => {
+				eprintln!("{err}");
+				std::process::exit(2);
+			}
+		},
 	}
 }
 
-// THESE MUST BE IN SYNC WITH LINKER SCRIPTS
-const BOOTROM_OFFSET: u64 = 0x00001000;
-const DRAM_BASE: u64 = 0x8000_0000;
-const DRAM_SIZE: u64 = 0x1000_0000;
-const UART_ADDR: u64 = 0x1000_0000;
-
-fn init_cpu(bootrom: PathBuf, kernel: PathBuf, logfile: Option<PathBuf>) -> WhiskerCpu {
-	let bootrom = fs::read(&bootrom).unwrap_or_else(|_| panic!("could not read bootrom file {}", bootrom.display()));
-	let kernel = fs::read(&kernel).unwrap_or_else(|_| panic!("could not read kernel file {}", kernel.display()));
-
-	let supported = SupportedExtensions::INTEGER
-		| SupportedExtensions::FLOAT
-		| SupportedExtensions::COMPRESSED
-		| SupportedExtensions::ATOMIC
-		| SupportedExtensions::MULTIPLY;
-
-	let mut mem = MemoryBuilder::default()
-		.bootrom(bootrom, PageBase::from_addr(BOOTROM_OFFSET))
-		.physical_size(DRAM_BASE)
-		.phys_mapping(PageBase::from_addr(DRAM_BASE), PageBase::from_addr(0), DRAM_SIZE)
-		// MMIO UART mapping
-		.add_mapping(
-			PageBase::from_addr(UART_ADDR),
-			PageEntry::MMIO {
-				on_read: Box::new(|_| unimplemented!("read from UART")),
-				on_write: Box::new(move |addr, val| {
-					if addr == UART_ADDR {
-						print!("{}", val as char);
-						io::stdout().flush().unwrap();
-					}
-				}),
-			},
-		)
-		.build();
+fn parse_vlen(s: &str) -> Result<usize, String> {
+	let vlen = s.parse::<usize>().map_err(|e| e.to_string())?;
+	if vlen.is_power_of_two() && (VectorRegisters::MIN_VLEN..=VectorRegisters::MAX_VLEN).contains(&vlen) {
+		Ok(vlen)
+	} else {
+		Err(format!(
+			"VLEN must be a power of two between {} and {}",
+			VectorRegisters::MIN_VLEN,
+			VectorRegisters::MAX_VLEN
+		))
+	}
+}
+
+fn parse_resolution(s: &str) -> Result<(usize, usize), String> {
+	let (width, height) = s.split_once('x').ok_or("the resolution must look like 640x480")?;
+	let width = width.parse::<usize>().map_err(|e| e.to_string())?;
+	let height = height.parse::<usize>().map_err(|e| e.to_string())?;
+	if width == 0 || height == 0 {
+		return Err("the resolution can't be zero".to_string());
+	}
+	Ok((width, height))
+}
 
-	mem.write_slice(DRAM_BASE, kernel.as_slice())
-		.expect("unable to copy kernel to memory");
+/// a file to copy into memory and the address to put it at
+#[derive(Debug, Clone)]
+struct LoadSpec {
+	path: PathBuf,
+	addr: u64,
+}
 
-	let mut cpu = WhiskerCpu::new(supported, mem, logfile);
+impl FromStr for LoadSpec {
+	type Err = String;
 
-	cpu.pc = BOOTROM_OFFSET;
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		// the last @ splits them, paths can contain one
+		let (path, addr) = s.rsplit_once('@').ok_or("expected PATH@ADDR")?;
+		if path.is_empty() {
+			return Err("the path can't be empty".to_string());
+		}
+		Ok(Self {
+			path: path.into(),
+			addr: parse_addr(addr)?,
+		})
+	}
+}
+
+fn parse_addr(s: &str) -> Result<u64, String> {
+	parse_number(s).map_err(|e| format!("invalid address {s:?}: {e}"))
+}
+
+fn parse_range(s: &str) -> Result<(u64, u64), String> {
+	let (start, size) = s.split_once('+').ok_or("expected ADDR+SIZE")?;
+	let size = parse_addr(size)?;
+	if size == 0 {
+		return Err("the size can't be zero".to_string());
+	}
+	Ok((parse_addr(start)?, size))
+}
+
+fn parse_reservation_granule(s: &str) -> Result<u64, String> {
+	let granule = parse_addr(s)?;
+	if granule.is_power_of_two() && granule >= 8 {
+		Ok(granule)
+	} else {
+		Err("the reservation granule must be a power of two of at least 8 bytes".to_string())
+	}
+}
+
+fn parse_bootrom_offset(s: &str) -> Result<u64, String> {
+	let addr = parse_addr(s)?;
+	if addr % PAGE_SIZE == 0 {
+		Ok(addr)
+	} else {
+		Err(format!("the bootrom has to start on a {PAGE_SIZE} byte page boundary"))
+	}
+}
+
+fn parse_machine(s: &str) -> Result<MachineLayout, String> {
+	MachineLayout::from_file(Path::new(s)).map_err(|err| format!("could not load machine description {s}: {err}"))
+}
+
+/// a count with an optional K, M or G suffix, like 100M
+fn parse_count(s: &str) -> Result<u64, String> {
+	let (digits, scale) = match s.as_bytes().last() {
+		Some(b'k' | b'K') => (&s[..s.len() - 1], 1_000),
+		Some(b'm' | b'M') => (&s[..s.len() - 1], 1_000_000),
+		Some(b'g' | b'G') => (&s[..s.len() - 1], 1_000_000_000),
+		_ => (s, 1),
+	};
+	let count = parse_number(digits).map_err(|e| format!("invalid count {s:?}: {e}"))?;
+	match count.checked_mul(scale) {
+		Some(0) => Err("the count can't be zero".to_string()),
+		Some(count) => Ok(count),
+		None => Err(format!("{s} is too large")),
+	}
+}
+
+fn parse_timeout(s: &str) -> Result<Duration, String> {
+	let secs = s.parse::<f64>().map_err(|e| e.to_string())?;
+	Duration::try_from_secs_f64(secs).map_err(|e| e.to_string())
+}
+
+fn parse_pmp_entries(s: &str) -> Result<usize, String> {
+	let entries = s.parse::<usize>().map_err(|e| e.to_string())?;
+	if matches!(entries, 0 | 16 | 64) {
+		Ok(entries)
+	} else {
+		Err("the number of PMP entries must be 0, 16 or 64".to_string())
+	}
+}
+
+// the device tree goes in the last 2MiB of DRAM like on QEMU, so it stays clear of the kernel
+const FDT_ALIGN: u64 = 0x20_0000;
+
+/// what init_cpu needs to build the machine, the rest of the options are applied to the cpu afterwards
+struct MachineConfig {
+	bootrom: PathBuf,
+	kernel: Option<PathBuf>,
+	logfile: Option<PathBuf>,
+	reload_on_reset: bool,
+	writable_bootrom: bool,
+	extensions: SupportedExtensions,
+	vlen: usize,
+	timebase_freq: u64,
+	framebuffer: Option<Framebuffer>,
+	layout: MachineLayout,
+	poison: Vec<(u64, u64)>,
+	reservation_granule: u64,
+	ram_file: Option<PathBuf>,
+}
+
+fn init_cpu(config: MachineConfig) -> WhiskerCpu {
+	let MachineConfig {
+		bootrom,
+		kernel,
+		logfile,
+		reload_on_reset,
+		writable_bootrom,
+		extensions,
+		vlen,
+		timebase_freq,
+		framebuffer,
+		layout,
+		poison,
+		reservation_granule,
+		ram_file,
+	} = config;
+	let bootrom_path = bootrom;
+	let bootrom =
+		fs::read(&bootrom_path).unwrap_or_else(|_| panic!("could not read bootrom file {}", bootrom_path.display()));
+	let kernel = kernel.map(|path| {
+		let image = fs::read(&path).unwrap_or_else(|_| panic!("could not read kernel file {}", path.display()));
+		(path, image)
+	});
+	let mut symbols = SymbolTable::default();
+
+	// an ELF bootrom is flattened into the ROM and starts at its entry point instead of the start of the ROM
+	let (bootrom, reset_vector) = if elf::is_elf(&bootrom) {
+		let image = ElfImage::parse(&bootrom)
+			.unwrap_or_else(|err| panic!("could not load bootrom file {}: {err}", bootrom_path.display()));
+		let bootrom = flatten_bootrom(&image, layout.bootrom)
+			.unwrap_or_else(|err| panic!("could not load bootrom file {}: {err}", bootrom_path.display()));
+		symbols.extend(image.symbols);
+		(bootrom, image.entry)
+	} else {
+		(bootrom, layout.bootrom)
+	};
+
+	// devices that raise interrupts or share the timebase need these before the cpu exists
+	let timer = Timer::new(timebase_freq);
+	let interrupt_lines = InterruptLines::default();
+	let clint = Clint::new(timer.clone(), interrupt_lines.clone());
+	let plic = Plic::new(interrupt_lines.clone());
+	let uart = Uart::new(plic.clone());
+	let reset_line = ResetLine::default();
+	let power_off_line = PowerOffLine::default();
+	let test_finisher = TestFinisher::new(reset_line.clone(), power_off_line.clone());
+
+	let bootrom_perms = if writable_bootrom {
+		Permissions::ALL
+	} else {
+		Permissions::READ | Permissions::EXECUTE
+	};
+	let mut mem = layout
+		.memory()
+		.bootrom(bootrom, PageBase::from_addr(layout.bootrom), bootrom_perms)
+		.reload_on_reset(reload_on_reset)
+		.reservation_granule(reservation_granule);
+	let devices = [
+		Some(clint.mmio_region(layout.devices.clint)),
+		Some(plic.mmio_region(layout.devices.plic)),
+		Some(uart.mmio_region(layout.devices.uart)),
+		Some(test_finisher.mmio_region(layout.devices.test_finisher)),
+		framebuffer
+			.as_ref()
+			.map(|framebuffer| framebuffer.mmio_region(layout.devices.framebuffer)),
+	];
+	for region in devices.into_iter().flatten() {
+		mem = mem.add_mmio(region);
+	}
+	for (start, size) in poison {
+		mem = mem.poison(start, size);
+	}
+	if let Some(path) = ram_file {
+		let file = fs::OpenOptions::new()
+			.read(true)
+			.write(true)
+			.create(true)
+			.truncate(false)
+			.open(&path)
+			.unwrap_or_else(|err| panic!("could not open RAM file {}: {err}", path.display()));
+		mem = mem.backing_file(file);
+	}
+	let mut mem = mem
+		.build()
+		.unwrap_or_else(|err| panic!("could not build the memory map: {err}"));
+
+	// a flat kernel is loaded where the layout says, the start of DRAM by default, an ELF kernel's segments go wherever they were linked
+	// without a kernel the bootrom is all there is, a2 still points at where one would be
+	let kernel_entry = match kernel {
+		Some((kernel_path, kernel)) if elf::is_elf(&kernel) => {
+			let image = ElfImage::parse(&kernel)
+				.unwrap_or_else(|err| panic!("could not load kernel file {}: {err}", kernel_path.display()));
+			for segment in image.segments {
+				let addr = segment.addr;
+				mem.load_image(addr, segment.data).unwrap_or_else(|_| {
+					panic!("the kernel segment at {addr:#x} doesn't fit in memory");
+				});
+			}
+			symbols.extend(image.symbols);
+			image.entry
+		}
+		Some((_, kernel)) => {
+			mem.load_image(layout.load.kernel, kernel)
+				.expect("unable to copy kernel to memory");
+			layout.load.kernel
+		}
+		None => layout.load.kernel,
+	};
+
+	let mut cpu = WhiskerCpu::new(extensions, mem, reset_vector, vlen, logfile);
+	cpu.set_kernel_entry(kernel_entry);
+	cpu.symbols = symbols;
+	cpu.timer = timer;
+	cpu.interrupt_lines = interrupt_lines;
+	cpu.reset_line = reset_line;
+	cpu.power_off_line = power_off_line;
+	cpu.clint = Some(clint);
+	cpu.plic = Some(plic);
+	cpu.uart = Some(uart);
+	cpu.framebuffer = framebuffer;
+	cpu.layout = layout;
 	cpu
 }
 
-fn run_gdb(mut cpu: WhiskerCpu) {
-	let conn: Box<dyn ConnectionExt<Error = std::io::Error>> = Box::new(gdb::wait_for_tcp().expect("listener to bind"));
-	let gdb = GdbStub::new(conn);
-	match gdb.run_blocking::<WhiskerEventLoop>(&mut cpu) {
-		Ok(dc_reason) => match dc_reason {
-			gdbstub::stub::DisconnectReason::TargetExited(result) => {
-				println!("Target exited: {result}")
+/// copies the bootrom's segments into a ROM image that starts at base
+fn flatten_bootrom(image: &ElfImage, base: u64) -> Result<Vec<u8>, String> {
+	let mut rom = Vec::new();
+	for segment in &image.segments {
+		let start = segment
+			.addr
+			.checked_sub(base)
+			.ok_or_else(|| format!("the segment at {:#x} is below the bootrom", segment.addr))? as usize;
+		let end = start + segment.data.len();
+		if rom.len() < end {
+			rom.resize(end, 0);
+		}
+		rom[start..end].copy_from_slice(&segment.data);
+	}
+	if !(base..base + rom.len() as u64).contains(&image.entry) {
+		return Err(format!("the entry point {:#x} is outside the bootrom", image.entry));
+	}
+	Ok(rom)
+}
+
+/// copies the extra files into memory after the kernel, so they can overwrite parts of it
+/// returns the address of the last device tree blob that was loaded, if any
+fn load_blobs(cpu: &mut WhiskerCpu, loads: &[LoadSpec]) -> Option<u64> {
+	let mut fdt_addr = None;
+	for LoadSpec { path, addr } in loads {
+		let blob = fs::read(path).unwrap_or_else(|_| panic!("could not read {}", path.display()));
+		if fdt::is_fdt(&blob) {
+			fdt_addr = Some(*addr);
+		}
+		cpu.mem
+			.load_image(*addr, blob)
+			.unwrap_or_else(|_| panic!("{} doesn't fit in memory at {addr:#x}", path.display()));
+	}
+	fdt_addr
+}
+
+/// describes the machine in a device tree, at the end of DRAM unless the layout says otherwise, and points a1 at it
+fn load_device_tree(cpu: &mut WhiskerCpu, timebase_freq: u64) {
+	let fdt = fdt::generate(&MachineDescription {
+		extensions: cpu.supported_extensions,
+		translation_mode: cpu.max_translation_mode,
+		timebase_freq,
+		layout: &cpu.layout,
+		framebuffer: cpu.framebuffer.as_ref(),
+	});
+	let dram = &cpu.layout.dram;
+	let addr = cpu
+		.layout
+		.load
+		.fdt
+		.unwrap_or((dram.base + dram.size - fdt.len() as u64) & !(FDT_ALIGN - 1));
+	cpu.mem
+		.load_image(addr, fdt)
+		.expect("unable to copy the device tree to memory");
+	cpu.set_fdt_addr(addr);
+}
+
+fn save_snapshot(cpu: &WhiskerCpu, path: Option<&Path>) {
+	if let Some(path) = path {
+		if let Err(err) = fs::write(path, cpu.save_snapshot()) {
+			eprintln!("could not save snapshot to {}: {err}", path.display());
+		}
+	}
+}
+
+// sits next to the periodic snapshots, it's what `whisker resume` rebuilds the machine from
+const RUN_RECORD: &str = "run.toml";
+
+/// the working directory and options of the run that took a directory's snapshots
+#[derive(Debug, Serialize, Deserialize)]
+struct RunRecord {
+	cwd: PathBuf,
+	args: Vec<String>,
+}
+
+fn record_run(dir: &Path, argv: &[String]) {
+	// resuming adds its own --restore, so the one this run may have been given is left out
+	let mut args = Vec::with_capacity(argv.len());
+	let mut argv = argv.iter();
+	while let Some(arg) = argv.next() {
+		if arg == "--restore" {
+			argv.next();
+		} else if !arg.starts_with("--restore=") {
+			args.push(arg.clone());
+		}
+	}
+	let record = RunRecord {
+		cwd: std::env::current_dir().expect("the working directory is gone"),
+		args,
+	};
+	let path = dir.join(RUN_RECORD);
+	let written = fs::create_dir_all(dir).and_then(|()| {
+		let text = toml::to_string(&record).map_err(io::Error::other)?;
+		fs::write(&path, text)
+	});
+	if let Err(err) = written {
+		panic!("could not write {}: {err}", path.display());
+	}
+}
+
+/// the run that took the snapshot, restoring it, with any extra options after the recorded ones
+fn resume_args(snapshot: &Path, extra: &[String]) -> Box<RunArgs> {
+	let fail = |msg: String| -> ! { CliArgs::command().error(ErrorKind::Io, msg).exit() };
+	let snapshot = snapshot
+		.canonicalize()
+		.unwrap_or_else(|err| fail(format!("could not find snapshot {}: {err}", snapshot.display())));
+	let record_path = snapshot.with_file_name(RUN_RECORD);
+	let record = fs::read_to_string(&record_path)
+		.map_err(|err| err.to_string())
+		.and_then(|text| toml::from_str::<RunRecord>(&text).map_err(|err| err.to_string()))
+		.unwrap_or_else(|err| fail(format!("could not read {}: {err}", record_path.display())));
+	// the recorded paths are relative to where that run was started
+	std::env::set_current_dir(&record.cwd)
+		.unwrap_or_else(|err| fail(format!("could not change to {}: {err}", record.cwd.display())));
+
+	let argv = ["whisker", "run"]
+		.into_iter()
+		.map(String::from)
+		.chain(record.args.iter().cloned())
+		.chain(extra.iter().cloned())
+		.chain(["--restore".to_string(), snapshot.display().to_string()]);
+	match CliArgs::try_parse_from(argv) {
+		Ok(CliArgs {
+			command: Commands::Run(mut args),
+		}) => {
+			args.argv = record.args;
+			args
+		}
+		Ok(_) => unreachable!("the recorded options always start with run"),
+		Err(err) => err.exit(),
+	}
+}
+
+fn run_gdb(mut cpu: WhiskerCpu, transport: &GdbTransport, save_on_exit: Option<PathBuf>) -> ! {
+	let listener = transport
+		.listen()
+		.unwrap_or_else(|err| panic!("could not wait for gdb on {transport:?}: {err}"));
+	let mut conn = wait_for_gdb(&listener);
+	cpu.stop_on_fatal_traps = true;
+	loop {
+		// every debugger starts out in plain remote mode
+		cpu.gdb_extended_mode = false;
+		let session = gdb::run_session(&mut cpu, conn, |cpu, code| {
+			eprintln!("Target exited: {code}, waiting for gdb to run it again");
+			save_snapshot(cpu, save_on_exit.as_deref());
+		});
+		match session {
+			Ok(DisconnectReason::TargetExited(result)) => {
+				eprintln!("Target exited: {result}");
+				save_snapshot(&cpu, save_on_exit.as_deref());
+				exit(&mut cpu, result.into());
 			}
-			gdbstub::stub::DisconnectReason::TargetTerminated(signal) => {
-				println!("Target terminated: {signal:?}");
+			Ok(DisconnectReason::TargetTerminated(signal)) => {
+				eprintln!("Target terminated: {signal:?}");
+				exit(&mut cpu, 0);
 			}
-			gdbstub::stub::DisconnectReason::Disconnect => {
-				cpu.exec_state = WhiskerExecState::Running;
-				loop {
-					// FIXME: handle this better
-					#[allow(unused_must_use)]
-					cpu.execute_one();
-				}
+			Ok(DisconnectReason::Disconnect) => {
+				eprintln!("Debugger detached, the machine keeps running until one attaches again or Ctrl-C");
+				conn = run_detached(&mut cpu, &listener, save_on_exit.as_deref());
 			}
-			gdbstub::stub::DisconnectReason::Kill => println!("(GDB) Received kill command"),
-		},
-		Err(err) => {
-			dbg!(&err);
-			if err.is_target_error() {
-				println!(
-					"target encountered a fatal error: {:?}",
-					err.into_target_error().unwrap()
-				)
-			} else if err.is_connection_error() {
-				let (err, kind) = err.into_connection_error().unwrap();
-				println!("connection error: {kind:?} - {err:?}")
-			} else {
-				println!("gdbstub encountered a fatal error: {err:?}")
+			Ok(DisconnectReason::Kill) => {
+				eprintln!("(GDB) Received kill command");
+				exit(&mut cpu, 0);
+			}
+			Err(err) => {
+				eprintln!("{err}");
+				exit(&mut cpu, 0);
 			}
 		}
 	}
 }
 
-fn run_normal(mut cpu: WhiskerCpu) {
+/// Ctrl-C still kills whisker while it's blocked waiting
+fn wait_for_gdb(listener: &GdbListener) -> GdbConnection {
+	gdb::catch_ctrl_c(false);
+	listener.accept().unwrap_or_else(|err| {
+		eprintln!("could not wait for gdb: {err}");
+		std::process::exit(1);
+	})
+}
+
+/// runs without a debugger until one attaches, or something stops the machine and it waits for one
+/// stops are Ctrl-C, a breakpoint gdb left behind or a trap the guest can't handle
+fn run_detached(cpu: &mut WhiskerCpu, listener: &GdbListener, save_on_exit: Option<&Path>) -> GdbConnection {
 	cpu.exec_state = WhiskerExecState::Running;
+	gdb::catch_ctrl_c(true);
 	loop {
-		// FIXME: handle this better
-		#[allow(unused_must_use)]
-		cpu.execute_one();
+		let mut attached = None;
+		let status = cpu.exec_gdb(|| {
+			attached = listener.try_accept().unwrap_or_else(|err| {
+				warn!("could not accept a gdb connection: {err}");
+				None
+			});
+			attached.is_some() || gdb::ctrl_c_pending()
+		});
+		let stopped = match status {
+			None => match attached {
+				Some(conn) => {
+					gdb::catch_ctrl_c(false);
+					cpu.exec_state = WhiskerExecState::Paused;
+					return conn;
+				}
+				None => gdb::take_ctrl_c().then_some("Ctrl-C"),
+			},
+			Some(WhiskerExecStatus::Exited(exit_code)) => {
+				save_snapshot(cpu, save_on_exit);
+				exit(cpu, exit_code);
+			}
+			Some(WhiskerExecStatus::HitPoison) => exit(cpu, 1),
+			Some(WhiskerExecStatus::FatalTrap(_)) => Some("a trap the guest can't handle"),
+			Some(_) => Some("a breakpoint or watchpoint"),
+		};
+		if let Some(reason) = stopped {
+			eprintln!("The machine stopped for {reason} at {}", cpu.symbols.describe(cpu.pc));
+			cpu.exec_state = WhiskerExecState::Paused;
+			return wait_for_gdb(listener);
+		}
+	}
+}
+
+/// process::exit doesn't run destructors, so the trace is finished and the stats and perf reported here first
+fn exit(cpu: &mut WhiskerCpu, code: i32) -> ! {
+	drop(cpu.tracer.take());
+	if let Some(stats) = cpu.opcode_stats.take() {
+		stats.report();
+	}
+	if let Some(perf) = cpu.perf.take() {
+		perf.report(cpu.instret, cpu.cycles);
+	}
+	std::process::exit(code);
+}
+
+/// the exit code timeout(1) uses, for when a limit stops the machine
+pub const LIMIT_EXIT_CODE: i32 = 124;
+// how many cycles go by between looking at the clock
+const TIMEOUT_CHECK_INTERVAL: u64 = 4096;
+
+/// limits for unattended runs, so a guest that never powers off can't hang forever
+#[derive(Debug, Clone, Copy)]
+struct RunLimits {
+	max_cycles: Option<u64>,
+	timeout: Option<Duration>,
+}
+
+/// --snapshot-every, each snapshot is named after how many intervals have gone by
+struct PeriodicSnapshots {
+	every: u64,
+	dir: PathBuf,
+}
+
+fn run_normal(
+	mut cpu: WhiskerCpu,
+	save_on_exit: Option<PathBuf>,
+	limits: RunLimits,
+	snapshots: Option<PeriodicSnapshots>,
+) -> ! {
+	cpu.exec_state = WhiskerExecState::Running;
+	let started = Instant::now();
+	let mut iterations = 0u64;
+	// cycles stand still while halted, and a restored machine already has the snapshot it came from
+	let mut last_snapshot = cpu.cycles;
+	loop {
+		if let Some(snapshots) = &snapshots {
+			if cpu.cycles % snapshots.every == 0 && cpu.cycles != last_snapshot {
+				last_snapshot = cpu.cycles;
+				let path = snapshots.dir.join(format!("snap{}", cpu.cycles / snapshots.every));
+				save_snapshot(&cpu, Some(&path));
+			}
+		}
+
+		let cycle_limit_hit = limits.max_cycles.is_some_and(|max| cpu.cycles >= max);
+		// cycles don't advance while halted, so the clock is read every time then
+		iterations += 1;
+		let check_clock = iterations % TIMEOUT_CHECK_INTERVAL == 0 || cpu.exec_state == WhiskerExecState::Halted;
+		let timed_out = check_clock && limits.timeout.is_some_and(|timeout| started.elapsed() >= timeout);
+		if check_clock {
+			if let Some(perf) = cpu.perf.as_mut() {
+				perf.tick(cpu.instret);
+			}
+		}
+		if cycle_limit_hit || timed_out {
+			let reason = if cycle_limit_hit {
+				format!("reached the limit of {} cycles", cpu.cycles)
+			} else {
+				format!("timed out after {:.3}s", started.elapsed().as_secs_f64())
+			};
+			eprintln!(
+				"The machine {reason} at {}, {} instructions retired",
+				cpu.symbols.describe(cpu.pc),
+				cpu.instret
+			);
+			exit(&mut cpu, LIMIT_EXIT_CODE);
+		}
+
+		// breakpoints only matter to gdb, so powering off, touching poisoned memory or a limit are the only ways out
+		match cpu.execute_one() {
+			Err(WhiskerExecStatus::Exited(exit_code)) => {
+				save_snapshot(&cpu, save_on_exit.as_deref());
+				exit(&mut cpu, exit_code);
+			}
+			Err(WhiskerExecStatus::HitPoison) => exit(&mut cpu, 1),
+			_ => {}
+		}
 	}
 }
@@ -1,55 +1,338 @@
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::fmt::Debug;
+use std::fs::File;
+use std::io;
+use std::ops::{BitOr, Range};
+use std::rc::Rc;
+use std::str::FromStr;
 use std::sync::atomic::{AtomicBool, Ordering};
 
 use tracing::*;
 
+use crate::mmu::AccessType;
+use crate::snapshot::{expect_eq, Snapshot, SnapshotReader, SnapshotWriter};
 use crate::soft::double::SoftDouble;
 use crate::soft::float::SoftFloat;
 
+// the size of a cache line, for RV64 hardware I believe this is common
+pub const DEFAULT_RESERVATION_GRANULE: u64 = 64;
+
 struct MemoryReservations {
-	// Physical address to hart id, this would be important if we ever do multithreading
-	reservations: HashMap<u64, usize>,
+	// the size of a reservation set in bytes, a power of two
+	granule: u64,
+	// Hart id to the reserved physical address, each hart holds at most one reservation
+	// this would be important if we ever do multithreading
+	reservations: HashMap<usize, u64>,
 }
 
 impl MemoryReservations {
-	// For RV64 hardware I believe this is common
-	const CACHE_LINE_SIZE: u64 = 64;
-
-	fn new() -> Self {
+	fn new(granule: u64) -> Self {
 		Self {
-			reservations: HashMap::with_capacity(1024),
+			granule,
+			reservations: HashMap::new(),
 		}
 	}
 
+	fn granule(&self, phys_addr: u64) -> u64 {
+		phys_addr & !(self.granule - 1)
+	}
+
+	/// replaces any reservation the hart already held
 	fn reserve(&mut self, phys_addr: u64, hart_id: usize) {
-		let aligned_addr = phys_addr & !(Self::CACHE_LINE_SIZE - 1);
-		self.reservations.insert(aligned_addr, hart_id);
+		let granule = self.granule(phys_addr);
+		self.reservations.insert(hart_id, granule);
+	}
+
+	/// drops every hart's reservation on the granules [phys_addr, phys_addr + len) touches
+	fn unreserve(&mut self, phys_addr: u64, len: u64) {
+		if self.reservations.is_empty() {
+			return;
+		}
+
+		let first = self.granule(phys_addr);
+		let end = phys_addr + len;
+		self.reservations.retain(|_, reserved| !(first..end).contains(reserved));
+	}
+
+	fn holds(&self, hart_id: usize) -> bool {
+		self.reservations.contains_key(&hart_id)
 	}
 
-	fn unreserve(&mut self, phys_addr: u64) {
-		let aligned_addr = phys_addr & !(Self::CACHE_LINE_SIZE - 1);
-		self.reservations.remove(&aligned_addr);
+	fn release(&mut self, hart_id: usize) {
+		self.reservations.remove(&hart_id);
 	}
 
-	fn is_reserved(&mut self, phys_addr: u64, hart_id: usize) -> bool {
-		let aligned_addr = phys_addr & !(Self::CACHE_LINE_SIZE - 1);
+	fn clear(&mut self) {
+		self.reservations.clear();
+	}
+
+	/// takes the hart's reservation, returning whether it covered phys_addr
+	/// an SC consumes the reservation whether it succeeds or not
+	fn take(&mut self, phys_addr: u64, hart_id: usize) -> bool {
+		let granule = self.granule(phys_addr);
 		self.reservations
-			.get(&aligned_addr)
-			.is_some_and(|hart| *hart == hart_id)
+			.remove(&hart_id)
+			.is_some_and(|reserved| reserved == granule)
 	}
 }
 
 pub struct Memory {
-	phys: Box<[u8]>,
+	phys: PhysicalMemory,
 	bootrom: Box<[u8]>,
+	// pristine copy of the bootrom, bootrom pages are writable so this is what gets restored on reset
+	bootrom_image: Box<[u8]>,
 	mappings: HashMap<PageBase, PageEntry>,
 
+	// virtual address -> image data for everything copied in with load_image
+	images: Vec<(u64, Box<[u8]>)>,
+	reload_on_reset: bool,
+
+	hooks: Vec<MemoryHook>,
+
+	poisoned: Vec<Range<u64>>,
+	// the first poisoned access since the hart last checked, reads only take &self so it's in a RefCell
+	poisoned_access: RefCell<Option<PoisonedAccess>>,
+
 	// If we were to do multithreading, this would probably need to be a Send Cell type
 	reservations: MemoryReservations,
 	atomic_lock: AtomicBool,
 }
 
+impl Snapshot for Memory {
+	/// physical memory is mostly zeroes, so only the pages with something in them are saved
+	fn save(&self, snapshot: &mut SnapshotWriter) {
+		snapshot.u64(self.phys.size);
+		for (idx, page) in self.phys.pages() {
+			if !is_zero(page) {
+				snapshot.bool(true);
+				snapshot.u64(idx);
+				snapshot.bytes(page);
+			}
+		}
+		snapshot.bool(false);
+		snapshot.bytes(&self.bootrom);
+	}
+
+	/// MMIO state belongs to the devices, which are restored separately
+	fn restore(&mut self, snapshot: &mut SnapshotReader) -> Result<(), String> {
+		expect_eq("physical memory size", self.phys.size, snapshot.u64()?)?;
+		self.phys.clear();
+		while snapshot.bool()? {
+			let addr = snapshot.u64()? * PAGE_SIZE;
+			let page = snapshot.bytes()?;
+			expect_eq("memory page size", PAGE_SIZE, page.len() as u64)?;
+			if addr >= self.phys.size {
+				return Err("the snapshot has memory past the end of physical memory".to_string());
+			}
+			self.phys.write(addr, page);
+		}
+		snapshot.bytes_into("bootrom size", &mut self.bootrom)?;
+		self.reservations.clear();
+		Ok(())
+	}
+}
+
+// compared against instead of checking byte by byte, which is far too slow for gigabytes of memory
+const ZERO_PAGE: [u8; PAGE_SIZE as usize] = [0; PAGE_SIZE as usize];
+
+fn is_zero(page: &[u8]) -> bool {
+	page == &ZERO_PAGE[..page.len()]
+}
+
+const PHYS_CHUNK_SIZE: u64 = 64 * 1024;
+
+/// guest RAM, by default allocated a chunk at a time the first time it's written
+/// untouched memory reads as zero, so a huge physical size costs nothing until the guest uses it
+struct PhysicalMemory {
+	size: u64,
+	backing: PhysicalBacking,
+}
+
+enum PhysicalBacking {
+	Chunks(Vec<Option<Box<[u8]>>>),
+	/// a host file mapped in whole, the OS pages it in and out and it keeps the guest's memory after exit
+	File(MappedFile),
+}
+
+impl PhysicalMemory {
+	fn new(size: u64) -> Self {
+		Self {
+			size,
+			backing: PhysicalBacking::Chunks((0..size.div_ceil(PHYS_CHUNK_SIZE)).map(|_| None).collect()),
+		}
+	}
+
+	/// the file is truncated first, so memory starts out zeroed like it does without one
+	fn file_backed(size: u64, file: File) -> io::Result<Self> {
+		file.set_len(0)?;
+		file.set_len(size)?;
+		Ok(Self {
+			size,
+			backing: PhysicalBacking::File(MappedFile::new(&file, size as usize)?),
+		})
+	}
+
+	/// copies a chunk at a time, chunks that were never written read as zero
+	fn read(&self, addr: u64, buf: &mut [u8]) {
+		let chunks = match &self.backing {
+			PhysicalBacking::Chunks(chunks) => chunks,
+			PhysicalBacking::File(file) => {
+				let addr = addr as usize;
+				buf.copy_from_slice(&file.bytes()[addr..addr + buf.len()]);
+				return;
+			}
+		};
+		let mut done = 0;
+		while done < buf.len() {
+			let addr = addr + done as u64;
+			let start = (addr % PHYS_CHUNK_SIZE) as usize;
+			let len = (buf.len() - done).min(PHYS_CHUNK_SIZE as usize - start);
+			let dest = &mut buf[done..done + len];
+			match &chunks[(addr / PHYS_CHUNK_SIZE) as usize] {
+				Some(chunk) => dest.copy_from_slice(&chunk[start..start + len]),
+				None => dest.fill(0),
+			}
+			done += len;
+		}
+	}
+
+	fn write(&mut self, addr: u64, bytes: &[u8]) {
+		let chunks = match &mut self.backing {
+			PhysicalBacking::Chunks(chunks) => chunks,
+			PhysicalBacking::File(file) => {
+				let addr = addr as usize;
+				file.bytes_mut()[addr..addr + bytes.len()].copy_from_slice(bytes);
+				return;
+			}
+		};
+		let mut done = 0;
+		while done < bytes.len() {
+			let addr = addr + done as u64;
+			let start = (addr % PHYS_CHUNK_SIZE) as usize;
+			let len = (bytes.len() - done).min(PHYS_CHUNK_SIZE as usize - start);
+			let chunk = chunks[(addr / PHYS_CHUNK_SIZE) as usize]
+				.get_or_insert_with(|| vec![0; PHYS_CHUNK_SIZE as usize].into_boxed_slice());
+			chunk[start..start + len].copy_from_slice(&bytes[done..done + len]);
+			done += len;
+		}
+	}
+
+	/// zeroes everything, chunks are freed and a file only has the pages with something in them cleared
+	fn clear(&mut self) {
+		match &mut self.backing {
+			PhysicalBacking::Chunks(chunks) => chunks.iter_mut().for_each(|chunk| *chunk = None),
+			PhysicalBacking::File(file) => file
+				.bytes_mut()
+				.chunks_mut(PAGE_SIZE as usize)
+				.filter(|page| !is_zero(page))
+				.for_each(|page| page.fill(0)),
+		}
+	}
+
+	/// every page that has been allocated and its index, for a file that's every page
+	fn pages(&self) -> Box<dyn Iterator<Item = (u64, &[u8])> + '_> {
+		let chunks = match &self.backing {
+			PhysicalBacking::Chunks(chunks) => chunks,
+			PhysicalBacking::File(file) => {
+				return Box::new(
+					file.bytes()
+						.chunks(PAGE_SIZE as usize)
+						.enumerate()
+						.map(|(idx, page)| (idx as u64, page)),
+				);
+			}
+		};
+		let pages_per_chunk = PHYS_CHUNK_SIZE / PAGE_SIZE;
+		Box::new(chunks.iter().enumerate().flat_map(move |(idx, chunk)| {
+			chunk.iter().flat_map(move |chunk| {
+				chunk
+					.chunks(PAGE_SIZE as usize)
+					.enumerate()
+					.map(move |(page, bytes)| (idx as u64 * pages_per_chunk + page as u64, bytes))
+			})
+		}))
+	}
+}
+
+/// a shared mapping of a whole file, writes go straight to the file
+struct MappedFile {
+	ptr: *mut u8,
+	len: usize,
+}
+
+#[cfg(unix)]
+mod mmap {
+	use std::ffi::{c_int, c_void};
+
+	// std already links libc
+	extern "C" {
+		pub fn mmap(addr: *mut c_void, len: usize, prot: c_int, flags: c_int, fd: c_int, offset: i64) -> *mut c_void;
+		pub fn munmap(addr: *mut c_void, len: usize) -> c_int;
+	}
+	pub const PROT_READ: c_int = 1;
+	pub const PROT_WRITE: c_int = 2;
+	pub const MAP_SHARED: c_int = 1;
+	pub const MAP_FAILED: *mut c_void = !0 as *mut c_void;
+}
+
+impl MappedFile {
+	#[cfg(unix)]
+	fn new(file: &File, len: usize) -> io::Result<Self> {
+		use std::os::fd::AsRawFd;
+
+		if len == 0 {
+			return Ok(Self {
+				ptr: std::ptr::NonNull::dangling().as_ptr(),
+				len,
+			});
+		}
+		// SAFETY: a fresh mapping doesn't alias anything, and it stays valid after the file is closed
+		let ptr = unsafe {
+			mmap::mmap(
+				std::ptr::null_mut(),
+				len,
+				mmap::PROT_READ | mmap::PROT_WRITE,
+				mmap::MAP_SHARED,
+				file.as_raw_fd(),
+				0,
+			)
+		};
+		if ptr == mmap::MAP_FAILED {
+			return Err(io::Error::last_os_error());
+		}
+		Ok(Self { ptr: ptr.cast(), len })
+	}
+
+	#[cfg(not(unix))]
+	fn new(_file: &File, _len: usize) -> io::Result<Self> {
+		Err(io::Error::new(
+			io::ErrorKind::Unsupported,
+			"file-backed memory is only supported on unix hosts",
+		))
+	}
+
+	fn bytes(&self) -> &[u8] {
+		// SAFETY: the mapping is len bytes and lives as long as self
+		unsafe { std::slice::from_raw_parts(self.ptr, self.len) }
+	}
+
+	fn bytes_mut(&mut self) -> &mut [u8] {
+		// SAFETY: the mapping is len bytes and lives as long as self, which is borrowed mutably
+		unsafe { std::slice::from_raw_parts_mut(self.ptr, self.len) }
+	}
+}
+
+impl Drop for MappedFile {
+	fn drop(&mut self) {
+		#[cfg(unix)]
+		if self.len != 0 {
+			// SAFETY: nothing borrows the mapping anymore
+			unsafe { mmap::munmap(self.ptr.cast(), self.len) };
+		}
+	}
+}
+
 impl Debug for Memory {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 		f.debug_struct("Memory").finish_non_exhaustive()
@@ -62,73 +345,271 @@ impl Memory {
 	/// where virt is the failing virtual address
 	#[track_caller]
 	pub fn read_slice(&self, offset: u64, buf: &mut [u8]) -> Result<(), u64> {
-		for (idx, val) in buf.iter_mut().enumerate() {
-			let offset = offset + idx as u64;
+		let result = self.read_bytes(offset, buf);
+		if !self.poisoned.is_empty() {
+			self.check_poisoned(offset, buf, false);
+		}
+		result?;
+		if !self.hooks.is_empty() {
+			self.run_hooks(offset, buf, |hook| hook.on_read.as_ref());
+		}
+		Ok(())
+	}
+
+	/// the writing primitive that does page lookups and such
+	/// returns Ok if the write succeeded, or Err(virt) if the write
+	/// where virt is the failing virtual address
+	#[track_caller]
+	pub fn write_slice(&mut self, offset: u64, val: &[u8]) -> Result<(), u64> {
+		if !self.poisoned.is_empty() {
+			self.check_poisoned(offset, val, true);
+		}
+		self.write_bytes(offset, val)?;
+		if !self.hooks.is_empty() {
+			self.run_hooks(offset, val, |hook| hook.on_write.as_ref());
+		}
+		Ok(())
+	}
+
+	/// read_slice for the debugger, hooks don't see these reads
+	pub fn debug_read_slice(&self, offset: u64, buf: &mut [u8]) -> Result<(), u64> {
+		self.read_bytes(offset, buf)
+	}
+
+	/// write_slice for the debugger, hooks don't see these writes but reservations are still dropped
+	pub fn debug_write_slice(&mut self, offset: u64, val: &[u8]) -> Result<(), u64> {
+		self.write_bytes(offset, val)
+	}
+
+	/// starts calling a hook for every access that overlaps its range
+	pub fn add_hook(&mut self, hook: MemoryHook) {
+		self.hooks.push(hook);
+	}
+
+	/// remembers the access if it touches a poisoned range, unless an earlier one hasn't been taken yet
+	fn check_poisoned(&self, offset: u64, data: &[u8], write: bool) {
+		let end = offset + data.len() as u64;
+		if !self
+			.poisoned
+			.iter()
+			.any(|range| offset < range.end && end > range.start)
+		{
+			return;
+		}
+		self.poisoned_access.borrow_mut().get_or_insert_with(|| PoisonedAccess {
+			addr: offset,
+			write,
+			data: data.to_vec(),
+		});
+	}
+
+	/// the first access to a poisoned range since the last call, if there was one
+	pub fn take_poisoned_access(&self) -> Option<PoisonedAccess> {
+		self.poisoned_access.take()
+	}
+
+	/// the whole access is reported even if only part of it is in a hook's range
+	/// accesses wider than 8 bytes are reported as 8 byte pieces
+	fn run_hooks(&self, offset: u64, data: &[u8], callback: impl Fn(&MemoryHook) -> Option<&HookCallback>) {
+		let end = offset + data.len() as u64;
+		for hook in &self.hooks {
+			if end <= hook.start || offset >= hook.end {
+				continue;
+			}
+			let Some(callback) = callback(hook) else {
+				continue;
+			};
+			for (idx, piece) in data.chunks(8).enumerate() {
+				let mut buf = [0; 8];
+				buf[..piece.len()].copy_from_slice(piece);
+				callback(offset + idx as u64 * 8, piece.len(), u64::from_le_bytes(buf));
+			}
+		}
+	}
+
+	#[track_caller]
+	fn read_bytes(&self, offset: u64, buf: &mut [u8]) -> Result<(), u64> {
+		if let Some(region) = self.sized_mmio(offset, buf.len()) {
+			trace!("Reading {} bytes from MMIO @ {:#018X}", buf.len(), offset);
+			let val = (region.on_read)(offset - region.base, buf.len());
+			buf.copy_from_slice(&val.to_le_bytes()[..buf.len()]);
+			return Ok(());
+		}
+
+		// the page is looked up once for each page the access touches
+		let mut done = 0;
+		while done < buf.len() {
+			let offset = offset + done as u64;
 			let base = PageBase::from_addr(offset);
 			let Some(page_entry) = self.mappings.get(&base) else {
 				trace!("no page entry for {:#018X}", offset);
 				return Err(offset);
 			};
 			let page_offset = offset - base.0;
+			let len = (buf.len() - done).min((PAGE_SIZE - page_offset) as usize);
+			let buf = &mut buf[done..done + len];
 
 			match page_entry {
-				PageEntry::PhysBacked { phys_base } => {
+				PageEntry::PhysBacked { phys_base, .. } => {
 					let offset = phys_base + page_offset;
-					trace!("Reading from physmem @ {:#018X}", offset);
-					*val = self.phys[offset as usize];
+					trace!("Reading {len} bytes from physmem @ {:#018X}", offset);
+					self.phys.read(offset, buf);
 				}
-				PageEntry::Bootrom { page_base } => {
-					let offset = page_base + page_offset;
-					trace!("Reading from bootrom @ {:#018X}", offset);
-					*val = self.bootrom[offset as usize];
+				PageEntry::Bootrom { page_base, .. } => {
+					let offset = (page_base + page_offset) as usize;
+					trace!("Reading {len} bytes from bootrom @ {:#018X}", offset);
+					buf.copy_from_slice(&self.bootrom[offset..offset + len]);
 				}
-				PageEntry::MMIO { on_read, .. } => {
-					trace!("Reading from MMIO @ {:#018X}", offset);
-					*val = on_read(offset);
+				PageEntry::MMIO(region) => {
+					trace!("Reading {len} bytes from MMIO @ {:#018X}", offset);
+					for (idx, val) in buf.iter_mut().enumerate() {
+						*val = region.read_byte(offset + idx as u64 - region.base);
+					}
 				}
 			}
+			done += len;
 		}
 		Ok(())
 	}
 
-	/// the writing primitive that does page lookups and such
-	/// returns Ok if the write succeeded, or Err(virt) if the write
-	/// where virt is the failing virtual address
 	#[track_caller]
-	pub fn write_slice(&mut self, offset: u64, val: &[u8]) -> Result<(), u64> {
-		for (idx, val) in val.into_iter().enumerate() {
-			let offset = offset + idx as u64;
+	fn write_bytes(&mut self, offset: u64, val: &[u8]) -> Result<(), u64> {
+		if let Some(region) = self.sized_mmio(offset, val.len()) {
+			trace!("Writing {} bytes to MMIO @ {:#018X}", val.len(), offset);
+			let mut buf = [0; 8];
+			buf[..val.len()].copy_from_slice(val);
+			(region.on_write)(offset - region.base, val.len(), u64::from_le_bytes(buf));
+			return Ok(());
+		}
+
+		// the page is looked up once for each page the access touches
+		let mut done = 0;
+		while done < val.len() {
+			let offset = offset + done as u64;
 			let base = PageBase::from_addr(offset);
 			let Some(page_entry) = self.mappings.get(&base) else {
 				trace!("no page entry for {:#018X}", offset);
 				return Err(offset);
 			};
 			let page_offset = offset - base.0;
+			let len = (val.len() - done).min((PAGE_SIZE - page_offset) as usize);
+			let val = &val[done..done + len];
 
 			match page_entry {
-				PageEntry::PhysBacked { phys_base } => {
-					// Invalidate reservations on memory whenever it's written to
+				PageEntry::PhysBacked { phys_base, .. } => {
+					// Invalidate reservations on memory whenever it's written to, by a hart, the debugger or
+					// an image being loaded, every write ends up here so an SC can't miss one
 					let phys_addr = phys_base + page_offset;
-					self.reservations.unreserve(phys_addr);
+					self.reservations.unreserve(phys_addr, len as u64);
 
-					trace!("Writing to physmem @ {:#018X}", phys_base);
-					self.phys[phys_addr as usize] = *val;
+					trace!("Writing {len} bytes to physmem @ {:#018X}", phys_addr);
+					self.phys.write(phys_addr, val);
 				}
-				// writing to bootrom is allowed, this makes it easier to write bootrom code
-				// without having to do loader shenanigans
-				PageEntry::Bootrom { page_base } => {
-					trace!("Writing to bootrom @ 0x{:#018X}", page_base);
-					self.bootrom[(page_base + page_offset) as usize] = *val;
+				// permissions are checked by the hart, so the debugger and image loading can always write here
+				PageEntry::Bootrom { page_base, .. } => {
+					let offset = (page_base + page_offset) as usize;
+					trace!("Writing {len} bytes to bootrom @ {:#018X}", offset);
+					self.bootrom[offset..offset + len].copy_from_slice(val);
 				}
-				PageEntry::MMIO { on_write, .. } => {
-					trace!("Writing to MMIO @ {:#018X}", offset);
-					on_write(offset, *val);
+				PageEntry::MMIO(region) => {
+					trace!("Writing {len} bytes to MMIO @ {:#018X}", offset);
+					for (idx, &byte) in val.iter().enumerate() {
+						region.write_byte(offset + idx as u64 - region.base, byte);
+					}
 				}
 			}
+			done += len;
 		}
 		Ok(())
 	}
 
+	/// whether every page len bytes at offset touch is mapped with the permission the access needs
+	pub fn allows(&self, offset: u64, len: u64, access: AccessType) -> bool {
+		let last = offset.saturating_add(len.max(1) - 1);
+		let mut page = PageBase::from_addr(offset).0;
+		loop {
+			match self.mappings.get(&PageBase(page)) {
+				Some(entry) if entry.permissions().allows(access) => {}
+				_ => return false,
+			}
+			match page.checked_add(PAGE_SIZE) {
+				Some(next) if next <= last => page = next,
+				_ => return true,
+			}
+		}
+	}
+
+	/// everything that's mapped in address order, neighbouring pages of the same kind and permissions are merged
+	pub fn regions(&self) -> Vec<(MappedRegion, Permissions)> {
+		let mut pages = self.mappings.iter().collect::<Vec<_>>();
+		pages.sort_by_key(|(base, _)| base.0);
+		let mut regions: Vec<(MappedRegion, Permissions)> = Vec::new();
+		for (base, entry) in pages {
+			let kind = match entry {
+				PageEntry::PhysBacked { .. } => RegionKind::Physical,
+				PageEntry::Bootrom { .. } => RegionKind::Bootrom,
+				PageEntry::MMIO(_) => RegionKind::Mmio,
+			};
+			let perms = entry.permissions();
+			match regions.last_mut() {
+				Some((last, last_perms)) if last.kind == kind && *last_perms == perms && last.end() == base.0 => {
+					last.size += PAGE_SIZE;
+				}
+				_ => regions.push((
+					MappedRegion {
+						kind,
+						base: base.0,
+						size: PAGE_SIZE,
+					},
+					perms,
+				)),
+			}
+		}
+		regions
+	}
+
+	/// the MMIO region an access goes to in one piece, if it's 1, 2, 4 or 8 bytes and stays inside the region
+	/// anything else is split into bytes
+	fn sized_mmio(&self, offset: u64, len: usize) -> Option<&MmioRegion> {
+		if !matches!(len, 1 | 2 | 4 | 8) {
+			return None;
+		}
+		match self.mappings.get(&PageBase::from_addr(offset))? {
+			PageEntry::MMIO(region) if offset + len as u64 <= region.base + region.size => Some(region),
+			_ => None,
+		}
+	}
+
+	/// copies an image into memory and remembers it so it can be copied back in on reset
+	/// returns Err(virt) if the copy failed
+	pub fn load_image(&mut self, offset: u64, image: Vec<u8>) -> Result<(), u64> {
+		self.write_bytes(offset, &image)?;
+		self.images.push((offset, image.into_boxed_slice()));
+		Ok(())
+	}
+
+	/// puts memory back into its power-on state
+	/// reservations are always dropped, if the memory was built with reload_on_reset
+	/// physical memory is also zeroed and the bootrom and loaded images are copied back in
+	pub fn reset(&mut self) {
+		self.reservations.clear();
+		if self.reload_on_reset {
+			self.reload();
+		}
+	}
+
+	/// zeroes physical memory and copies the bootrom and loaded images back in, whatever reload_on_reset says
+	pub fn reload(&mut self) {
+		self.phys.clear();
+		self.bootrom.copy_from_slice(&self.bootrom_image);
+		let images = std::mem::take(&mut self.images);
+		for (offset, image) in images.iter() {
+			self.write_bytes(*offset, image)
+				.expect("image was loaded successfully before reset");
+		}
+		self.images = images;
+	}
+
 	/// Returns Err(virt_addr) on failure
 	fn translate_address(&self, virt_addr: u64) -> Result<u64, u64> {
 		let base = PageBase::from_addr(virt_addr);
@@ -138,12 +619,8 @@ impl Memory {
 		let page_offset = virt_addr - base.0;
 
 		match page_entry {
-			PageEntry::PhysBacked { phys_base } => Ok(phys_base + page_offset),
-			PageEntry::Bootrom { page_base: _ }
-			| PageEntry::MMIO {
-				on_read: _,
-				on_write: _,
-			} => Err(virt_addr), // TODO: What to do for Bootrom & MMIO?
+			PageEntry::PhysBacked { phys_base, .. } => Ok(phys_base + page_offset),
+			PageEntry::Bootrom { .. } | PageEntry::MMIO(_) => Err(virt_addr), // TODO: What to do for Bootrom & MMIO?
 		}
 	}
 
@@ -160,6 +637,17 @@ impl Memory {
 		result
 	}
 
+	/// drops the reservation held by hart_id, if any
+	/// this happens on traps and xRET so an SC can't succeed across them
+	pub fn release_reservation(&mut self, hart_id: usize) {
+		self.reservations.release(hart_id);
+	}
+
+	/// whether hart_id still holds the reservation from its last LR
+	pub fn has_reservation(&self, hart_id: usize) -> bool {
+		self.reservations.holds(hart_id)
+	}
+
 	/// Returns Err(virt_addr) on failure
 	pub fn load_reserved_word(&mut self, virt_addr: u64, hart_id: usize) -> Result<u32, u64> {
 		let phys_addr = self.translate_address(virt_addr)?;
@@ -178,17 +666,14 @@ impl Memory {
 	pub fn store_conditional_word(&mut self, virt_addr: u64, hart_id: usize, word: u32) -> Result<bool, u64> {
 		let phys_addr = self.translate_address(virt_addr)?;
 
-		let is_reserved = self.reservations.is_reserved(phys_addr, hart_id);
-		if !is_reserved {
-			return Ok(false);
-		}
-
 		self.with_atomic_lock(|this| {
-			let write_result = this.write_u32(virt_addr, word);
-
-			this.reservations.unreserve(phys_addr);
+			if !this.reservations.take(phys_addr, hart_id) {
+				return Ok(false);
+			}
 
-			Ok(write_result.is_ok())
+			// the write drops any other hart's reservation on the same granule
+			this.write_u32(virt_addr, word)?;
+			Ok(true)
 		})
 	}
 
@@ -196,17 +681,53 @@ impl Memory {
 	pub fn store_conditional_dword(&mut self, virt_addr: u64, hart_id: usize, dword: u64) -> Result<bool, u64> {
 		let phys_addr = self.translate_address(virt_addr)?;
 
-		let is_reserved = self.reservations.is_reserved(phys_addr, hart_id);
-		if !is_reserved {
-			return Ok(false);
-		}
+		self.with_atomic_lock(|this| {
+			if !this.reservations.take(phys_addr, hart_id) {
+				return Ok(false);
+			}
+
+			// the write drops any other hart's reservation on the same granule
+			this.write_u64(virt_addr, dword)?;
+			Ok(true)
+		})
+	}
+
+	/// Returns Ok(original_value) or Err(virt_addr)
+	pub fn atomic_op_qword<F: FnOnce(u128) -> Option<u128>>(&mut self, virt_addr: u64, op: F) -> Result<u128, u64> {
+		self.with_atomic_lock(|this| {
+			let qword = this.read_u128(virt_addr)?;
 
+			if let Some(replacement) = op(qword) {
+				this.write_u128(virt_addr, replacement)?;
+			}
+
+			Ok(qword)
+		})
+	}
+
+	/// Returns Ok(original_value) or Err(virt_addr)
+	pub fn atomic_op_byte<F: FnOnce(u8) -> Option<u8>>(&mut self, virt_addr: u64, op: F) -> Result<u8, u64> {
 		self.with_atomic_lock(|this| {
-			let write_result = this.write_u64(virt_addr, dword);
+			let byte = this.read_u8(virt_addr)?;
 
-			this.reservations.unreserve(phys_addr);
+			if let Some(replacement) = op(byte) {
+				this.write_u8(virt_addr, replacement)?;
+			}
 
-			Ok(write_result.is_ok())
+			Ok(byte)
+		})
+	}
+
+	/// Returns Ok(original_value) or Err(virt_addr)
+	pub fn atomic_op_half<F: FnOnce(u16) -> Option<u16>>(&mut self, virt_addr: u64, op: F) -> Result<u16, u64> {
+		self.with_atomic_lock(|this| {
+			let half = this.read_u16(virt_addr)?;
+
+			if let Some(replacement) = op(half) {
+				this.write_u16(virt_addr, replacement)?;
+			}
+
+			Ok(half)
 		})
 	}
 
@@ -241,24 +762,194 @@ impl Memory {
 	}
 }
 
+/// what a mapping can be used for, the hart raises an access fault for anything else
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Permissions(u8);
+
+impl Permissions {
+	pub const READ: Self = Self(1 << 0);
+	pub const WRITE: Self = Self(1 << 1);
+	pub const EXECUTE: Self = Self(1 << 2);
+	pub const ALL: Self = Self(Self::READ.0 | Self::WRITE.0 | Self::EXECUTE.0);
+
+	pub const fn has(self, other: Self) -> bool {
+		(self.0 & other.0) == other.0
+	}
+
+	pub fn allows(self, access: AccessType) -> bool {
+		match access {
+			AccessType::Fetch => self.has(Self::EXECUTE),
+			AccessType::Load => self.has(Self::READ),
+			// AMOs read too, but there's no such thing as write-only memory here
+			AccessType::Store => self.has(Self::WRITE),
+		}
+	}
+}
+
+/// parses permissions written like "rwx" or "rx"
+impl FromStr for Permissions {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		s.chars().try_fold(Self(0), |perms, c| match c {
+			'r' => Ok(perms | Self::READ),
+			'w' => Ok(perms | Self::WRITE),
+			'x' => Ok(perms | Self::EXECUTE),
+			_ => Err(format!("invalid permissions {s:?}, expected some of r, w and x")),
+		})
+	}
+}
+
+impl BitOr for Permissions {
+	type Output = Self;
+	fn bitor(self, rhs: Self) -> Self::Output {
+		Permissions(self.0 | rhs.0)
+	}
+}
+
 pub enum PageEntry {
 	PhysBacked {
 		phys_base: u64,
+		perms: Permissions,
 	},
 	Bootrom {
 		page_base: u64,
+		perms: Permissions,
 	},
-	MMIO {
-		on_read: Box<dyn Fn(u64) -> u8>,
-		on_write: Box<dyn Fn(u64, u8)>,
-	},
+	/// every page of a region shares it
+	MMIO(Rc<MmioRegion>),
+}
+
+impl PageEntry {
+	pub fn permissions(&self) -> Permissions {
+		match self {
+			PageEntry::PhysBacked { perms, .. } | PageEntry::Bootrom { perms, .. } => *perms,
+			PageEntry::MMIO(region) => region.perms,
+		}
+	}
+}
+
+/// called with the address, the access size in bytes and the zero-extended value
+pub type HookCallback = Box<dyn Fn(u64, usize, u64)>;
+
+/// observes guest accesses to [start, end) without changing them, hooks run after the access succeeds
+/// accesses made for the debugger or to load images aren't reported
+pub struct MemoryHook {
+	pub start: u64,
+	pub end: u64,
+	pub on_read: Option<HookCallback>,
+	pub on_write: Option<HookCallback>,
+}
+
+impl MemoryHook {
+	pub fn new(start: u64, size: u64) -> Self {
+		Self {
+			start,
+			end: start.saturating_add(size),
+			on_read: None,
+			on_write: None,
+		}
+	}
+
+	pub fn on_read(mut self, callback: impl Fn(u64, usize, u64) + 'static) -> Self {
+		self.on_read = Some(Box::new(callback));
+		self
+	}
+
+	pub fn on_write(mut self, callback: impl Fn(u64, usize, u64) + 'static) -> Self {
+		self.on_write = Some(Box::new(callback));
+		self
+	}
+}
+
+/// a guest access that touched a poisoned range
+/// reads that failed partway through have zeroes for the bytes that couldn't be read
+#[derive(Debug, Clone)]
+pub struct PoisonedAccess {
+	pub addr: u64,
+	pub write: bool,
+	/// the bytes read or written, in memory order
+	pub data: Vec<u8>,
+}
+
+impl PoisonedAccess {
+	/// the little endian value of the bytes, as hex
+	pub fn value(&self) -> String {
+		let digits: String = self.data.iter().rev().map(|byte| format!("{byte:02x}")).collect();
+		format!("0x{digits}")
+	}
+}
+
+/// a device's register window, every page in it is routed to the same handlers
+/// handlers get the offset into the region and the access size in bytes, 1, 2, 4 or 8
+/// values are zero-extended and accesses of other sizes or past the end of the region are split into bytes
+/// the region is mapped in whole pages, the bytes of the last page past its size read as zero and ignore writes
+pub struct MmioRegion {
+	pub base: u64,
+	pub size: u64,
+	/// registers can be read and written but not executed unless this says otherwise
+	pub perms: Permissions,
+	pub on_read: Box<dyn Fn(u64, usize) -> u64>,
+	pub on_write: Box<dyn Fn(u64, usize, u64)>,
+}
+
+impl MmioRegion {
+	pub fn new(
+		base: u64,
+		size: u64,
+		on_read: impl Fn(u64, usize) -> u64 + 'static,
+		on_write: impl Fn(u64, usize, u64) + 'static,
+	) -> Self {
+		Self {
+			base,
+			size,
+			perms: Permissions::READ | Permissions::WRITE,
+			on_read: Box::new(on_read),
+			on_write: Box::new(on_write),
+		}
+	}
+
+	fn read_byte(&self, offset: u64) -> u8 {
+		if offset >= self.size {
+			return 0;
+		}
+		(self.on_read)(offset, 1) as u8
+	}
+
+	fn write_byte(&self, offset: u64, val: u8) {
+		if offset < self.size {
+			(self.on_write)(offset, 1, u64::from(val));
+		}
+	}
+
+	/// a region for devices whose registers are all a byte wide
+	/// wider accesses are split into bytes, lowest address first
+	pub fn byte_wide(
+		base: u64,
+		size: u64,
+		on_read: impl Fn(u64) -> u8 + 'static,
+		on_write: impl Fn(u64, u8) + 'static,
+	) -> Self {
+		Self::new(
+			base,
+			size,
+			move |offset, size| {
+				(0..size as u64).fold(0, |acc, byte| acc | (u64::from(on_read(offset + byte)) << (byte * 8)))
+			},
+			move |offset, size, val| {
+				for byte in 0..size as u64 {
+					on_write(offset + byte, (val >> (byte * 8)) as u8);
+				}
+			},
+		)
+	}
 }
 
 fn align_to_page(addr: u64) -> u64 {
 	(addr + (PAGE_SIZE - 1)) & !(PAGE_SIZE - 1)
 }
 
-const PAGE_SIZE: u64 = 4096;
+pub const PAGE_SIZE: u64 = 4096;
 #[derive(Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 /// INVARIANT: is a multiple of PAGE_SIZE
 pub struct PageBase(u64);
@@ -297,26 +988,30 @@ macro_rules! impl_mem_rw {
 	};
 }
 
-impl_mem_rw!(u8, u16, u32, u64, SoftFloat, SoftDouble);
+impl_mem_rw!(u8, u16, u32, u64, u128, SoftFloat, SoftDouble);
 
 #[derive(Default)]
 pub struct MemoryBuilder {
 	// size of physical memory
 	physical: Option<u64>,
-	// physical addr -> (virt addr, map_size bytes)
-	physical_mappings: HashMap<PageBase, (PageBase, u64)>,
+	// (virt addr, physical addr, map_size bytes, permissions)
+	physical_mappings: Vec<(PageBase, PageBase, u64, Permissions)>,
 
-	misc_maps: HashMap<PageBase, PageEntry>,
-	// bootrom data, virtual offset
-	bootrom: Option<(Box<[u8]>, PageBase)>,
+	mmio_regions: Vec<MmioRegion>,
+	// bootrom data, virtual offset, permissions
+	bootrom: Option<(Box<[u8]>, PageBase, Permissions)>,
+	reload_on_reset: bool,
+	poisoned: Vec<Range<u64>>,
+	backing_file: Option<File>,
+	reservation_granule: Option<u64>,
 }
 
 impl MemoryBuilder {
-	pub fn bootrom(mut self, mut bootrom: Vec<u8>, addr: PageBase) -> Self {
+	pub fn bootrom(mut self, mut bootrom: Vec<u8>, addr: PageBase, perms: Permissions) -> Self {
 		assert!(self.bootrom.is_none(), "cannot set bootrom more than once");
 		let padded_len = align_to_page(bootrom.len() as u64);
 		bootrom.resize(padded_len as usize, 0_u8);
-		self.bootrom = Some((bootrom.into_boxed_slice(), addr));
+		self.bootrom = Some((bootrom.into_boxed_slice(), addr, perms));
 		self
 	}
 
@@ -331,65 +1026,315 @@ impl MemoryBuilder {
 		self
 	}
 
-	pub fn phys_mapping(mut self, virt_base: PageBase, phys_base: PageBase, size: u64) -> Self {
+	pub fn phys_mapping(mut self, virt_base: PageBase, phys_base: PageBase, size: u64, perms: Permissions) -> Self {
 		assert_eq!(size % PAGE_SIZE, 0);
-		let prev = self.physical_mappings.insert(virt_base, (phys_base, size));
-		assert!(prev.is_none());
+		self.physical_mappings.push((virt_base, phys_base, size, perms));
+		self
+	}
+
+	/// backs physical memory with a host file instead of the heap, the file is truncated and resized to fit
+	pub fn backing_file(mut self, file: File) -> Self {
+		self.backing_file = Some(file);
 		self
 	}
 
-	pub fn add_mapping(mut self, virt_addr: PageBase, entry: PageEntry) -> Self {
-		let prev = self.misc_maps.insert(virt_addr, entry);
+	/// the size of the reservation set LR takes, a power of two of at least 8 bytes so LR.D fits
+	/// by default it's DEFAULT_RESERVATION_GRANULE
+	pub fn reservation_granule(mut self, bytes: u64) -> Self {
 		assert!(
-			prev.is_none(),
-			"cannot overwrite mapping for virtual address {:#018X}",
-			virt_addr.0
+			bytes.is_power_of_two() && bytes >= 8,
+			"the reservation granule must be a power of two of at least 8 bytes"
 		);
+		self.reservation_granule = Some(bytes);
+		self
+	}
+
+	/// whether memory should be wiped and reloaded from its images when the machine resets,
+	/// by default memory is preserved like a warm reboot
+	pub fn reload_on_reset(mut self, reload: bool) -> Self {
+		self.reload_on_reset = reload;
+		self
+	}
+
+	/// stops the hart with a report whenever the guest touches [start, start + size)
+	/// the range doesn't have to be mapped or page aligned, and accesses still go through as normal
+	pub fn poison(mut self, start: u64, size: u64) -> Self {
+		self.poisoned.push(start..start.saturating_add(size));
+		self
+	}
+
+	/// maps a device over [base, base + size), the base has to be page aligned
+	pub fn add_mmio(mut self, region: MmioRegion) -> Self {
+		assert_eq!(region.base % PAGE_SIZE, 0);
+		self.mmio_regions.push(region);
 		self
 	}
 
-	#[track_caller] // provides better panic location for caller
-	pub fn build(self) -> Memory {
-		let phys = vec![0_u8; self.physical.unwrap_or(0) as usize].into_boxed_slice();
+	/// every mapping is checked against every other one first, so all the overlaps are reported at once
+	pub fn build(self) -> Result<Memory, MemoryBuildError> {
+		self.check_overlaps()?;
+
+		let size = self.physical.unwrap_or(0);
+		let phys = match self.backing_file {
+			Some(file) => PhysicalMemory::file_backed(size, file).map_err(MemoryBuildError::BackingFile)?,
+			None => PhysicalMemory::new(size),
+		};
 		let mut mappings = HashMap::new();
 
-		let (bootrom, virt_addr) = self.bootrom.unwrap_or_default();
+		let (bootrom, virt_addr, bootrom_perms) =
+			self.bootrom
+				.unwrap_or((Box::default(), PageBase::default(), Permissions::ALL));
 		for offset in (0..bootrom.len() as u64).step_by(PAGE_SIZE as usize) {
 			// INVARIANT: virtual address is verified to be a multiple of page size
 			// and loop ensures that it's only offset by page size
-			mappings.insert(PageBase(virt_addr.0 + offset), PageEntry::Bootrom { page_base: offset });
+			mappings.insert(
+				PageBase(virt_addr.0 + offset),
+				PageEntry::Bootrom {
+					page_base: offset,
+					perms: bootrom_perms,
+				},
+			);
 		}
 
-		for (virt_base, (phys_base, map_size)) in self.physical_mappings.into_iter() {
+		for (virt_base, phys_base, map_size, perms) in self.physical_mappings {
 			for offset in (0..map_size).step_by(PAGE_SIZE as usize) {
-				let virt = PageBase(virt_base.0 + offset);
-				let prev = mappings.insert(
-					virt,
+				mappings.insert(
+					PageBase(virt_base.0 + offset),
 					PageEntry::PhysBacked {
 						phys_base: phys_base.0 + offset,
+						perms,
 					},
 				);
-				assert!(
-					prev.is_none(),
-					"overlapped virtual address {:?} in physical mapping {:?} size {:#018X})",
-					virt,
-					virt_base,
-					map_size
-				);
 			}
 		}
 
-		for (virt, entry) in self.misc_maps.into_iter() {
-			let prev = mappings.insert(virt, entry);
-			assert!(prev.is_none(), "overlapped virtual address {:?} in misc mapping", virt);
+		for region in self.mmio_regions {
+			let region = Rc::new(region);
+			for offset in (0..align_to_page(region.size)).step_by(PAGE_SIZE as usize) {
+				mappings.insert(PageBase(region.base + offset), PageEntry::MMIO(region.clone()));
+			}
 		}
 
-		Memory {
+		Ok(Memory {
 			phys,
 			mappings,
+			bootrom_image: bootrom.clone(),
 			bootrom,
-			reservations: MemoryReservations::new(),
+			images: Vec::new(),
+			reload_on_reset: self.reload_on_reset,
+			hooks: Vec::new(),
+			poisoned: self.poisoned,
+			poisoned_access: RefCell::default(),
+			reservations: MemoryReservations::new(self.reservation_granule.unwrap_or(DEFAULT_RESERVATION_GRANULE)),
 			atomic_lock: AtomicBool::default(),
+		})
+	}
+
+	/// every mapping with the page aligned range it covers
+	fn regions(&self) -> Vec<MappedRegion> {
+		let bootrom = self.bootrom.iter().map(|(bootrom, base, _)| MappedRegion {
+			kind: RegionKind::Bootrom,
+			base: base.0,
+			size: bootrom.len() as u64,
+		});
+		let physical = self
+			.physical_mappings
+			.iter()
+			.map(|&(virt_base, _, size, _)| MappedRegion {
+				kind: RegionKind::Physical,
+				base: virt_base.0,
+				size,
+			});
+		let mmio = self.mmio_regions.iter().map(|region| MappedRegion {
+			kind: RegionKind::Mmio,
+			base: region.base,
+			size: align_to_page(region.size),
+		});
+		bootrom.chain(physical).chain(mmio).collect()
+	}
+
+	fn check_overlaps(&self) -> Result<(), MemoryBuildError> {
+		let regions = self.regions();
+		let mut conflicts = Vec::new();
+		for (idx, first) in regions.iter().enumerate() {
+			for second in &regions[idx + 1..] {
+				if first.overlaps(second) {
+					conflicts.push((*first, *second));
+				}
+			}
+		}
+		if conflicts.is_empty() {
+			Ok(())
+		} else {
+			Err(MemoryBuildError::Overlaps(conflicts))
 		}
 	}
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegionKind {
+	Bootrom,
+	Physical,
+	Mmio,
+}
+
+/// a range of addresses a mapping covers, rounded up to whole pages
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MappedRegion {
+	pub kind: RegionKind,
+	pub base: u64,
+	pub size: u64,
+}
+
+impl MappedRegion {
+	fn end(&self) -> u64 {
+		self.base.saturating_add(self.size)
+	}
+
+	fn overlaps(&self, other: &MappedRegion) -> bool {
+		self.size != 0 && other.size != 0 && self.base < other.end() && other.base < self.end()
+	}
+}
+
+impl std::fmt::Display for MappedRegion {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let kind = match self.kind {
+			RegionKind::Bootrom => "the bootrom",
+			RegionKind::Physical => "memory",
+			RegionKind::Mmio => "MMIO",
+		};
+		write!(f, "{kind} at {:#018X}..{:#018X}", self.base, self.end())
+	}
+}
+
+#[derive(Debug)]
+pub enum MemoryBuildError {
+	/// the mappings given to the builder overlap, each pair that does is listed once
+	Overlaps(Vec<(MappedRegion, MappedRegion)>),
+	/// the file backing physical memory couldn't be resized or mapped
+	BackingFile(io::Error),
+}
+
+impl std::fmt::Display for MemoryBuildError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			MemoryBuildError::Overlaps(conflicts) => {
+				write!(f, "{} overlapping mappings", conflicts.len())?;
+				for (first, second) in conflicts {
+					write!(f, "\n  {first} overlaps {second}")?;
+				}
+				Ok(())
+			}
+			MemoryBuildError::BackingFile(err) => write!(f, "could not map the backing file: {err}"),
+		}
+	}
+}
+
+impl std::error::Error for MemoryBuildError {}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const DRAM_BASE: u64 = 0x8000_0000;
+	const HART: usize = 0;
+
+	fn memory(granule: u64) -> Memory {
+		MemoryBuilder::default()
+			.physical_size(0x10000)
+			.phys_mapping(
+				PageBase::from_addr(DRAM_BASE),
+				PageBase::from_addr(0),
+				0x10000,
+				Permissions::ALL,
+			)
+			.reservation_granule(granule)
+			.build()
+			.unwrap()
+	}
+
+	#[test]
+	fn sc_succeeds_without_intervening_writes() {
+		let mut mem = memory(64);
+		mem.load_reserved_word(DRAM_BASE, HART).unwrap();
+		assert!(mem.store_conditional_word(DRAM_BASE, HART, 1).unwrap());
+		assert_eq!(mem.read_u32(DRAM_BASE).unwrap(), 1);
+	}
+
+	#[test]
+	fn sc_consumes_the_reservation() {
+		let mut mem = memory(64);
+		mem.load_reserved_word(DRAM_BASE, HART).unwrap();
+		assert!(mem.store_conditional_word(DRAM_BASE, HART, 1).unwrap());
+		assert!(!mem.store_conditional_word(DRAM_BASE, HART, 2).unwrap());
+		assert_eq!(mem.read_u32(DRAM_BASE).unwrap(), 1);
+	}
+
+	#[test]
+	fn hart_store_to_the_granule_clears_it() {
+		let mut mem = memory(64);
+		mem.load_reserved_word(DRAM_BASE, HART).unwrap();
+		mem.write_u8(DRAM_BASE + 63, 0xFF).unwrap();
+		assert!(!mem.store_conditional_word(DRAM_BASE, HART, 1).unwrap());
+	}
+
+	#[test]
+	fn store_outside_the_granule_keeps_it() {
+		let mut mem = memory(64);
+		mem.load_reserved_word(DRAM_BASE, HART).unwrap();
+		mem.write_u8(DRAM_BASE + 64, 0xFF).unwrap();
+		assert!(mem.store_conditional_word(DRAM_BASE, HART, 1).unwrap());
+	}
+
+	#[test]
+	fn store_reaching_into_the_granule_clears_it() {
+		let mut mem = memory(64);
+		mem.load_reserved_word(DRAM_BASE + 64, HART).unwrap();
+		mem.write_slice(DRAM_BASE + 60, &[0xFF; 8]).unwrap();
+		assert!(!mem.store_conditional_word(DRAM_BASE + 64, HART, 1).unwrap());
+	}
+
+	#[test]
+	fn granule_size_is_configurable() {
+		let mut small = memory(8);
+		small.load_reserved_dword(DRAM_BASE, HART).unwrap();
+		small.write_u64(DRAM_BASE + 8, 0xFF).unwrap();
+		assert!(small.store_conditional_dword(DRAM_BASE, HART, 1).unwrap());
+
+		let mut large = memory(4096);
+		large.load_reserved_dword(DRAM_BASE, HART).unwrap();
+		large.write_u64(DRAM_BASE + 2048, 0xFF).unwrap();
+		assert!(!large.store_conditional_dword(DRAM_BASE, HART, 1).unwrap());
+	}
+
+	#[test]
+	fn debugger_write_clears_it() {
+		let mut mem = memory(64);
+		mem.load_reserved_word(DRAM_BASE, HART).unwrap();
+		mem.debug_write_slice(DRAM_BASE + 4, &[1, 2, 3, 4]).unwrap();
+		assert!(!mem.store_conditional_word(DRAM_BASE, HART, 1).unwrap());
+	}
+
+	#[test]
+	fn loaded_image_clears_it() {
+		let mut mem = memory(64);
+		mem.load_reserved_word(DRAM_BASE, HART).unwrap();
+		mem.load_image(DRAM_BASE + 16, vec![0xAA; 16]).unwrap();
+		assert!(!mem.store_conditional_word(DRAM_BASE, HART, 1).unwrap());
+	}
+
+	#[test]
+	fn other_harts_keep_theirs() {
+		let mut mem = memory(64);
+		mem.load_reserved_word(DRAM_BASE, HART).unwrap();
+		mem.load_reserved_word(DRAM_BASE + 128, HART + 1).unwrap();
+		assert!(mem.store_conditional_word(DRAM_BASE, HART, 1).unwrap());
+		assert!(mem.store_conditional_word(DRAM_BASE + 128, HART + 1, 1).unwrap());
+	}
+
+	#[test]
+	#[should_panic(expected = "power of two")]
+	fn granule_must_be_a_power_of_two() {
+		let _ = MemoryBuilder::default().reservation_granule(48);
+	}
+}
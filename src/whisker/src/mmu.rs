@@ -0,0 +1,448 @@
+use std::collections::HashMap;
+
+use crate::cpu::{WatchKind, WhiskerCpu};
+use crate::csr::Mstatus;
+use crate::mem::Memory;
+use crate::pmp::Pmp;
+use crate::soft::double::SoftDouble;
+use crate::soft::float::SoftFloat;
+use crate::ty::{PrivilegeMode, TrapIdx};
+
+const PAGE_SHIFT: u64 = 12;
+const PAGE_SIZE: u64 = 1 << PAGE_SHIFT;
+// each level of the page table translates 9 bits of the virtual page number
+const LEVEL_BITS: u64 = 9;
+const PTE_SIZE: u64 = 8;
+
+/// the kinds of memory access, they need different permissions and raise different faults
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessType {
+	Fetch,
+	Load,
+	/// AMOs and SCs count as stores even though they also read
+	Store,
+}
+
+impl AccessType {
+	pub fn page_fault(self) -> TrapIdx {
+		match self {
+			AccessType::Fetch => TrapIdx::INSTRUCTION_PAGE_FAULT,
+			AccessType::Load => TrapIdx::LOAD_PAGE_FAULT,
+			AccessType::Store => TrapIdx::STORE_PAGE_FAULT,
+		}
+	}
+
+	pub fn access_fault(self) -> TrapIdx {
+		match self {
+			AccessType::Fetch => TrapIdx::INSTRUCTION_ACCESS_FAULT,
+			AccessType::Load => TrapIdx::LOAD_ACCESS_FAULT,
+			AccessType::Store => TrapIdx::STORE_ACCESS_FAULT,
+		}
+	}
+
+	pub fn misaligned(self) -> TrapIdx {
+		match self {
+			AccessType::Fetch => TrapIdx::INSTRUCTION_ADDR_MISALIGNED,
+			AccessType::Load => TrapIdx::LOAD_ADDR_MISALIGNED,
+			AccessType::Store => TrapIdx::STORE_ADDR_MISALIGNED,
+		}
+	}
+}
+
+/// the address translation schemes satp can select, in order of increasing address space size
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, clap::ValueEnum)]
+pub enum TranslationMode {
+	/// no translation
+	Bare,
+	/// 39 bit virtual addresses, 3 levels
+	Sv39,
+	/// 48 bit virtual addresses, 4 levels
+	Sv48,
+	/// 57 bit virtual addresses, 5 levels
+	#[default]
+	Sv57,
+}
+
+impl TranslationMode {
+	/// decodes satp.MODE, None for modes we don't support
+	pub fn from_bits(bits: u64) -> Option<Self> {
+		match bits {
+			0 => Some(Self::Bare),
+			8 => Some(Self::Sv39),
+			9 => Some(Self::Sv48),
+			10 => Some(Self::Sv57),
+			_ => None,
+		}
+	}
+
+	/// the number of page table levels
+	fn levels(self) -> u64 {
+		match self {
+			Self::Bare => 0,
+			Self::Sv39 => 3,
+			Self::Sv48 => 4,
+			Self::Sv57 => 5,
+		}
+	}
+
+	/// the width of a virtual address
+	fn va_bits(self) -> u64 {
+		PAGE_SHIFT + LEVEL_BITS * self.levels()
+	}
+}
+
+/// satp with accessors for its fields
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Satp(pub u64);
+
+#[allow(unused)]
+impl Satp {
+	const MODE_SHIFT: u64 = 60;
+	const ASID_SHIFT: u64 = 44;
+	pub const ASID: u64 = 0xFFFF << Self::ASID_SHIFT;
+	pub const PPN: u64 = (1 << Self::ASID_SHIFT) - 1;
+
+	/// None if satp holds a mode we don't support, the cpu never lets that happen
+	pub fn mode(self) -> Option<TranslationMode> {
+		TranslationMode::from_bits(self.0 >> Self::MODE_SHIFT)
+	}
+
+	pub fn asid(self) -> u64 {
+		(self.0 & Self::ASID) >> Self::ASID_SHIFT
+	}
+
+	/// the physical page of the root page table
+	pub fn ppn(self) -> u64 {
+		self.0 & Self::PPN
+	}
+}
+
+/// a page table entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PageTableEntry(u64);
+
+impl PageTableEntry {
+	const VALID: u64 = 1 << 0;
+	const READ: u64 = 1 << 1;
+	const WRITE: u64 = 1 << 2;
+	const EXECUTE: u64 = 1 << 3;
+	const USER: u64 = 1 << 4;
+	const GLOBAL: u64 = 1 << 5;
+	const ACCESSED: u64 = 1 << 6;
+	const DIRTY: u64 = 1 << 7;
+	const PPN_SHIFT: u64 = 10;
+	const PPN: u64 = ((1 << 44) - 1) << Self::PPN_SHIFT;
+	// N, PBMT and the reserved bits, we don't implement Svnapot or Svpbmt so these must be zero
+	const RESERVED: u64 = !((1 << 54) - 1);
+
+	fn has(self, flag: u64) -> bool {
+		self.0 & flag != 0
+	}
+
+	fn ppn(self) -> u64 {
+		(self.0 & Self::PPN) >> Self::PPN_SHIFT
+	}
+
+	fn is_leaf(self) -> bool {
+		self.has(Self::READ) || self.has(Self::EXECUTE)
+	}
+
+	/// whether this is an invalid entry or one using a reserved encoding
+	fn is_invalid(self) -> bool {
+		!self.has(Self::VALID) || (!self.has(Self::READ) && self.has(Self::WRITE)) || self.0 & Self::RESERVED != 0
+	}
+
+	/// whether a leaf entry allows an access, both by its permissions and its U bit
+	fn allows(self, ctx: &TranslationContext, access: AccessType) -> bool {
+		let allowed = match access {
+			AccessType::Fetch => self.has(Self::EXECUTE),
+			AccessType::Load => self.has(Self::READ) || (ctx.mxr && self.has(Self::EXECUTE)),
+			AccessType::Store => self.has(Self::WRITE),
+		};
+		let privilege_allowed = match ctx.privilege {
+			PrivilegeMode::User => self.has(Self::USER),
+			// S-mode can never execute user pages, and only touches their data with SUM set
+			PrivilegeMode::Supervisor => !self.has(Self::USER) || (access != AccessType::Fetch && ctx.sum),
+			PrivilegeMode::Machine => unreachable!("M-mode accesses aren't translated"),
+		};
+		allowed && privilege_allowed
+	}
+}
+
+/// everything outside of the page tables that translation depends on
+#[derive(Debug, Clone, Copy)]
+pub struct TranslationContext {
+	pub satp: Satp,
+	/// the privilege the access is checked against, loads and stores are affected by mstatus.MPRV
+	pub privilege: PrivilegeMode,
+	/// S-mode may load from and store to user pages
+	pub sum: bool,
+	/// loads may read from execute-only pages
+	pub mxr: bool,
+}
+
+impl TranslationContext {
+	/// whether addresses have to go through the page tables at all
+	fn translates(&self) -> bool {
+		self.privilege != PrivilegeMode::Machine && self.satp.mode().is_some_and(|mode| mode != TranslationMode::Bare)
+	}
+}
+
+/// walks the page tables to find the physical address of vaddr, or finds it in the TLB
+/// the accessed and dirty bits are updated in the page tables as needed, the page table accesses are checked by the PMP
+/// returns the fault to raise if the access isn't allowed
+pub fn translate(
+	mem: &mut Memory,
+	pmp: &Pmp,
+	tlb: &mut Tlb,
+	ctx: &TranslationContext,
+	vaddr: u64,
+	access: AccessType,
+) -> Result<u64, TrapIdx> {
+	if !ctx.translates() {
+		return Ok(vaddr);
+	}
+	let mode = ctx.satp.mode().expect("satp only holds supported modes");
+
+	// the bits above the virtual address have to be copies of its top bit
+	let va_bits = mode.va_bits();
+	let upper = (vaddr as i64) >> (va_bits - 1);
+	if upper != 0 && upper != -1 {
+		return Err(access.page_fault());
+	}
+
+	let asid = ctx.satp.asid();
+	if let Some(entry) = tlb.lookup(vaddr, asid) {
+		// anything the cached entry doesn't allow, including setting D, is retried with a fresh walk
+		let needs_dirty = access == AccessType::Store && !entry.pte.has(PageTableEntry::DIRTY);
+		if entry.pte.allows(ctx, access) && !needs_dirty {
+			return Ok(entry.physical(vaddr));
+		}
+	}
+
+	let mut table = ctx.satp.ppn() << PAGE_SHIFT;
+	let mut level = mode.levels() - 1;
+	let (pte_addr, mut pte) = loop {
+		let vpn = (vaddr >> (PAGE_SHIFT + LEVEL_BITS * level)) & ((1 << LEVEL_BITS) - 1);
+		let pte_addr = table + vpn * PTE_SIZE;
+		// the walk happens on behalf of S-mode whatever the privilege of the access is
+		if !pmp.check(pte_addr, PTE_SIZE, PrivilegeMode::Supervisor, AccessType::Load) {
+			return Err(access.access_fault());
+		}
+		let pte = PageTableEntry(mem.read_u64(pte_addr).map_err(|_| access.access_fault())?);
+
+		if pte.is_invalid() {
+			return Err(access.page_fault());
+		}
+		if pte.is_leaf() {
+			break (pte_addr, pte);
+		}
+		if level == 0 {
+			return Err(access.page_fault());
+		}
+		table = pte.ppn() << PAGE_SHIFT;
+		level -= 1;
+	};
+
+	if !pte.allows(ctx, access) {
+		return Err(access.page_fault());
+	}
+
+	// superpages have to be aligned to their size
+	let ppn_low_mask = (page_size(level) >> PAGE_SHIFT) - 1;
+	if pte.ppn() & ppn_low_mask != 0 {
+		return Err(access.page_fault());
+	}
+
+	// A and D are set by the hardware, instead of raising a page fault for software to set them
+	let mut updated = pte;
+	updated.0 |= PageTableEntry::ACCESSED;
+	if access == AccessType::Store {
+		updated.0 |= PageTableEntry::DIRTY;
+	}
+	if updated != pte {
+		if !pmp.check(pte_addr, PTE_SIZE, PrivilegeMode::Supervisor, AccessType::Store) {
+			return Err(access.access_fault());
+		}
+		mem.write_u64(pte_addr, updated.0).map_err(|_| access.access_fault())?;
+		pte = updated;
+	}
+
+	let entry = TlbEntry { asid, pte, level };
+	tlb.insert(vaddr, entry);
+	Ok(entry.physical(vaddr))
+}
+
+/// the size of the page a leaf PTE at level maps
+fn page_size(level: u64) -> u64 {
+	PAGE_SIZE << (LEVEL_BITS * level)
+}
+
+/// a cached leaf PTE
+#[derive(Debug, Clone, Copy)]
+struct TlbEntry {
+	asid: u64,
+	pte: PageTableEntry,
+	level: u64,
+}
+
+impl TlbEntry {
+	fn physical(self, vaddr: u64) -> u64 {
+		let page_size = page_size(self.level);
+		(self.pte.ppn() << PAGE_SHIFT) & !(page_size - 1) | (vaddr & (page_size - 1))
+	}
+
+	fn covers(self, vpn: u64, vaddr: u64) -> bool {
+		let page_mask = !(page_size(self.level) - 1);
+		(vpn << PAGE_SHIFT) & page_mask == vaddr & page_mask
+	}
+}
+
+/// caches translations until SFENCE.VMA flushes them
+/// entries are stored per 4KiB page, so a superpage can take up more than one
+#[derive(Debug, Default)]
+pub struct Tlb {
+	// virtual page number -> the leaf PTE that maps it
+	entries: HashMap<u64, TlbEntry>,
+}
+
+impl Tlb {
+	// a real TLB is much smaller, this only bounds memory use
+	const CAPACITY: usize = 4096;
+
+	fn lookup(&self, vaddr: u64, asid: u64) -> Option<TlbEntry> {
+		self.entries
+			.get(&(vaddr >> PAGE_SHIFT))
+			.copied()
+			.filter(|entry| entry.asid == asid || entry.pte.has(PageTableEntry::GLOBAL))
+	}
+
+	fn insert(&mut self, vaddr: u64, entry: TlbEntry) {
+		if self.entries.len() >= Self::CAPACITY {
+			self.entries.clear();
+		}
+		self.entries.insert(vaddr >> PAGE_SHIFT, entry);
+	}
+
+	/// the flush done by SFENCE.VMA, None means every address or every address space
+	/// flushing a single address space leaves global mappings alone
+	pub fn flush(&mut self, vaddr: Option<u64>, asid: Option<u64>) {
+		match (vaddr, asid) {
+			(None, None) => self.entries.clear(),
+			_ => self.entries.retain(|vpn, entry| {
+				let address_matches = vaddr.is_none_or(|vaddr| entry.covers(*vpn, vaddr));
+				let asid_matches = asid.is_none_or(|asid| entry.asid == asid && !entry.pte.has(PageTableEntry::GLOBAL));
+				!(address_matches && asid_matches)
+			}),
+		}
+	}
+}
+
+impl WhiskerCpu {
+	/// the translation state for an access, taking mstatus.MPRV into account for loads and stores
+	pub fn translation_context(&self, access: AccessType) -> TranslationContext {
+		let status = Mstatus(self.csrs.read_mstatus());
+		let privilege = if self.privilege == PrivilegeMode::Machine && access != AccessType::Fetch && status.mprv() {
+			status.mpp().expect("MPP always holds a legal mode")
+		} else {
+			self.privilege
+		};
+
+		TranslationContext {
+			satp: Satp(self.csrs.read_satp()),
+			privilege,
+			sum: status.sum(),
+			mxr: status.mxr(),
+		}
+	}
+
+	/// translates a virtual address and checks that len bytes there can be accessed
+	/// returns the trap to raise if either fails
+	pub fn translate(&mut self, vaddr: u64, len: u64, access: AccessType) -> Result<u64, (TrapIdx, u64)> {
+		let ctx = self.translation_context(access);
+		let paddr =
+			translate(&mut self.mem, &self.pmp, &mut self.tlb, &ctx, vaddr, access).map_err(|cause| (cause, vaddr))?;
+		if !self.pmp.check(paddr, len, ctx.privilege, access) {
+			return Err((access.access_fault(), vaddr));
+		}
+		// the mapping's own permissions, like ROM not being writable or device registers not being executable
+		if !self.mem.allows(paddr, len, access) {
+			return Err((access.access_fault(), vaddr));
+		}
+		Ok(paddr)
+	}
+
+	/// reads through the MMU, accesses that cross a page boundary are translated one page at a time
+	/// returns the trap to raise on failure, xtval is the first virtual address that couldn't be read
+	pub fn read_virt_slice(&mut self, vaddr: u64, buf: &mut [u8], access: AccessType) -> Result<(), (TrapIdx, u64)> {
+		let mut done = 0;
+		while done < buf.len() {
+			let vaddr = vaddr.wrapping_add(done as u64);
+			let len = page_chunk_len(vaddr, buf.len() - done);
+			let paddr = self.translate(vaddr, len as u64, access)?;
+			self.mem
+				.read_slice(paddr, &mut buf[done..done + len])
+				.map_err(|failed| (access.access_fault(), vaddr + (failed - paddr)))?;
+			// watchpoints are on virtual addresses and never stop on instruction fetches
+			if access != AccessType::Fetch {
+				self.watch_access(vaddr, len as u64, WatchKind::Read);
+			}
+			done += len;
+		}
+		Ok(())
+	}
+
+	/// writes through the MMU, accesses that cross a page boundary are translated one page at a time
+	/// both pages are translated before anything is written so a fault doesn't leave a partial store behind
+	/// returns the trap to raise on failure, xtval is the first virtual address that couldn't be written
+	pub fn write_virt_slice(&mut self, vaddr: u64, val: &[u8]) -> Result<(), (TrapIdx, u64)> {
+		let mut chunks = Vec::with_capacity(2);
+		let mut done = 0;
+		while done < val.len() {
+			let vaddr = vaddr.wrapping_add(done as u64);
+			let len = page_chunk_len(vaddr, val.len() - done);
+			chunks.push((
+				vaddr,
+				self.translate(vaddr, len as u64, AccessType::Store)?,
+				done..done + len,
+			));
+			done += len;
+		}
+
+		for (vaddr, paddr, range) in chunks {
+			let len = range.len() as u64;
+			self.mem
+				.write_slice(paddr, &val[range])
+				.map_err(|failed| (TrapIdx::STORE_ACCESS_FAULT, vaddr + (failed - paddr)))?;
+			self.watch_access(vaddr, len, WatchKind::Write);
+		}
+		Ok(())
+	}
+}
+
+/// how much of an access of len bytes at vaddr fits before the next page boundary
+fn page_chunk_len(vaddr: u64, len: usize) -> usize {
+	let to_boundary = PAGE_SIZE - (vaddr & (PAGE_SIZE - 1));
+	len.min(to_boundary as usize)
+}
+
+macro_rules! impl_virt_rw {
+	($($ty:ty),*) => {
+		#[allow(unused)]
+		impl WhiskerCpu {
+			$(paste::paste!{
+				pub fn [<read_virt_ $ty:snake>](&mut self, vaddr: u64, access: AccessType) -> Result<$ty, (TrapIdx, u64)> {
+					let mut buf = <$ty>::to_le_bytes($ty::default());
+					self.read_virt_slice(vaddr, &mut buf, access)?;
+					Ok(<$ty>::from_le_bytes(buf))
+				}
+
+				pub fn [<write_virt_ $ty:snake>](&mut self, vaddr: u64, val: $ty) -> Result<(), (TrapIdx, u64)> {
+					self.write_virt_slice(vaddr, $ty::to_le_bytes(val).as_slice())
+				}
+			})*
+		}
+	};
+}
+
+impl_virt_rw!(u8, u16, u32, u64, SoftFloat, SoftDouble);
@@ -0,0 +1,191 @@
+use crate::mmu::AccessType;
+use crate::snapshot::{expect_eq, Snapshot, SnapshotReader, SnapshotWriter};
+use crate::ty::PrivilegeMode;
+
+/// the architectural limit on the number of PMP entries
+pub const MAX_PMP_ENTRIES: usize = 64;
+/// OpenSBI and most real hardware expect 16 entries
+pub const DEFAULT_PMP_ENTRIES: usize = 16;
+
+// pmpaddr holds bits 55:2 of the address
+const ADDR_MASK: u64 = (1 << 54) - 1;
+
+/// one byte of pmpcfg
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct PmpConfig(u8);
+
+impl PmpConfig {
+	const READ: u8 = 1 << 0;
+	const WRITE: u8 = 1 << 1;
+	const EXECUTE: u8 = 1 << 2;
+	const MATCH_SHIFT: u8 = 3;
+	const MATCH: u8 = 0b11 << Self::MATCH_SHIFT;
+	const LOCKED: u8 = 1 << 7;
+
+	fn has(self, flag: u8) -> bool {
+		self.0 & flag != 0
+	}
+
+	fn matching(self) -> AddressMatching {
+		match (self.0 & Self::MATCH) >> Self::MATCH_SHIFT {
+			0b00 => AddressMatching::Off,
+			0b01 => AddressMatching::TopOfRange,
+			0b10 => AddressMatching::NaturallyAligned4,
+			_ => AddressMatching::NaturallyAlignedPowerOfTwo,
+		}
+	}
+
+	/// clears the reserved bits and the reserved R=0 W=1 combination
+	fn legalize(mut self) -> Self {
+		self.0 &= Self::READ | Self::WRITE | Self::EXECUTE | Self::MATCH | Self::LOCKED;
+		if !self.has(Self::READ) {
+			self.0 &= !Self::WRITE;
+		}
+		self
+	}
+
+	fn allows(self, access: AccessType) -> bool {
+		match access {
+			AccessType::Fetch => self.has(Self::EXECUTE),
+			AccessType::Load => self.has(Self::READ),
+			AccessType::Store => self.has(Self::WRITE),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AddressMatching {
+	Off,
+	TopOfRange,
+	NaturallyAligned4,
+	NaturallyAlignedPowerOfTwo,
+}
+
+/// the physical memory protection unit, checks every physical access against the pmpcfg and pmpaddr CSRs
+#[derive(Debug, Clone)]
+pub struct Pmp {
+	cfg: [PmpConfig; MAX_PMP_ENTRIES],
+	addr: [u64; MAX_PMP_ENTRIES],
+	// the CSRs for entries past this are read-only zero
+	entries: usize,
+}
+
+impl Pmp {
+	pub fn new(entries: usize) -> Self {
+		assert!(entries <= MAX_PMP_ENTRIES);
+		Self {
+			cfg: [PmpConfig::default(); MAX_PMP_ENTRIES],
+			addr: [0; MAX_PMP_ENTRIES],
+			entries,
+		}
+	}
+
+	/// every entry is unlocked and turned off on reset
+	pub fn reset(&mut self) {
+		*self = Self::new(self.entries);
+	}
+
+	/// reads pmpcfgN, on RV64 only the even ones exist and each holds 8 entries
+	pub fn read_cfg(&self, reg: usize) -> u64 {
+		let first = reg * 4;
+		self.cfg[first..first + 8]
+			.iter()
+			.rev()
+			.fold(0, |acc, cfg| (acc << 8) | u64::from(cfg.0))
+	}
+
+	/// writes pmpcfgN, the bytes for locked or unimplemented entries are left alone
+	pub fn write_cfg(&mut self, reg: usize, val: u64) {
+		let first = reg * 4;
+		for idx in first..(first + 8).min(self.entries) {
+			if self.cfg[idx].has(PmpConfig::LOCKED) {
+				continue;
+			}
+			let byte = (val >> ((idx - first) * 8)) as u8;
+			self.cfg[idx] = PmpConfig(byte).legalize();
+		}
+	}
+
+	pub fn read_addr(&self, idx: usize) -> u64 {
+		self.addr[idx]
+	}
+
+	/// locking an entry also locks pmpaddr of the entry below it when it's used as the bottom of a TOR range
+	pub fn write_addr(&mut self, idx: usize, val: u64) {
+		if idx >= self.entries || self.cfg[idx].has(PmpConfig::LOCKED) {
+			return;
+		}
+		if let Some(next) = self.cfg.get(idx + 1) {
+			if next.has(PmpConfig::LOCKED) && next.matching() == AddressMatching::TopOfRange {
+				return;
+			}
+		}
+		self.addr[idx] = val & ADDR_MASK;
+	}
+
+	/// the bytes an entry covers as [start, end), None if it's off
+	fn range(&self, idx: usize) -> Option<(u64, u64)> {
+		let addr = self.addr[idx];
+		match self.cfg[idx].matching() {
+			AddressMatching::Off => None,
+			AddressMatching::TopOfRange => {
+				let start = if idx == 0 { 0 } else { self.addr[idx - 1] << 2 };
+				// the range is empty if the bottom isn't below the top
+				(start < addr << 2).then_some((start, addr << 2))
+			}
+			AddressMatching::NaturallyAligned4 => Some((addr << 2, (addr << 2) + 4)),
+			AddressMatching::NaturallyAlignedPowerOfTwo => {
+				// the number of trailing ones encodes the size
+				let ones = addr.trailing_ones();
+				let size = 1_u64 << (ones + 3);
+				let start = (addr & !((1 << ones) - 1)) << 2;
+				Some((start, start + size))
+			}
+		}
+	}
+
+	/// whether an access of len bytes at paddr is allowed
+	/// the lowest numbered entry that matches any of the bytes decides, and it has to match all of them
+	/// M-mode is only restricted by locked entries, S and U-mode can't access anything no entry matches
+	pub fn check(&self, paddr: u64, len: u64, privilege: PrivilegeMode, access: AccessType) -> bool {
+		let end = paddr.saturating_add(len);
+		for idx in 0..self.entries {
+			let Some((start, stop)) = self.range(idx) else {
+				continue;
+			};
+			if paddr >= stop || end <= start {
+				continue;
+			}
+			if paddr < start || end > stop {
+				return false;
+			}
+
+			let cfg = self.cfg[idx];
+			if privilege == PrivilegeMode::Machine && !cfg.has(PmpConfig::LOCKED) {
+				return true;
+			}
+			return cfg.allows(access);
+		}
+
+		privilege == PrivilegeMode::Machine || self.entries == 0
+	}
+}
+
+impl Snapshot for Pmp {
+	fn save(&self, snapshot: &mut SnapshotWriter) {
+		snapshot.u64(self.entries as u64);
+		for idx in 0..self.entries {
+			snapshot.u8(self.cfg[idx].0);
+			snapshot.u64(self.addr[idx]);
+		}
+	}
+
+	fn restore(&mut self, snapshot: &mut SnapshotReader) -> Result<(), String> {
+		expect_eq("PMP entries", self.entries, snapshot.u64()? as usize)?;
+		for idx in 0..self.entries {
+			self.cfg[idx] = PmpConfig(snapshot.u8()?).legalize();
+			self.addr[idx] = snapshot.u64()? & ADDR_MASK;
+		}
+		Ok(())
+	}
+}
@@ -1,6 +1,7 @@
 use crate::{
-	soft::{double::SoftDouble, float::SoftFloat},
-	ty::{FPRegisterIndex, GPRegisterIndex},
+	snapshot::{expect_eq, Snapshot, SnapshotReader, SnapshotWriter},
+	soft::{double::SoftDouble, float::SoftFloat, half::SoftHalf},
+	ty::{FPRegisterIndex, GPRegisterIndex, VectorRegisterIndex},
 };
 
 #[derive(Default, Debug)]
@@ -41,10 +42,13 @@ impl GPRegisters {
 #[derive(Default, Debug)]
 pub struct FPRegisters {
 	x: [u64; 32],
+	// set by every write, the cpu uses it to mark mstatus.FS dirty
+	dirty: bool,
 }
 
 impl FPRegisters {
 	const NAN_BOX_MASK: u64 = 0xFFFFFFFF_00000000;
+	const HALF_NAN_BOX_MASK: u64 = 0xFFFFFFFF_FFFF0000;
 
 	pub fn get_raw(&self, index: FPRegisterIndex) -> u64 {
 		let index = index.as_usize();
@@ -54,21 +58,45 @@ impl FPRegisters {
 	pub fn set_raw(&mut self, index: FPRegisterIndex, value: u64) {
 		let index = index.as_usize();
 		self.x[index] = value;
+		self.dirty = true;
+	}
+
+	/// whether any register was written since the last call
+	pub fn take_dirty(&mut self) -> bool {
+		std::mem::take(&mut self.dirty)
 	}
 
-	#[allow(unused)]
 	pub fn get_double(&self, index: FPRegisterIndex) -> SoftDouble {
 		SoftDouble::from_u64(self.get_raw(index))
 	}
 
-	#[allow(unused)]
 	pub fn set_double(&mut self, index: FPRegisterIndex, val: SoftDouble) {
 		self.set_raw(index, val.to_u64());
 	}
 
+	pub fn get_half(&self, index: FPRegisterIndex) -> SoftHalf {
+		// halfs are NaN boxed the same way floats are
+		let raw = self.get_raw(index);
+		if raw & Self::HALF_NAN_BOX_MASK != Self::HALF_NAN_BOX_MASK {
+			SoftHalf::CANONICAL_NAN
+		} else {
+			SoftHalf::from_u16(raw as u16)
+		}
+	}
+
+	pub fn set_half(&mut self, index: FPRegisterIndex, val: SoftHalf) {
+		self.set_raw(index, u64::from(val.to_u16()) | Self::HALF_NAN_BOX_MASK);
+	}
+
 	pub fn get_float(&self, index: FPRegisterIndex) -> SoftFloat {
 		// floats are NaN boxed, they live in the low 32 bits of the reg
-		SoftFloat::from_u32(self.get_raw(index) as u32)
+		// anything that isn't properly boxed reads as the canonical NaN
+		let raw = self.get_raw(index);
+		if raw & Self::NAN_BOX_MASK != Self::NAN_BOX_MASK {
+			SoftFloat::CANONICAL_NAN
+		} else {
+			SoftFloat::from_u32(raw as u32)
+		}
 	}
 
 	pub fn set_float(&mut self, index: FPRegisterIndex, val: SoftFloat) {
@@ -83,3 +111,156 @@ impl FPRegisters {
 		self.x.copy_from_slice(regs);
 	}
 }
+
+/// the vector register file, 32 registers of VLEN bits each
+/// registers are laid out back to back so a register group is just a larger slice
+#[derive(Debug)]
+pub struct VectorRegisters {
+	vlenb: usize,
+	x: Vec<u8>,
+}
+
+impl VectorRegisters {
+	pub const MIN_VLEN: usize = 64;
+	pub const MAX_VLEN: usize = 65536;
+
+	/// vlen is in bits and must be a power of two between MIN_VLEN and MAX_VLEN
+	pub fn new(vlen: usize) -> Self {
+		assert!(vlen.is_power_of_two() && (Self::MIN_VLEN..=Self::MAX_VLEN).contains(&vlen));
+		Self {
+			vlenb: vlen / 8,
+			x: vec![0; vlen / 8 * 32],
+		}
+	}
+
+	/// VLEN in bytes
+	pub fn vlenb(&self) -> usize {
+		self.vlenb
+	}
+
+	/// reads element idx of the register group starting at base, sized sew_bytes, zero extended
+	pub fn get_elem(&self, base: VectorRegisterIndex, sew_bytes: usize, idx: usize) -> u64 {
+		let start = base.as_usize() * self.vlenb + idx * sew_bytes;
+		let mut buf = [0u8; 8];
+		buf[..sew_bytes].copy_from_slice(&self.x[start..start + sew_bytes]);
+		u64::from_le_bytes(buf)
+	}
+
+	/// writes the low sew_bytes of val to element idx of the register group starting at base
+	pub fn set_elem(&mut self, base: VectorRegisterIndex, sew_bytes: usize, idx: usize, val: u64) {
+		let start = base.as_usize() * self.vlenb + idx * sew_bytes;
+		self.x[start..start + sew_bytes].copy_from_slice(&val.to_le_bytes()[..sew_bytes]);
+	}
+
+	/// reads bit idx of a mask register
+	pub fn get_mask(&self, reg: VectorRegisterIndex, idx: usize) -> bool {
+		let byte = self.x[reg.as_usize() * self.vlenb + idx / 8];
+		(byte >> (idx % 8)) & 1 == 1
+	}
+
+	/// writes bit idx of a mask register
+	pub fn set_mask(&mut self, reg: VectorRegisterIndex, idx: usize, val: bool) {
+		let byte = &mut self.x[reg.as_usize() * self.vlenb + idx / 8];
+		if val {
+			*byte |= 1 << (idx % 8);
+		} else {
+			*byte &= !(1 << (idx % 8));
+		}
+	}
+}
+
+/// the decoded contents of the vtype CSR
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VectorType {
+	/// selected element width in bytes
+	pub sew_bytes: usize,
+	/// log2 of the register group multiplier, -3..=3
+	pub lmul_log2: i32,
+}
+
+impl VectorType {
+	pub const ILLEGAL: u64 = 1 << 63;
+	// the widest element we support
+	const ELEN_BYTES: usize = 8;
+
+	/// decodes a vtype value, returns None if the setting isn't supported and vill should be set
+	pub fn parse(vtype: u64) -> Option<Self> {
+		// vma, vta, vsew and vlmul live in the low 8 bits, everything else is reserved
+		if vtype >> 8 != 0 {
+			return None;
+		}
+
+		let vsew = (vtype >> 3) & 0b111;
+		if vsew > 0b011 {
+			return None;
+		}
+		let sew_bytes = 1 << vsew;
+
+		let lmul_log2 = match vtype & 0b111 {
+			0b000 => 0,
+			0b001 => 1,
+			0b010 => 2,
+			0b011 => 3,
+			0b101 => -3,
+			0b110 => -2,
+			0b111 => -1,
+			_ => return None,
+		};
+
+		// fractional lmul needs SEW <= LMUL * ELEN
+		if lmul_log2 < 0 && sew_bytes > Self::ELEN_BYTES >> -lmul_log2 {
+			return None;
+		}
+
+		Some(Self { sew_bytes, lmul_log2 })
+	}
+
+	/// the max number of elements an instruction can operate on, LMUL * VLEN / SEW
+	pub fn vlmax(&self, vlenb: usize) -> usize {
+		if self.lmul_log2 >= 0 {
+			(vlenb << self.lmul_log2) / self.sew_bytes
+		} else {
+			(vlenb >> -self.lmul_log2) / self.sew_bytes
+		}
+	}
+
+	/// the number of whole registers in a register group, fractional groups still take up one register
+	pub fn group_regs(&self) -> usize {
+		1 << self.lmul_log2.max(0)
+	}
+}
+
+impl Snapshot for GPRegisters {
+	fn save(&self, snapshot: &mut SnapshotWriter) {
+		snapshot.u64s(&self.x);
+	}
+
+	fn restore(&mut self, snapshot: &mut SnapshotReader) -> Result<(), String> {
+		snapshot.u64s(&mut self.x)
+	}
+}
+
+impl Snapshot for FPRegisters {
+	fn save(&self, snapshot: &mut SnapshotWriter) {
+		snapshot.u64s(&self.x);
+	}
+
+	/// mstatus.FS is restored along with the CSRs, so nothing is marked dirty
+	fn restore(&mut self, snapshot: &mut SnapshotReader) -> Result<(), String> {
+		snapshot.u64s(&mut self.x)?;
+		self.dirty = false;
+		Ok(())
+	}
+}
+
+impl Snapshot for VectorRegisters {
+	fn save(&self, snapshot: &mut SnapshotWriter) {
+		snapshot.u64(self.vlenb as u64);
+		snapshot.bytes(&self.x);
+	}
+
+	fn restore(&mut self, snapshot: &mut SnapshotReader) -> Result<(), String> {
+		expect_eq("VLEN", self.vlenb * 8, snapshot.u64()? as usize * 8)?;
+		snapshot.bytes_into("vector register file size", &mut self.x)
+	}
+}
@@ -0,0 +1,468 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::rc::Rc;
+use std::sync::mpsc::Receiver;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use tracing::warn;
+
+use crate::cpu::WhiskerCpu;
+use crate::mmu::AccessType;
+use crate::ty::GPRegisterIndex;
+
+// an ebreak is a semihosting call when it's uncompressed and sits between slli x0, x0, 0x1f and srai x0, x0, 7
+const SEMIHOSTING_ENTRY: u32 = 0x01F0_1013;
+const SEMIHOSTING_EXIT: u32 = 0x4070_5013;
+
+const SYS_OPEN: u64 = 0x01;
+const SYS_CLOSE: u64 = 0x02;
+const SYS_WRITEC: u64 = 0x03;
+const SYS_WRITE0: u64 = 0x04;
+const SYS_WRITE: u64 = 0x05;
+const SYS_READ: u64 = 0x06;
+const SYS_READC: u64 = 0x07;
+const SYS_ISERROR: u64 = 0x08;
+const SYS_ISTTY: u64 = 0x09;
+const SYS_SEEK: u64 = 0x0A;
+const SYS_FLEN: u64 = 0x0C;
+const SYS_REMOVE: u64 = 0x0E;
+const SYS_RENAME: u64 = 0x0F;
+const SYS_CLOCK: u64 = 0x10;
+const SYS_TIME: u64 = 0x11;
+const SYS_ERRNO: u64 = 0x13;
+const SYS_GET_CMDLINE: u64 = 0x15;
+const SYS_HEAPINFO: u64 = 0x16;
+const SYS_EXIT: u64 = 0x18;
+const SYS_EXIT_EXTENDED: u64 = 0x20;
+
+// the reason SYS_EXIT gives when the program finished normally, the subcode is its exit code
+const ADP_STOPPED_APPLICATION_EXIT: u64 = 0x20026;
+
+// opening this name gives the console instead of a file, the mode picks stdin, stdout or stderr
+const CONSOLE_NAME: &str = ":tt";
+
+pub const EBADF: i32 = 9;
+pub const EIO: i32 = 5;
+pub const EFAULT: i32 = 14;
+pub const EINVAL: i32 = 22;
+pub const ENOSYS: i32 = 38;
+const ENAMETOOLONG: i32 = 36;
+
+// the longest file name a guest can pass
+const PATH_MAX: u64 = 4096;
+// the most of a guest's buffer that's held on the host at once
+const COPY_CHUNK: u64 = 64 * 1024;
+
+/// a file the guest opened, the console ones go wherever whisker's stdio does
+#[derive(Debug)]
+pub enum HostFile {
+	File(File),
+	Stdin,
+	Stdout,
+	Stderr,
+}
+
+/// stdin as the guest's host calls see it, fed by the one thread that reads stdin
+/// it's empty when gdb or the UART owns stdin, reads then see the end of the input
+#[derive(Debug, Clone, Default)]
+pub struct ConsoleInput(Option<Rc<Receiver<u8>>>);
+
+impl ConsoleInput {
+	pub fn new(input: Receiver<u8>) -> Self {
+		Self(Some(Rc::new(input)))
+	}
+
+	/// waits for the first byte then takes whatever else has arrived, 0 means the end of the input
+	pub fn read(&self, buf: &mut [u8]) -> usize {
+		let Some(input) = &self.0 else {
+			return 0;
+		};
+		let mut len = 0;
+		for slot in buf.iter_mut() {
+			let byte = if len == 0 {
+				input.recv().ok()
+			} else {
+				input.try_recv().ok()
+			};
+			let Some(byte) = byte else {
+				break;
+			};
+			*slot = byte;
+			len += 1;
+		}
+		len
+	}
+
+	/// a byte if one has arrived, for consoles the guest polls
+	pub fn try_read(&self) -> Option<u8> {
+		self.0.as_ref()?.try_recv().ok()
+	}
+}
+
+/// services the semihosting calls a guest makes, so bare-metal programs can print and use the host's files
+/// the guest can reach any file whisker can, so only run trusted programs with it
+pub struct Semihosting {
+	// handles are the index plus one, 0 isn't a valid handle
+	files: Vec<Option<HostFile>>,
+	errno: i32,
+	started: Instant,
+	console: Box<dyn Write>,
+	input: ConsoleInput,
+}
+
+impl std::fmt::Debug for Semihosting {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("Semihosting")
+			.field("files", &self.files)
+			.field("errno", &self.errno)
+			.finish_non_exhaustive()
+	}
+}
+
+impl Semihosting {
+	/// console output goes to console, which is stdout unless something else owns it
+	pub fn new(console: Box<dyn Write>, input: ConsoleInput) -> Self {
+		Self {
+			files: Vec::new(),
+			errno: 0,
+			started: Instant::now(),
+			console,
+			input,
+		}
+	}
+
+	/// runs the call and returns what goes in a0, failed calls set the errno SYS_ERRNO reports
+	fn call(&mut self, cpu: &mut WhiskerCpu, op: u64, param: u64) -> i64 {
+		let result = match op {
+			SYS_OPEN => self.open(cpu, param),
+			SYS_CLOSE => self.close(cpu, param),
+			SYS_WRITEC => self.write_char(cpu, param),
+			SYS_WRITE0 => self.write_string(cpu, param),
+			SYS_WRITE => self.write(cpu, param),
+			SYS_READ => self.read(cpu, param),
+			SYS_READC => Ok(self.read_console_char()),
+			SYS_ISERROR => args::<1>(cpu, param).map(|[status]| i64::from((status as i64) < 0)),
+			SYS_ISTTY => self.is_tty(cpu, param),
+			SYS_SEEK => self.seek(cpu, param),
+			SYS_FLEN => self.file_len(cpu, param),
+			SYS_REMOVE => remove(cpu, param),
+			SYS_RENAME => rename(cpu, param),
+			SYS_CLOCK => Ok((self.started.elapsed().as_millis() / 10) as i64),
+			SYS_TIME => Ok(SystemTime::now()
+				.duration_since(UNIX_EPOCH)
+				.map_or(0, |time| time.as_secs() as i64)),
+			SYS_ERRNO => Ok(i64::from(self.errno)),
+			SYS_GET_CMDLINE => get_cmdline(cpu, param),
+			SYS_HEAPINFO => heap_info(cpu, param),
+			SYS_EXIT | SYS_EXIT_EXTENDED => exit(cpu, param),
+			_ => {
+				warn!("the guest made an unsupported semihosting call {op:#x}");
+				Err(ENOSYS)
+			}
+		};
+		result.unwrap_or_else(|errno| {
+			self.errno = errno;
+			-1
+		})
+	}
+
+	fn file(&mut self, handle: u64) -> Result<&mut HostFile, i32> {
+		let idx = handle.checked_sub(1).ok_or(EBADF)? as usize;
+		self.files.get_mut(idx).and_then(Option::as_mut).ok_or(EBADF)
+	}
+
+	fn open(&mut self, cpu: &mut WhiskerCpu, param: u64) -> Result<i64, i32> {
+		let [name, mode, len] = args(cpu, param)?;
+		let name = GuestMemory::Virtual.path(cpu, name, len)?;
+		// the modes are fopen's r, rb, r+, r+b, w, wb, w+, w+b, a, ab, a+, a+b in order
+		let file = if name == CONSOLE_NAME {
+			match mode {
+				0..=3 => HostFile::Stdin,
+				4..=7 => HostFile::Stdout,
+				8..=11 => HostFile::Stderr,
+				_ => return Err(EINVAL),
+			}
+		} else {
+			let update = mode & 2 != 0;
+			let mut options = OpenOptions::new();
+			match mode / 4 {
+				0 => options.read(true).write(update),
+				1 => options.write(true).create(true).truncate(true).read(update),
+				2 => options.append(true).create(true).read(update),
+				_ => return Err(EINVAL),
+			};
+			HostFile::File(options.open(name).map_err(errno)?)
+		};
+
+		let idx = match self.files.iter().position(Option::is_none) {
+			Some(idx) => idx,
+			None => {
+				self.files.push(None);
+				self.files.len() - 1
+			}
+		};
+		self.files[idx] = Some(file);
+		Ok(idx as i64 + 1)
+	}
+
+	fn close(&mut self, cpu: &mut WhiskerCpu, param: u64) -> Result<i64, i32> {
+		let [handle] = args(cpu, param)?;
+		self.file(handle)?;
+		self.files[handle as usize - 1] = None;
+		Ok(0)
+	}
+
+	fn write_char(&mut self, cpu: &mut WhiskerCpu, addr: u64) -> Result<i64, i32> {
+		let byte = cpu.read_virt_u8(addr, AccessType::Load).map_err(|_| EFAULT)?;
+		self.write_console(&[byte])?;
+		Ok(0)
+	}
+
+	fn write_string(&mut self, cpu: &mut WhiskerCpu, mut addr: u64) -> Result<i64, i32> {
+		let mut string = Vec::new();
+		loop {
+			let byte = cpu.read_virt_u8(addr, AccessType::Load).map_err(|_| EFAULT)?;
+			if byte == 0 {
+				break;
+			}
+			string.push(byte);
+			addr = addr.wrapping_add(1);
+		}
+		self.write_console(&string)?;
+		Ok(0)
+	}
+
+	fn write_console(&mut self, bytes: &[u8]) -> Result<(), i32> {
+		self.console.write_all(bytes).map_err(errno)?;
+		self.console.flush().map_err(errno)
+	}
+
+	/// returns how many bytes weren't written
+	fn write(&mut self, cpu: &mut WhiskerCpu, param: u64) -> Result<i64, i32> {
+		let [handle, buf, len] = args(cpu, param)?;
+		let virt = GuestMemory::Virtual;
+		let written = match self.file(handle)? {
+			HostFile::File(file) => virt.copy_out(cpu, buf, len, |data| file.write_all(data).map_err(errno)),
+			HostFile::Stdout => virt.copy_out(cpu, buf, len, |data| self.write_console(data)),
+			HostFile::Stderr => virt.copy_out(cpu, buf, len, |data| io::stderr().write_all(data).map_err(errno)),
+			HostFile::Stdin => Err(EBADF),
+		};
+		match written {
+			Ok(()) => Ok(0),
+			Err(errno) => {
+				self.errno = errno;
+				Ok(len as i64)
+			}
+		}
+	}
+
+	/// returns how many bytes weren't read, all of them means the end of the file
+	fn read(&mut self, cpu: &mut WhiskerCpu, param: u64) -> Result<i64, i32> {
+		let [handle, buf, len] = args(cpu, param)?;
+		let virt = GuestMemory::Virtual;
+		let read = match self.file(handle)? {
+			HostFile::File(file) => virt.copy_in(cpu, buf, len, |data| file.read(data).map_err(errno))?,
+			HostFile::Stdin => virt.copy_in(cpu, buf, len, |data| Ok(self.input.read(data)))?,
+			HostFile::Stdout | HostFile::Stderr => return Err(EBADF),
+		};
+		Ok((len - read) as i64)
+	}
+
+	fn read_console_char(&self) -> i64 {
+		let mut byte = [0];
+		match self.input.read(&mut byte) {
+			0 => -1,
+			_ => i64::from(byte[0]),
+		}
+	}
+
+	fn is_tty(&mut self, cpu: &mut WhiskerCpu, param: u64) -> Result<i64, i32> {
+		let [handle] = args(cpu, param)?;
+		Ok(i64::from(!matches!(self.file(handle)?, HostFile::File(_))))
+	}
+
+	fn seek(&mut self, cpu: &mut WhiskerCpu, param: u64) -> Result<i64, i32> {
+		let [handle, pos] = args(cpu, param)?;
+		match self.file(handle)? {
+			HostFile::File(file) => file.seek(SeekFrom::Start(pos)).map_err(errno)?,
+			_ => return Err(EINVAL),
+		};
+		Ok(0)
+	}
+
+	fn file_len(&mut self, cpu: &mut WhiskerCpu, param: u64) -> Result<i64, i32> {
+		let [handle] = args(cpu, param)?;
+		match self.file(handle)? {
+			HostFile::File(file) => Ok(file.metadata().map_err(errno)?.len() as i64),
+			_ => Err(EINVAL),
+		}
+	}
+}
+
+impl WhiskerCpu {
+	/// whether the ebreak at pc is a semihosting call, which needs the marker instructions on both sides
+	pub fn is_semihosting_call(&mut self, pc: u64) -> bool {
+		// compressed ebreaks never are
+		if self.pc != pc.wrapping_add(4) {
+			return false;
+		}
+		let entry = self.read_virt_u32(pc.wrapping_sub(4), AccessType::Fetch);
+		let exit = self.read_virt_u32(pc.wrapping_add(4), AccessType::Fetch);
+		entry == Ok(SEMIHOSTING_ENTRY) && exit == Ok(SEMIHOSTING_EXIT)
+	}
+
+	/// the operation is in a0 and its parameter in a1, the result goes back in a0
+	pub fn semihosting_call(&mut self) {
+		let Some(mut semihosting) = self.semihosting.take() else {
+			return;
+		};
+		let op = self.registers.get(GPRegisterIndex::A0);
+		let param = self.registers.get(GPRegisterIndex::A1);
+		let result = semihosting.call(self, op, param);
+		self.registers.set(GPRegisterIndex::A0, result as u64);
+		self.semihosting = Some(semihosting);
+	}
+}
+
+/// reads the call's parameter block, N 64 bit fields at addr
+fn args<const N: usize>(cpu: &mut WhiskerCpu, addr: u64) -> Result<[u64; N], i32> {
+	GuestMemory::Virtual.words(cpu, addr)
+}
+
+pub fn errno(err: io::Error) -> i32 {
+	err.raw_os_error().unwrap_or(EIO)
+}
+
+/// how host calls reach the guest's buffers, semihosting goes through the MMU like the guest's own loads and htif doesn't
+/// lengths come from the guest, so buffers are copied a chunk at a time instead of all at once
+#[derive(Debug, Clone, Copy)]
+pub enum GuestMemory {
+	Virtual,
+	Physical,
+}
+
+impl GuestMemory {
+	fn read(self, cpu: &mut WhiskerCpu, addr: u64, buf: &mut [u8]) -> Result<(), i32> {
+		match self {
+			Self::Virtual => cpu.read_virt_slice(addr, buf, AccessType::Load).map_err(|_| EFAULT),
+			Self::Physical => cpu.mem.debug_read_slice(addr, buf).map_err(|_| EFAULT),
+		}
+	}
+
+	pub fn write(self, cpu: &mut WhiskerCpu, addr: u64, data: &[u8]) -> Result<(), i32> {
+		match self {
+			Self::Virtual => cpu.write_virt_slice(addr, data).map_err(|_| EFAULT),
+			Self::Physical => cpu.mem.debug_write_slice(addr, data).map_err(|_| EFAULT),
+		}
+	}
+
+	/// N 64 bit words at addr
+	pub fn words<const N: usize>(self, cpu: &mut WhiskerCpu, addr: u64) -> Result<[u64; N], i32> {
+		let mut bytes = vec![0; N * 8];
+		self.read(cpu, addr, &mut bytes)?;
+		Ok(std::array::from_fn(|idx| {
+			u64::from_le_bytes(bytes[idx * 8..idx * 8 + 8].try_into().unwrap())
+		}))
+	}
+
+	/// a file name len bytes long, a nul at the end is dropped
+	pub fn path(self, cpu: &mut WhiskerCpu, addr: u64, len: u64) -> Result<String, i32> {
+		if len > PATH_MAX {
+			return Err(ENAMETOOLONG);
+		}
+		let mut name = vec![0; len as usize];
+		self.read(cpu, addr, &mut name)?;
+		if name.last() == Some(&0) {
+			name.pop();
+		}
+		String::from_utf8(name).map_err(|_| EINVAL)
+	}
+
+	/// hands the len bytes at addr to sink in order, stopping at the first error
+	pub fn copy_out(
+		self,
+		cpu: &mut WhiskerCpu,
+		addr: u64,
+		len: u64,
+		mut sink: impl FnMut(&[u8]) -> Result<(), i32>,
+	) -> Result<(), i32> {
+		let mut chunk = vec![0; len.min(COPY_CHUNK) as usize];
+		let mut done = 0;
+		while done < len {
+			let size = (len - done).min(COPY_CHUNK) as usize;
+			self.read(cpu, addr.wrapping_add(done), &mut chunk[..size])?;
+			sink(&chunk[..size])?;
+			done += size as u64;
+		}
+		Ok(())
+	}
+
+	/// fills up to len bytes at addr from source, returning how many it gave
+	/// stops early when source gives less than it was asked for, like at the end of a file
+	pub fn copy_in(
+		self,
+		cpu: &mut WhiskerCpu,
+		addr: u64,
+		len: u64,
+		mut source: impl FnMut(&mut [u8]) -> Result<usize, i32>,
+	) -> Result<u64, i32> {
+		let mut chunk = vec![0; len.min(COPY_CHUNK) as usize];
+		let mut done = 0;
+		while done < len {
+			let size = (len - done).min(COPY_CHUNK) as usize;
+			let read = source(&mut chunk[..size])?;
+			self.write(cpu, addr.wrapping_add(done), &chunk[..read])?;
+			done += read as u64;
+			if read < size {
+				break;
+			}
+		}
+		Ok(done)
+	}
+}
+
+fn remove(cpu: &mut WhiskerCpu, param: u64) -> Result<i64, i32> {
+	let [name, len] = args(cpu, param)?;
+	let name = GuestMemory::Virtual.path(cpu, name, len)?;
+	fs::remove_file(name).map_err(errno)?;
+	Ok(0)
+}
+
+fn rename(cpu: &mut WhiskerCpu, param: u64) -> Result<i64, i32> {
+	let [from, from_len, to, to_len] = args(cpu, param)?;
+	let from = GuestMemory::Virtual.path(cpu, from, from_len)?;
+	let to = GuestMemory::Virtual.path(cpu, to, to_len)?;
+	fs::rename(from, to).map_err(errno)?;
+	Ok(0)
+}
+
+/// there's no command line to pass on, so the guest gets an empty one
+fn get_cmdline(cpu: &mut WhiskerCpu, param: u64) -> Result<i64, i32> {
+	let [buf, len] = args(cpu, param)?;
+	if len == 0 {
+		return Err(EINVAL);
+	}
+	cpu.write_virt_slice(buf, &[0]).map_err(|_| EFAULT)?;
+	cpu.write_virt_slice(param + 8, &0u64.to_le_bytes())
+		.map_err(|_| EFAULT)?;
+	Ok(0)
+}
+
+/// zeroes tell the guest's runtime to pick the heap and stack itself
+fn heap_info(cpu: &mut WhiskerCpu, param: u64) -> Result<i64, i32> {
+	let [block] = args(cpu, param)?;
+	cpu.write_virt_slice(block, &[0; 32]).map_err(|_| EFAULT)?;
+	Ok(0)
+}
+
+/// a normal exit powers off with the guest's exit code, anything else is a failure
+fn exit(cpu: &mut WhiskerCpu, param: u64) -> Result<i64, i32> {
+	let [reason, code] = args(cpu, param)?;
+	let code = if reason == ADP_STOPPED_APPLICATION_EXIT {
+		code as i32
+	} else {
+		1
+	};
+	cpu.power_off_line.request(code);
+	Ok(0)
+}
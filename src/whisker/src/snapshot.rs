@@ -0,0 +1,147 @@
+const SNAPSHOT_MAGIC: &[u8; 8] = b"WHISKSNP";
+// bumped whenever the layout changes, old snapshots are rejected instead of misread
+const SNAPSHOT_VERSION: u32 = 1;
+
+/// state that can be saved to a snapshot and restored from one later
+/// restoring expects the machine to be built the same way as the one that was saved
+pub trait Snapshot {
+	fn save(&self, snapshot: &mut SnapshotWriter);
+	fn restore(&mut self, snapshot: &mut SnapshotReader) -> Result<(), String>;
+}
+
+/// everything is little endian and written back to back in the order it's saved in
+#[derive(Debug)]
+pub struct SnapshotWriter(Vec<u8>);
+
+impl SnapshotWriter {
+	pub fn new() -> Self {
+		let mut writer = Self(SNAPSHOT_MAGIC.to_vec());
+		writer.u32(SNAPSHOT_VERSION);
+		writer
+	}
+
+	pub fn u8(&mut self, val: u8) {
+		self.0.push(val);
+	}
+
+	pub fn bool(&mut self, val: bool) {
+		self.u8(u8::from(val));
+	}
+
+	pub fn u16(&mut self, val: u16) {
+		self.0.extend_from_slice(&val.to_le_bytes());
+	}
+
+	pub fn u32(&mut self, val: u32) {
+		self.0.extend_from_slice(&val.to_le_bytes());
+	}
+
+	pub fn u64(&mut self, val: u64) {
+		self.0.extend_from_slice(&val.to_le_bytes());
+	}
+
+	pub fn u64s(&mut self, vals: &[u64]) {
+		for &val in vals {
+			self.u64(val);
+		}
+	}
+
+	/// the length is written first
+	pub fn bytes(&mut self, bytes: &[u8]) {
+		self.u64(bytes.len() as u64);
+		self.0.extend_from_slice(bytes);
+	}
+
+	pub fn finish(self) -> Vec<u8> {
+		self.0
+	}
+}
+
+#[derive(Debug)]
+pub struct SnapshotReader<'a> {
+	bytes: &'a [u8],
+	pos: usize,
+}
+
+impl<'a> SnapshotReader<'a> {
+	pub fn new(bytes: &'a [u8]) -> Result<Self, String> {
+		let mut reader = Self { bytes, pos: 0 };
+		if reader.take(SNAPSHOT_MAGIC.len())? != SNAPSHOT_MAGIC {
+			return Err("not a whisker snapshot".to_string());
+		}
+		let version = reader.u32()?;
+		if version != SNAPSHOT_VERSION {
+			return Err(format!(
+				"the snapshot is version {version}, this whisker only reads version {SNAPSHOT_VERSION}"
+			));
+		}
+		Ok(reader)
+	}
+
+	fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+		let bytes = self
+			.bytes
+			.get(self.pos..self.pos + len)
+			.ok_or("the snapshot is truncated")?;
+		self.pos += len;
+		Ok(bytes)
+	}
+
+	pub fn u8(&mut self) -> Result<u8, String> {
+		Ok(self.take(1)?[0])
+	}
+
+	pub fn bool(&mut self) -> Result<bool, String> {
+		Ok(self.u8()? != 0)
+	}
+
+	pub fn u16(&mut self) -> Result<u16, String> {
+		Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+	}
+
+	pub fn u32(&mut self) -> Result<u32, String> {
+		Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+	}
+
+	pub fn u64(&mut self) -> Result<u64, String> {
+		Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+	}
+
+	pub fn u64s(&mut self, vals: &mut [u64]) -> Result<(), String> {
+		for val in vals {
+			*val = self.u64()?;
+		}
+		Ok(())
+	}
+
+	pub fn bytes(&mut self) -> Result<&'a [u8], String> {
+		let len = self.u64()? as usize;
+		self.take(len)
+	}
+
+	/// like bytes, but the length has to match what's already there
+	pub fn bytes_into(&mut self, what: &str, buf: &mut [u8]) -> Result<(), String> {
+		let bytes = self.bytes()?;
+		expect_eq(what, buf.len(), bytes.len())?;
+		buf.copy_from_slice(bytes);
+		Ok(())
+	}
+
+	/// errors if anything was left unread, which means the snapshot doesn't match the machine
+	pub fn finish(self) -> Result<(), String> {
+		if self.pos != self.bytes.len() {
+			return Err("the snapshot has trailing data".to_string());
+		}
+		Ok(())
+	}
+}
+
+/// checks that part of the machine is configured the same as when the snapshot was taken
+pub fn expect_eq<T: PartialEq + std::fmt::Debug>(what: &str, current: T, saved: T) -> Result<(), String> {
+	if current != saved {
+		return Err(format!(
+			"the snapshot was taken with {what} {saved:?}, but this machine has {current:?}"
+		));
+	}
+	Ok(())
+}
@@ -2,6 +2,7 @@ use crate::cpu::WhiskerCpu;
 
 pub mod double;
 pub mod float;
+pub mod half;
 
 /// Defined on unpriv isa page 119
 #[derive(Debug, Clone, Copy)]
@@ -21,7 +22,6 @@ pub enum FClass {
 }
 
 impl FClass {
-	#[allow(unused)]
 	pub const fn to_shift(self) -> u16 {
 		match self {
 			Self::NegativeInfinity => 1 << 0,
@@ -104,12 +104,24 @@ impl RoundingMode {
 		}
 	}
 
+	/// reads the frm field of fcsr, returns None if it holds a reserved value
+	pub fn from_frm(cpu: &WhiskerCpu) -> Option<Self> {
+		let frm = ((cpu.csrs.read_fcsr() & FCSR_ROUNDING_MODE_MASK) >> 5) as u8;
+		Self::from_u8(frm).filter(|rm| *rm != RoundingMode::Dynamic)
+	}
+
+	/// the softfloat rounding mode to use, dynamic is read from the CPU
+	fn resolve(self, cpu: &WhiskerCpu) -> u8 {
+		match self {
+			// UNWRAP: the CPU checks frm before executing an instruction with a dynamic rounding mode
+			RoundingMode::Dynamic => Self::from_frm(cpu).unwrap().to_sf_u8(),
+			rm => rm.to_sf_u8(),
+		}
+	}
+
 	/// This should NEVER be used outside of the `soft` module, hence marked as unsafe
 	pub unsafe fn write_thread_local(self, cpu: &WhiskerCpu) {
-		let val = match self {
-			RoundingMode::Dynamic => (cpu.csrs.read_fcsr() & FCSR_ROUNDING_MODE_MASK >> 5) as u8,
-			rm => rm.to_sf_u8(),
-		};
+		let val = self.resolve(cpu);
 		unsafe {
 			softfloat_sys::softfloat_roundingMode_write_helper(val);
 		}
@@ -148,8 +160,13 @@ impl ExceptionFlags {
 		self.0 & Self::FLAG_INVALID != 0
 	}
 
+	/// accrues the flags into fflags, the softfloat flags share the same bit layout
 	pub fn update_cpu(self, cpu: &mut WhiskerCpu) {
-		todo!()
+		cpu.accrue_fflags(self.0);
+	}
+
+	pub fn clear_softfloat() {
+		unsafe { softfloat_sys::softfloat_exceptionFlags_write_helper(0) };
 	}
 
 	pub fn get_from_softfloat() -> Self {
@@ -158,4 +175,5 @@ impl ExceptionFlags {
 	}
 }
 
+pub const FCSR_FLAGS_MASK: u64 = 0b00011111;
 pub const FCSR_ROUNDING_MODE_MASK: u64 = 0b11100000;
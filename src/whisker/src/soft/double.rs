@@ -4,7 +4,7 @@ use softfloat_sys::float64_t;
 
 use crate::cpu::WhiskerCpu;
 
-use super::{FClass, RoundingMode};
+use super::{float::SoftFloat, half::SoftHalf, ExceptionFlags, FClass, RoundingMode};
 
 #[derive(Debug, Clone, Copy)]
 #[repr(transparent)]
@@ -12,6 +12,8 @@ pub struct SoftDouble(float64_t);
 
 #[allow(unused)]
 impl SoftDouble {
+	pub const CANONICAL_NAN: Self = Self(float64_t { v: 0x7FF8000000000000 });
+
 	pub const fn from_f64(value: f64) -> Self {
 		Self(float64_t { v: value.to_bits() })
 	}
@@ -81,42 +83,196 @@ impl SoftDouble {
 	}
 
 	pub fn is_nan(&self) -> bool {
-		Self::get_exponent(self.0.v) == Self::EXPONENT_BITS && Self::get_mantissa(self.0.v) != 0u64
+		Self::get_exponent(self.0.v) == Self::EXPONENT_MASK && Self::get_mantissa(self.0.v) != 0u64
+	}
+
+	pub fn is_snan(&self) -> bool {
+		self.is_nan() && (Self::get_mantissa(self.0.v) & Self::QUIET_NAN_MASK == 0)
+	}
+
+	pub fn is_qnan(&self) -> bool {
+		self.is_nan() && (Self::get_mantissa(self.0.v) & Self::QUIET_NAN_MASK != 0)
 	}
 
 	pub fn add(&self, other: &Self, rm: RoundingMode, cpu: &mut WhiskerCpu) -> Self {
 		unsafe { rm.write_thread_local(cpu) };
-		Self(unsafe { softfloat_sys::f64_add(self.0, other.0) })
+		ExceptionFlags::clear_softfloat();
+		let res = unsafe { softfloat_sys::f64_add(self.0, other.0) };
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		Self(res)
 	}
 
 	pub fn sub(&self, other: &Self, rm: RoundingMode, cpu: &mut WhiskerCpu) -> Self {
 		unsafe { rm.write_thread_local(cpu) };
-		Self(unsafe { softfloat_sys::f64_sub(self.0, other.0) })
+		ExceptionFlags::clear_softfloat();
+		let res = unsafe { softfloat_sys::f64_sub(self.0, other.0) };
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		Self(res)
 	}
 
 	pub fn mul(&self, other: &Self, rm: RoundingMode, cpu: &mut WhiskerCpu) -> Self {
 		unsafe { rm.write_thread_local(cpu) };
-		Self(unsafe { softfloat_sys::f64_mul(self.0, other.0) })
+		ExceptionFlags::clear_softfloat();
+		let res = unsafe { softfloat_sys::f64_mul(self.0, other.0) };
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		Self(res)
 	}
 
 	pub fn div(&self, other: &Self, rm: RoundingMode, cpu: &mut WhiskerCpu) -> Self {
 		unsafe { rm.write_thread_local(cpu) };
-		Self(unsafe { softfloat_sys::f64_div(self.0, other.0) })
+		ExceptionFlags::clear_softfloat();
+		let res = unsafe { softfloat_sys::f64_div(self.0, other.0) };
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		Self(res)
 	}
 
 	pub fn rem(&self, other: &Self, rm: RoundingMode, cpu: &mut WhiskerCpu) -> Self {
 		unsafe { rm.write_thread_local(cpu) };
-		Self(unsafe { softfloat_sys::f64_rem(self.0, other.0) })
+		ExceptionFlags::clear_softfloat();
+		let res = unsafe { softfloat_sys::f64_rem(self.0, other.0) };
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		Self(res)
 	}
 
 	pub fn mul_add(&self, mul: &Self, add: &Self, rm: RoundingMode, cpu: &mut WhiskerCpu) -> Self {
 		unsafe { rm.write_thread_local(cpu) };
-		Self(unsafe { softfloat_sys::f64_mulAdd(self.0, mul.0, add.0) })
+		ExceptionFlags::clear_softfloat();
+		let res = unsafe { softfloat_sys::f64_mulAdd(self.0, mul.0, add.0) };
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		Self(res)
+	}
+
+	/// converts to an integer, out of range values and NaNs saturate as the RISC-V spec requires
+	pub fn convert_to_i32(&self, rm: RoundingMode, cpu: &mut WhiskerCpu) -> i32 {
+		ExceptionFlags::clear_softfloat();
+		let res = unsafe { softfloat_sys::f64_to_i32(self.0, rm.resolve(cpu), true) };
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		res as i32
+	}
+
+	/// converts to an integer, out of range values and NaNs saturate as the RISC-V spec requires
+	pub fn convert_to_u32(&self, rm: RoundingMode, cpu: &mut WhiskerCpu) -> u32 {
+		ExceptionFlags::clear_softfloat();
+		let res = unsafe { softfloat_sys::f64_to_ui32(self.0, rm.resolve(cpu), true) };
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		res as u32
+	}
+
+	/// converts to an integer, out of range values and NaNs saturate as the RISC-V spec requires
+	pub fn convert_to_i64(&self, rm: RoundingMode, cpu: &mut WhiskerCpu) -> i64 {
+		ExceptionFlags::clear_softfloat();
+		let res = unsafe { softfloat_sys::f64_to_i64(self.0, rm.resolve(cpu), true) };
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		res as i64
+	}
+
+	/// converts to an integer, out of range values and NaNs saturate as the RISC-V spec requires
+	pub fn convert_to_u64(&self, rm: RoundingMode, cpu: &mut WhiskerCpu) -> u64 {
+		ExceptionFlags::clear_softfloat();
+		let res = unsafe { softfloat_sys::f64_to_ui64(self.0, rm.resolve(cpu), true) };
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		res as u64
+	}
+
+	pub fn convert_from_i32(val: i32, rm: RoundingMode, cpu: &mut WhiskerCpu) -> Self {
+		unsafe { rm.write_thread_local(cpu) };
+		ExceptionFlags::clear_softfloat();
+		let res = unsafe { softfloat_sys::i32_to_f64(val) };
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		Self(res)
+	}
+
+	pub fn convert_from_u32(val: u32, rm: RoundingMode, cpu: &mut WhiskerCpu) -> Self {
+		unsafe { rm.write_thread_local(cpu) };
+		ExceptionFlags::clear_softfloat();
+		let res = unsafe { softfloat_sys::ui32_to_f64(val) };
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		Self(res)
+	}
+
+	pub fn convert_from_i64(val: i64, rm: RoundingMode, cpu: &mut WhiskerCpu) -> Self {
+		unsafe { rm.write_thread_local(cpu) };
+		ExceptionFlags::clear_softfloat();
+		let res = unsafe { softfloat_sys::i64_to_f64(val) };
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		Self(res)
+	}
+
+	pub fn convert_from_u64(val: u64, rm: RoundingMode, cpu: &mut WhiskerCpu) -> Self {
+		unsafe { rm.write_thread_local(cpu) };
+		ExceptionFlags::clear_softfloat();
+		let res = unsafe { softfloat_sys::ui64_to_f64(val) };
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		Self(res)
+	}
+
+	pub fn convert_to_float(&self, rm: RoundingMode, cpu: &mut WhiskerCpu) -> SoftFloat {
+		unsafe { rm.write_thread_local(cpu) };
+		ExceptionFlags::clear_softfloat();
+		let res = SoftFloat::from_u32(unsafe { softfloat_sys::f64_to_f32(self.0) }.v);
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		res
+	}
+
+	pub fn convert_to_half(&self, rm: RoundingMode, cpu: &mut WhiskerCpu) -> SoftHalf {
+		unsafe { rm.write_thread_local(cpu) };
+		ExceptionFlags::clear_softfloat();
+		let res = SoftHalf::from_u16(unsafe { softfloat_sys::f64_to_f16(self.0) }.v);
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		res
+	}
+
+	/// IEEE 754-2019 minimumNumber
+	pub fn min(&self, other: &Self, cpu: &mut WhiskerCpu) -> Self {
+		self.min_max(other, true, cpu)
+	}
+
+	/// IEEE 754-2019 maximumNumber
+	pub fn max(&self, other: &Self, cpu: &mut WhiskerCpu) -> Self {
+		self.min_max(other, false, cpu)
+	}
+
+	fn min_max(&self, other: &Self, min: bool, cpu: &mut WhiskerCpu) -> Self {
+		if self.is_snan() || other.is_snan() {
+			ExceptionFlags(ExceptionFlags::FLAG_INVALID).update_cpu(cpu);
+		}
+
+		match (self.is_nan(), other.is_nan()) {
+			(true, true) => Self::CANONICAL_NAN,
+			(true, false) => *other,
+			(false, true) => *self,
+			(false, false) => {
+				let sign_bit = 1 << (Self::BITS - 1);
+				if (self.0.v | other.0.v) & !sign_bit == 0 {
+					// both are zeros, -0.0 is considered less than +0.0
+					if min {
+						Self::from_u64(self.0.v | other.0.v)
+					} else {
+						Self::from_u64(self.0.v & other.0.v)
+					}
+				} else {
+					let less = unsafe { softfloat_sys::f64_lt_quiet(self.0, other.0) };
+					if less == min {
+						*self
+					} else {
+						*other
+					}
+				}
+			}
+		}
+	}
+
+	/// flips the sign bit, this is exact and never raises any exceptions
+	pub fn neg(&self) -> Self {
+		Self::from_u64(self.0.v ^ (1 << (Self::BITS - 1)))
 	}
 
 	pub fn sqrt(&self, rm: RoundingMode, cpu: &mut WhiskerCpu) -> Self {
 		unsafe { rm.write_thread_local(cpu) };
-		Self(unsafe { softfloat_sys::f64_sqrt(self.0) })
+		ExceptionFlags::clear_softfloat();
+		let res = unsafe { softfloat_sys::f64_sqrt(self.0) };
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		Self(res)
 	}
 }
 
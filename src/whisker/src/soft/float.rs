@@ -4,7 +4,7 @@ use softfloat_sys::float32_t;
 
 use crate::cpu::WhiskerCpu;
 
-use super::{ExceptionFlags, FClass, RoundingMode};
+use super::{double::SoftDouble, half::SoftHalf, ExceptionFlags, FClass, RoundingMode};
 
 #[derive(Debug, Clone, Copy)]
 #[repr(transparent)]
@@ -14,6 +14,7 @@ pub struct SoftFloat(float32_t);
 #[allow(unused)]
 impl SoftFloat {
 	pub const ZERO: Self = Self::from_f32(0_f32);
+	pub const CANONICAL_NAN: Self = Self(float32_t { v: 0x7FC00000 });
 
 	pub const fn from_f32(value: f32) -> Self {
 		Self(float32_t { v: value.to_bits() })
@@ -88,14 +89,16 @@ impl SoftFloat {
 	}
 
 	pub fn is_snan(&self) -> bool {
-		self.is_nan() && (Self::get_mantissa(self.0.v) & Self::QUIET_NAN_MASK != 0)
+		self.is_nan() && (Self::get_mantissa(self.0.v) & Self::QUIET_NAN_MASK == 0)
 	}
+
 	pub fn is_qnan(&self) -> bool {
-		self.is_nan() && (Self::get_mantissa(self.0.v) & Self::QUIET_NAN_MASK == 0)
+		self.is_nan() && (Self::get_mantissa(self.0.v) & Self::QUIET_NAN_MASK != 0)
 	}
 
 	pub fn add(&self, other: &Self, rm: RoundingMode, cpu: &mut WhiskerCpu) -> Self {
 		unsafe { rm.write_thread_local(cpu) };
+		ExceptionFlags::clear_softfloat();
 		let res = unsafe { softfloat_sys::f32_add(self.0, other.0) };
 		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
 		Self(res)
@@ -103,32 +106,175 @@ impl SoftFloat {
 
 	pub fn sub(&self, other: &Self, rm: RoundingMode, cpu: &mut WhiskerCpu) -> Self {
 		unsafe { rm.write_thread_local(cpu) };
-		Self(unsafe { softfloat_sys::f32_sub(self.0, other.0) })
+		ExceptionFlags::clear_softfloat();
+		let res = unsafe { softfloat_sys::f32_sub(self.0, other.0) };
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		Self(res)
 	}
 
 	pub fn mul(&self, other: &Self, rm: RoundingMode, cpu: &mut WhiskerCpu) -> Self {
 		unsafe { rm.write_thread_local(cpu) };
-		Self(unsafe { softfloat_sys::f32_mul(self.0, other.0) })
+		ExceptionFlags::clear_softfloat();
+		let res = unsafe { softfloat_sys::f32_mul(self.0, other.0) };
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		Self(res)
 	}
 
 	pub fn div(&self, other: &Self, rm: RoundingMode, cpu: &mut WhiskerCpu) -> Self {
 		unsafe { rm.write_thread_local(cpu) };
-		Self(unsafe { softfloat_sys::f32_div(self.0, other.0) })
+		ExceptionFlags::clear_softfloat();
+		let res = unsafe { softfloat_sys::f32_div(self.0, other.0) };
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		Self(res)
 	}
 
 	pub fn rem(&self, other: &Self, rm: RoundingMode, cpu: &mut WhiskerCpu) -> Self {
 		unsafe { rm.write_thread_local(cpu) };
-		Self(unsafe { softfloat_sys::f32_rem(self.0, other.0) })
+		ExceptionFlags::clear_softfloat();
+		let res = unsafe { softfloat_sys::f32_rem(self.0, other.0) };
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		Self(res)
 	}
 
 	pub fn mul_add(&self, mul: &Self, add: &Self, rm: RoundingMode, cpu: &mut WhiskerCpu) -> Self {
 		unsafe { rm.write_thread_local(cpu) };
-		Self(unsafe { softfloat_sys::f32_mulAdd(self.0, mul.0, add.0) })
+		ExceptionFlags::clear_softfloat();
+		let res = unsafe { softfloat_sys::f32_mulAdd(self.0, mul.0, add.0) };
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		Self(res)
+	}
+
+	/// converts to an integer, out of range values and NaNs saturate as the RISC-V spec requires
+	pub fn convert_to_i32(&self, rm: RoundingMode, cpu: &mut WhiskerCpu) -> i32 {
+		ExceptionFlags::clear_softfloat();
+		let res = unsafe { softfloat_sys::f32_to_i32(self.0, rm.resolve(cpu), true) };
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		res as i32
+	}
+
+	/// converts to an integer, out of range values and NaNs saturate as the RISC-V spec requires
+	pub fn convert_to_u32(&self, rm: RoundingMode, cpu: &mut WhiskerCpu) -> u32 {
+		ExceptionFlags::clear_softfloat();
+		let res = unsafe { softfloat_sys::f32_to_ui32(self.0, rm.resolve(cpu), true) };
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		res as u32
+	}
+
+	/// converts to an integer, out of range values and NaNs saturate as the RISC-V spec requires
+	pub fn convert_to_i64(&self, rm: RoundingMode, cpu: &mut WhiskerCpu) -> i64 {
+		ExceptionFlags::clear_softfloat();
+		let res = unsafe { softfloat_sys::f32_to_i64(self.0, rm.resolve(cpu), true) };
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		res as i64
+	}
+
+	/// converts to an integer, out of range values and NaNs saturate as the RISC-V spec requires
+	pub fn convert_to_u64(&self, rm: RoundingMode, cpu: &mut WhiskerCpu) -> u64 {
+		ExceptionFlags::clear_softfloat();
+		let res = unsafe { softfloat_sys::f32_to_ui64(self.0, rm.resolve(cpu), true) };
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		res as u64
+	}
+
+	pub fn convert_from_i32(val: i32, rm: RoundingMode, cpu: &mut WhiskerCpu) -> Self {
+		unsafe { rm.write_thread_local(cpu) };
+		ExceptionFlags::clear_softfloat();
+		let res = unsafe { softfloat_sys::i32_to_f32(val) };
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		Self(res)
+	}
+
+	pub fn convert_from_u32(val: u32, rm: RoundingMode, cpu: &mut WhiskerCpu) -> Self {
+		unsafe { rm.write_thread_local(cpu) };
+		ExceptionFlags::clear_softfloat();
+		let res = unsafe { softfloat_sys::ui32_to_f32(val) };
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		Self(res)
+	}
+
+	pub fn convert_from_i64(val: i64, rm: RoundingMode, cpu: &mut WhiskerCpu) -> Self {
+		unsafe { rm.write_thread_local(cpu) };
+		ExceptionFlags::clear_softfloat();
+		let res = unsafe { softfloat_sys::i64_to_f32(val) };
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		Self(res)
+	}
+
+	pub fn convert_from_u64(val: u64, rm: RoundingMode, cpu: &mut WhiskerCpu) -> Self {
+		unsafe { rm.write_thread_local(cpu) };
+		ExceptionFlags::clear_softfloat();
+		let res = unsafe { softfloat_sys::ui64_to_f32(val) };
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		Self(res)
+	}
+
+	/// widening is always exact
+	pub fn convert_to_double(&self, cpu: &mut WhiskerCpu) -> SoftDouble {
+		ExceptionFlags::clear_softfloat();
+		let res = SoftDouble::from_u64(unsafe { softfloat_sys::f32_to_f64(self.0) }.v);
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		res
+	}
+
+	pub fn convert_to_half(&self, rm: RoundingMode, cpu: &mut WhiskerCpu) -> SoftHalf {
+		unsafe { rm.write_thread_local(cpu) };
+		ExceptionFlags::clear_softfloat();
+		let res = SoftHalf::from_u16(unsafe { softfloat_sys::f32_to_f16(self.0) }.v);
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		res
+	}
+
+	/// IEEE 754-2019 minimumNumber
+	pub fn min(&self, other: &Self, cpu: &mut WhiskerCpu) -> Self {
+		self.min_max(other, true, cpu)
+	}
+
+	/// IEEE 754-2019 maximumNumber
+	pub fn max(&self, other: &Self, cpu: &mut WhiskerCpu) -> Self {
+		self.min_max(other, false, cpu)
+	}
+
+	fn min_max(&self, other: &Self, min: bool, cpu: &mut WhiskerCpu) -> Self {
+		if self.is_snan() || other.is_snan() {
+			ExceptionFlags(ExceptionFlags::FLAG_INVALID).update_cpu(cpu);
+		}
+
+		match (self.is_nan(), other.is_nan()) {
+			(true, true) => Self::CANONICAL_NAN,
+			(true, false) => *other,
+			(false, true) => *self,
+			(false, false) => {
+				let sign_bit = 1 << (Self::BITS - 1);
+				if (self.0.v | other.0.v) & !sign_bit == 0 {
+					// both are zeros, -0.0 is considered less than +0.0
+					if min {
+						Self::from_u32(self.0.v | other.0.v)
+					} else {
+						Self::from_u32(self.0.v & other.0.v)
+					}
+				} else {
+					let less = unsafe { softfloat_sys::f32_lt_quiet(self.0, other.0) };
+					if less == min {
+						*self
+					} else {
+						*other
+					}
+				}
+			}
+		}
+	}
+
+	/// flips the sign bit, this is exact and never raises any exceptions
+	pub fn neg(&self) -> Self {
+		Self::from_u32(self.0.v ^ (1 << (Self::BITS - 1)))
 	}
 
 	pub fn sqrt(&self, rm: RoundingMode, cpu: &mut WhiskerCpu) -> Self {
 		unsafe { rm.write_thread_local(cpu) };
-		Self(unsafe { softfloat_sys::f32_sqrt(self.0) })
+		ExceptionFlags::clear_softfloat();
+		let res = unsafe { softfloat_sys::f32_sqrt(self.0) };
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		Self(res)
 	}
 }
 
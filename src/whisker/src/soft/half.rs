@@ -0,0 +1,315 @@
+use std::cmp::Ordering;
+
+use softfloat_sys::float16_t;
+
+use crate::cpu::WhiskerCpu;
+
+use super::{double::SoftDouble, float::SoftFloat, ExceptionFlags, FClass, RoundingMode};
+
+#[derive(Debug, Clone, Copy)]
+#[repr(transparent)]
+#[allow(unused)]
+pub struct SoftHalf(float16_t);
+
+#[allow(unused)]
+impl SoftHalf {
+	pub const ZERO: Self = Self(float16_t { v: 0 });
+	pub const CANONICAL_NAN: Self = Self(float16_t { v: 0x7E00 });
+
+	pub fn from_u16(value: u16) -> Self {
+		Self(float16_t { v: value })
+	}
+
+	pub fn to_u16(self) -> u16 {
+		self.0.v
+	}
+
+	pub fn from_le_bytes(bytes: [u8; 2]) -> Self {
+		Self(float16_t {
+			v: u16::from_le_bytes(bytes),
+		})
+	}
+
+	pub fn to_le_bytes(self) -> [u8; 2] {
+		self.0.v.to_le_bytes()
+	}
+
+	pub fn fclass(self) -> FClass {
+		let sign = Self::get_sign(self.0.v);
+		let exponent = Self::get_exponent(self.0.v);
+		let mantissa = Self::get_mantissa(self.0.v);
+
+		if exponent == Self::EXPONENT_MASK {
+			if mantissa == 0 {
+				if sign == 0 {
+					FClass::PositiveInfinity
+				} else {
+					FClass::NegativeInfinity
+				}
+			} else if (mantissa & Self::QUIET_NAN_MASK) == 0 {
+				FClass::SignalingNaN
+			} else {
+				FClass::QuietNaN
+			}
+		} else if exponent == 0 {
+			if mantissa == 0 {
+				if sign == 0 {
+					FClass::PositiveZero
+				} else {
+					FClass::NegativeZero
+				}
+			} else if sign == 0 {
+				FClass::PositiveSubNormal
+			} else {
+				FClass::NegativeSubnormal
+			}
+		} else if sign == 0 {
+			FClass::PositiveNormal
+		} else {
+			FClass::NegativeNormal
+		}
+	}
+
+	pub fn is_nan(&self) -> bool {
+		Self::get_exponent(self.0.v) == Self::EXPONENT_MASK && Self::get_mantissa(self.0.v) != 0u16
+	}
+
+	pub fn is_snan(&self) -> bool {
+		self.is_nan() && (Self::get_mantissa(self.0.v) & Self::QUIET_NAN_MASK == 0)
+	}
+
+	pub fn is_qnan(&self) -> bool {
+		self.is_nan() && (Self::get_mantissa(self.0.v) & Self::QUIET_NAN_MASK != 0)
+	}
+
+	pub fn add(&self, other: &Self, rm: RoundingMode, cpu: &mut WhiskerCpu) -> Self {
+		unsafe { rm.write_thread_local(cpu) };
+		ExceptionFlags::clear_softfloat();
+		let res = unsafe { softfloat_sys::f16_add(self.0, other.0) };
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		Self(res)
+	}
+
+	pub fn sub(&self, other: &Self, rm: RoundingMode, cpu: &mut WhiskerCpu) -> Self {
+		unsafe { rm.write_thread_local(cpu) };
+		ExceptionFlags::clear_softfloat();
+		let res = unsafe { softfloat_sys::f16_sub(self.0, other.0) };
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		Self(res)
+	}
+
+	pub fn mul(&self, other: &Self, rm: RoundingMode, cpu: &mut WhiskerCpu) -> Self {
+		unsafe { rm.write_thread_local(cpu) };
+		ExceptionFlags::clear_softfloat();
+		let res = unsafe { softfloat_sys::f16_mul(self.0, other.0) };
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		Self(res)
+	}
+
+	pub fn div(&self, other: &Self, rm: RoundingMode, cpu: &mut WhiskerCpu) -> Self {
+		unsafe { rm.write_thread_local(cpu) };
+		ExceptionFlags::clear_softfloat();
+		let res = unsafe { softfloat_sys::f16_div(self.0, other.0) };
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		Self(res)
+	}
+
+	pub fn rem(&self, other: &Self, rm: RoundingMode, cpu: &mut WhiskerCpu) -> Self {
+		unsafe { rm.write_thread_local(cpu) };
+		ExceptionFlags::clear_softfloat();
+		let res = unsafe { softfloat_sys::f16_rem(self.0, other.0) };
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		Self(res)
+	}
+
+	pub fn mul_add(&self, mul: &Self, add: &Self, rm: RoundingMode, cpu: &mut WhiskerCpu) -> Self {
+		unsafe { rm.write_thread_local(cpu) };
+		ExceptionFlags::clear_softfloat();
+		let res = unsafe { softfloat_sys::f16_mulAdd(self.0, mul.0, add.0) };
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		Self(res)
+	}
+
+	/// converts to an integer, out of range values and NaNs saturate as the RISC-V spec requires
+	pub fn convert_to_i32(&self, rm: RoundingMode, cpu: &mut WhiskerCpu) -> i32 {
+		ExceptionFlags::clear_softfloat();
+		let res = unsafe { softfloat_sys::f16_to_i32(self.0, rm.resolve(cpu), true) };
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		res as i32
+	}
+
+	/// converts to an integer, out of range values and NaNs saturate as the RISC-V spec requires
+	pub fn convert_to_u32(&self, rm: RoundingMode, cpu: &mut WhiskerCpu) -> u32 {
+		ExceptionFlags::clear_softfloat();
+		let res = unsafe { softfloat_sys::f16_to_ui32(self.0, rm.resolve(cpu), true) };
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		res as u32
+	}
+
+	/// converts to an integer, out of range values and NaNs saturate as the RISC-V spec requires
+	pub fn convert_to_i64(&self, rm: RoundingMode, cpu: &mut WhiskerCpu) -> i64 {
+		ExceptionFlags::clear_softfloat();
+		let res = unsafe { softfloat_sys::f16_to_i64(self.0, rm.resolve(cpu), true) };
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		res as i64
+	}
+
+	/// converts to an integer, out of range values and NaNs saturate as the RISC-V spec requires
+	pub fn convert_to_u64(&self, rm: RoundingMode, cpu: &mut WhiskerCpu) -> u64 {
+		ExceptionFlags::clear_softfloat();
+		let res = unsafe { softfloat_sys::f16_to_ui64(self.0, rm.resolve(cpu), true) };
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		res as u64
+	}
+
+	pub fn convert_from_i32(val: i32, rm: RoundingMode, cpu: &mut WhiskerCpu) -> Self {
+		unsafe { rm.write_thread_local(cpu) };
+		ExceptionFlags::clear_softfloat();
+		let res = unsafe { softfloat_sys::i32_to_f16(val) };
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		Self(res)
+	}
+
+	pub fn convert_from_u32(val: u32, rm: RoundingMode, cpu: &mut WhiskerCpu) -> Self {
+		unsafe { rm.write_thread_local(cpu) };
+		ExceptionFlags::clear_softfloat();
+		let res = unsafe { softfloat_sys::ui32_to_f16(val) };
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		Self(res)
+	}
+
+	pub fn convert_from_i64(val: i64, rm: RoundingMode, cpu: &mut WhiskerCpu) -> Self {
+		unsafe { rm.write_thread_local(cpu) };
+		ExceptionFlags::clear_softfloat();
+		let res = unsafe { softfloat_sys::i64_to_f16(val) };
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		Self(res)
+	}
+
+	pub fn convert_from_u64(val: u64, rm: RoundingMode, cpu: &mut WhiskerCpu) -> Self {
+		unsafe { rm.write_thread_local(cpu) };
+		ExceptionFlags::clear_softfloat();
+		let res = unsafe { softfloat_sys::ui64_to_f16(val) };
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		Self(res)
+	}
+
+	/// widening is always exact
+	pub fn convert_to_float(&self, cpu: &mut WhiskerCpu) -> SoftFloat {
+		ExceptionFlags::clear_softfloat();
+		let res = SoftFloat::from_u32(unsafe { softfloat_sys::f16_to_f32(self.0) }.v);
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		res
+	}
+
+	/// widening is always exact
+	pub fn convert_to_double(&self, cpu: &mut WhiskerCpu) -> SoftDouble {
+		ExceptionFlags::clear_softfloat();
+		let res = SoftDouble::from_u64(unsafe { softfloat_sys::f16_to_f64(self.0) }.v);
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		res
+	}
+
+	/// IEEE 754-2019 minimumNumber
+	pub fn min(&self, other: &Self, cpu: &mut WhiskerCpu) -> Self {
+		self.min_max(other, true, cpu)
+	}
+
+	/// IEEE 754-2019 maximumNumber
+	pub fn max(&self, other: &Self, cpu: &mut WhiskerCpu) -> Self {
+		self.min_max(other, false, cpu)
+	}
+
+	fn min_max(&self, other: &Self, min: bool, cpu: &mut WhiskerCpu) -> Self {
+		if self.is_snan() || other.is_snan() {
+			ExceptionFlags(ExceptionFlags::FLAG_INVALID).update_cpu(cpu);
+		}
+
+		match (self.is_nan(), other.is_nan()) {
+			(true, true) => Self::CANONICAL_NAN,
+			(true, false) => *other,
+			(false, true) => *self,
+			(false, false) => {
+				let sign_bit = 1 << (Self::BITS - 1);
+				if (self.0.v | other.0.v) & !sign_bit == 0 {
+					// both are zeros, -0.0 is considered less than +0.0
+					if min {
+						Self::from_u16(self.0.v | other.0.v)
+					} else {
+						Self::from_u16(self.0.v & other.0.v)
+					}
+				} else {
+					let less = unsafe { softfloat_sys::f16_lt_quiet(self.0, other.0) };
+					if less == min {
+						*self
+					} else {
+						*other
+					}
+				}
+			}
+		}
+	}
+
+	/// flips the sign bit, this is exact and never raises any exceptions
+	pub fn neg(&self) -> Self {
+		Self::from_u16(self.0.v ^ (1 << (Self::BITS - 1)))
+	}
+
+	pub fn sqrt(&self, rm: RoundingMode, cpu: &mut WhiskerCpu) -> Self {
+		unsafe { rm.write_thread_local(cpu) };
+		ExceptionFlags::clear_softfloat();
+		let res = unsafe { softfloat_sys::f16_sqrt(self.0) };
+		ExceptionFlags::get_from_softfloat().update_cpu(cpu);
+		Self(res)
+	}
+}
+
+#[allow(unused)]
+impl SoftHalf {
+	const BITS: u16 = 16;
+	const MANTISSA_BITS: u16 = 10;
+	const EXPONENT_BITS: u16 = Self::BITS - Self::MANTISSA_BITS - 1;
+
+	const MANTISSA_MASK: u16 = (1 << Self::MANTISSA_BITS) - 1;
+	const EXPONENT_MASK: u16 = (1 << Self::EXPONENT_BITS) - 1;
+
+	const QUIET_NAN_MASK: u16 = 1 << (Self::MANTISSA_BITS - 1);
+
+	const fn get_sign(value: u16) -> u16 {
+		value >> (Self::EXPONENT_BITS + Self::MANTISSA_BITS)
+	}
+
+	const fn get_exponent(value: u16) -> u16 {
+		(value >> Self::MANTISSA_BITS) & Self::EXPONENT_MASK
+	}
+
+	const fn get_mantissa(value: u16) -> u16 {
+		value & Self::MANTISSA_MASK
+	}
+}
+
+impl Default for SoftHalf {
+	fn default() -> Self {
+		Self::ZERO
+	}
+}
+
+impl PartialEq for SoftHalf {
+	fn eq(&self, other: &Self) -> bool {
+		unsafe { softfloat_sys::f16_eq(self.0, other.0) }
+	}
+}
+
+impl PartialOrd for SoftHalf {
+	fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+		if self.is_nan() || other.is_nan() {
+			None
+		} else if unsafe { softfloat_sys::f16_eq(self.0, other.0) } {
+			Some(Ordering::Equal)
+		} else if unsafe { softfloat_sys::f16_lt(self.0, other.0) } {
+			Some(Ordering::Less)
+		} else {
+			Some(Ordering::Greater)
+		}
+	}
+}
@@ -0,0 +1,175 @@
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::insn::Instruction;
+
+/// counts retired instructions by class and mnemonic, reported when whisker exits
+#[derive(Debug)]
+pub struct OpcodeStats {
+	/// keyed by the start of the instruction's debug output, like `IntExtension(AddImmediate`
+	counts: HashMap<String, u64>,
+	// the key of the instruction being executed, counted once it retires
+	current: String,
+	print: bool,
+	json_out: Option<PathBuf>,
+}
+
+impl OpcodeStats {
+	pub fn new(print: bool, json_out: Option<PathBuf>) -> Self {
+		Self {
+			counts: HashMap::new(),
+			current: String::new(),
+			print,
+			json_out,
+		}
+	}
+
+	pub fn decoded(&mut self, insn: &Instruction) {
+		self.current.clear();
+		write!(self.current, "{insn:?}").unwrap();
+		// the class and the variant inside it, the operands are cut off
+		let class_end = self.current.find('(').unwrap_or(self.current.len());
+		let mnemonic_end = self.current[class_end + 1..]
+			.find(|c: char| !c.is_alphanumeric() && c != '_')
+			.map_or(self.current.len(), |idx| class_end + 1 + idx);
+		self.current.truncate(mnemonic_end);
+	}
+
+	pub fn retired(&mut self) {
+		match self.counts.get_mut(self.current.as_str()) {
+			Some(count) => *count += 1,
+			None => {
+				self.counts.insert(self.current.clone(), 1);
+			}
+		}
+	}
+
+	/// (class, mnemonic, count) from the most to the least executed
+	fn histogram(&self) -> Vec<(&str, &str, u64)> {
+		let mut histogram = self
+			.counts
+			.iter()
+			.map(|(key, count)| {
+				let (class, mnemonic) = key.split_once('(').unwrap_or((key, ""));
+				(class, mnemonic, *count)
+			})
+			.collect::<Vec<_>>();
+		histogram.sort_unstable_by(|a, b| b.2.cmp(&a.2).then((a.0, a.1).cmp(&(b.0, b.1))));
+		histogram
+	}
+
+	fn class_totals(&self) -> Vec<(&str, u64)> {
+		let mut totals = HashMap::<&str, u64>::new();
+		for (class, _, count) in self.histogram() {
+			*totals.entry(class).or_default() += count;
+		}
+		let mut totals = totals.into_iter().collect::<Vec<_>>();
+		totals.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+		totals
+	}
+
+	pub fn report(&self) {
+		if self.print {
+			eprint!("{}", self.text());
+		}
+		if let Some(path) = &self.json_out {
+			if let Err(err) = fs::write(path, self.json()) {
+				eprintln!("could not write opcode stats to {}: {err}", path.display());
+			}
+		}
+	}
+
+	fn text(&self) -> String {
+		let total = self.counts.values().sum::<u64>();
+		let percent = |count: u64| count as f64 * 100.0 / total.max(1) as f64;
+		let mut out = format!("retired {total} instructions\n");
+		for (class, count) in self.class_totals() {
+			writeln!(out, "{count:>12} {:>6.2}%  {class}", percent(count)).unwrap();
+		}
+		writeln!(out).unwrap();
+		for (class, mnemonic, count) in self.histogram() {
+			writeln!(out, "{count:>12} {:>6.2}%  {class:<20} {mnemonic}", percent(count)).unwrap();
+		}
+		out
+	}
+
+	fn json(&self) -> String {
+		let total = self.counts.values().sum::<u64>();
+		let mut out = format!("{{\"total\":{total},\"classes\":{{");
+		for (idx, (class, count)) in self.class_totals().into_iter().enumerate() {
+			let sep = if idx == 0 { "" } else { "," };
+			write!(out, "{sep}\"{class}\":{count}").unwrap();
+		}
+		out.push_str("},\"mnemonics\":[");
+		for (idx, (class, mnemonic, count)) in self.histogram().into_iter().enumerate() {
+			let sep = if idx == 0 { "" } else { "," };
+			write!(
+				out,
+				"{sep}{{\"class\":\"{class}\",\"mnemonic\":\"{mnemonic}\",\"count\":{count}}}"
+			)
+			.unwrap();
+		}
+		out.push_str("]}\n");
+		out
+	}
+}
+
+/// how fast the guest ran, reported when whisker exits and optionally every so often
+#[derive(Debug)]
+pub struct PerfSummary {
+	started: Instant,
+	start_instret: u64,
+	interval: Option<Duration>,
+	last_report: Instant,
+	last_instret: u64,
+}
+
+impl PerfSummary {
+	/// instret is where the count starts, a restored machine has already retired some
+	pub fn new(interval: Option<Duration>, instret: u64) -> Self {
+		let now = Instant::now();
+		Self {
+			started: now,
+			start_instret: instret,
+			interval,
+			last_report: now,
+			last_instret: instret,
+		}
+	}
+
+	/// prints a line for the last interval if it's over
+	pub fn tick(&mut self, instret: u64) {
+		let Some(interval) = self.interval else {
+			return;
+		};
+		let elapsed = self.last_report.elapsed();
+		if elapsed < interval {
+			return;
+		}
+		let retired = instret - self.last_instret;
+		eprintln!(
+			"perf: {retired} instructions in the last {:.3}s, {:.2} MIPS",
+			elapsed.as_secs_f64(),
+			mips(retired, elapsed)
+		);
+		self.last_report = Instant::now();
+		self.last_instret = instret;
+	}
+
+	pub fn report(&self, instret: u64, cycles: u64) {
+		let elapsed = self.started.elapsed();
+		let retired = instret - self.start_instret;
+		eprintln!(
+			"perf: {retired} instructions ({cycles} cycles in total) in {:.3}s, {:.2} MIPS",
+			elapsed.as_secs_f64(),
+			mips(retired, elapsed)
+		);
+	}
+}
+
+fn mips(instructions: u64, elapsed: Duration) -> f64 {
+	instructions as f64 / elapsed.as_secs_f64().max(f64::EPSILON) / 1_000_000.0
+}
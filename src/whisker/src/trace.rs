@@ -0,0 +1,198 @@
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::cpu::WhiskerCpu;
+use crate::ty::{GPRegisterIndex, TrapIdx};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum TraceFormat {
+	/// a line per instruction with the pc, raw bits, decoded instruction and the registers it changed
+	#[default]
+	Text,
+	/// the same fields as a JSON object per line
+	Json,
+}
+
+/// writes an instruction trace to a file, separate from the logfile's full register dumps
+#[derive(Debug)]
+pub struct Tracer {
+	out: BufWriter<File>,
+	format: TraceFormat,
+	/// only instructions with a pc in one of these (start, size) ranges are traced, every one if it's empty
+	pc_ranges: Vec<(u64, u64)>,
+	/// with a limit only the last lines are kept, they're written out when the tracer is dropped
+	ring: VecDeque<String>,
+	keep_last: Option<usize>,
+}
+
+/// the registers before an instruction, compared afterwards to find the ones it changed
+pub struct RegisterState {
+	x: [u64; 32],
+	f: [u64; 32],
+}
+
+impl Tracer {
+	pub fn create(
+		path: &Path,
+		format: TraceFormat,
+		pc_ranges: Vec<(u64, u64)>,
+		keep_last: Option<usize>,
+	) -> io::Result<Self> {
+		Ok(Self {
+			out: BufWriter::new(File::create(path)?),
+			format,
+			pc_ranges,
+			ring: VecDeque::with_capacity(keep_last.unwrap_or(0)),
+			keep_last,
+		})
+	}
+
+	pub fn wants(&self, pc: u64) -> bool {
+		self.pc_ranges.is_empty()
+			|| self
+				.pc_ranges
+				.iter()
+				.any(|&(start, size)| pc.wrapping_sub(start) < size)
+	}
+
+	fn emit(&mut self, line: String) {
+		match self.keep_last {
+			Some(limit) => {
+				if self.ring.len() == limit {
+					self.ring.pop_front();
+				}
+				if limit != 0 {
+					self.ring.push_back(line);
+				}
+			}
+			None => writeln!(self.out, "{line}").expect("failed to write to the trace"),
+		}
+	}
+}
+
+impl Drop for Tracer {
+	fn drop(&mut self) {
+		for line in self.ring.drain(..) {
+			writeln!(self.out, "{line}").expect("failed to write to the trace");
+		}
+		self.out.flush().expect("failed to write to the trace");
+	}
+}
+
+/// one traced instruction, raw and insn are missing when it couldn't be fetched or decoded
+struct TraceLine {
+	pc: u64,
+	raw: Option<(u32, u64)>,
+	insn: Option<String>,
+	changed: Vec<(String, u64)>,
+	trap: Option<u64>,
+}
+
+impl TraceLine {
+	fn text(&self) -> String {
+		let raw = match self.raw {
+			Some((raw, size)) => format!("{raw:0width$x}", width = size as usize * 2),
+			None => "????????".to_string(),
+		};
+		let mut line = format!(
+			"{:#018x} {raw:<8} {}",
+			self.pc,
+			self.insn.as_deref().unwrap_or("<not decoded>")
+		);
+		for (reg, val) in &self.changed {
+			write!(line, " {reg}={val:#x}").unwrap();
+		}
+		if let Some(cause) = self.trap {
+			write!(line, " trap={cause:#x}").unwrap();
+		}
+		line
+	}
+
+	fn json(&self) -> String {
+		let mut line = format!("{{\"pc\":\"{:#x}\"", self.pc);
+		match (&self.raw, &self.insn) {
+			(Some((raw, _)), Some(insn)) => write!(line, ",\"raw\":\"{raw:#x}\",\"insn\":\"{}\"", escape_json(insn)),
+			_ => write!(line, ",\"raw\":null,\"insn\":null"),
+		}
+		.unwrap();
+		line.push_str(",\"changed\":{");
+		for (idx, (reg, val)) in self.changed.iter().enumerate() {
+			let sep = if idx == 0 { "" } else { "," };
+			write!(line, "{sep}\"{reg}\":\"{val:#x}\"").unwrap();
+		}
+		line.push('}');
+		match self.trap {
+			Some(cause) => write!(line, ",\"trap\":\"{cause:#x}\"}}").unwrap(),
+			None => line.push_str(",\"trap\":null}"),
+		}
+		line
+	}
+}
+
+fn escape_json(s: &str) -> String {
+	let mut escaped = String::with_capacity(s.len());
+	for c in s.chars() {
+		match c {
+			'"' => escaped.push_str("\\\""),
+			'\\' => escaped.push_str("\\\\"),
+			c if c.is_control() => write!(escaped, "\\u{:04x}", u32::from(c)).unwrap(),
+			c => escaped.push(c),
+		}
+	}
+	escaped
+}
+
+impl WhiskerCpu {
+	/// the registers to compare against, if the instruction at pc is traced
+	pub fn trace_before(&self, pc: u64) -> Option<RegisterState> {
+		let tracer = self.tracer.as_ref()?;
+		tracer.wants(pc).then(|| RegisterState {
+			x: *self.registers.regs(),
+			f: *self.fp_registers.get_all_raw(),
+		})
+	}
+
+	/// fetched is the raw bits, their size and the decoded instruction, if it got that far
+	pub fn trace_after(
+		&mut self,
+		pc: u64,
+		before: RegisterState,
+		fetched: Option<(u32, u64, String)>,
+		trap: Option<TrapIdx>,
+	) {
+		let mut changed = Vec::new();
+		for (idx, (old, new)) in before.x.iter().zip(self.registers.regs()).enumerate() {
+			if old != new {
+				let reg = GPRegisterIndex::new(idx as u8).unwrap();
+				changed.push((reg.display().to_string(), *new));
+			}
+		}
+		for (idx, (old, new)) in before.f.iter().zip(self.fp_registers.get_all_raw()).enumerate() {
+			if old != new {
+				changed.push((format!("f{idx}"), *new));
+			}
+		}
+		let (raw, insn) = match fetched {
+			Some((raw, size, insn)) => (Some((raw, size)), Some(insn)),
+			None => (None, None),
+		};
+		let line = TraceLine {
+			pc,
+			raw,
+			insn,
+			changed,
+			trap: trap.map(|cause| cause.inner()),
+		};
+		let Some(tracer) = self.tracer.as_mut() else {
+			return;
+		};
+		let line = match tracer.format {
+			TraceFormat::Text => line.text(),
+			TraceFormat::Json => line.json(),
+		};
+		tracer.emit(line);
+	}
+}
@@ -1,12 +1,14 @@
 use std::fmt::Debug;
 use std::marker::PhantomData;
 use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, Not};
+use std::str::FromStr;
 
 // Do we want to make specific structs for this?
 // I think it's fine to use the <X>Registers struct since it's only-
 // used for compiler help.
 pub type GPRegisterIndex = RegisterIndex<GPRegsIdx>;
 pub type FPRegisterIndex = RegisterIndex<FPRegsIdx>;
+pub type VectorRegisterIndex = RegisterIndex<VectorRegsIdx>;
 pub type UnknownRegisterIndex = RegisterIndex<()>;
 
 /// a valid register index 0..=31
@@ -37,6 +39,17 @@ impl UnknownRegisterIndex {
 	pub fn to_fp(self) -> FPRegisterIndex {
 		RegisterIndex(self.0, PhantomData)
 	}
+
+	pub fn to_vec(self) -> VectorRegisterIndex {
+		RegisterIndex(self.0, PhantomData)
+	}
+}
+
+impl GPRegisterIndex {
+	/// some compressed formats are shared between integer and float instructions, so they decode a GP index
+	pub fn to_fp(self) -> FPRegisterIndex {
+		RegisterIndex(self.0, PhantomData)
+	}
 }
 
 impl From<UnknownRegisterIndex> for GPRegisterIndex {
@@ -61,6 +74,22 @@ impl GPRegisterIndex {
 	pub const SP: GPRegisterIndex = RegisterIndex(2, PhantomData);
 	pub const GLOBAL_PTR: GPRegisterIndex = RegisterIndex(3, PhantomData);
 	pub const THREAD_PTR: GPRegisterIndex = RegisterIndex(4, PhantomData);
+	pub const A0: GPRegisterIndex = RegisterIndex(10, PhantomData);
+	pub const A1: GPRegisterIndex = RegisterIndex(11, PhantomData);
+	pub const A2: GPRegisterIndex = RegisterIndex(12, PhantomData);
+
+	/// takes either the ABI name or xN, fp is accepted for s0
+	pub fn from_name(name: &str) -> Option<Self> {
+		if name == "fp" {
+			return Self::new(8);
+		}
+		if let Some(idx) = name.strip_prefix('x').and_then(|idx| idx.parse::<u8>().ok()) {
+			return Self::new(idx);
+		}
+		(0..=31)
+			.map(|idx| RegisterIndex(idx, PhantomData))
+			.find(|reg: &Self| reg.display() == name)
+	}
 
 	pub fn display(&self) -> &'static str {
 		match self.0 {
@@ -157,6 +186,12 @@ impl Debug for FPRegisterIndex {
 	}
 }
 
+impl Debug for VectorRegisterIndex {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "Reg(v{})", self.0)
+	}
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct SupportedExtensions(u64);
 
@@ -189,10 +224,126 @@ impl SupportedExtensions {
 	pub const Y_RESERVED: Self = Self(1 << 24);
 	pub const Z_RESERVED: Self = Self(1 << 25);
 
+	// multi-letter extensions, these don't have a bit in misa
+	pub const ZFH: Self = Self(1 << 32);
+	pub const ZBA: Self = Self(1 << 33);
+	pub const ZBB: Self = Self(1 << 34);
+	pub const ZBC: Self = Self(1 << 35);
+	pub const ZBS: Self = Self(1 << 36);
+	pub const ZICOND: Self = Self(1 << 37);
+	pub const ZAWRS: Self = Self(1 << 38);
+	pub const ZIHINTPAUSE: Self = Self(1 << 39);
+	pub const ZABHA: Self = Self(1 << 40);
+	pub const ZACAS: Self = Self(1 << 41);
+
+	/// everything whisker implements, what --isa defaults to
+	pub const IMPLEMENTED: Self = Self(
+		Self::INTEGER.0
+			| Self::FLOAT.0
+			| Self::DOUBLE.0
+			| Self::ZFH.0
+			| Self::COMPRESSED.0
+			| Self::ATOMIC.0
+			| Self::MULTIPLY.0
+			| Self::ZBA.0
+			| Self::ZBB.0
+			| Self::ZBC.0
+			| Self::ZBS.0
+			| Self::ZICOND.0
+			| Self::ZAWRS.0
+			| Self::ZIHINTPAUSE.0
+			| Self::ZABHA.0
+			| Self::ZACAS.0
+			| Self::VECTOR.0
+			| Self::SUPERVISOR.0
+			| Self::USER_MODE.0,
+	);
+
+	// an extension can only be enabled along with the one it builds on
+	const REQUIRES: [(Self, Self); 6] = [
+		(Self::DOUBLE, Self::FLOAT),
+		(Self::ZFH, Self::FLOAT),
+		(Self::VECTOR, Self::DOUBLE),
+		(Self::ZABHA, Self::ATOMIC),
+		(Self::ZACAS, Self::ATOMIC),
+		(Self::ZAWRS, Self::ATOMIC),
+	];
+
+	// the single letter extensions, these line up with the bits in misa
+	const LETTERS_MASK: u64 = (1 << 26) - 1;
+	// MXL=2, XLEN is 64
+	const MISA_MXL_64: u64 = 2 << 62;
+
+	/// the names used in ISA strings, in canonical order
+	/// the ones that are always implemented are listed with no flags so `has` is always true
+	const NAMES: [(Self, &'static str); 23] = [
+		(Self::INTEGER, "i"),
+		(Self::MULTIPLY, "m"),
+		(Self::ATOMIC, "a"),
+		(Self::FLOAT, "f"),
+		(Self::DOUBLE, "d"),
+		(Self::QUAD_FLOAT, "q"),
+		(Self::COMPRESSED, "c"),
+		(Self::VECTOR, "v"),
+		(Self::HYPERVISOR, "h"),
+		(Self::empty(), "zicntr"),
+		(Self::ZICOND, "zicond"),
+		(Self::empty(), "zicsr"),
+		(Self::empty(), "zifencei"),
+		(Self::ZIHINTPAUSE, "zihintpause"),
+		(Self::empty(), "zihpm"),
+		(Self::ZABHA, "zabha"),
+		(Self::ZACAS, "zacas"),
+		(Self::ZAWRS, "zawrs"),
+		(Self::ZFH, "zfh"),
+		(Self::ZBA, "zba"),
+		(Self::ZBB, "zbb"),
+		(Self::ZBC, "zbc"),
+		(Self::ZBS, "zbs"),
+	];
+
 	pub const fn empty() -> Self {
 		SupportedExtensions(0)
 	}
 
+	/// the value of misa with these extensions enabled
+	pub const fn misa(self) -> u64 {
+		Self::MISA_MXL_64 | (self.0 & Self::LETTERS_MASK)
+	}
+
+	/// keeps the multi-letter extensions and only the letters that are also set in misa
+	pub const fn with_misa_letters(self, misa: u64) -> Self {
+		SupportedExtensions(self.0 & (!Self::LETTERS_MASK | misa))
+	}
+
+	/// the name of every extension that's enabled, in canonical order
+	pub fn isa_extensions(self) -> Vec<&'static str> {
+		Self::NAMES
+			.iter()
+			.filter(|(ext, _)| self.has(*ext))
+			.map(|(_, name)| *name)
+			.collect()
+	}
+
+	/// the ISA string, like rv64imac_zicsr, single letters are run together and the rest are separated by underscores
+	pub fn isa_string(self) -> String {
+		let mut isa = "rv64".to_string();
+		for name in self.isa_extensions() {
+			if name.len() > 1 {
+				isa.push('_');
+			}
+			isa.push_str(name);
+		}
+		isa
+	}
+
+	fn name(self) -> &'static str {
+		Self::NAMES
+			.iter()
+			.find(|(ext, _)| *ext == self)
+			.map_or("?", |(_, name)| *name)
+	}
+
 	pub const fn has(self, other: Self) -> bool {
 		(self.0 & other.0) == other.0
 	}
@@ -208,6 +359,48 @@ impl SupportedExtensions {
 	}
 }
 
+impl FromStr for SupportedExtensions {
+	type Err = String;
+
+	/// an ISA string like rv64imafdc_zicsr, the privilege modes aren't part of it so S and U are always enabled
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let isa = s.to_ascii_lowercase();
+		let rest = isa
+			.strip_prefix("rv64")
+			.ok_or_else(|| format!("{s:?} isn't an rv64 ISA string"))?;
+		// the single letters come first, the first multi-letter extension can follow them without an underscore
+		let (letters, multi) = rest.split_at(rest.find(['z', 's', 'x', '_']).unwrap_or(rest.len()));
+		let letters = letters.char_indices().map(|(idx, c)| &letters[idx..idx + c.len_utf8()]);
+		let names = letters.chain(multi.split('_').filter(|name| !name.is_empty()));
+
+		let mut extensions = Self::SUPERVISOR | Self::USER_MODE;
+		for name in names {
+			if name == "g" {
+				extensions |= Self::INTEGER | Self::MULTIPLY | Self::ATOMIC | Self::FLOAT | Self::DOUBLE;
+				continue;
+			}
+			let (ext, _) = Self::NAMES
+				.iter()
+				.find(|(_, known)| *known == name)
+				.ok_or_else(|| format!("unknown extension {name:?}"))?;
+			if !Self::IMPLEMENTED.has(*ext) {
+				return Err(format!("whisker doesn't implement the {name} extension"));
+			}
+			extensions |= *ext;
+		}
+
+		if !extensions.has(Self::INTEGER) {
+			return Err("the base integer ISA i is required".to_string());
+		}
+		for (ext, required) in Self::REQUIRES {
+			if extensions.has(ext) && !extensions.has(required) {
+				return Err(format!("the {} extension requires {}", ext.name(), required.name()));
+			}
+		}
+		Ok(extensions)
+	}
+}
+
 impl BitOr for SupportedExtensions {
 	type Output = Self;
 	fn bitor(self, rhs: Self) -> Self::Output {
@@ -273,6 +466,10 @@ impl TrapIdx {
 	pub fn inner(&self) -> u64 {
 		self.0
 	}
+
+	pub fn from_inner(inner: u64) -> Self {
+		Self(inner)
+	}
 }
 
 #[allow(unused)]
@@ -285,17 +482,51 @@ impl TrapIdx {
 	pub const ILLEGAL_INSTRUCTION: Self = Self(2);
 	pub const BREAKPOINT: Self = Self(3);
 	pub const LOAD_ADDR_MISALIGNED: Self = Self(4);
-	pub const STORE_ADDR_MISALIGNED: Self = Self(5);
-	pub const STORE_ACCESS_FAULT: Self = Self(6);
-	pub const ECALL_UMODE: Self = Self(7);
-	pub const ECALL_SMODE: Self = Self(8);
-	pub const ECALL_MMODE: Self = Self(10);
+	pub const LOAD_ACCESS_FAULT: Self = Self(5);
+	pub const STORE_ADDR_MISALIGNED: Self = Self(6);
+	pub const STORE_ACCESS_FAULT: Self = Self(7);
+	pub const ECALL_UMODE: Self = Self(8);
+	pub const ECALL_SMODE: Self = Self(9);
+	pub const ECALL_MMODE: Self = Self(11);
 	pub const INSTRUCTION_PAGE_FAULT: Self = Self(12);
 	pub const LOAD_PAGE_FAULT: Self = Self(13);
 	pub const STORE_PAGE_FAULT: Self = Self(15);
 	pub const SOFTWARE_CHECK: Self = Self(18);
 	pub const HARDWARE_CHECK: Self = Self(19);
 	pub const MEOW_ERR: Self = Self(31);
+
+	// interrupt causes line up with their bits in mip and mie
+	pub const SUPERVISOR_SOFTWARE_INTERRUPT: Self = Self::interrupt(1);
+	pub const MACHINE_SOFTWARE_INTERRUPT: Self = Self::interrupt(3);
+	pub const SUPERVISOR_TIMER_INTERRUPT: Self = Self::interrupt(5);
+	pub const MACHINE_TIMER_INTERRUPT: Self = Self::interrupt(7);
+	pub const SUPERVISOR_EXTERNAL_INTERRUPT: Self = Self::interrupt(9);
+	pub const MACHINE_EXTERNAL_INTERRUPT: Self = Self::interrupt(11);
+
+	pub const fn interrupt(code: u64) -> Self {
+		Self(Self::INTERRUPT_MASK | code)
+	}
+}
+
+/// the privilege level the hart is currently executing at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(u8)]
+pub enum PrivilegeMode {
+	User = 0b00,
+	Supervisor = 0b01,
+	Machine = 0b11,
+}
+
+impl PrivilegeMode {
+	/// decodes a 2 bit privilege field like mstatus.MPP, 0b10 is reserved
+	pub fn from_bits(bits: u64) -> Option<Self> {
+		match bits & 0b11 {
+			0b00 => Some(Self::User),
+			0b01 => Some(Self::Supervisor),
+			0b11 => Some(Self::Machine),
+			_ => None,
+		}
+	}
 }
 
 /// these exist to allow the generic RegisterIndex to derive things without needing the underlying register
@@ -304,3 +535,5 @@ impl TrapIdx {
 pub enum GPRegsIdx {}
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum FPRegsIdx {}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum VectorRegsIdx {}
@@ -1,3 +1,5 @@
+use std::num::ParseIntError;
+
 /// extracts bits start..=end from val
 pub fn extract_bits_8(val: u8, start: u8, end: u8) -> u8 {
 	assert!(start <= end);
@@ -46,3 +48,22 @@ pub fn sign_ext_imm(imm: u32, sign_bit_idx: u8) -> i64 {
 	};
 	(imm as i64) | high_bits
 }
+
+/// full 128 bit carry-less (xor) product of two values
+pub fn carryless_mul(lhs: u64, rhs: u64) -> u128 {
+	let mut product = 0u128;
+	for bit in 0..u64::BITS {
+		if (rhs >> bit) & 1 == 1 {
+			product ^= u128::from(lhs) << bit;
+		}
+	}
+	product
+}
+
+/// numbers can be hex with a 0x prefix or decimal, either can use _ as a separator
+pub fn parse_number(s: &str) -> Result<u64, ParseIntError> {
+	match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+		Some(hex) => u64::from_str_radix(&hex.replace('_', ""), 16),
+		None => s.replace('_', "").parse::<u64>(),
+	}
+}